@@ -36,6 +36,11 @@ const viewIdent = tsf.createIdentifier('view')
 // === Public API ===
 
 export function implement(schema: Schema.Schema): string {
+  if (schema.header == null) {
+    throw new Error(
+      'The schema does not specify a header; it was produced by an old version of `enso-parser-schema`.',
+    )
+  }
   const file = ts.createSourceFile('source.ts', '', ts.ScriptTarget.ESNext, false, ts.ScriptKind.TS)
   const printer = ts.createPrinter({
     newLine: ts.NewLineKind.LineFeed,
@@ -79,6 +84,7 @@ export function implement(schema: Schema.Schema): string {
       // Ignore child types; they are generated when `makeAbstractType` processes the parent.
     }
   }
+  makeHeaderCheck(schema.header).forEach(emit)
   return output
 }
 
@@ -482,6 +488,57 @@ function makeAbstractType(
   return { module: moduleDecl, export: abstractTypeExport }
 }
 
+/**
+ * Define the header serialized data is expected to begin with, and a function that reads the root of
+ * the serialized tree after checking that the data was produced from the types this code was
+ * generated from.
+ */
+function makeHeaderCheck(header: Schema.Header): ts.Statement[] {
+  const versionIdent = tsf.createIdentifier('FORMAT_VERSION')
+  const hashIdent = tsf.createIdentifier('TYPE_GRAPH_HASH')
+  const treeIdent = tsf.createIdentifier('Tree')
+  const viewParam = tsf.createParameterDeclaration(
+    [],
+    undefined,
+    viewIdent,
+    undefined,
+    support.DataView,
+    undefined,
+  )
+  const rootAddress = tsf.createBinaryExpression(
+    tsf.createPropertyAccessExpression(viewIdent, 'byteLength'),
+    ts.SyntaxKind.MinusToken,
+    tsf.createNumericLiteral(4),
+  )
+  return [
+    makeExportConstVariable(versionIdent.text, tsf.createNumericLiteral(header.version)),
+    makeExportConstVariable(hashIdent.text, tsf.createBigIntLiteral(`0x${header.typeGraphHash}n`)),
+    tsf.createFunctionDeclaration(
+      [modifiers.export],
+      undefined,
+      'deserializeTree',
+      [],
+      [viewParam],
+      tsf.createTypeReferenceNode(treeIdent),
+      tsf.createBlock(
+        [
+          tsf.createExpressionStatement(
+            tsf.createCallExpression(support.checkHeader, [], [viewIdent, versionIdent, hashIdent]),
+          ),
+          tsf.createReturnStatement(
+            tsf.createCallExpression(
+              tsf.createPropertyAccessExpression(treeIdent, 'read'),
+              [],
+              [viewIdent, rootAddress],
+            ),
+          ),
+        ],
+        true,
+      ),
+    ),
+  ]
+}
+
 function makeExportConstVariable(
   varName: string,
   initializer: ts.Expression,
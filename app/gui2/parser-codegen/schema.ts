@@ -1,6 +1,11 @@
 export type Schema = {
   types: Types
   serialization: Serialization
+  header?: Header
+}
+export type Header = {
+  version: number
+  typeGraphHash: string
 }
 export type TypeId = string
 export type Types = {
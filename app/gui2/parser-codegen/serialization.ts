@@ -28,6 +28,7 @@ export const supportImports = {
   readOption: false,
   readResult: false,
   readEnum: false,
+  checkHeader: false,
   readSequence: false,
   readString: false,
   visitSequence: false,
@@ -52,6 +53,7 @@ export const support = {
   readOption: tsf.createIdentifier('readOption'),
   readResult: tsf.createIdentifier('readResult'),
   readEnum: tsf.createIdentifier('readEnum'),
+  checkHeader: tsf.createIdentifier('checkHeader'),
   readSequence: tsf.createIdentifier('readSequence'),
   readString: tsf.createIdentifier('readString'),
   visitSequence: tsf.createIdentifier('visitSequence'),
@@ -81,7 +81,12 @@ export function casesOrThrow(cases: ts.CaseClause[], error: string): ts.CaseBloc
 
 export function throwError(error: string): ts.Statement {
   return tsf.createThrowStatement(
-    tsf.createNewExpression(tsf.createIdentifier('Error'), [], [tsf.createStringLiteral(error)]),
+    tsf.createNewExpression(
+      // The generated module may define its own `Error` type, so refer to the builtin explicitly.
+      tsf.createPropertyAccessExpression(tsf.createIdentifier('globalThis'), 'Error'),
+      [],
+      [tsf.createStringLiteral(error)],
+    ),
   )
 }
 
@@ -1,5 +1,6 @@
 use wasm_bindgen::prelude::*;
 
+use enso_parser::semantic_tokens;
 use enso_parser::Parser;
 
 
@@ -20,6 +21,37 @@ pub fn parse(code: &str) -> Vec<u8> {
     enso_parser::format::serialize(&ast).expect("Failed to serialize AST to binary format")
 }
 
+#[wasm_bindgen]
+pub fn parse_with_ids(code: &str, id_map_json: &str) -> Vec<u8> {
+    let id_map = serde_json::from_str(id_map_json).expect("Failed to deserialize ID map");
+    let (ast, ids) = PARSER.with(|parser| parser.run_with_ids(code, &id_map));
+    enso_parser::format::serialize_tree_with_ids(&ast, &ids)
+        .expect("Failed to serialize AST to binary format")
+}
+
+#[wasm_bindgen]
+pub fn assign_ids(code: &str, id_map_json: &str) -> String {
+    let id_map = serde_json::from_str(id_map_json).expect("Failed to deserialize ID map");
+    let (_, ids) = PARSER.with(|parser| parser.run_with_ids(code, &id_map));
+    serde_json::to_string(&ids).expect("Failed to serialize ID map to JSON")
+}
+
+#[wasm_bindgen]
+pub fn semantic_tokens(code: &str) -> Vec<u32> {
+    let ast = PARSER.with(|parser| parser.run(code));
+    semantic_tokens::encode(&semantic_tokens::collect(&ast))
+}
+
+#[wasm_bindgen]
+pub fn semantic_tokens_legend() -> String {
+    let token_types: Vec<_> = semantic_tokens::Kind::ALL.iter().map(|kind| kind.name()).collect();
+    let token_modifiers: Vec<_> =
+        semantic_tokens::Modifier::ALL.iter().map(|modifier| modifier.name()).collect();
+    let legend =
+        serde_json::json!({ "tokenTypes": token_types, "tokenModifiers": token_modifiers });
+    legend.to_string()
+}
+
 #[wasm_bindgen]
 pub fn is_ident_or_operator(code: &str) -> u32 {
     let parsed = enso_parser::lexer::run(code);
@@ -1,6 +1,14 @@
 import { createXXHash128 } from 'hash-wasm'
 import type { IDataType } from 'hash-wasm/dist/lib/util'
-import init, { is_ident_or_operator, parse, parse_doc_to_json } from '../../rust-ffi/pkg/rust_ffi'
+import init, {
+  assign_ids,
+  is_ident_or_operator,
+  parse,
+  parse_doc_to_json,
+  parse_with_ids,
+  semantic_tokens,
+  semantic_tokens_legend,
+} from '../../rust-ffi/pkg/rust_ffi'
 import { assertDefined } from '../util/assert'
 import { isNode } from '../util/detect'
 
@@ -29,5 +37,14 @@ export async function initializeFFI(path?: string | undefined) {
 // TODO[ao]: We cannot to that, because the ffi is used by cjs modules.
 // await initializeFFI()
 
-/* eslint-disable-next-line camelcase */
-export { is_ident_or_operator, parse_doc_to_json, parse as parse_tree }
+/* eslint-disable camelcase */
+export {
+  assign_ids,
+  is_ident_or_operator,
+  parse_doc_to_json,
+  parse as parse_tree,
+  parse_with_ids as parse_tree_with_ids,
+  semantic_tokens,
+  semantic_tokens_legend,
+}
+/* eslint-enable camelcase */
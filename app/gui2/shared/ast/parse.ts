@@ -31,10 +31,10 @@ import {
   type SourceRangeKey,
 } from '../yjsModel'
 import { graphParentPointers } from './debug'
-import { parse_tree, xxHash128 } from './ffi'
+import { parse_tree, parse_tree_with_ids, xxHash128 } from './ffi'
 import * as RawAst from './generated/ast'
 import { MutableModule } from './mutableModule'
-import type { LazyObject } from './parserSupport'
+import { splitNodeIds, type LazyObject } from './parserSupport'
 import {
   App,
   Assignment,
@@ -65,12 +65,29 @@ import {
 /** Return the raw parser output for the given code. */
 export function parseEnso(code: string): RawAst.Tree.BodyBlock {
   const blob = parse_tree(code)
-  const tree = RawAst.Tree.read(new DataView(blob.buffer), blob.byteLength - 4)
+  const tree = RawAst.deserializeTree(new DataView(blob.buffer))
   // The root of the parser output is always a body block.
   assert(tree.type === RawAst.Tree.Type.BodyBlock)
   return tree
 }
 
+/**
+ * Return the raw parser output for the given code, and the ID of every node, by the address of its
+ * object (see `LazyObject.address`). Nodes at the locations of entries of the ID map receive their
+ * IDs; other nodes receive deterministic fresh IDs.
+ */
+export function parseEnsoWithIds(
+  code: string,
+  idMapJson: string,
+): { tree: RawAst.Tree.BodyBlock; ids: Map<number, string> } {
+  const blob = parse_tree_with_ids(code, idMapJson)
+  const { tree: view, ids } = splitNodeIds(new DataView(blob.buffer))
+  const tree = RawAst.deserializeTree(view)
+  // The root of the parser output is always a body block.
+  assert(tree.type === RawAst.Tree.Type.BodyBlock)
+  return { tree, ids }
+}
+
 /** Print the AST and re-parse it, copying `externalId`s (but not other metadata) from the original. */
 export function normalize(rootIn: Ast): Ast {
   const printed = print(rootIn)
@@ -16,6 +16,11 @@ export abstract class LazyObject {
     this._v = view
   }
 
+  /** The location of the object's fields in the serialized data. */
+  get address(): number {
+    return this._v.byteOffset
+  }
+
   visitChildren(_visitor: ObjectVisitor): boolean {
     return false
   }
@@ -197,6 +202,51 @@ export function readString(view: DataView, address: number): string {
   return textDecoder.decode(bytes)
 }
 
+/**
+ * Check that the data begins with the header of the format and types the reader was generated
+ * from: the format version as a `u32`, followed by the type graph hash as a `u64`.
+ */
+export function checkHeader(view: DataView, version: number, typeGraphHash: bigint) {
+  if (view.byteLength < 12) throw new Error('Serialized data is too short to contain a header.')
+  const foundVersion = readU32(view, 0)
+  const foundHash = readU64(view, 4)
+  if (foundVersion !== version || foundHash !== typeGraphHash) {
+    throw new Error(
+      `Expected format version ${version} with type graph hash ${typeGraphHash.toString(16)}, ` +
+        `found version ${foundVersion} with hash ${foundHash.toString(16)}. ` +
+        'The parser does not match its generated bindings; regenerate them with ' +
+        '`npm run generate-ast-schema && npm run generate-ast-types`.',
+    )
+  }
+}
+
+/** The size of an entry of the table of node IDs: a `u32` address, and a UUID as two `u64`s. */
+const ID_ENTRY_SIZE = 4 + 8 + 8
+
+/**
+ * Separate the table of node IDs from serialized data that ends with one: the data is followed by
+ * an entry for each node, and then the number of entries as a `u32`. Returns a view of the data
+ * preceding the table, and the ID of each node by the address of its object (see
+ * `LazyObject.address`).
+ */
+export function splitNodeIds(view: DataView): { tree: DataView; ids: Map<number, string> } {
+  if (view.byteLength < 4) throw new Error('Serialized data is too short to contain node IDs.')
+  const countAddress = view.byteLength - 4
+  const tableAddress = countAddress - readU32(view, countAddress) * ID_ENTRY_SIZE
+  if (tableAddress < 0) throw new Error('Serialized data is too short to contain node IDs.')
+  const ids = new Map<number, string>()
+  for (let entry = tableAddress; entry < countAddress; entry += ID_ENTRY_SIZE) {
+    const id = uuidFromU64Pair(readU64(view, entry + 4), readU64(view, entry + 12))
+    ids.set(readU32(view, entry), id)
+  }
+  return { tree: new DataView(view.buffer, view.byteOffset, tableAddress), ids }
+}
+
+function uuidFromU64Pair(high: bigint, low: bigint): string {
+  const hex = high.toString(16).padStart(16, '0') + low.toString(16).padStart(16, '0')
+  return hex.replace(/^(.{8})(.{4})(.{4})(.{4})(.{12})$/, '$1-$2-$3-$4-$5')
+}
+
 export function readEnum<T>(readers: Reader<T>[], view: DataView, address: number): T {
   const data = readPointer(view, address)
   const discriminant = readU32(data, 0)
@@ -1,5 +1,6 @@
 package org.enso.compiler.core;
 
+import java.util.List;
 import org.enso.compiler.core.ir.Expression;
 import org.enso.compiler.core.ir.Module;
 import org.enso.syntax2.Parser;
@@ -35,6 +36,10 @@ public final class EnsoParser implements AutoCloseable {
     return parser.parse(src);
   }
 
+  public List<Tree> parseBatch(List<? extends CharSequence> sources) {
+    return parser.parseBatch(sources);
+  }
+
   public Module generateIR(Tree t) {
     return TreeToIr.MODULE.translate(t);
   }
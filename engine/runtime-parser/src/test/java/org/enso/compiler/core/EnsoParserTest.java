@@ -39,6 +39,19 @@ public class EnsoParserTest {
     """);
   }
 
+  @Test
+  public void testParseBatch() throws Exception {
+    var sources =
+        List.of("main = 7.foo", "type T\n    A x\n", "", "main =\n    x = 1\n    x + 2\n");
+    var trees = ensoCompiler.parseBatch(sources);
+    var batched = trees.stream().map(ensoCompiler::generateIR).toList();
+    assertEquals(sources.size(), batched.size());
+    for (var i = 0; i < sources.size(); i++) {
+      var single = ensoCompiler.compile(sources.get(i));
+      assertIR("Batched parse of input " + i, single, batched.get(i));
+    }
+  }
+
   @Test
   public void testLocationsSimpleArithmeticExpression() throws Exception {
     parseTest("""
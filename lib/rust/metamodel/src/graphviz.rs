@@ -75,7 +75,7 @@ impl std::fmt::Display for Graph {
         let field_edge_attrs = vec![];
         let optional_field_edge_attrs = vec![format!("style=dashed")];
         let subtype_edge_attrs = vec![format!("arrowhead=dot")];
-        writeln!(f, "digraph refs {{")?;
+        let mut dot = Dot::new("refs");
         let non_leafs: BTreeSet<_> = self.edges.iter().map(|(x, _, _)| x).cloned().collect();
         let mut pruned = BTreeSet::new();
         for (id, node) in &self.nodes {
@@ -102,7 +102,7 @@ impl std::fmt::Display for Graph {
                 NodeType::AbstractStruct => "diamond",
             };
             attrs.push(format!("shape={shape}"));
-            writeln!(f, "{:?} [{}];", id, attrs.join(","))?;
+            dot.nodes.push(DotNode { id: id.clone(), attrs });
         }
         for (x, y, edgetype) in &self.edges {
             if pruned.contains(x) || pruned.contains(y) {
@@ -114,9 +114,91 @@ impl std::fmt::Display for Graph {
                 EdgeType::OptionalField => &optional_field_edge_attrs,
                 EdgeType::Subtype => &subtype_edge_attrs,
             };
-            writeln!(f, "{:?} -> {:?} [{}];", x, y, attrs.join(","))?;
+            dot.edges.push(DotEdge { from: x.clone(), to: y.clone(), attrs: attrs.clone() });
         }
-        writeln!(f, "}}")?;
-        Ok(())
+        write!(f, "{dot}")
+    }
+}
+
+
+
+// ===========
+// === Dot ===
+// ===========
+
+/// A directed graph in the DOT language, with the GraphViz attributes of each element given as
+/// `name=value` strings.
+#[derive(Debug, Default, Clone)]
+pub struct Dot {
+    /// The name of the graph.
+    pub name:     String,
+    /// The nodes that are not part of a cluster.
+    pub nodes:    Vec<DotNode>,
+    /// Groups of nodes that are drawn together.
+    pub clusters: Vec<DotCluster>,
+    #[allow(missing_docs)]
+    pub edges:    Vec<DotEdge>,
+}
+
+/// A node of a [`Dot`] graph.
+#[derive(Debug, Clone)]
+pub struct DotNode {
+    /// The name identifying the node.
+    pub id:    String,
+    #[allow(missing_docs)]
+    pub attrs: Vec<String>,
+}
+
+/// A cluster of a [`Dot`] graph.
+#[derive(Debug, Default, Clone)]
+pub struct DotCluster {
+    /// The attributes of the subgraph, such as its label.
+    pub attrs: Vec<String>,
+    #[allow(missing_docs)]
+    pub nodes: Vec<DotNode>,
+}
+
+/// An edge of a [`Dot`] graph, between nodes identified by name.
+#[derive(Debug, Clone)]
+pub struct DotEdge {
+    #[allow(missing_docs)]
+    pub from:  String,
+    #[allow(missing_docs)]
+    pub to:    String,
+    #[allow(missing_docs)]
+    pub attrs: Vec<String>,
+}
+
+impl Dot {
+    /// Create an empty graph.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into(), ..Default::default() }
+    }
+}
+
+impl std::fmt::Display for Dot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "digraph {} {{", self.name)?;
+        for (index, cluster) in self.clusters.iter().enumerate() {
+            writeln!(f, "subgraph cluster_{index} {{")?;
+            writeln!(f, "graph [{}];", cluster.attrs.join(","))?;
+            for node in &cluster.nodes {
+                writeln!(f, "{node}")?;
+            }
+            writeln!(f, "}}")?;
+        }
+        for node in &self.nodes {
+            writeln!(f, "{node}")?;
+        }
+        for DotEdge { from, to, attrs } in &self.edges {
+            writeln!(f, "{:?} -> {:?} [{}];", from, to, attrs.join(","))?;
+        }
+        writeln!(f, "}}")
+    }
+}
+
+impl std::fmt::Display for DotNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} [{}];", self.id, self.attrs.join(","))
     }
 }
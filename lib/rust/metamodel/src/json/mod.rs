@@ -0,0 +1,262 @@
+//! Representation of data models as JSON.
+//!
+//! A [`rust`](crate::rust) data model is described by a [JSON Schema](https://json-schema.org/)
+//! document matching the JSON that `serde_json` produces for values of the modeled types, with the
+//! default (externally-tagged) `serde` representations:
+//! - A struct with named fields is an object with a property for each field. A tuple struct with
+//!   one field is represented by the field's value, and other tuple structs are arrays.
+//! - A unit variant of an enum is a string naming the variant. Other variants are objects with a
+//!   single property, named by the variant, whose value represents the variant's fields in the same
+//!   way as a struct's.
+//! - An `Option` is `null` or the value, a `Result` is an enum with the variants `Ok` and `Err`,
+//!   and a `char` is a one-character string.
+//!
+//! Properties are named by the Rust identifiers of the fields; `#[reflect(rename)]` does not affect
+//! the JSON representation. `serde` attributes are not visible to the data model, so a field that
+//! `serde` skips must also be skipped with `#[reflect(skip)]`, and a field serialized by a custom
+//! function must declare the type it is serialized as with `#[reflect(as)]`.
+//!
+//! # Why the `rust` graph, and not the `meta` graph
+//!
+//! The other generators work from a [`meta::TypeGraph`](crate::meta::TypeGraph), but the
+//! conversion to it ([`rust::to_meta`](crate::rust::to_meta)) discards the structure that `serde`
+//! serializes:
+//! - Fields marked `#[reflect(flatten)]` are inlined into the parent with prefixed names (e.g.
+//!   `code_repr_begin`), while `serde` serializes them as nested objects (`"code":{"repr":…}`).
+//! - Fields marked `#[reflect(subtype)]`, and the variants of enums, become a parent/child class
+//!   hierarchy distinguished by discriminants, while `serde` serializes the enclosing value and the
+//!   variant as nested objects (`"variant":{"BodyBlock":{…}}`).
+//! - Fields and types are named by `#[reflect(rename)]`, which `serde` ignores.
+//!
+//! A schema built from the `meta` graph would therefore describe a different document than the one
+//! `serde_json` produces.
+
+use crate::rust::*;
+
+use serde_json::json;
+use serde_json::Map;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::VecDeque;
+
+
+
+// ==============
+// === Export ===
+// ==============
+
+pub mod validate;
+
+pub use validate::validate;
+pub use validate::ValidationError;
+
+
+
+// =================
+// === Constants ===
+// =================
+
+/// The JSON Schema dialect of the generated schemas.
+pub const JSON_SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+/// The version of the OpenAPI Specification that generated documents conform to.
+pub const OPENAPI_VERSION: &str = "3.1.0";
+
+const SCHEMA_DEFINITIONS: &str = "#/$defs/";
+const OPENAPI_DEFINITIONS: &str = "#/components/schemas/";
+
+
+
+// ==============
+// === Format ===
+// ==============
+
+/// The JSON representation of the data of a Rust type, and the types it contains.
+#[derive(Debug)]
+pub struct Format {
+    types: BTreeMap<TypeId, TypeData>,
+    root:  TypeId,
+    names: BTreeMap<TypeId, String>,
+}
+
+impl Format {
+    /// Define the JSON representation of values of the `root` type.
+    pub fn new(root: TypeData) -> Self {
+        let root_id = root.id;
+        let types = reachable_types(root);
+        let names = definition_names(&types);
+        let types = types.into_iter().map(|ty| (ty.id, ty)).collect();
+        Self { types, root: root_id, names }
+    }
+
+    /// Produce a JSON Schema document describing the representation of the root type. Every
+    /// struct and enum type is defined in the `$defs` of the document, under its Rust name (see
+    /// [`Format::definition_name`]).
+    pub fn schema(&self) -> Value {
+        let mut schema = self.type_schema(self.root, SCHEMA_DEFINITIONS);
+        schema["$schema"] = JSON_SCHEMA_DIALECT.into();
+        schema["$defs"] = self.definitions(SCHEMA_DEFINITIONS).into();
+        schema
+    }
+
+    /// Produce an OpenAPI document defining the representation of every struct and enum type as a
+    /// component schema.
+    pub fn openapi(&self, title: &str, version: &str) -> Value {
+        json!({
+            "openapi": OPENAPI_VERSION,
+            "jsonSchemaDialect": JSON_SCHEMA_DIALECT,
+            "info": { "title": title, "version": version },
+            "paths": {},
+            "components": { "schemas": self.definitions(OPENAPI_DEFINITIONS) },
+        })
+    }
+
+    /// The name under which a struct or enum type is defined. Types are named by their Rust
+    /// identifiers. Instances of a generic type are distinguished by the type of their
+    /// `#[reflect(subtype)]` field, if they have one (e.g. `Token.Ident`); other types with the
+    /// same name are given numeric suffixes, in the order they are reached from the root.
+    pub fn definition_name(&self, id: TypeId) -> Option<&str> {
+        self.names.get(&id).map(String::as_str)
+    }
+
+    fn definitions(&self, prefix: &str) -> Map<String, Value> {
+        let mut definitions = Map::new();
+        for (id, name) in &self.names {
+            definitions.insert(name.clone(), self.definition(*id, prefix));
+        }
+        definitions
+    }
+
+    fn definition(&self, id: TypeId, prefix: &str) -> Value {
+        match &self.types[&id].data {
+            Data::Struct(Struct { fields, .. }) => self.fields_schema(fields, prefix),
+            Data::Enum(Enum { variants }) => {
+                let alternatives: Vec<_> = variants
+                    .iter()
+                    .map(|Variant { ident, fields, .. }| match fields {
+                        Fields::Unit => json!({ "const": ident }),
+                        _ => object([(ident.clone(), self.fields_schema(fields, prefix))]),
+                    })
+                    .collect();
+                json!({ "oneOf": alternatives })
+            }
+            Data::Primitive(_) => unreachable!("Primitive types are not defined."),
+        }
+    }
+
+    fn fields_schema(&self, fields: &Fields, prefix: &str) -> Value {
+        match fields {
+            Fields::Named(fields) => object(
+                fields
+                    .iter()
+                    .map(|field| (field_name(field), self.type_schema(field.type_.id, prefix))),
+            ),
+            Fields::Unnamed(fields) if fields.len() == 1 =>
+                self.type_schema(fields[0].type_.id, prefix),
+            Fields::Unnamed(fields) => {
+                let items: Vec<_> =
+                    fields.iter().map(|field| self.type_schema(field.type_.id, prefix)).collect();
+                json!({ "type": "array", "prefixItems": items, "items": false })
+            }
+            Fields::Unit => json!({ "type": "null" }),
+        }
+    }
+
+    /// Produce a schema for values of a type, referring to the definition of a struct or enum type,
+    /// or describing a primitive type inline.
+    fn type_schema(&self, id: TypeId, prefix: &str) -> Value {
+        let primitive = match &self.types[&id].data {
+            Data::Struct(_) | Data::Enum(_) => return json!({ "$ref": self.pointer(id, prefix) }),
+            Data::Primitive(primitive) => primitive,
+        };
+        match *primitive {
+            Primitive::Bool => json!({ "type": "boolean" }),
+            Primitive::Usize | Primitive::U64 => integer(u64::MIN, u64::MAX),
+            Primitive::U32 => integer(u32::MIN, u32::MAX),
+            Primitive::I32 => integer(i32::MIN, i32::MAX),
+            Primitive::Char => json!({ "type": "string", "minLength": 1, "maxLength": 1 }),
+            Primitive::String => json!({ "type": "string" }),
+            Primitive::Vec(t0) =>
+                json!({ "type": "array", "items": self.type_schema(t0.id, prefix) }),
+            Primitive::Option(t0) =>
+                json!({ "anyOf": [self.type_schema(t0.id, prefix), { "type": "null" }] }),
+            Primitive::Result(t0, t1) => json!({ "oneOf": [
+                object([("Ok".to_owned(), self.type_schema(t0.id, prefix))]),
+                object([("Err".to_owned(), self.type_schema(t1.id, prefix))]),
+            ] }),
+        }
+    }
+
+    fn pointer(&self, id: TypeId, prefix: &str) -> String {
+        format!("{prefix}{}", self.names[&id])
+    }
+}
+
+
+// === Helpers ===
+
+/// Return the types reachable from the root, in breadth-first order.
+fn reachable_types(root: TypeData) -> Vec<TypeData> {
+    let mut types = vec![];
+    let mut seen = BTreeSet::from([root.id]);
+    let mut to_visit = VecDeque::from([root]);
+    while let Some(ty) = to_visit.pop_front() {
+        for lazy in ty.referenced_types() {
+            if seen.insert(lazy.id) {
+                to_visit.push_back(lazy.evaluate());
+            }
+        }
+        types.push(ty);
+    }
+    types
+}
+
+/// Assign a unique definition name to each struct and enum type; see [`Format::definition_name`].
+fn definition_names(types: &[TypeData]) -> BTreeMap<TypeId, String> {
+    let defined = || types.iter().filter(|ty| !matches!(ty.data, Data::Primitive(_)));
+    let mut counts = BTreeMap::<&str, usize>::new();
+    defined().for_each(|ty| *counts.entry(&ty.name).or_default() += 1);
+    let subtype = |ty: &TypeData| match &ty.data {
+        Data::Struct(Struct { fields: Fields::Named(fields), .. }) =>
+            fields.iter().find(|field| field.subtype).map(|field| field.type_.evaluate().name),
+        _ => None,
+    };
+    let mut names = BTreeMap::new();
+    let mut used = BTreeSet::new();
+    for ty in defined() {
+        let base = match subtype(ty) {
+            Some(subtype) if counts[ty.name.as_str()] > 1 => format!("{}.{subtype}", ty.name),
+            _ => ty.name.clone(),
+        };
+        let mut name = base.clone();
+        let mut suffix = 1;
+        while !used.insert(name.clone()) {
+            name = format!("{base}_{suffix}");
+            suffix += 1;
+        }
+        names.insert(ty.id, name);
+    }
+    names
+}
+
+/// The name of the field in the `serde` representation, which omits the prefix of a raw
+/// identifier.
+fn field_name(field: &NamedField) -> String {
+    field.name.trim_start_matches("r#").to_owned()
+}
+
+/// A schema for an object with the given properties, all of which are required.
+fn object(properties: impl IntoIterator<Item = (String, Value)>) -> Value {
+    let properties: Map<_, _> = properties.into_iter().collect();
+    let required: Vec<_> = properties.keys().cloned().collect();
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": false,
+    })
+}
+
+fn integer(minimum: impl Into<Value>, maximum: impl Into<Value>) -> Value {
+    json!({ "type": "integer", "minimum": minimum.into(), "maximum": maximum.into() })
+}
@@ -0,0 +1,240 @@
+//! Checking JSON values against the schemas produced by [`Format`].
+//!
+//! This supports the subset of JSON Schema that the generated schemas use: local `$ref`s, `type`,
+//! `const`, integer ranges, string lengths, the array keywords `prefixItems` and `items`, the
+//! object keywords `properties`, `required` and `additionalProperties`, and the combinators `oneOf`
+//! and `anyOf`. Other keywords (e.g. annotations such as `description`) are ignored.
+
+use super::*;
+
+use std::cmp::Ordering;
+
+
+
+// ==================
+// === Validation ===
+// ==================
+
+/// Check that a value matches a schema. The schema is identified by a `reference` to a location
+/// within the `document`: `"#"` refers to the document itself (e.g. for a document produced by
+/// [`Format::schema`]), and `"#/components/schemas/Tree"` would refer to a component schema of
+/// an OpenAPI document.
+pub fn validate(document: &Value, reference: &str, value: &Value) -> Result<(), ValidationError> {
+    let validator = Validator { document };
+    let schema = validator.resolve(reference, "")?;
+    validator.check(schema, value, "")
+}
+
+struct Validator<'d> {
+    document: &'d Value,
+}
+
+impl<'d> Validator<'d> {
+    fn resolve(&self, reference: &str, path: &str) -> Result<&'d Value, ValidationError> {
+        let pointer = reference.strip_prefix('#');
+        let schema = pointer.and_then(|pointer| self.document.pointer(pointer));
+        schema.ok_or_else(|| error(path, format!("unresolved reference: {reference}")))
+    }
+
+    fn check(&self, schema: &Value, value: &Value, path: &str) -> Result<(), ValidationError> {
+        let schema = match schema {
+            Value::Bool(true) => return Ok(()),
+            Value::Bool(false) => return Err(error(path, "no value is allowed here")),
+            Value::Object(schema) => schema,
+            _ => return Err(error(path, "invalid schema")),
+        };
+        if let Some(reference) = schema.get("$ref") {
+            let reference = reference.as_str().unwrap_or_default();
+            self.check(self.resolve(reference, path)?, value, path)?;
+        }
+        if let Some(type_) = schema.get("type") {
+            let types = match type_ {
+                Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+                _ => vec![type_.as_str().unwrap_or_default()],
+            };
+            if !types.iter().any(|type_| has_type(value, type_)) {
+                return Err(error(path, format!("expected {}, found {value}", types.join(" or "))));
+            }
+        }
+        if let Some(expected) = schema.get("const") {
+            if value != expected {
+                return Err(error(path, format!("expected {expected}, found {value}")));
+            }
+        }
+        self.check_number(schema, value, path)?;
+        self.check_string(schema, value, path)?;
+        if let Value::Array(elements) = value {
+            self.check_array(schema, elements, path)?;
+        }
+        if let Value::Object(object) = value {
+            self.check_object(schema, object, path)?;
+        }
+        if let Some(Value::Array(alternatives)) = schema.get("oneOf") {
+            let matches =
+                alternatives.iter().filter(|s| self.check(s, value, path).is_ok()).count();
+            if matches != 1 {
+                let n = alternatives.len();
+                return Err(error(path, format!("matches {matches} of {n} `oneOf` alternatives")));
+            }
+        }
+        if let Some(Value::Array(alternatives)) = schema.get("anyOf") {
+            if !alternatives.iter().any(|schema| self.check(schema, value, path).is_ok()) {
+                return Err(error(path, "matches none of the `anyOf` alternatives"));
+            }
+        }
+        Ok(())
+    }
+
+    fn check_number(
+        &self,
+        schema: &Map<String, Value>,
+        value: &Value,
+        path: &str,
+    ) -> Result<(), ValidationError> {
+        if !value.is_number() {
+            return Ok(());
+        }
+        if let Some(minimum) = schema.get("minimum") {
+            if compare(value, minimum) == Some(Ordering::Less) {
+                return Err(error(path, format!("{value} is less than {minimum}")));
+            }
+        }
+        if let Some(maximum) = schema.get("maximum") {
+            if compare(value, maximum) == Some(Ordering::Greater) {
+                return Err(error(path, format!("{value} is greater than {maximum}")));
+            }
+        }
+        Ok(())
+    }
+
+    fn check_string(
+        &self,
+        schema: &Map<String, Value>,
+        value: &Value,
+        path: &str,
+    ) -> Result<(), ValidationError> {
+        let len = match value {
+            Value::String(s) => s.chars().count() as u64,
+            _ => return Ok(()),
+        };
+        let min = schema.get("minLength").and_then(Value::as_u64).unwrap_or(u64::MIN);
+        let max = schema.get("maxLength").and_then(Value::as_u64).unwrap_or(u64::MAX);
+        match (min..=max).contains(&len) {
+            true => Ok(()),
+            false => Err(error(path, format!("length of {value} is not in {min}..={max}"))),
+        }
+    }
+
+    fn check_array(
+        &self,
+        schema: &Map<String, Value>,
+        elements: &[Value],
+        path: &str,
+    ) -> Result<(), ValidationError> {
+        let no_prefix = vec![];
+        let prefix = match schema.get("prefixItems") {
+            Some(Value::Array(prefix)) => prefix,
+            _ => &no_prefix,
+        };
+        for (i, element) in elements.iter().enumerate() {
+            let item = prefix.get(i).or_else(|| schema.get("items"));
+            if let Some(item) = item {
+                self.check(item, element, &format!("{path}/{i}"))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn check_object(
+        &self,
+        schema: &Map<String, Value>,
+        object: &Map<String, Value>,
+        path: &str,
+    ) -> Result<(), ValidationError> {
+        let no_properties = Map::new();
+        let properties = match schema.get("properties") {
+            Some(Value::Object(properties)) => properties,
+            _ => &no_properties,
+        };
+        // Check the keys before the values, so that an alternative of a `oneOf` with a different
+        // shape is rejected without validating the whole subtree.
+        if let Some(Value::Array(required)) = schema.get("required") {
+            for key in required.iter().filter_map(Value::as_str) {
+                if !object.contains_key(key) {
+                    return Err(error(path, format!("missing property `{key}`")));
+                }
+            }
+        }
+        if let Some(additional) = schema.get("additionalProperties") {
+            for (key, value) in object {
+                if !properties.contains_key(key) {
+                    self.check(additional, value, &format!("{path}/{key}"))?;
+                }
+            }
+        }
+        // Likewise, properties with constant values usually identify an alternative.
+        let (constant, other): (Vec<_>, Vec<_>) =
+            properties.iter().partition(|(_, schema)| schema.get("const").is_some());
+        for (key, schema) in constant.into_iter().chain(other) {
+            if let Some(value) = object.get(key) {
+                self.check(schema, value, &format!("{path}/{key}"))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+
+// === Helpers ===
+
+fn has_type(value: &Value, type_: &str) -> bool {
+    match type_ {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        _ => false,
+    }
+}
+
+/// Compare two numbers, exactly if both are integers.
+fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    let integer = |value: &Value| {
+        let signed = value.as_i64().map(i128::from);
+        signed.or_else(|| value.as_u64().map(i128::from))
+    };
+    match (integer(a), integer(b)) {
+        (Some(a), Some(b)) => Some(a.cmp(&b)),
+        _ => a.as_f64()?.partial_cmp(&b.as_f64()?),
+    }
+}
+
+
+
+// =============
+// === Error ===
+// =============
+
+/// A value that doesn't match a schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    /// A JSON Pointer identifying the invalid part of the value.
+    pub path:    String,
+    /// A description of the problem.
+    pub message: String,
+}
+
+fn error(path: &str, message: impl Into<String>) -> ValidationError {
+    ValidationError { path: path.to_owned(), message: message.into() }
+}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid value at `{}`: {}.", self.path, self.message)
+    }
+}
+
+impl std::error::Error for ValidationError {}
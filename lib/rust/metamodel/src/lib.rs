@@ -7,6 +7,8 @@
 //! The core modules define the metamodels, and operations on them:
 //! - [`rust`]: A metamodel representing data models in the Rust typesystem.
 //! - [`java`]: A metamodel representing data models in the Java typesystem.
+//! - [`typescript`]: A metamodel representing data models in the TypeScript typesystem.
+//! - [`json`]: Describes the JSON representation of [`rust`] data models with JSON Schema.
 //! - [`meta`]: An abstract metamodel, used to perform language-independent analysis of data models,
 //!   and as an intermediate when translating data models between language-specific metamodels.
 //!
@@ -24,6 +26,17 @@
 //! - Derive deserialization for the Java data model, using [`java::bincode`].
 //! - Generate Java code implementing the data model, using [`java::to_syntax`].
 //!
+//! Rust-to-TypeScript transpilation follows the same pattern:
+//! - Translate the data model to a TypeScript data model using [`rust::to_meta`] and
+//!   [`typescript::from_meta`].
+//! - Derive lazy readers for a compact binary format, using [`typescript::format`].
+//! - Generate TypeScript code implementing the data model, using [`typescript::to_syntax`].
+//!
+//! Data can also be exported to JSON, for consumers that don't use generated code:
+//! - Serialize the data with `serde_json`.
+//! - Describe its representation with [`json::Format::schema`] (or [`json::Format::openapi`]),
+//!   using the Rust data model.
+//!
 //! Other use cases supported include:
 //! - Analyze a data model's serialization to generate exhaustive test cases, using
 //!   [`meta::serialization::testcases`].
@@ -61,6 +74,10 @@ mod data_structures;
 pub mod graphviz;
 #[cfg(feature = "java")]
 pub mod java;
+#[cfg(feature = "json")]
+pub mod json;
 pub mod meta;
 #[cfg(feature = "rust")]
 pub mod rust;
+#[cfg(feature = "typescript")]
+pub mod typescript;
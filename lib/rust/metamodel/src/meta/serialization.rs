@@ -628,7 +628,7 @@ impl<'p> Interpreter<'p> {
             }
             pc += 1;
         }
-        assert_eq!(&stack, &[]);
+        assert!(stack.is_empty());
         pc
     }
 }
@@ -655,6 +655,6 @@ fn collect_continuations(program: &[Op]) -> BTreeMap<usize, usize> {
             _ => (),
         }
     }
-    assert_eq!(&switch_stack, &[]);
+    assert!(switch_stack.is_empty());
     continuations
 }
@@ -122,6 +122,8 @@ pub enum Primitive {
     Usize,
     /// A `u32`.
     U32,
+    /// A `u64`.
+    U64,
     /// An `i32`.
     I32,
     /// A `char`.
@@ -205,6 +207,7 @@ impl ReferencedTypes for Primitive {
             | Primitive::Usize
             | Primitive::String
             | Primitive::U32
+            | Primitive::U64
             | Primitive::I32
             | Primitive::Char => vec![],
             Primitive::Vec(ty) | Primitive::Option(ty) => vec![*ty],
@@ -306,6 +309,7 @@ impl Primitive {
             Primitive::Bool
             | Primitive::Usize
             | Primitive::U32
+            | Primitive::U64
             | Primitive::I32
             | Primitive::Char
             | Primitive::String
@@ -140,6 +140,7 @@ impl ToMeta {
         let primitive = match primitive {
             Primitive::Bool => meta::Primitive::Bool,
             Primitive::U32 => meta::Primitive::U32,
+            Primitive::U64 => meta::Primitive::U64,
             Primitive::I32 => meta::Primitive::I32,
             Primitive::Char => meta::Primitive::Char,
             // In platform-independent formats, a `usize` is serialized as 64 bits.
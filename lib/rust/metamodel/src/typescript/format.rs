@@ -0,0 +1,420 @@
+//! Derivation of readers for TypeScript types, supporting a compact binary format that can be
+//! accessed lazily.
+//!
+//! # Layout
+//!
+//! All values are little-endian. The data of an object is stored at some *address*, relative to a
+//! `DataView`; when an object is read, its fields are read from fixed offsets from its address.
+//!
+//! - Integers are stored with their natural size; a `boolean` is stored in one byte.
+//! - A class without child types is stored inline, with the fields of its ancestors (if any)
+//!   followed by its own fields.
+//! - A class with child types is stored as a 32-bit pointer to a 32-bit discriminant identifying
+//!   the child type, followed by the inline data of the child.
+//! - A string or sequence is stored as a pointer to a 32-bit length, followed by the UTF-8 bytes or
+//!   the inline elements.
+//! - An optional value is stored as a one-byte tag, followed by a pointer to the inline value.
+//! - A `Result` is stored as a pointer to a 32-bit discriminant (`0` for success, `1` for error),
+//!   followed by the inline value.
+//!
+//! # Derived members
+//!
+//! For each class, [`derive`] adds:
+//! - A static `read` method, constructing a view of the object at an address.
+//! - A `get` accessor for each field, which reads the field when it is accessed.
+//! - A `visitChildren` method, which applies a visitor to each object reachable through the fields
+//!   (not including objects reachable through other objects), if there are any such fields.
+//!
+//! The code depends on the runtime support module of the generated bindings for the primitive
+//! reading and visiting operations.
+
+use crate::typescript::implementation::*;
+use crate::typescript::*;
+
+
+
+// ==============
+// === Layout ===
+// ==============
+
+/// The size of a pointer, in bytes.
+pub const POINTER: usize = 4;
+
+/// The sizes of the types in a [`TypeGraph`], and the offsets of their fields.
+#[derive(Debug, Default)]
+pub struct Layout {
+    sizes:   BTreeMap<TypeId, usize>,
+    offsets: BTreeMap<FieldId, usize>,
+}
+
+impl Layout {
+    /// Compute the layout of all the types in the graph.
+    pub fn new(graph: &TypeGraph) -> Self {
+        let mut layout = Self::default();
+        let mut uncomputed: Vec<_> = graph.types.keys().collect();
+        // Each step will make progress as long as there is no cycle in the types stored inline,
+        // which would only occur if the input contained an infinite-sized type.
+        while !uncomputed.is_empty() {
+            let uncomputed_before_step = uncomputed.len();
+            uncomputed.retain(|&id| match layout.compute_size(graph, id) {
+                Some(size) => {
+                    layout.sizes.insert(id, size);
+                    false
+                }
+                None => true,
+            });
+            assert_ne!(uncomputed.len(), uncomputed_before_step);
+        }
+        for (id, ty) in graph.types.iter().filter(|(_, ty)| ty.builtin.is_none()) {
+            let mut offset = ty.parent.map_or(0, |parent| layout.sizes[&parent]);
+            for field in &ty.fields {
+                layout.offsets.insert(field.id(), offset);
+                offset += layout.field_size(graph, &field.data).unwrap();
+            }
+            debug_assert_eq!(offset, layout.sizes[&id]);
+        }
+        layout
+    }
+
+    /// The number of bytes a value of the type occupies when stored as a field of a containing
+    /// object, or element of a sequence.
+    pub fn size(&self, graph: &TypeGraph, id: TypeId) -> usize {
+        self.stored_size(graph, id).unwrap()
+    }
+
+    /// The offset of the field from the address of an object containing it.
+    pub fn offset(&self, field: FieldId) -> usize {
+        self.offsets[&field]
+    }
+
+    /// Returns the size of the type, including fields inherited from ancestor types, if the sizes
+    /// it depends on have been computed.
+    fn compute_size(&self, graph: &TypeGraph, id: TypeId) -> Option<usize> {
+        let ty = &graph[id];
+        Some(match ty.builtin {
+            Some(Builtin::Primitive(primitive)) => primitive_size(primitive),
+            Some(Builtin::Option(_)) => 1 + POINTER,
+            Some(Builtin::Sequence(_) | Builtin::Result(_, _) | Builtin::String) => POINTER,
+            None => {
+                let mut size = match ty.parent {
+                    Some(parent) => *self.sizes.get(&parent)?,
+                    None => 0,
+                };
+                for field in &ty.fields {
+                    size += self.field_size(graph, &field.data)?;
+                }
+                size
+            }
+        })
+    }
+
+    fn field_size(&self, graph: &TypeGraph, data: &FieldData) -> Option<usize> {
+        match data {
+            FieldData::Primitive(primitive) => Some(primitive_size(*primitive)),
+            FieldData::Object { optional: true, .. } => Some(1 + POINTER),
+            FieldData::Object { type_, optional: false } => self.stored_size(graph, *type_),
+        }
+    }
+
+    fn stored_size(&self, graph: &TypeGraph, id: TypeId) -> Option<usize> {
+        match graph[id].discriminants.is_empty() {
+            true => self.sizes.get(&id).copied(),
+            false => Some(POINTER),
+        }
+    }
+}
+
+fn primitive_size(primitive: Primitive) -> usize {
+    match primitive {
+        Primitive::Boolean => 1,
+        Primitive::Number { .. } => 4,
+        Primitive::BigInt { .. } => 8,
+    }
+}
+
+
+
+// ====================
+// === Derive Reads ===
+// ====================
+
+/// Add members to every class in the graph that read its data in the format described in the
+/// [module documentation](self).
+pub fn derive(graph: &mut TypeGraph) {
+    let layout = Layout::new(graph);
+    let mut members = BTreeMap::new();
+    for (id, ty) in graph.types.iter() {
+        if ty.builtin.is_none() {
+            let readers = Readers { graph, layout: &layout };
+            members.insert(id, readers.members(id));
+        }
+    }
+    for (id, members) in members {
+        graph[id].members.extend(members);
+    }
+}
+
+/// The field of a `LazyObject` holding its view of the serialized data.
+const VIEW_FIELD: &str = "this._v";
+
+#[derive(Debug, Copy, Clone)]
+struct Readers<'g> {
+    graph:  &'g TypeGraph,
+    layout: &'g Layout,
+}
+
+impl<'g> Readers<'g> {
+    fn members(&self, id: TypeId) -> Vec<syntax::Member> {
+        let ty = &self.graph[id];
+        let mut members = vec![];
+        if let Some(read) = self.read_method(id) {
+            members.push(read.into());
+        }
+        let mut visits = vec![];
+        for field in &ty.fields {
+            let address = self.layout.offset(field.id()).to_string();
+            let type_ = quote_type(self.graph, &field.data);
+            let mut getter = syntax::Method::getter(&field.name, type_);
+            getter.body = format!("return {}", self.read_field(&field.data, VIEW_FIELD, &address));
+            members.push(getter.into());
+            visits.extend(self.visit_field(&field.data, VIEW_FIELD, &address));
+        }
+        if !visits.is_empty() {
+            let mut visit = syntax::Method::new("visitChildren", syntax::Type::named("boolean"));
+            visit.arguments = vec![("visitor".to_owned(), syntax::Type::named("ObjectVisitor"))];
+            let mut terms = vec!["super.visitChildren(visitor)".to_owned()];
+            terms.extend(visits.into_iter().map(|visit| format!("!!{visit}")));
+            visit.body = format!("return (\n  {}\n)", terms.join(" ||\n  "));
+            members.push(visit.into());
+        }
+        members
+    }
+
+    /// Produce a static method that reads an object of the type from an address.
+    fn read_method(&self, id: TypeId) -> Option<syntax::Method> {
+        let ty = &self.graph[id];
+        let name = path(self.graph, id);
+        let body = if !ty.discriminants.is_empty() {
+            let mut body = vec![
+                "const data = readPointer(view, address)".to_owned(),
+                "const discriminant = readU32(data, 0)".to_owned(),
+                "switch (discriminant) {".to_owned(),
+            ];
+            for (key, &child) in &ty.discriminants {
+                body.push(format!("  case {key}:"));
+                body.push(format!("    return {}.read(data, 4)", path(self.graph, child)));
+            }
+            body.push("  default:".to_owned());
+            // The generated module may define its own `Error` type, so refer to the builtin.
+            let error = format!("`Invalid {name} discriminant: ${{discriminant}}.`");
+            body.push(format!("    throw new globalThis.Error({error})"));
+            body.push("}".to_owned());
+            body.join("\n")
+        } else if !ty.abstract_ {
+            format!("return new {name}(readOffset(view, address))")
+        } else {
+            return None;
+        };
+        let mut method = syntax::Method::new("read", syntax::Type::named(name));
+        method.static_ = true;
+        method.arguments = vec![
+            ("view".to_owned(), syntax::Type::named("DataView")),
+            ("address".to_owned(), syntax::Type::named("number")),
+        ];
+        method.body = body;
+        Some(method)
+    }
+
+    /// An expression reading a field's value.
+    fn read_field(&self, data: &FieldData, view: &str, address: &str) -> String {
+        match data {
+            FieldData::Primitive(primitive) =>
+                format!("{}({view}, {address})", primitive_reader(*primitive)),
+            FieldData::Object { type_, optional: true } =>
+                format!("readOption({view}, {address}, {})", self.read_closure(*type_)),
+            FieldData::Object { type_, optional: false } => self.read(*type_, view, address),
+        }
+    }
+
+    /// An expression reading a value of a type.
+    fn read(&self, id: TypeId, view: &str, address: &str) -> String {
+        match self.graph[id].builtin {
+            Some(Builtin::Primitive(primitive)) =>
+                format!("{}({view}, {address})", primitive_reader(primitive)),
+            Some(Builtin::String) => format!("readString({view}, {address})"),
+            Some(Builtin::Option(t0)) =>
+                format!("readOption({view}, {address}, {})", self.read_closure(t0)),
+            Some(Builtin::Sequence(t0)) => {
+                let size = self.layout.size(self.graph, t0);
+                format!("readSequence({view}, {address}, {size}, {})", self.read_closure(t0))
+            }
+            Some(Builtin::Result(t0, t1)) => {
+                let (ok, err) = (self.read_closure(t0), self.read_closure(t1));
+                format!("readResult({view}, {address}, {ok}, {err})")
+            }
+            None => format!("{}.read({view}, {address})", path(self.graph, id)),
+        }
+    }
+
+    /// An expression evaluating to a function that reads a value of a type, given a view and an
+    /// address.
+    fn read_closure(&self, id: TypeId) -> String {
+        match self.graph[id].builtin {
+            Some(Builtin::Primitive(primitive)) => primitive_reader(primitive).to_owned(),
+            Some(Builtin::String) => "readString".to_owned(),
+            None => format!("{}.read", path(self.graph, id)),
+            Some(Builtin::Option(_) | Builtin::Sequence(_) | Builtin::Result(_, _)) =>
+                format!("(view, address) => {}", self.read(id, "view", "address")),
+        }
+    }
+
+    /// An expression applying `visitor` to the objects in a field, if it can contain objects.
+    fn visit_field(&self, data: &FieldData, view: &str, address: &str) -> Option<String> {
+        match data {
+            FieldData::Primitive(_) => None,
+            FieldData::Object { type_, optional: true } => self
+                .visit_closure(*type_)
+                .map(|visit| format!("visitOption({view}, {address}, {visit})")),
+            FieldData::Object { type_, optional: false } => self.visit(*type_, view, address),
+        }
+    }
+
+    /// An expression applying `visitor` to the objects in a value of a type, if it can contain
+    /// objects.
+    fn visit(&self, id: TypeId, view: &str, address: &str) -> Option<String> {
+        match self.graph[id].builtin {
+            Some(Builtin::Primitive(_) | Builtin::String) => None,
+            Some(Builtin::Option(t0)) => self
+                .visit_closure(t0)
+                .map(|visit| format!("visitOption({view}, {address}, {visit})")),
+            Some(Builtin::Sequence(t0)) => self.visit_closure(t0).map(|visit| {
+                let size = self.layout.size(self.graph, t0);
+                format!("visitSequence({view}, {address}, {size}, {visit})")
+            }),
+            Some(Builtin::Result(t0, t1)) => {
+                let (ok, err) = (self.visit_closure(t0), self.visit_closure(t1));
+                if ok.is_none() && err.is_none() {
+                    return None;
+                }
+                let ok = ok.unwrap_or_else(|| "null".to_owned());
+                let err = err.unwrap_or_else(|| "null".to_owned());
+                Some(format!("visitResult({view}, {address}, {ok}, {err})"))
+            }
+            None => Some(format!("visitor({})", self.read(id, view, address))),
+        }
+    }
+
+    /// An expression evaluating to a function that applies `visitor` to the objects in a value of
+    /// a type, given a view and an address; returns `None` if the type cannot contain objects.
+    fn visit_closure(&self, id: TypeId) -> Option<String> {
+        let visit = self.visit(id, "view", "address")?;
+        Some(format!("(view, address) => {visit}"))
+    }
+}
+
+fn primitive_reader(primitive: Primitive) -> &'static str {
+    match primitive {
+        Primitive::Boolean => "readBool",
+        Primitive::Number { signed: false } => "readU32",
+        Primitive::Number { signed: true } => "readI32",
+        Primitive::BigInt { signed: false } => "readU64",
+        Primitive::BigInt { signed: true } => "readI64",
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::meta;
+
+    /// Define a graph equivalent to:
+    /// ```ignore
+    /// enum Tree { Leaf { value: u32 }, Branch { subtrees: Vec<Tree>, label: Option<String> } }
+    /// ```
+    /// where every tree also has a `span: u64` field.
+    fn tree_graph() -> (TypeGraph, BTreeMap<meta::TypeId, TypeId>, [meta::TypeId; 3]) {
+        let mut meta = meta::TypeGraph::new();
+        let mut primitive = |name, primitive| {
+            let name = meta::TypeName::from_pascal_case(name);
+            meta.types.insert(meta::Type::new(name, meta::Data::Primitive(primitive)))
+        };
+        let u32_ = primitive("U32", meta::Primitive::U32);
+        let u64_ = primitive("U64", meta::Primitive::U64);
+        let string = primitive("String", meta::Primitive::String);
+        let label = primitive("OptionString", meta::Primitive::Option(string));
+        let field = |name, type_| meta::Field::named(meta::FieldName::from_snake_case(name), type_);
+        let tree_name = meta::TypeName::from_pascal_case("Tree");
+        let tree_fields = vec![field("span", u64_)];
+        let tree = meta.types.insert(meta::Type::new(tree_name, meta::Data::Struct(tree_fields)));
+        let children_name = meta::TypeName::from_pascal_case("VecTree");
+        let children = meta::Data::Primitive(meta::Primitive::Sequence(tree));
+        let children = meta.types.insert(meta::Type::new(children_name, children));
+        let leaf_name = meta::TypeName::from_pascal_case("Leaf");
+        let leaf_fields = vec![field("value", u32_)];
+        let mut leaf = meta::Type::new(leaf_name, meta::Data::Struct(leaf_fields));
+        leaf.parent = Some(tree);
+        let leaf = meta.types.insert(leaf);
+        let branch_name = meta::TypeName::from_pascal_case("Branch");
+        let branch_fields = vec![field("subtrees", children), field("label", label)];
+        let mut branch = meta::Type::new(branch_name, meta::Data::Struct(branch_fields));
+        branch.parent = Some(tree);
+        let branch = meta.types.insert(branch);
+        meta[tree].abstract_ = true;
+        meta[tree].discriminants = [(0, leaf), (1, branch)].into_iter().collect();
+        let (graph, meta_to_ts) = from_meta(&meta);
+        let graph = transform::optional_to_undefined(graph);
+        (graph, meta_to_ts, [tree, leaf, branch])
+    }
+
+    #[test]
+    fn layout() {
+        let (graph, meta_to_ts, [tree, leaf, branch]) = tree_graph();
+        let (tree, leaf, branch) = (meta_to_ts[&tree], meta_to_ts[&leaf], meta_to_ts[&branch]);
+        let layout = Layout::new(&graph);
+        assert_eq!(layout.size(&graph, tree), POINTER);
+        assert_eq!(layout.size(&graph, leaf), 8 + 4);
+        assert_eq!(layout.size(&graph, branch), 8 + POINTER + 1 + POINTER);
+        let offset = |id: TypeId, name| layout.offset(graph[id].find_field(name).unwrap().id());
+        assert_eq!(offset(tree, "span"), 0);
+        assert_eq!(offset(leaf, "value"), 8);
+        assert_eq!(offset(branch, "subtrees"), 8);
+        assert_eq!(offset(branch, "label"), 8 + POINTER);
+    }
+
+    #[test]
+    fn readers() {
+        let (mut graph, _, _) = tree_graph();
+        derive(&mut graph);
+        let code = to_syntax(&graph, "./support").to_string();
+        let expected = [
+            "export namespace Tree {",
+            "  export abstract class AbstractBase extends LazyObject {",
+            "    get span(): bigint {\n      return readU64(this._v, 0)\n    }",
+            "  export const enum Type {\n    Leaf = 0,\n    Branch = 1,\n  }",
+            "  export class Leaf extends AbstractBase {\n    readonly type: Type.Leaf",
+            "      return new Tree.Leaf(readOffset(view, address))",
+            "    get value(): number {\n      return readU32(this._v, 8)\n    }",
+            "    get subtrees(): IterableIterator<Tree> {\n      \
+             return readSequence(this._v, 8, 4, Tree.read)\n    }",
+            "    get label(): string | undefined {\n      \
+             return readOption(this._v, 12, readString)\n    }",
+            "        !!visitSequence(this._v, 8, 4, (view, address) => \
+             visitor(Tree.read(view, address)))",
+            "  export type Tree = Leaf | Branch",
+            "  export function read(view: DataView, address: number): Tree {",
+            "      case 1:\n        return Tree.Branch.read(data, 4)",
+            "  export function isInstance(obj: unknown): obj is Tree {",
+            "export type Tree = Tree.Tree",
+        ];
+        for expected in expected {
+            assert!(code.contains(expected), "Expected:\n{expected}\nIn:\n{code}");
+        }
+        assert!(!code.contains("static read(view: DataView, address: number): Tree {"));
+    }
+}
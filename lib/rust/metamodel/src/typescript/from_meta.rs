@@ -0,0 +1,168 @@
+//! Translating a data model in the highly-abstracted `meta` representation to a data model in the
+//! `crate::typescript` representation.
+//!
+//! The main differences between the models are:
+//! - Some `meta` types are represented by TypeScript primitives, which fields refer to directly;
+//!   the rest are referred to as [`Type`]s, which may be builtins (e.g. `string`) or classes.
+//! - Field names are converted to camelCase, and renamed if they would conflict with a member the
+//!   generated classes define for other purposes.
+
+use crate::typescript::*;
+
+use crate::meta;
+
+
+
+// ============================
+// === TypeScript from Meta ===
+// ============================
+
+/// Names that cannot be used for fields, because the generated classes define members with these
+/// names (or because the name has a special meaning in a class body).
+const RESERVED: &[&str] = &["constructor", "type", "children", "visitChildren"];
+
+/// Translate a data model in the [`meta`] representation to a data model in the TypeScript
+/// typesystem.
+pub fn from_meta(graph: &meta::TypeGraph) -> (TypeGraph, BTreeMap<meta::TypeId, TypeId>) {
+    let primitives = Default::default();
+    let mut typescript = TypeGraph::default();
+    let mut type_promises: BTreeMap<_, _> =
+        graph.types.keys().map(|id| (id, typescript.types.unbound_key())).collect();
+    let meta_to_ts = type_promises.iter().map(|(key, value)| (*key, value.into())).collect();
+    let mut from_meta = FromMeta { typescript, meta_to_ts, primitives };
+    // Translate primitives first, because we need to know whether a type is primitive when we
+    // reference the type.
+    let mut unbound_ids: Vec<_> = type_promises.keys().copied().collect();
+    for &id_ in &unbound_ids {
+        if let meta::Data::Primitive(ty) = &graph[id_].data {
+            match from_meta.primitive(ty) {
+                Ok(prim) => {
+                    from_meta.primitives.insert(id_, prim);
+                    let ty = Type::primitive(prim);
+                    from_meta.typescript.types.bind(type_promises.remove(&id_).unwrap(), ty);
+                }
+                Err(ty) => {
+                    from_meta.typescript.types.bind(type_promises.remove(&id_).unwrap(), ty);
+                }
+            }
+        }
+    }
+    unbound_ids.clear();
+    unbound_ids.extend(type_promises.keys().copied());
+    // Translate structs.
+    for id_ in unbound_ids {
+        let ty = &graph[id_];
+        let fields_ = match &ty.data {
+            meta::Data::Primitive(_) => continue,
+            meta::Data::Struct(fields_) => fields_,
+        };
+        let ty = from_meta.class(ty, fields_);
+        from_meta.typescript.types.bind(type_promises.remove(&id_).unwrap(), ty);
+    }
+    let FromMeta { typescript, meta_to_ts, .. } = from_meta;
+    (typescript, meta_to_ts)
+}
+
+#[derive(Debug)]
+struct FromMeta {
+    typescript: TypeGraph,
+    meta_to_ts: BTreeMap<meta::TypeId, TypeId>,
+    primitives: BTreeMap<meta::TypeId, Primitive>,
+}
+
+impl FromMeta {
+    /// Translate a primitive in the [`meta`] model to either a TypeScript primitive, or a builtin
+    /// type.
+    fn primitive(&self, ty: &meta::Primitive) -> Result<Primitive, Type> {
+        match ty {
+            meta::Primitive::Bool => Ok(Primitive::Boolean),
+            meta::Primitive::I32 => Ok(Primitive::Number { signed: true }),
+            meta::Primitive::I64 => Ok(Primitive::BigInt { signed: true }),
+            meta::Primitive::U32 => Ok(Primitive::Number { signed: false }),
+            meta::Primitive::U64 => Ok(Primitive::BigInt { signed: false }),
+            meta::Primitive::Char => Ok(Primitive::Number { signed: false }),
+            meta::Primitive::String => Err(Type::string()),
+            meta::Primitive::Option(t0_) => Err(Type::option(self.meta_to_ts[t0_])),
+            meta::Primitive::Sequence(t0_) => Err(Type::sequence(self.meta_to_ts[t0_])),
+            meta::Primitive::Result(t0_, t1_) =>
+                Err(Type::result(self.meta_to_ts[t0_], self.meta_to_ts[t1_])),
+        }
+    }
+
+    /// Translate a type in the [`meta`] model to a TypeScript class.
+    fn class<'f>(
+        &self,
+        ty: &meta::Type,
+        fields_: impl IntoIterator<Item = &'f meta::Field>,
+    ) -> Type {
+        let name = ty.name.to_pascal_case();
+        let abstract_ = ty.abstract_;
+        let parent = ty.parent.as_ref().map(|id| self.meta_to_ts[id]);
+        let fields = fields_
+            .into_iter()
+            .map(|field| {
+                let meta::Field { name, type_, .. } = field;
+                let name = field_name(name);
+                match self.primitives.get(type_) {
+                    Some(primitive) => Field::primitive(name, *primitive),
+                    None => Field::object(name, self.meta_to_ts[type_], false),
+                }
+            })
+            .collect();
+        let discriminants =
+            ty.discriminants.iter().map(|(key, id)| (*key, self.meta_to_ts[id])).collect();
+        Type { name, parent, abstract_, fields, discriminants, ..Default::default() }
+    }
+}
+
+fn field_name(name: &meta::FieldName) -> String {
+    let name = name.to_camel_case().expect("Tuples not supported.");
+    match RESERVED.contains(&name.as_str()) {
+        true => format!("{name}_"),
+        false => name,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_converting_graph() {
+        let mut meta = meta::TypeGraph::new();
+        let u64_name = meta::TypeName::from_pascal_case("U64");
+        let u64_ty = meta::Type::new(u64_name, meta::Data::Primitive(meta::Primitive::U64));
+        let u64_ = meta.types.insert(u64_ty);
+        let inner_field_name = meta::FieldName::from_snake_case("inner_field");
+        let inner_fields = vec![meta::Field::named(inner_field_name, u64_)];
+        let inner_name = meta::TypeName::from_pascal_case("Inner");
+        let inner =
+            meta.types.insert(meta::Type::new(inner_name, meta::Data::Struct(inner_fields)));
+        let option_name = meta::TypeName::from_pascal_case("OptionInner");
+        let option_ty =
+            meta::Type::new(option_name, meta::Data::Primitive(meta::Primitive::Option(inner)));
+        let option = meta.types.insert(option_ty);
+        let outer_field_type_name = meta::FieldName::from_snake_case("type");
+        let outer_name = meta::TypeName::from_pascal_case("Outer");
+        let outer_fields = vec![meta::Field::named(outer_field_type_name, option)];
+        let outer_ty = meta::Type::new(outer_name, meta::Data::Struct(outer_fields));
+        let outer = meta.types.insert(outer_ty);
+        let (typescript, meta_to_ts) = from_meta(&meta);
+        let outer_ = meta_to_ts[&outer];
+        let inner_ = meta_to_ts[&inner];
+        let option_ = meta_to_ts[&option];
+        assert_eq!(typescript[outer_].name, "Outer");
+        assert_eq!(typescript[inner_].name, "Inner");
+        assert_eq!(typescript[inner_].fields[0].name, "innerField");
+        assert_eq!(
+            typescript[inner_].fields[0].data,
+            FieldData::Primitive(Primitive::BigInt { signed: false })
+        );
+        assert_eq!(typescript[outer_].fields[0].name, "type_");
+        assert_eq!(typescript[outer_].fields[0].data, FieldData::Object {
+            type_:    option_,
+            optional: false,
+        });
+        assert_eq!(typescript[option_].as_builtin(), Some(&Builtin::Option(inner_)));
+    }
+}
@@ -0,0 +1,238 @@
+//! Given a [`typescript`] representation of a data model, produce a [`typescript::syntax`] tree
+//! that can be rendered to TypeScript code implementing the data model.
+//!
+//! The generated classes are views of serialized data: every class extends `LazyObject` from the
+//! support module, and is constructed from a `DataView`. The members that read the data are not
+//! produced here; they are derived for a particular serialization format (see [`format`]).
+//!
+//! A type with child types is implemented as a namespace, containing an abstract base class, a
+//! class for each child type, and a union of the child types.
+
+use crate::typescript::*;
+
+
+
+// =========================================
+// === Implementing TypeScript Datatypes ===
+// =========================================
+
+/// Names exported by the support module; each name is paired with a value indicating whether it
+/// is a type.
+const SUPPORT: &[(&str, bool)] = &[
+    ("LazyObject", false),
+    ("ObjectVisitor", true),
+    ("Result", true),
+    ("readBool", false),
+    ("readI32", false),
+    ("readI64", false),
+    ("readOffset", false),
+    ("readOption", false),
+    ("readPointer", false),
+    ("readResult", false),
+    ("readSequence", false),
+    ("readString", false),
+    ("readU32", false),
+    ("readU64", false),
+    ("visitOption", false),
+    ("visitResult", false),
+    ("visitSequence", false),
+];
+
+/// Produce TypeScript syntax implementing all the types modeled in a [`TypeGraph`]. The generated
+/// module imports its runtime support from the module specified by `support`.
+pub fn implement(graph: &TypeGraph, support: &str) -> syntax::Module {
+    let names = SUPPORT.iter().map(|&(name, type_only)| (name.to_owned(), type_only)).collect();
+    let imports = vec![syntax::Import { names, from: support.to_owned() }];
+    let mut items = vec![];
+    for (id, ty) in graph.types.iter() {
+        if ty.builtin.is_some() || ty.parent.is_some() {
+            continue;
+        }
+        match ty.discriminants.is_empty() {
+            true => items.push(syntax::Item::Class(implement_class(graph, id))),
+            false => items.extend(implement_abstract(graph, id)),
+        }
+    }
+    let header = Default::default();
+    syntax::Module { header, imports, items }
+}
+
+/// For some [`Type`] (identified by ID) in a [`TypeGraph`], get its qualified name. If it is not
+/// a child type, this will be the same as its unqualified name; if it is a child type, it will be
+/// qualified by the namespace of its parent.
+///
+/// # Examples
+///
+/// For a child type `Ident` of a type `Tree`, the `path` would be "Tree.Ident".
+pub fn path(graph: &TypeGraph, id: TypeId) -> String {
+    let mut components = vec![];
+    let mut next_id = Some(id);
+    while let Some(id) = next_id {
+        let ty = &graph[id];
+        components.push(ty.name.as_str());
+        next_id = ty.parent;
+    }
+    components.reverse();
+    components.join(".")
+}
+
+/// Given a [`TypeGraph`] and a definition of a field's contents ([`FieldData`]), produce a type
+/// expression for the field's values.
+pub fn quote_type(graph: &TypeGraph, data: &FieldData) -> syntax::Type {
+    match data {
+        FieldData::Object { type_, optional: false } => quote_class_type(graph, *type_),
+        FieldData::Object { type_, optional: true } =>
+            syntax::Type::optional(quote_class_type(graph, *type_)),
+        FieldData::Primitive(primitive) => syntax::Type::named(primitive.name()),
+    }
+}
+
+/// Given a [`TypeGraph`] and an ID identifying a [`Type`], produce a type expression referring to
+/// the type.
+pub fn quote_class_type(graph: &TypeGraph, id: TypeId) -> syntax::Type {
+    let quote = |id| quote_class_type(graph, id);
+    match graph[id].builtin {
+        Some(Builtin::Option(t0)) => syntax::Type::optional(quote(t0)),
+        Some(Builtin::Sequence(t0)) => syntax::Type::generic(&graph[id].name, vec![quote(t0)]),
+        Some(Builtin::Result(t0, t1)) =>
+            syntax::Type::generic(&graph[id].name, vec![quote(t0), quote(t1)]),
+        Some(Builtin::String) | Some(Builtin::Primitive(_)) | None =>
+            syntax::Type::named(path(graph, id)),
+    }
+}
+
+
+// === Helpers ===
+
+/// The argument of the constructor of a generated class.
+const VIEW: &str = "view";
+/// The class that all the generated classes inherit from.
+const BASE: &str = "LazyObject";
+/// The name, within the namespace of a type with child types, of the base class of the children.
+const ABSTRACT_BASE: &str = "AbstractBase";
+/// The name, within the namespace of a type with child types, of the `enum` of child types.
+const TYPE_ENUM: &str = "Type";
+
+/// Produce a constructor that accepts a view of the serialized object, and passes it to the
+/// parent class constructor.
+fn view_constructor(visibility: Option<syntax::Visibility>) -> syntax::Method {
+    let mut constructor = syntax::Method::constructor();
+    constructor.arguments = vec![(VIEW.to_owned(), syntax::Type::named("DataView"))];
+    constructor.visibility = visibility;
+    constructor.body = format!("super({VIEW})");
+    constructor
+}
+
+/// Produce a representation of TypeScript syntax defining a `class` for a [`Type`] without child
+/// types.
+fn implement_class(graph: &TypeGraph, id: TypeId) -> syntax::Class {
+    let ty = &graph[id];
+    let mut members = vec![view_constructor(None).into()];
+    members.extend(ty.members.iter().cloned());
+    let export = true;
+    let abstract_ = ty.abstract_;
+    let name = ty.name.clone();
+    let parent = Some(syntax::Type::named(BASE));
+    syntax::Class { export, abstract_, name, parent, members }
+}
+
+/// Produce a representation of TypeScript syntax defining a [`Type`] that has child types.
+///
+/// The type is defined as a namespace, along with a type alias exporting the union of the child
+/// types under the name of the namespace.
+fn implement_abstract(graph: &TypeGraph, id: TypeId) -> [syntax::Item; 2] {
+    let ty = &graph[id];
+    let name = ty.name.clone();
+    let (statics, members): (Vec<_>, Vec<_>) = ty
+        .members
+        .iter()
+        .cloned()
+        .partition(|member| matches!(member, syntax::Member::Method(method) if method.static_));
+    let mut base_members = vec![view_constructor(Some(syntax::Visibility::Protected)).into()];
+    base_members.extend(members);
+    let base = syntax::Class {
+        export:    true,
+        abstract_: true,
+        name:      ABSTRACT_BASE.to_owned(),
+        parent:    Some(syntax::Type::named(BASE)),
+        members:   base_members,
+    };
+    let mut items = vec![syntax::Item::Class(base)];
+    let children: Vec<_> = ty.discriminants.iter().map(|(&key, &id)| (key, id)).collect();
+    let child_names: Vec<_> = children.iter().map(|&(_, id)| graph[id].name.clone()).collect();
+    items.push(syntax::Item::Enum(syntax::Enum {
+        export:   true,
+        const_:   true,
+        name:     TYPE_ENUM.to_owned(),
+        variants: children.iter().map(|&(key, id)| (graph[id].name.clone(), key)).collect(),
+    }));
+    let quoted_names: Vec<_> = child_names.iter().map(|name| format!("'{name}'")).collect();
+    items.push(syntax::Item::Const(syntax::Const {
+        export: true,
+        name:   "typeNames".to_owned(),
+        value:  format!("[{}] as const", quoted_names.join(", ")),
+    }));
+    for &(_, child) in &children {
+        items.push(syntax::Item::Class(implement_child(graph, child)));
+    }
+    let union = child_names.iter().map(syntax::Type::named).collect();
+    items.push(syntax::Item::TypeAlias(syntax::TypeAlias {
+        export: true,
+        name:   name.clone(),
+        type_:  syntax::Type::Union(union),
+    }));
+    for member in statics {
+        if let syntax::Member::Method(method) = member {
+            let syntax::Method { name, arguments, return_, body, .. } = method;
+            let export = true;
+            items.push(syntax::Item::Function(syntax::Function {
+                export,
+                name,
+                arguments,
+                return_,
+                body,
+            }));
+        }
+    }
+    let mut is_instance = syntax::Function::new("isInstance", syntax::Type::Predicate {
+        argument: "obj".to_owned(),
+        type_:    Box::new(syntax::Type::named(&name)),
+    });
+    is_instance.arguments = vec![("obj".to_owned(), syntax::Type::named("unknown"))];
+    is_instance.body = format!("return obj instanceof {ABSTRACT_BASE}");
+    items.push(syntax::Item::Function(is_instance));
+    let namespace = syntax::Namespace { name: name.clone(), items };
+    let alias = syntax::TypeAlias {
+        export: true,
+        type_: syntax::Type::named(format!("{name}.{name}")),
+        name,
+    };
+    [syntax::Item::Namespace(namespace), syntax::Item::TypeAlias(alias)]
+}
+
+/// Produce a representation of TypeScript syntax defining a `class` for a child type. The class
+/// is defined within the namespace of its parent; it has a `type` property identifying which child
+/// type it is.
+fn implement_child(graph: &TypeGraph, id: TypeId) -> syntax::Class {
+    let ty = &graph[id];
+    assert!(
+        ty.discriminants.is_empty(),
+        "Multi-level type hierarchies have not been implemented: {}",
+        path(graph, id)
+    );
+    let name = ty.name.clone();
+    let type_ = format!("{TYPE_ENUM}.{name}");
+    let type_property = syntax::Property {
+        readonly: true,
+        name:     "type".to_owned(),
+        type_:    syntax::Type::named(&type_),
+    };
+    let mut constructor = view_constructor(None);
+    constructor.body = format!("super({VIEW})\nthis.type = {type_}");
+    let mut members = vec![syntax::Member::Property(type_property), constructor.into()];
+    members.extend(ty.members.iter().cloned());
+    let export = true;
+    let abstract_ = false;
+    let parent = Some(syntax::Type::named(ABSTRACT_BASE));
+    syntax::Class { export, abstract_, name, parent, members }
+}
@@ -0,0 +1,215 @@
+//! Representation of datatype definitions in the TypeScript typesystem.
+
+
+
+mod from_meta;
+mod implementation;
+
+use crate::data_structures::VecMap;
+use derive_more::Index;
+use derive_more::IndexMut;
+use std::collections::BTreeMap;
+
+
+
+// ==============
+// === Export ===
+// ==============
+
+pub mod format;
+pub mod syntax;
+pub mod transform;
+
+pub use from_meta::from_meta;
+pub use implementation::implement as to_syntax;
+
+
+
+// ==============================
+// === Type Parameterizations ===
+// ==============================
+
+/// Globally unique, stable identifier for a `Field`.
+pub type FieldId = crate::data_structures::Id<Field>;
+/// Identifies a TypeScript type within a `TypeGraph`.
+pub type TypeId = crate::data_structures::vecmap::Key<Type>;
+
+
+
+// ======================
+// === Datatype Types ===
+// ======================
+
+/// A TypeScript type: either a class, or a builtin type.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Type {
+    /// The name of the type, not including the namespace of its parent.
+    pub name:      String,
+    /// The parent type, if any. A child type is defined in a namespace named after its parent.
+    pub parent:    Option<TypeId>,
+    /// Whether this type is `abstract`.
+    pub abstract_: bool,
+    /// The data fields.
+    pub fields:    Vec<Field>,
+    /// Members to include in the class definition, in addition to those implied by its fields.
+    ///
+    /// Static members of an abstract type are emitted as functions in the type's namespace.
+    pub members:   Vec<syntax::Member>,
+    builtin:       Option<Builtin>,
+    // Attributes
+    discriminants: BTreeMap<usize, TypeId>,
+}
+
+impl Type {
+    fn builtin(name: &str, builtin: Builtin) -> Self {
+        let name = name.to_owned();
+        let builtin = Some(builtin);
+        Type { name, builtin, ..Default::default() }
+    }
+
+    /// Define a type for an optional value of a type; in TypeScript, such a value is represented
+    /// as a union with `undefined`.
+    pub fn option(param: TypeId) -> Self {
+        Self::builtin("Option", Builtin::Option(param))
+    }
+
+    /// Define a type for a sequence of values of a type.
+    pub fn sequence(param: TypeId) -> Self {
+        Self::builtin("IterableIterator", Builtin::Sequence(param))
+    }
+
+    /// Define a type for a value that is either a success value of one type, or an error value of
+    /// another type.
+    pub fn result(ok: TypeId, err: TypeId) -> Self {
+        Self::builtin("Result", Builtin::Result(ok, err))
+    }
+
+    /// Define a type for TypeScript's `string`.
+    pub fn string() -> Self {
+        Self::builtin("string", Builtin::String)
+    }
+
+    /// Define a type for a TypeScript primitive. Fields refer to primitives directly (see
+    /// [`FieldData::Primitive`]); these types are only needed as parameters of other types.
+    pub fn primitive(primitive: Primitive) -> Self {
+        Self::builtin(primitive.name(), Builtin::Primitive(primitive))
+    }
+
+    /// If this is a builtin type, return its definition.
+    pub fn as_builtin(&self) -> Option<&Builtin> {
+        self.builtin.as_ref()
+    }
+
+    /// The values identifying the concrete child types of this type.
+    pub fn discriminants(&self) -> &BTreeMap<usize, TypeId> {
+        &self.discriminants
+    }
+
+    /// Get a field by name.
+    pub fn find_field(&self, name: &str) -> Option<&Field> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+}
+
+/// A type that is provided by TypeScript, or by the support library of the generated code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Builtin {
+    /// A value that may be `undefined`.
+    Option(TypeId),
+    /// Zero or more values of a type.
+    Sequence(TypeId),
+    /// A value that may be one type in a success case, or another type in a failure case.
+    Result(TypeId, TypeId),
+    /// A string.
+    String,
+    /// A primitive.
+    Primitive(Primitive),
+}
+
+/// A data field of a type.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Field {
+    #[allow(missing_docs)]
+    pub name: String,
+    #[allow(missing_docs)]
+    pub data: FieldData,
+    id:       FieldId,
+}
+
+impl Field {
+    /// Create a field referencing a `Type`.
+    pub fn object(name: impl Into<String>, type_: TypeId, optional: bool) -> Self {
+        let name = name.into();
+        let data = FieldData::Object { type_, optional };
+        let id = Default::default();
+        Self { name, data, id }
+    }
+
+    /// Create a field holding primitive data.
+    pub fn primitive(name: impl Into<String>, primitive: Primitive) -> Self {
+        let name = name.into();
+        let data = FieldData::Primitive(primitive);
+        let id = Default::default();
+        Self { name, data, id }
+    }
+
+    #[allow(missing_docs)]
+    pub fn id(&self) -> FieldId {
+        self.id
+    }
+}
+
+/// A field's data contents.
+#[derive(Debug, Clone, PartialEq, Eq, Copy, PartialOrd, Ord, Hash)]
+pub enum FieldData {
+    /// A value of a `Type`.
+    Object {
+        #[allow(missing_docs)]
+        type_:    TypeId,
+        /// If `true`, the value may be `undefined`.
+        optional: bool,
+    },
+    /// A primitive value.
+    Primitive(Primitive),
+}
+
+/// A TypeScript primitive type.
+#[derive(Debug, Clone, PartialEq, Eq, Copy, PartialOrd, Ord, Hash)]
+pub enum Primitive {
+    /// TypeScript's `boolean`.
+    Boolean,
+    /// TypeScript's `number`, holding a 32-bit integer.
+    Number {
+        /// If `true`, the value is read as a signed integer.
+        signed: bool,
+    },
+    /// TypeScript's `bigint`, holding a 64-bit integer.
+    BigInt {
+        /// If `true`, the value is read as a signed integer.
+        signed: bool,
+    },
+}
+
+impl Primitive {
+    /// The name of the TypeScript type.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Primitive::Boolean => "boolean",
+            Primitive::Number { .. } => "number",
+            Primitive::BigInt { .. } => "bigint",
+        }
+    }
+}
+
+
+
+// ============================
+// === Systems of Datatypes ===
+// ============================
+
+/// A system of TypeScript `Type`s.
+#[derive(Debug, Default, Index, IndexMut)]
+pub struct TypeGraph {
+    #[allow(missing_docs)]
+    pub types: VecMap<Type>,
+}
@@ -0,0 +1,514 @@
+//! TypeScript syntax.
+//!
+//! The rendered code follows the conventions of the TypeScript code in the GUI, as enforced by its
+//! formatter: 2-space indentation, single-quoted strings, and no semicolons.
+
+use std::fmt;
+
+
+
+const INDENT: &str = "  ";
+/// Imports longer than this are rendered with one name per line.
+const LINE_WIDTH: usize = 100;
+
+
+
+// ===================
+// === Syntax Data ===
+// ===================
+
+/// A module.
+#[derive(Debug, Default)]
+pub struct Module {
+    /// Comment lines to render at the beginning of the module.
+    pub header:  Vec<String>,
+    #[allow(missing_docs)]
+    pub imports: Vec<Import>,
+    #[allow(missing_docs)]
+    pub items:   Vec<Item>,
+}
+
+/// An `import` declaration.
+#[derive(Debug)]
+pub struct Import {
+    /// The imported names; each name is paired with a value indicating whether it is a type-only
+    /// import.
+    pub names: Vec<(String, bool)>,
+    /// The module specifier.
+    pub from:  String,
+}
+
+/// A module-level or namespace-level declaration.
+#[derive(Debug)]
+pub enum Item {
+    #[allow(missing_docs)]
+    Class(Class),
+    #[allow(missing_docs)]
+    Namespace(Namespace),
+    #[allow(missing_docs)]
+    Enum(Enum),
+    #[allow(missing_docs)]
+    TypeAlias(TypeAlias),
+    #[allow(missing_docs)]
+    Function(Function),
+    #[allow(missing_docs)]
+    Const(Const),
+}
+
+/// A class definition.
+#[derive(Debug)]
+pub struct Class {
+    #[allow(missing_docs)]
+    pub export:    bool,
+    #[allow(missing_docs)]
+    pub abstract_: bool,
+    #[allow(missing_docs)]
+    pub name:      String,
+    #[allow(missing_docs)]
+    pub parent:    Option<Type>,
+    #[allow(missing_docs)]
+    pub members:   Vec<Member>,
+}
+
+/// A `namespace` declaration. Namespaces are always exported.
+#[derive(Debug)]
+pub struct Namespace {
+    #[allow(missing_docs)]
+    pub name:  String,
+    #[allow(missing_docs)]
+    pub items: Vec<Item>,
+}
+
+/// An `enum` declaration, with explicit values.
+#[derive(Debug)]
+pub struct Enum {
+    #[allow(missing_docs)]
+    pub export:   bool,
+    /// Whether this is a `const enum`, which is inlined at the usage sites.
+    pub const_:   bool,
+    #[allow(missing_docs)]
+    pub name:     String,
+    #[allow(missing_docs)]
+    pub variants: Vec<(String, usize)>,
+}
+
+/// A `type` alias declaration.
+#[derive(Debug)]
+pub struct TypeAlias {
+    #[allow(missing_docs)]
+    pub export: bool,
+    #[allow(missing_docs)]
+    pub name:   String,
+    #[allow(missing_docs)]
+    pub type_:  Type,
+}
+
+/// A function declaration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Function {
+    #[allow(missing_docs)]
+    pub export:    bool,
+    #[allow(missing_docs)]
+    pub name:      String,
+    #[allow(missing_docs)]
+    pub arguments: Vec<(String, Type)>,
+    #[allow(missing_docs)]
+    pub return_:   Option<Type>,
+    /// Literal body, not including brackets.
+    pub body:      String,
+}
+
+/// A `const` variable declaration.
+#[derive(Debug)]
+pub struct Const {
+    #[allow(missing_docs)]
+    pub export: bool,
+    #[allow(missing_docs)]
+    pub name:   String,
+    /// Literal expression.
+    pub value:  String,
+}
+
+/// A member of a class.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Member {
+    #[allow(missing_docs)]
+    Property(Property),
+    #[allow(missing_docs)]
+    Method(Method),
+}
+
+/// A class property declaration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Property {
+    #[allow(missing_docs)]
+    pub readonly: bool,
+    #[allow(missing_docs)]
+    pub name:     String,
+    #[allow(missing_docs)]
+    pub type_:    Type,
+}
+
+/// A method, accessor, or constructor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Method {
+    #[allow(missing_docs)]
+    pub kind:       MethodKind,
+    #[allow(missing_docs)]
+    pub name:       String,
+    #[allow(missing_docs)]
+    pub arguments:  Vec<(String, Type)>,
+    /// Visibility modifier; if None, the TypeScript default is public.
+    pub visibility: Option<Visibility>,
+    /// Return type; if None, it is inferred.
+    pub return_:    Option<Type>,
+    #[allow(missing_docs)]
+    pub static_:    bool,
+    /// Literal body, not including brackets.
+    pub body:       String,
+}
+
+/// Distinguishes the kinds of [`Method`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MethodKind {
+    /// An ordinary method.
+    Method,
+    /// A `get` accessor.
+    Getter,
+    /// A constructor.
+    Constructor,
+}
+
+/// TypeScript visibility modifier keyword for a class member.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Visibility {
+    #[allow(missing_docs)]
+    Private,
+    #[allow(missing_docs)]
+    Protected,
+}
+
+/// A type expression.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Type {
+    /// A type referenced by name, possibly with type arguments.
+    Named {
+        /// The name of the type, which may be qualified by namespaces.
+        name:   String,
+        /// Type arguments.
+        params: Vec<Type>,
+    },
+    /// A union of types.
+    Union(Vec<Type>),
+    /// A type predicate, as used in the return type of a type guard.
+    Predicate {
+        /// The argument the predicate applies to.
+        argument: String,
+        /// The type the argument is asserted to have.
+        type_:    Box<Type>,
+    },
+}
+
+impl Type {
+    /// A simple type.
+    pub fn named(name: impl Into<String>) -> Self {
+        let name = name.into();
+        let params = vec![];
+        Type::Named { name, params }
+    }
+
+    /// A generic type.
+    pub fn generic(name: impl Into<String>, params: Vec<Type>) -> Self {
+        let name = name.into();
+        Type::Named { name, params }
+    }
+
+    /// A union of the given type with `undefined`.
+    pub fn optional(type_: Type) -> Self {
+        Type::Union(vec![type_, Type::named("undefined")])
+    }
+}
+
+
+// === Constructors ===
+
+impl Method {
+    /// Create a method.
+    pub fn new(name: impl Into<String>, return_: Type) -> Self {
+        let name = name.into();
+        let return_ = Some(return_);
+        let kind = MethodKind::Method;
+        let arguments = Default::default();
+        let visibility = Default::default();
+        let static_ = Default::default();
+        let body = Default::default();
+        Method { kind, name, arguments, visibility, return_, static_, body }
+    }
+
+    /// Create a `get` accessor.
+    pub fn getter(name: impl Into<String>, return_: Type) -> Self {
+        Method { kind: MethodKind::Getter, ..Self::new(name, return_) }
+    }
+
+    /// Create a constructor.
+    pub fn constructor() -> Self {
+        let name = "constructor".to_owned();
+        let kind = MethodKind::Constructor;
+        let arguments = Default::default();
+        let visibility = Default::default();
+        let return_ = Default::default();
+        let static_ = Default::default();
+        let body = Default::default();
+        Method { kind, name, arguments, visibility, return_, static_, body }
+    }
+}
+
+impl Function {
+    /// Create an exported function.
+    pub fn new(name: impl Into<String>, return_: Type) -> Self {
+        let export = true;
+        let name = name.into();
+        let arguments = Default::default();
+        let return_ = Some(return_);
+        let body = Default::default();
+        Function { export, name, arguments, return_, body }
+    }
+}
+
+impl From<Method> for Member {
+    fn from(method: Method) -> Self {
+        Member::Method(method)
+    }
+}
+
+
+
+// =========================
+// === Rendering to Text ===
+// =========================
+
+/// Writes lines of code at a current level of indentation.
+struct Printer<'a, 'b> {
+    f:      &'a mut fmt::Formatter<'b>,
+    indent: usize,
+}
+
+impl<'a, 'b> Printer<'a, 'b> {
+    fn new(f: &'a mut fmt::Formatter<'b>) -> Self {
+        Self { f, indent: 0 }
+    }
+
+    fn line(&mut self, line: impl fmt::Display) -> fmt::Result {
+        for _ in 0..self.indent {
+            self.f.write_str(INDENT)?;
+        }
+        writeln!(self.f, "{line}")
+    }
+
+    /// Write each line of some literal code.
+    fn lines(&mut self, code: &str) -> fmt::Result {
+        code.lines().try_for_each(|line| self.line(line))
+    }
+
+    /// Write a bracketed block, with its contents indented.
+    fn block(
+        &mut self,
+        opener: impl fmt::Display,
+        contents: impl FnOnce(&mut Self) -> fmt::Result,
+    ) -> fmt::Result {
+        self.line(format_args!("{opener} {{"))?;
+        self.indent += 1;
+        contents(self)?;
+        self.indent -= 1;
+        self.line("}")
+    }
+}
+
+impl fmt::Display for Module {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Module { header, imports, items } = self;
+        let mut printer = Printer::new(f);
+        for line in header {
+            printer.line(format_args!("// {line}"))?;
+        }
+        for import in imports {
+            import.print(&mut printer)?;
+        }
+        for item in items {
+            item.print(&mut printer)?;
+        }
+        Ok(())
+    }
+}
+
+impl Import {
+    fn print(&self, p: &mut Printer) -> fmt::Result {
+        let Import { names, from } = self;
+        let names: Vec<_> = names
+            .iter()
+            .map(|(name, type_only)| match type_only {
+                true => format!("type {name}"),
+                false => name.clone(),
+            })
+            .collect();
+        let single_line = format!("import {{ {} }} from '{from}'", names.join(", "));
+        if single_line.len() <= LINE_WIDTH {
+            return p.line(single_line);
+        }
+        p.line("import {")?;
+        p.indent += 1;
+        for name in names {
+            p.line(format_args!("{name},"))?;
+        }
+        p.indent -= 1;
+        p.line(format_args!("}} from '{from}'"))
+    }
+}
+
+impl Item {
+    fn print(&self, p: &mut Printer) -> fmt::Result {
+        match self {
+            Item::Class(class) => class.print(p),
+            Item::Namespace(namespace) => namespace.print(p),
+            Item::Enum(enum_) => enum_.print(p),
+            Item::TypeAlias(alias) => alias.print(p),
+            Item::Function(function) => function.print(p),
+            Item::Const(const_) => const_.print(p),
+        }
+    }
+}
+
+fn export(export: bool) -> &'static str {
+    match export {
+        true => "export ",
+        false => "",
+    }
+}
+
+impl Class {
+    fn print(&self, p: &mut Printer) -> fmt::Result {
+        let Class { export: export_, abstract_, name, parent, members } = self;
+        let mut tokens = vec![];
+        abstract_.then(|| tokens.push("abstract".to_string()));
+        tokens.push("class".to_string());
+        tokens.push(name.clone());
+        if let Some(parent) = parent {
+            tokens.push("extends".to_string());
+            tokens.push(parent.to_string());
+        }
+        let opener = format!("{}{}", export(*export_), tokens.join(" "));
+        p.block(opener, |p| members.iter().try_for_each(|member| member.print(p)))
+    }
+}
+
+impl Namespace {
+    fn print(&self, p: &mut Printer) -> fmt::Result {
+        let Namespace { name, items } = self;
+        p.block(format_args!("export namespace {name}"), |p| {
+            items.iter().try_for_each(|item| item.print(p))
+        })
+    }
+}
+
+impl Enum {
+    fn print(&self, p: &mut Printer) -> fmt::Result {
+        let Enum { export: export_, const_, name, variants } = self;
+        let const_ = if *const_ { "const " } else { "" };
+        p.block(format_args!("{}{const_}enum {name}", export(*export_)), |p| {
+            variants.iter().try_for_each(|(name, value)| p.line(format_args!("{name} = {value},")))
+        })
+    }
+}
+
+impl TypeAlias {
+    fn print(&self, p: &mut Printer) -> fmt::Result {
+        let TypeAlias { export: export_, name, type_ } = self;
+        p.line(format_args!("{}type {name} = {type_}", export(*export_)))
+    }
+}
+
+impl Function {
+    fn print(&self, p: &mut Printer) -> fmt::Result {
+        let Function { export: export_, name, arguments, return_, body } = self;
+        let signature = signature(name, arguments, return_);
+        p.block(format_args!("{}function {signature}", export(*export_)), |p| p.lines(body))
+    }
+}
+
+impl Const {
+    fn print(&self, p: &mut Printer) -> fmt::Result {
+        let Const { export: export_, name, value } = self;
+        p.line(format_args!("{}const {name} = {value}", export(*export_)))
+    }
+}
+
+impl Member {
+    fn print(&self, p: &mut Printer) -> fmt::Result {
+        match self {
+            Member::Property(property) => property.print(p),
+            Member::Method(method) => method.print(p),
+        }
+    }
+}
+
+impl Property {
+    fn print(&self, p: &mut Printer) -> fmt::Result {
+        let Property { readonly, name, type_ } = self;
+        let readonly = if *readonly { "readonly " } else { "" };
+        p.line(format_args!("{readonly}{name}: {type_}"))
+    }
+}
+
+impl Method {
+    fn print(&self, p: &mut Printer) -> fmt::Result {
+        let Method { kind, name, arguments, visibility, return_, static_, body } = self;
+        let mut tokens = vec![];
+        if let Some(visibility) = visibility {
+            tokens.push(visibility.to_string());
+        }
+        static_.then(|| tokens.push("static".to_string()));
+        if *kind == MethodKind::Getter {
+            tokens.push("get".to_string());
+        }
+        tokens.push(signature(name, arguments, return_));
+        p.block(tokens.join(" "), |p| p.lines(body))
+    }
+}
+
+fn signature(name: &str, arguments: &[(String, Type)], return_: &Option<Type>) -> String {
+    let arguments: Vec<_> = arguments.iter().map(|(name, ty)| format!("{name}: {ty}")).collect();
+    let arguments = arguments.join(", ");
+    match return_ {
+        Some(return_) => format!("{name}({arguments}): {return_}"),
+        None => format!("{name}({arguments})"),
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Named { name, params } => {
+                write!(f, "{name}")?;
+                if !params.is_empty() {
+                    let params: Vec<_> = params.iter().map(|ty| ty.to_string()).collect();
+                    write!(f, "<{}>", params.join(", "))?;
+                }
+                Ok(())
+            }
+            Type::Union(types) => {
+                let types: Vec<_> = types.iter().map(|ty| ty.to_string()).collect();
+                write!(f, "{}", types.join(" | "))
+            }
+            Type::Predicate { argument, type_ } => write!(f, "{argument} is {type_}"),
+        }
+    }
+}
+
+impl fmt::Display for Visibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Visibility::Private => "private",
+            Visibility::Protected => "protected",
+        })
+    }
+}
@@ -0,0 +1,84 @@
+//! Transformations of TypeScript datamodels.
+
+use crate::typescript::*;
+
+
+
+// =============================
+// === Optional to Undefined ===
+// =============================
+
+/// Rewrite the typegraph so that fields holding an optional value refer to the wrapped type
+/// directly, and are marked as possibly `undefined`.
+///
+/// `Option` types that are not the type of a field (e.g. the elements of a sequence) are retained.
+///
+/// `TypeId` validity:
+/// `TypeId`s that referred to `Option` types: Resolvable only if the `Option` type is still
+/// referenced by another type.
+/// All other `TypeId`s: Unaffected.
+pub fn optional_to_undefined(mut graph: TypeGraph) -> TypeGraph {
+    let mut option_to_type = BTreeMap::new();
+    for (id, ty) in graph.types.iter() {
+        if let Some(Builtin::Option(wrapped)) = ty.builtin {
+            option_to_type.insert(id, wrapped);
+        }
+    }
+    let no_multilevel = "Handling of multi-level optionality has not been implemented.";
+    for ty in option_to_type.values() {
+        assert!(!option_to_type.contains_key(ty), "{}", no_multilevel);
+    }
+    for ty in graph.types.values_mut() {
+        for field in &mut ty.fields {
+            if let FieldData::Object { type_, optional } = &mut field.data {
+                if let Some(mapped) = option_to_type.get(type_) {
+                    assert!(!*optional, "{}", no_multilevel);
+                    *optional = true;
+                    *type_ = *mapped;
+                }
+            }
+        }
+    }
+    let mut referenced = std::collections::BTreeSet::new();
+    for ty in graph.types.values() {
+        match ty.builtin {
+            Some(Builtin::Option(t0) | Builtin::Sequence(t0)) => {
+                referenced.insert(t0);
+            }
+            Some(Builtin::Result(t0, t1)) => {
+                referenced.insert(t0);
+                referenced.insert(t1);
+            }
+            Some(Builtin::String | Builtin::Primitive(_)) | None => {}
+        }
+    }
+    for &id in option_to_type.keys() {
+        if !referenced.contains(&id) {
+            graph.types.remove(id);
+        }
+    }
+    graph
+}
+
+
+
+// =======================
+// === Renaming Fields ===
+// =======================
+
+/// Rename fields of all types in the graph. Each pair in the input specifies a field name, and the
+/// name to give any fields with that name.
+pub fn rename_fields<'a>(
+    mut graph: TypeGraph,
+    renames: impl IntoIterator<Item = (&'a str, &'a str)>,
+) -> TypeGraph {
+    let renames: BTreeMap<_, _> = renames.into_iter().collect();
+    for ty in graph.types.values_mut() {
+        for field in &mut ty.fields {
+            if let Some(name) = renames.get(field.name.as_str()) {
+                field.name = name.to_string();
+            }
+        }
+    }
+    graph
+}
@@ -0,0 +1,17 @@
+//! Check the parser's round-trip invariants for arbitrary input.
+//!
+//! Run with `cargo fuzz run round_trip` from `lib/rust/parser/debug`. Crashing inputs can be
+//! minimized with `cargo fuzz tmin round_trip <artifact>`, and added to the regression cases in
+//! `tests/parse.rs`.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+
+
+fuzz_target!(|code: &str| {
+    if let Err(violation) = enso_parser_debug::fuzz::check(code) {
+        panic!("{violation}");
+    }
+});
@@ -0,0 +1,20 @@
+//! Check the parser's round-trip invariants for Enso-like inputs, generated by
+//! [`enso_parser_debug::fuzz::TokenSoup`] from a fuzzed seed. Compared to the `round_trip` target,
+//! this finds failures that depend on combinations of tokens much faster.
+//!
+//! Run with `cargo fuzz run token_soup` from `lib/rust/parser/debug`.
+
+#![no_main]
+
+use enso_parser_debug::fuzz;
+use libfuzzer_sys::fuzz_target;
+
+
+
+fuzz_target!(|seed: u64| {
+    let code = fuzz::TokenSoup::new(seed).generate();
+    if let Err(violation) = fuzz::check(&code) {
+        let minimized = fuzz::minimize_violation(&code, &violation);
+        panic!("{violation}\n    input: {code:?}\n    minimized: {minimized:?}");
+    }
+});
@@ -1,6 +1,10 @@
 //! Parses Enso sources and reports any syntax errors, while performing internal consistency checks.
-//! Source files may be specified as command line arguments; if none a provided, source code will be
-//! read from standard input.
+//! Source files, or directories containing them, may be specified as command line arguments; if
+//! none are provided, source code will be read from standard input.
+//!
+//! Errors are reported with excerpts of the source code; with `--color`, the reports are styled for
+//! display in a terminal. With `--json`, the errors found in each file are printed instead as a
+//! line of JSON.
 
 // === Non-Standard Linter Configuration ===
 #![allow(clippy::option_map_unit_fn)]
@@ -11,8 +15,6 @@
 #![warn(trivial_casts)]
 #![warn(unused_qualifications)]
 
-use enso_parser::prelude::*;
-
 
 
 // =============
@@ -20,59 +22,35 @@ use enso_parser::prelude::*;
 // =============
 
 fn main() {
-    let args = std::env::args().skip(1);
+    let (flags, paths): (Vec<_>, Vec<_>) =
+        std::env::args().skip(1).partition(|arg| arg.starts_with("--"));
+    let options = Options {
+        json:  flags.iter().any(|flag| flag == "--json"),
+        color: flags.iter().any(|flag| flag == "--color"),
+    };
     let mut parser = enso_parser::Parser::new();
-    if args.len() == 0 {
-        use std::io::Read;
-        let mut input = String::new();
-        std::io::stdin().read_to_string(&mut input).unwrap();
-        check_file("<stdin>", input.as_str(), &mut parser);
-    } else {
-        args.for_each(|path| {
-            check_file(&path, &std::fs::read_to_string(&path).unwrap(), &mut parser)
-        });
+    for source in enso_parser_debug::read_sources(paths).unwrap() {
+        check_file(&source.path.to_string_lossy(), source.code(), &mut parser, &options);
     }
 }
 
-fn check_file(path: &str, mut code: &str, parser: &mut enso_parser::Parser) {
-    if let Some((_meta, code_)) = enso_parser::metadata::parse(code) {
-        code = code_;
-    }
+/// Output settings.
+#[derive(Debug)]
+struct Options {
+    /// Print the diagnostics for each file as a line of JSON, instead of rendering reports.
+    json:  bool,
+    /// Style the rendered reports with ANSI escape codes.
+    color: bool,
+}
+
+fn check_file(path: &str, code: &str, parser: &mut enso_parser::Parser, options: &Options) {
     let ast = parser.run(code);
-    let errors = RefCell::new(vec![]);
-    ast.visit_trees(|tree| {
-        if let enso_parser::syntax::tree::Variant::Invalid(err) = &*tree.variant {
-            let error = format!("{}: {}", err.error.message, tree.code());
-            errors.borrow_mut().push((error, tree.span.clone()));
-        } else if let enso_parser::syntax::tree::Variant::TextLiteral(text) = &*tree.variant {
-            for element in &text.elements {
-                if let enso_parser::syntax::tree::TextElement::Escape { token } = element {
-                    if token.variant.value.is_none() {
-                        let escape = token.code.to_string();
-                        let error = format!("Invalid escape sequence: {escape}");
-                        errors.borrow_mut().push((error, tree.span.clone()));
-                    }
-                }
-            }
-        }
-    });
-    for (error, span) in &*errors.borrow() {
-        let whitespace = &span.left_offset.code.repr;
-        let start = whitespace.as_ptr() as usize + whitespace.len() - code.as_ptr() as usize;
-        let mut line = 1;
-        let mut char = 0;
-        for (i, c) in code.char_indices() {
-            if i >= start {
-                break;
-            }
-            if c == '\n' {
-                line += 1;
-                char = 0;
-            } else {
-                char += 1;
-            }
-        }
-        eprintln!("{path}:{line}:{char}: {}", &error);
+    let diagnostics = enso_parser::diagnostics::collect(&ast);
+    if options.json {
+        println!("{}", enso_parser::diagnostics::to_json(path, &diagnostics));
+    } else if !diagnostics.is_empty() {
+        let renderer = enso_parser::diagnostics::Renderer::new(path, code);
+        eprintln!("{}", renderer.with_color(options.color).render_all(&diagnostics));
     }
     for (parsed, original) in ast.code().lines().zip(code.lines()) {
         assert_eq!(parsed, original, "Bug: dropped tokens, while parsing: {path}");
@@ -0,0 +1,71 @@
+//! Compares two versions of an Enso module, and prints the structural differences between them
+//! (see [`diff`]).
+//!
+//! Usage: `diff [--json] OLD NEW`
+//!
+//! If a file has a metadata section, the IDs it assigns to nodes are used to match the trees of the
+//! two versions. Each change is printed on a line, as `+` (inserted), `-` (deleted), `~` (updated),
+//! or `>` (moved), followed by the location, kind and first line of code of the tree in each
+//! version it occurs in. With `--json`, the changes are written as a JSON array instead.
+
+// === Non-Standard Linter Configuration ===
+#![allow(clippy::option_map_unit_fn)]
+#![allow(clippy::precedence)]
+#![allow(dead_code)]
+#![deny(unconditional_recursion)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(unused_qualifications)]
+
+use enso_parser::metadata;
+use enso_parser::metadata::IdMap;
+use enso_parser_debug::diff;
+use enso_parser_debug::document;
+
+
+
+fn main() {
+    let (flags, args): (Vec<_>, Vec<_>) =
+        std::env::args().skip(1).partition(|arg| arg.starts_with("--"));
+    let mut json = false;
+    for flag in &flags {
+        match flag.as_str() {
+            "--json" => json = true,
+            _ => panic!("Unexpected argument: {flag}"),
+        }
+    }
+    let [old, new] = &args[..] else { panic!("Usage: diff [--json] OLD NEW") };
+    let old = std::fs::read_to_string(old).unwrap();
+    let new = std::fs::read_to_string(new).unwrap();
+    let (old_code, old_ids) = split(&old);
+    let (new_code, new_ids) = split(&new);
+    let parser = enso_parser::Parser::new();
+    let schema = document::Schema::new();
+    let (old_tree, new_tree) = (parser.run(old_code), parser.run(new_code));
+    let old_document = document::Document::new(&schema, &old_tree, old_code);
+    let new_document = document::Document::new(&schema, &new_tree, new_code);
+    let mut old = diff::Version::new(&old_document);
+    let mut new = diff::Version::new(&new_document);
+    if let (Some(old_ids), Some(new_ids)) = (&old_ids, &new_ids) {
+        old = old.with_ids(old_ids);
+        new = new.with_ids(new_ids);
+    }
+    let changes = diff::diff(&schema, old, new);
+    if json {
+        serde_json::to_writer_pretty(std::io::stdout(), &changes).unwrap();
+        println!();
+    } else {
+        for change in &changes {
+            println!("{change}");
+        }
+    }
+}
+
+/// Separate the code of a module from the IDs of its metadata section, if it has a valid one.
+fn split(input: &str) -> (&str, Option<IdMap>) {
+    match metadata::parse_section(input) {
+        Some((Ok(section), code)) => (code, Some(section.id_map)),
+        Some((Err(_), code)) => (code, None),
+        None => (input, None),
+    }
+}
@@ -0,0 +1,74 @@
+//! Formats Enso sources in the canonical layout of [`enso_parser::pretty`].
+//!
+//! Usage: `format [--check] [--write] [FILE...]`
+//!
+//! Source files may be specified as command line arguments; if none are provided, source code will
+//! be read from standard input. By default, the formatted code is printed. With `--write`, each
+//! file is replaced by its formatted code instead; with `--check`, nothing is written, and the
+//! names of the files that are not formatted are printed. Exits with a non-zero status if any file
+//! could not be formatted without changing its meaning, or, with `--check`, if any file is not
+//! formatted.
+
+// === Non-Standard Linter Configuration ===
+#![allow(clippy::option_map_unit_fn)]
+#![allow(clippy::precedence)]
+#![allow(dead_code)]
+#![deny(unconditional_recursion)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(unused_qualifications)]
+
+use enso_parser::pretty;
+
+
+
+fn main() {
+    let (flags, paths): (Vec<_>, Vec<_>) =
+        std::env::args().skip(1).partition(|arg| arg.starts_with("--"));
+    let mut check = false;
+    let mut write = false;
+    for flag in &flags {
+        match flag.as_str() {
+            "--check" => check = true,
+            "--write" => write = true,
+            _ => panic!("Unexpected argument: {flag}"),
+        }
+    }
+    let parser = enso_parser::Parser::new();
+    let mut failed = false;
+    if paths.is_empty() {
+        use std::io::Read;
+        let mut input = String::new();
+        std::io::stdin().read_to_string(&mut input).unwrap();
+        match pretty::format_module(&parser, &input) {
+            Ok(formatted) if check => failed |= formatted != input,
+            Ok(formatted) => print!("{formatted}"),
+            Err(error) => {
+                eprintln!("<stdin>: {error}");
+                failed = true;
+            }
+        }
+    }
+    for path in &paths {
+        let input = std::fs::read_to_string(path).unwrap();
+        match pretty::format_module(&parser, &input) {
+            Ok(formatted) if check =>
+                if formatted != input {
+                    println!("{path}");
+                    failed = true;
+                },
+            Ok(formatted) if write =>
+                if formatted != input {
+                    std::fs::write(path, formatted).unwrap();
+                },
+            Ok(formatted) => print!("{formatted}"),
+            Err(error) => {
+                eprintln!("{path}: {error}");
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
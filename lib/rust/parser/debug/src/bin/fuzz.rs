@@ -0,0 +1,60 @@
+//! Check the parser's round-trip invariants for generated inputs (see [`enso_parser_debug::fuzz`]).
+//!
+//! Usage: `fuzz [--seed=N] [--cases=N] [--update-regressions]`
+//!
+//! Inputs are generated from the seeds `N..N+cases`. Any failures are minimized and reported; with
+//! `--update-regressions`, the minimized inputs are also added to the regression cases in
+//! `tests/parse.rs`. Exits with a non-zero status if any failure is found.
+
+// === Non-Standard Linter Configuration ===
+#![allow(clippy::option_map_unit_fn)]
+#![allow(clippy::precedence)]
+#![allow(dead_code)]
+#![deny(unconditional_recursion)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(unused_qualifications)]
+
+use enso_parser_debug::fuzz;
+
+
+
+/// The file containing the regression cases.
+const PARSE_TESTS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/parse.rs");
+
+fn main() {
+    let mut seed = 0;
+    let mut cases = 10_000;
+    let mut update_regressions = false;
+    for arg in std::env::args().skip(1) {
+        if let Some(value) = arg.strip_prefix("--seed=") {
+            seed = value.parse().expect("Invalid seed.");
+        } else if let Some(value) = arg.strip_prefix("--cases=") {
+            cases = value.parse().expect("Invalid number of cases.");
+        } else if arg == "--update-regressions" {
+            update_regressions = true;
+        } else {
+            panic!("Unexpected argument: {arg}");
+        }
+    }
+    // Violations are reported by `fuzz::run`; don't print every panic encountered while minimizing.
+    std::panic::set_hook(Box::new(|_| {}));
+    let failures = fuzz::run(seed..seed + cases);
+    let _ = std::panic::take_hook();
+    for failure in &failures {
+        println!("{failure}");
+    }
+    if update_regressions {
+        let mut source = std::fs::read_to_string(PARSE_TESTS).unwrap();
+        for failure in &failures {
+            if let Some(updated) = fuzz::insert_regression(&source, &failure.minimized) {
+                source = updated;
+            }
+        }
+        std::fs::write(PARSE_TESTS, source).unwrap();
+    }
+    println!("Checked {cases} inputs; found {} distinct failures.", failures.len());
+    if !failures.is_empty() {
+        std::process::exit(1);
+    }
+}
@@ -14,5 +14,6 @@ fn main() {
         code = code_;
     }
     let ast = enso_parser::Parser::new().run(code);
-    serde_json::to_writer(std::io::stdout(), &ast).unwrap();
+    let json = enso_parser::serialization::tree_to_json(&ast).unwrap();
+    serde_json::to_writer(std::io::stdout(), &json).unwrap();
 }
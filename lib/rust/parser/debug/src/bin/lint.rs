@@ -0,0 +1,117 @@
+//! Checks Enso sources with the lint rules (see [`enso_parser::lint`]), and reports their findings.
+//!
+//! Usage: `lint [--json] [--color] [--fix] [--rules] [--allow=RULE] [--warn=RULE] [--deny=RULE]
+//! [PATH...]`
+//!
+//! Each path may be a source file, or a directory to search for sources; if none are provided,
+//! source code will be read from standard input. The findings are reported with excerpts of the
+//! source code, like syntax errors are by `check_syntax`; with `--json`, the findings in each file
+//! are printed instead as a line of JSON. The level of a rule can be set with `--allow`, `--warn`
+//! and `--deny`; `--rules` lists the rules with their levels. With `--fix`, the suggested fixes are
+//! applied to the files (or, for standard input, the fixed code is printed), and the findings that
+//! remain are reported. Exits with a non-zero status if any finding is at the `deny` level.
+
+// === Non-Standard Linter Configuration ===
+#![allow(clippy::option_map_unit_fn)]
+#![allow(clippy::precedence)]
+#![allow(dead_code)]
+#![deny(unconditional_recursion)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(unused_qualifications)]
+
+use enso_parser::diagnostics::Renderer;
+use enso_parser::lint;
+use enso_parser::lint::Level;
+use enso_parser::lint::Linter;
+use enso_parser::project::SourceFile;
+use enso_parser::syntax::tree::error::Severity;
+
+
+
+fn main() {
+    let (flags, paths): (Vec<_>, Vec<_>) =
+        std::env::args().skip(1).partition(|arg| arg.starts_with("--"));
+    let mut linter = Linter::new();
+    let mut options = Options::default();
+    let mut list_rules = false;
+    for flag in &flags {
+        let level =
+            [("--allow=", Level::Allow), ("--warn=", Level::Warn), ("--deny=", Level::Deny)]
+                .into_iter()
+                .find_map(|(prefix, level)| Some((flag.strip_prefix(prefix)?, level)));
+        if let Some((rule, level)) = level {
+            linter = linter.with_level(rule, level).unwrap_or_else(|| {
+                eprintln!("Unknown rule: {rule}");
+                std::process::exit(2);
+            });
+            continue;
+        }
+        match flag.as_str() {
+            "--json" => options.json = true,
+            "--color" => options.color = true,
+            "--fix" => options.fix = true,
+            "--rules" => list_rules = true,
+            _ => panic!("Unexpected argument: {flag}"),
+        }
+    }
+    if list_rules {
+        for (rule, level) in linter.rules() {
+            let level = format!("{level:?}").to_lowercase();
+            println!("{:<24} {level:<5} {}", rule.name(), rule.description());
+        }
+        return;
+    }
+    let parser = enso_parser::Parser::new();
+    let mut denied = false;
+    for source in enso_parser_debug::read_sources(paths).unwrap() {
+        denied |= lint_file(&parser, &linter, &source, &options);
+    }
+    if denied {
+        std::process::exit(1);
+    }
+}
+
+/// Output settings.
+#[derive(Debug, Default)]
+struct Options {
+    /// Print the findings for each file as a line of JSON, instead of rendering reports.
+    json:  bool,
+    /// Style the rendered reports with ANSI escape codes.
+    color: bool,
+    /// Apply the suggested fixes.
+    fix:   bool,
+}
+
+/// Check the file, and report the findings. Returns whether any finding is an error.
+fn lint_file(
+    parser: &enso_parser::Parser,
+    linter: &Linter,
+    source: &SourceFile,
+    options: &Options,
+) -> bool {
+    let path = source.path.to_string_lossy();
+    let mut code = source.code();
+    let mut lints = linter.check(&parser.run(code));
+    let fixed;
+    if options.fix && lints.iter().any(|lint| lint.fix.is_some()) {
+        fixed = lint::apply_fixes(code, &lints);
+        let contents = fixed.clone() + &source.contents[code.len()..];
+        if source.path.as_os_str() == "<stdin>" {
+            print!("{contents}");
+        } else {
+            std::fs::write(&source.path, contents).unwrap();
+        }
+        code = &fixed;
+        lints = linter.check(&parser.run(code));
+    }
+    if options.json {
+        println!("{}", lint::to_json(&path, &lints));
+    } else if !lints.is_empty() {
+        let renderer = Renderer::new(&path, code).with_color(options.color);
+        let reports: Vec<_> =
+            lints.iter().map(|lint| renderer.render_report(lint.report())).collect();
+        eprintln!("{}", reports.join("\n"));
+    }
+    lints.iter().any(|lint| lint.severity == Severity::Error)
+}
@@ -0,0 +1,55 @@
+//! Prints the outlines of all the modules of an Enso library as JSON (see
+//! [`enso_parser::outline`]).
+//!
+//! Usage: `outline [--threads=N] ROOT`
+//!
+//! The root is the directory of the library, containing its `package.yaml` and `src` directory. The
+//! output is an array with an element for each module, giving its qualified name, its path, and its
+//! outline. The qualified names of modules, and the names in imports and exports starting with
+//! `project`, are resolved using the namespace and name of the library from its `package.yaml`; if
+//! it is absent, module names are relative to the library.
+
+// === Non-Standard Linter Configuration ===
+#![allow(clippy::option_map_unit_fn)]
+#![allow(clippy::precedence)]
+#![allow(dead_code)]
+#![deny(unconditional_recursion)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(unused_qualifications)]
+
+use enso_parser::outline;
+use enso_parser::project;
+
+
+
+fn main() {
+    let mut threads = project::default_threads();
+    let mut root = None;
+    for arg in std::env::args().skip(1) {
+        if let Some(value) = arg.strip_prefix("--threads=") {
+            threads = value.parse().expect("Invalid number of threads.");
+        } else if arg.starts_with("--") {
+            panic!("Unexpected argument: {arg}");
+        } else {
+            root = Some(arg);
+        }
+    }
+    let root = root.expect("Usage: outline [--threads=N] ROOT");
+    let library = project::Library::open(root);
+    let src = library.sources_dir();
+    let sources = project::SourceFile::discover(&src).unwrap();
+    let parser = enso_parser::Parser::new();
+    let batch = project::parse(&parser, &sources, threads);
+    let modules = batch.files.iter().map(|file| {
+        let module = library.module_name(file.path.strip_prefix(&src).unwrap());
+        serde_json::json!({
+            "module": module,
+            "path": file.path,
+            "outline": outline::extract(&file.tree, library.name.as_deref()),
+        })
+    });
+    let modules: Vec<_> = modules.collect();
+    serde_json::to_writer_pretty(std::io::stdout(), &modules).unwrap();
+    println!();
+}
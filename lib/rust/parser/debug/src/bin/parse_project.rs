@@ -0,0 +1,67 @@
+//! Parses all the Enso sources of a project in parallel, and reports the syntax errors found and
+//! timing statistics.
+//!
+//! Usage: `parse_project [--threads=N] [--json] [--color] [--timing] ROOT`
+//!
+//! By default, the sources are distributed among as many threads as the system can run in
+//! parallel. Errors are reported with excerpts of the source code, as by `check_syntax`; with
+//! `--json`, the diagnostics of each file are printed instead as a line of JSON. With `--timing`,
+//! the time spent parsing each file is also printed. Exits with a non-zero status if any errors
+//! were found.
+
+// === Non-Standard Linter Configuration ===
+#![allow(clippy::option_map_unit_fn)]
+#![allow(clippy::precedence)]
+#![allow(dead_code)]
+#![deny(unconditional_recursion)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(unused_qualifications)]
+
+use enso_parser::diagnostics;
+use enso_parser::project;
+
+
+
+fn main() {
+    let mut threads = project::default_threads();
+    let mut json = false;
+    let mut color = false;
+    let mut timing = false;
+    let mut root = None;
+    for arg in std::env::args().skip(1) {
+        if let Some(value) = arg.strip_prefix("--threads=") {
+            threads = value.parse().expect("Invalid number of threads.");
+        } else if arg == "--json" {
+            json = true;
+        } else if arg == "--color" {
+            color = true;
+        } else if arg == "--timing" {
+            timing = true;
+        } else if arg.starts_with("--") {
+            panic!("Unexpected argument: {arg}");
+        } else {
+            root = Some(arg);
+        }
+    }
+    let root = root.expect("Usage: parse_project [--threads=N] [--json] [--color] [--timing] ROOT");
+    let sources = project::SourceFile::discover(root).unwrap();
+    let parser = enso_parser::Parser::new();
+    let batch = project::parse(&parser, &sources, threads);
+    for file in &batch.files {
+        let path = file.path.to_string_lossy();
+        if json {
+            println!("{}", diagnostics::to_json(&path, &file.diagnostics));
+        } else if !file.diagnostics.is_empty() {
+            let renderer = diagnostics::Renderer::new(&path, file.code).with_color(color);
+            eprintln!("{}", renderer.render_all(&file.diagnostics));
+        }
+        if timing {
+            eprintln!("{path}: {:?}", file.duration);
+        }
+    }
+    eprintln!("{}.", batch.statistics);
+    if batch.has_errors() {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,86 @@
+//! Runs a structural query over Enso sources, and prints the matches (see [`query`]).
+//!
+//! Usage: `query [--kinds] QUERY [PATH...]`
+//!
+//! Each path may be a source file, or a directory to search for sources; if none are provided,
+//! source code will be read from standard input. Each match is printed with its location and the
+//! first line of its code, followed by the values of its captures. With `--kinds`, the kinds of
+//! nodes that can be queried are listed with their fields instead. Exits with a non-zero status if
+//! the query is invalid.
+
+// === Non-Standard Linter Configuration ===
+#![allow(clippy::option_map_unit_fn)]
+#![allow(clippy::precedence)]
+#![allow(dead_code)]
+#![deny(unconditional_recursion)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(unused_qualifications)]
+
+use enso_parser::project::SourceFile;
+use enso_parser_debug::document;
+use enso_parser_debug::query;
+
+
+
+fn main() {
+    let (flags, mut args): (Vec<_>, Vec<_>) =
+        std::env::args().skip(1).partition(|arg| arg.starts_with("--"));
+    let mut kinds = false;
+    for flag in &flags {
+        match flag.as_str() {
+            "--kinds" => kinds = true,
+            _ => panic!("Unexpected argument: {flag}"),
+        }
+    }
+    let schema = document::Schema::new();
+    if kinds {
+        for (kind, fields) in schema.kinds() {
+            let fields: Vec<_> = fields.iter().map(|field| format!("{field}:")).collect();
+            println!("({})", [kind].into_iter().chain(fields).collect::<Vec<_>>().join(" "));
+        }
+        return;
+    }
+    if args.is_empty() {
+        panic!("Usage: query [--kinds] QUERY [PATH...]");
+    }
+    let text = args.remove(0);
+    let query = query::Query::new(&schema, &text).unwrap_or_else(|error| {
+        eprintln!("Invalid query: {error}");
+        std::process::exit(1);
+    });
+    let parser = enso_parser::Parser::new();
+    for source in &enso_parser_debug::read_sources(args).unwrap() {
+        run(&parser, &schema, &query, source);
+    }
+}
+
+fn run(
+    parser: &enso_parser::Parser,
+    schema: &document::Schema,
+    q: &query::Query,
+    source: &SourceFile,
+) {
+    let code = source.code();
+    let tree = parser.run(code);
+    let document = document::Document::new(schema, &tree, code);
+    let path = source.path.display();
+    for m in q.matches(&document) {
+        println!("{path}:{}: {}", position(m.node), first_line(&document.text(m.node)));
+        for (name, value) in &m.captures {
+            println!("    @{name} {}: {}", position(value), first_line(&document.text(value)));
+        }
+    }
+}
+
+/// The one-based line and column at which the value starts.
+fn position(value: &document::Value) -> String {
+    match value.range() {
+        Some(range) => format!("{}:{}", range.start.line + 1, range.start.col16 + 1),
+        None => "-".to_owned(),
+    }
+}
+
+fn first_line(code: &str) -> &str {
+    code.lines().next().unwrap_or_default()
+}
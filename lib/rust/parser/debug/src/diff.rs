@@ -0,0 +1,675 @@
+//! Structural differences between syntax trees.
+//!
+//! [`diff`] compares two versions of a module, and describes how the trees of the old version were
+//! changed to produce the new one: which trees were inserted, deleted, updated in place, or moved
+//! to another position. The trees are compared in their generic representation (see
+//! [`crate::document`]), so every kind of tree is supported without specific knowledge of it.
+//!
+//! # Matching
+//!
+//! The comparison begins by pairing each tree of the old version with the tree of the new version
+//! it became, if any, in the style of the GumTree algorithm:
+//! 1. Trees that have the same stable ID in the [`IdMap`]s of the two versions are paired.
+//! 2. Identical subtrees are paired, largest first. Subtrees consisting of a single tree, such as
+//!    identifiers, are left to the last step: an identifier occurring in both versions is not
+//!    necessarily the same occurrence.
+//! 3. Working upwards, a tree is paired with a tree of the same kind that contains the partners of
+//!    its descendants, if the two trees have at least half of their descendants in common.
+//! 4. Within each pair, the remaining children are paired in order: identical subtrees first, then
+//!    trees of the same kind, preferring those with the same content and similar descendants.
+//!
+//! The *content* of a tree is the code of its own tokens, excluding whitespace and the tokens of
+//! its child trees, together with any primitive values among its fields.
+//!
+//! # Changes
+//!
+//! - A tree with no partner in the old version was inserted; one with no partner in the new version
+//!   was deleted. Only the outermost tree of an inserted or deleted subtree is reported.
+//! - A tree whose kind or content differs from its partner's was updated.
+//! - A tree was moved if its parent is not paired with its partner's parent, or if its position
+//!   relative to its siblings has changed.
+
+use crate::document::Document;
+use crate::document::Node;
+use crate::document::Schema;
+use crate::document::Value;
+use enso_parser::metadata::IdMap;
+use enso_parser::metadata::Span;
+use enso_parser::source::code::Location;
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::fmt;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::iter;
+use std::ops::Range;
+use uuid::Uuid;
+
+
+
+// ==============
+// === Change ===
+// ==============
+
+/// A difference between two versions of a module.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "change", rename_all = "lowercase")]
+#[allow(missing_docs)]
+pub enum Change {
+    /// A tree of the new version with no counterpart in the old version.
+    Insert { new: Place },
+    /// A tree of the old version with no counterpart in the new version.
+    Delete { old: Place },
+    /// A tree whose kind or content has changed.
+    Update { old: Place, new: Place },
+    /// A tree that has a different parent, or a different position among its siblings.
+    Move { old: Place, new: Place },
+}
+
+/// A tree in one of the versions being compared.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Place {
+    /// The kind of the tree (see [`Schema::kind_name`]).
+    pub kind:  String,
+    /// The location of the code of the tree.
+    pub range: Range<Location>,
+    /// The code of the tree.
+    pub code:  String,
+    /// The stable ID of the tree, if it has one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id:    Option<Uuid>,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Insert { new } => write!(f, "+ {new}"),
+            Change::Delete { old } => write!(f, "- {old}"),
+            Change::Update { old, new } => write!(f, "~ {old} -> {new}"),
+            Change::Move { old, new } => write!(f, "> {old} -> {new}"),
+        }
+    }
+}
+
+impl fmt::Display for Place {
+    /// Write the one-based line and column of the tree, its kind, and the first line of its code.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Location { line, col16, .. } = self.range.start;
+        let mut lines = self.code.lines();
+        let first = lines.next().unwrap_or_default();
+        let more = if lines.next().is_some() { " ..." } else { "" };
+        write!(f, "{}:{} {} `{first}`{more}", line + 1, col16 + 1, self.kind)
+    }
+}
+
+
+
+// ============
+// === Diff ===
+// ============
+
+/// One of the versions of a module being compared.
+#[derive(Debug, Clone, Copy)]
+pub struct Version<'a, 's> {
+    /// The tree of the module.
+    pub document: &'a Document<'s>,
+    /// The stable IDs of its trees, if available.
+    pub ids:      Option<&'a IdMap>,
+}
+
+impl<'a, 's> Version<'a, 's> {
+    /// Constructor.
+    pub fn new(document: &'a Document<'s>) -> Self {
+        Self { document, ids: None }
+    }
+
+    /// Identify the trees of the version by the IDs of the map, where it provides them.
+    pub fn with_ids(self, ids: &'a IdMap) -> Self {
+        Self { ids: Some(ids), ..self }
+    }
+}
+
+/// Compare two versions of a module.
+///
+/// Insertions, updates, and moves are returned in the order of their trees in the new version;
+/// they are followed by the deletions, in the order of their trees in the old version.
+pub fn diff(schema: &Schema, old: Version, new: Version) -> Vec<Change> {
+    let old = Trees::new(schema, old);
+    let new = Trees::new(schema, new);
+    let mut matching = Matching::new(&old, &new);
+    matching.match_ids();
+    matching.match_identical();
+    matching.match_similar();
+    matching.match_children();
+    matching.changes(schema)
+}
+
+
+// === Trees ===
+
+/// The trees of a version, in preorder.
+struct Trees<'a, 's> {
+    document: &'a Document<'s>,
+    trees:    Vec<Entry<'a>>,
+}
+
+#[derive(Debug)]
+struct Entry<'a> {
+    value:    &'a Value,
+    node:     &'a Node,
+    parent:   Option<usize>,
+    children: Vec<usize>,
+    /// The index following the last descendant of the tree.
+    end:      usize,
+    content:  Vec<String>,
+    /// A hash of the kind and content of the tree.
+    label:    u64,
+    /// A hash of the kinds and contents of the trees of the subtree, and of its shape.
+    hash:     u64,
+    id:       Option<Uuid>,
+}
+
+impl<'a, 's> Trees<'a, 's> {
+    fn new(schema: &Schema, version: Version<'a, 's>) -> Self {
+        let ids: HashMap<Span, Uuid> = version.ids.into_iter().flat_map(IdMap::iter).collect();
+        let mut trees = Self { document: version.document, trees: vec![] };
+        trees.add(schema, version.document.root(), None, &ids);
+        trees
+    }
+
+    fn add(
+        &mut self,
+        schema: &Schema,
+        value: &'a Value,
+        parent: Option<usize>,
+        ids: &HashMap<Span, Uuid>,
+    ) -> usize {
+        let Value::Node(node) = value else { unreachable!("A tree is represented by a node.") };
+        let index = self.trees.len();
+        let mut content = vec![];
+        let mut children = vec![];
+        self.collect(schema, value, true, &mut content, &mut children);
+        let id = node.range.as_ref().and_then(|range| {
+            let start = range.start.utf16 as usize;
+            ids.get(&Span::new(start, range.end.utf16 as usize - start)).copied()
+        });
+        let mut hasher = DefaultHasher::new();
+        (node.type_, &content).hash(&mut hasher);
+        let label = hasher.finish();
+        self.trees.push(Entry {
+            value,
+            node,
+            parent,
+            children: vec![],
+            end: 0,
+            content,
+            label,
+            hash: 0,
+            id,
+        });
+        let children: Vec<_> =
+            children.into_iter().map(|child| self.add(schema, child, Some(index), ids)).collect();
+        let mut hasher = DefaultHasher::new();
+        label.hash(&mut hasher);
+        children.iter().for_each(|child| self.trees[*child].hash.hash(&mut hasher));
+        let end = self.trees.len();
+        let entry = &mut self.trees[index];
+        entry.children = children;
+        entry.end = end;
+        entry.hash = hasher.finish();
+        index
+    }
+
+    /// Find the child trees of a value, and the content it contributes to the tree containing it.
+    fn collect(
+        &self,
+        schema: &Schema,
+        value: &'a Value,
+        is_tree: bool,
+        content: &mut Vec<String>,
+        children: &mut Vec<&'a Value>,
+    ) {
+        match value {
+            Value::Node(node) if !is_tree && schema.is_tree(node.type_) => children.push(value),
+            Value::Node(node) if !is_tree && node.range.is_some() => {
+                let code = self.document.text(value);
+                if !code.trim().is_empty() {
+                    content.push(code.into_owned());
+                }
+            }
+            Value::Node(node) =>
+                for (_, value) in &node.fields {
+                    self.collect(schema, value, false, content, children);
+                },
+            Value::Sequence(values) =>
+                for value in values {
+                    self.collect(schema, value, false, content, children);
+                },
+            Value::None => {}
+            _ => content.push(self.document.text(value).into_owned()),
+        }
+    }
+
+    fn size(&self, index: usize) -> usize {
+        self.trees[index].end - index
+    }
+
+    /// The sorted labels of the trees of a subtree.
+    fn labels(&self, index: usize) -> Vec<u64> {
+        let mut labels: Vec<_> =
+            self.trees[index..self.trees[index].end].iter().map(|entry| entry.label).collect();
+        labels.sort_unstable();
+        labels
+    }
+
+    fn ancestors(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        iter::successors(self.trees[index].parent, |i| self.trees[*i].parent)
+    }
+
+    fn place(&self, schema: &Schema, index: usize) -> Place {
+        let entry = &self.trees[index];
+        Place {
+            kind:  schema.kind_name(entry.node.type_),
+            range: entry.node.range.clone().unwrap(),
+            code:  self.document.text(entry.value).into_owned(),
+            id:    entry.id,
+        }
+    }
+}
+
+
+// === Matching ===
+
+/// The smallest subtree that is paired with an identical subtree regardless of its context.
+const MIN_IDENTICAL_SIZE: usize = 2;
+
+/// The proportion of their descendants two trees must have in common to be paired by similarity.
+const MIN_SIMILARITY: f64 = 0.5;
+
+/// A pairing of trees of the old version with trees of the new version.
+struct Matching<'t, 'a, 's> {
+    old:        &'t Trees<'a, 's>,
+    new:        &'t Trees<'a, 's>,
+    old_to_new: Vec<Option<usize>>,
+    new_to_old: Vec<Option<usize>>,
+}
+
+impl<'t, 'a, 's> Matching<'t, 'a, 's> {
+    fn new(old: &'t Trees<'a, 's>, new: &'t Trees<'a, 's>) -> Self {
+        let old_to_new = vec![None; old.trees.len()];
+        let new_to_old = vec![None; new.trees.len()];
+        Self { old, new, old_to_new, new_to_old }
+    }
+
+    fn pair(&mut self, old: usize, new: usize) {
+        if self.old_to_new[old].is_none() && self.new_to_old[new].is_none() {
+            self.old_to_new[old] = Some(new);
+            self.new_to_old[new] = Some(old);
+        }
+    }
+
+    /// Pair the trees of two identical subtrees.
+    fn pair_subtrees(&mut self, old: usize, new: usize) {
+        for offset in 0..self.old.size(old) {
+            self.pair(old + offset, new + offset);
+        }
+    }
+
+    fn is_identical(&self, old: usize, new: usize) -> bool {
+        let old_size = self.old.size(old);
+        old_size == self.new.size(new)
+            && self.old.trees[old].hash == self.new.trees[new].hash
+            && (0..old_size).all(|offset| {
+                let (old, new) = (&self.old.trees[old + offset], &self.new.trees[new + offset]);
+                old.node.type_ == new.node.type_ && old.content == new.content
+            })
+    }
+
+    /// Pair the trees that have the same IDs. If several trees share an ID, they are paired in
+    /// order.
+    fn match_ids(&mut self) {
+        let mut by_id: HashMap<Uuid, VecDeque<usize>> = HashMap::new();
+        for (new, entry) in self.new.trees.iter().enumerate() {
+            if let Some(id) = entry.id {
+                by_id.entry(id).or_default().push_back(new);
+            }
+        }
+        for (old, entry) in self.old.trees.iter().enumerate() {
+            let partner = entry.id.and_then(|id| by_id.get_mut(&id)?.pop_front());
+            if let Some(new) = partner {
+                self.pair(old, new);
+            }
+        }
+    }
+
+    /// Pair identical subtrees, largest first. When a subtree has several unpaired identical
+    /// counterparts, one whose parent is paired with the subtree's parent is preferred; otherwise,
+    /// the nearest one.
+    fn match_identical(&mut self) {
+        let mut by_hash: HashMap<u64, Vec<usize>> = HashMap::new();
+        for (new, entry) in self.new.trees.iter().enumerate() {
+            if self.new.size(new) >= MIN_IDENTICAL_SIZE {
+                by_hash.entry(entry.hash).or_default().push(new);
+            }
+        }
+        let mut old = 0;
+        while old < self.old.trees.len() {
+            let entry = &self.old.trees[old];
+            if self.old_to_new[old].is_none() && self.old.size(old) >= MIN_IDENTICAL_SIZE {
+                let candidates = by_hash.get(&entry.hash).into_iter().flatten().copied();
+                let candidates = candidates.filter(|new| self.new_to_old[*new].is_none());
+                let candidates = candidates.filter(|new| self.is_identical(old, *new));
+                let parent = entry.parent.and_then(|parent| self.old_to_new[parent]);
+                let distance = |new: &usize| {
+                    let in_context = parent.is_some() && self.new.trees[*new].parent == parent;
+                    (!in_context, new.abs_diff(old))
+                };
+                if let Some(new) = candidates.min_by_key(distance) {
+                    self.pair_subtrees(old, new);
+                    old = entry.end;
+                    continue;
+                }
+            }
+            old += 1;
+        }
+    }
+
+    /// Working upwards, pair each unpaired tree with the tree of the same kind that contains the
+    /// most partners of its descendants, if they are similar enough. The roots are always paired.
+    fn match_similar(&mut self) {
+        for old in (0..self.old.trees.len()).rev() {
+            if self.old_to_new[old].is_some() {
+                continue;
+            }
+            let type_ = self.old.trees[old].node.type_;
+            let mut common: BTreeMap<usize, usize> = BTreeMap::new();
+            for descendant in old + 1..self.old.trees[old].end {
+                let Some(partner) = self.old_to_new[descendant] else { continue };
+                for ancestor in self.new.ancestors(partner) {
+                    let entry = &self.new.trees[ancestor];
+                    if self.new_to_old[ancestor].is_none() && entry.node.type_ == type_ {
+                        *common.entry(ancestor).or_default() += 1;
+                    }
+                }
+            }
+            let old_descendants = self.old.size(old) - 1;
+            let similarity = |(new, common): (&usize, &usize)| {
+                let descendants = old_descendants + self.new.size(*new) - 1;
+                (2 * common) as f64 / descendants as f64
+            };
+            let best = common.iter().map(|pair| (*pair.0, similarity(pair)));
+            let best =
+                best.fold(None, |best: Option<(usize, f64)>, (new, similarity)| match best {
+                    Some((_, best_similarity)) if best_similarity >= similarity => best,
+                    _ => Some((new, similarity)),
+                });
+            if let Some((new, similarity)) = best
+                && similarity >= MIN_SIMILARITY
+            {
+                self.pair(old, new);
+            }
+        }
+        if !self.old.trees.is_empty() && !self.new.trees.is_empty() {
+            self.pair(0, 0);
+        }
+    }
+
+    /// Pair the unpaired children of each pair of trees, preserving their order.
+    fn match_children(&mut self) {
+        for old in 0..self.old.trees.len() {
+            let Some(new) = self.old_to_new[old] else { continue };
+            let old_children = &self.old.trees[old].children;
+            let new_children = &self.new.trees[new].children;
+            let unpaired = |matching: &Self| {
+                let olds = old_children.iter().copied();
+                let olds: Vec<_> = olds.filter(|o| matching.old_to_new[*o].is_none()).collect();
+                let news = new_children.iter().copied();
+                let news: Vec<_> = news.filter(|n| matching.new_to_old[*n].is_none()).collect();
+                (olds, news)
+            };
+            let (olds, news) = unpaired(self);
+            let identical = |o: usize, n: usize| self.is_identical(o, n).then_some(1.0);
+            let pairs = align(&olds, &news, identical);
+            pairs.into_iter().for_each(|(o, n)| self.pair_subtrees(o, n));
+            let (olds, news) = unpaired(self);
+            let old_labels: HashMap<_, _> =
+                olds.iter().map(|o| (*o, self.old.labels(*o))).collect();
+            let new_labels: HashMap<_, _> =
+                news.iter().map(|n| (*n, self.new.labels(*n))).collect();
+            let (old_trees, new_trees) = (&self.old.trees, &self.new.trees);
+            let similar = |o: usize, n: usize| {
+                let (old, new) = (&old_trees[o], &new_trees[n]);
+                let same_content = if old.content == new.content { 1.0 } else { 0.0 };
+                let similarity = similarity(&old_labels[&o], &new_labels[&n]);
+                (old.node.type_ == new.node.type_).then_some(1.0 + same_content + similarity)
+            };
+            align(&olds, &news, similar).into_iter().for_each(|(o, n)| self.pair(o, n));
+        }
+    }
+
+    /// Determine which paired trees of the new version have been moved.
+    fn moved(&self) -> Vec<bool> {
+        let mut moved = vec![false; self.new.trees.len()];
+        for (new, entry) in self.new.trees.iter().enumerate() {
+            let Some(old) = self.new_to_old[new] else { continue };
+            let old_parent = self.old.trees[old].parent;
+            moved[new] |= old_parent.and_then(|parent| self.old_to_new[parent]) != entry.parent;
+            let olds = self.old.trees[old].children.iter().filter_map(|o| self.old_to_new[*o]);
+            let olds: Vec<_> = olds.filter(|n| self.new.trees[*n].parent == Some(new)).collect();
+            let news = entry.children.iter().copied();
+            let news: Vec<_> = news.filter(|n| !olds.is_empty() && olds.contains(n)).collect();
+            let in_order = align(&olds, &news, |o, n| (o == n).then_some(1.0));
+            for child in news {
+                if !in_order.iter().any(|(_, n)| *n == child) {
+                    moved[child] = true;
+                }
+            }
+        }
+        moved
+    }
+
+    fn changes(&self, schema: &Schema) -> Vec<Change> {
+        let moved = self.moved();
+        let mut changes = vec![];
+        for (index, entry) in self.new.trees.iter().enumerate() {
+            let new = || self.new.place(schema, index);
+            match self.new_to_old[index] {
+                None => {
+                    let parent_paired = entry.parent.map_or(true, |p| self.new_to_old[p].is_some());
+                    if parent_paired {
+                        changes.push(Change::Insert { new: new() });
+                    }
+                }
+                Some(old_index) => {
+                    let old = || self.old.place(schema, old_index);
+                    let old_entry = &self.old.trees[old_index];
+                    if old_entry.node.type_ != entry.node.type_
+                        || old_entry.content != entry.content
+                    {
+                        changes.push(Change::Update { old: old(), new: new() });
+                    }
+                    if moved[index] {
+                        changes.push(Change::Move { old: old(), new: new() });
+                    }
+                }
+            }
+        }
+        for (index, entry) in self.old.trees.iter().enumerate() {
+            let parent_paired = entry.parent.map_or(true, |p| self.old_to_new[p].is_some());
+            if self.old_to_new[index].is_none() && parent_paired {
+                changes.push(Change::Delete { old: self.old.place(schema, index) });
+            }
+        }
+        changes
+    }
+}
+
+/// Align two sequences: find the pairs of their elements, in order, that have the greatest total
+/// score. Elements for which the score function returns [`None`] cannot be paired.
+fn align(
+    a: &[usize],
+    b: &[usize],
+    score: impl Fn(usize, usize) -> Option<f64>,
+) -> Vec<(usize, usize)> {
+    let width = b.len() + 1;
+    let scores: Vec<_> = a.iter().flat_map(|x| b.iter().map(|y| score(*x, *y))).collect();
+    let score = |i: usize, j: usize| scores[i * b.len() + j];
+    let mut totals = vec![0.0f64; (a.len() + 1) * width];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            let skip = totals[(i + 1) * width + j].max(totals[i * width + j + 1]);
+            let take = score(i, j).map(|score| score + totals[(i + 1) * width + j + 1]);
+            totals[i * width + j] = take.map_or(skip, |take| take.max(skip));
+        }
+    }
+    let mut pairs = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        let take = score(i, j).map(|score| score + totals[(i + 1) * width + j + 1]);
+        if take == Some(totals[i * width + j]) {
+            pairs.push((a[i], b[j]));
+            i += 1;
+            j += 1;
+        } else if totals[(i + 1) * width + j] >= totals[i * width + j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+/// The proportion of the elements of two sorted multisets that they have in common.
+fn similarity(a: &[u64], b: &[u64]) -> f64 {
+    let (mut i, mut j, mut common) = (0, 0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            Ordering::Less => i += 1,
+            Ordering::Greater => j += 1,
+            Ordering::Equal => {
+                common += 1;
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    (2 * common) as f64 / (a.len() + b.len()) as f64
+}
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Compare the two versions of the code, and describe the changes.
+    fn run(old: &str, new: &str, ids: Option<(&IdMap, &IdMap)>) -> Vec<String> {
+        let schema = Schema::new();
+        let parser = enso_parser::Parser::new();
+        let (old_tree, new_tree) = (parser.run(old), parser.run(new));
+        let old_document = Document::new(&schema, &old_tree, old);
+        let new_document = Document::new(&schema, &new_tree, new);
+        let (mut old, mut new) = (Version::new(&old_document), Version::new(&new_document));
+        if let Some((old_ids, new_ids)) = ids {
+            (old, new) = (old.with_ids(old_ids), new.with_ids(new_ids));
+        }
+        diff(&schema, old, new).iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn unchanged() {
+        let code = "import Standard.Base\n\nmain =\n    x = foo 1 2\n    x + 1\n";
+        assert_eq!(run(code, code, None), Vec::<String>::new());
+    }
+
+    #[test]
+    fn update() {
+        let changes = run("main = foo 1 x", "main = foo 2 x", None);
+        assert_eq!(changes, ["~ 1:12 Number `1` -> 1:12 Number `2`"]);
+        let changes = run("main = a + b", "main = a * b", None);
+        assert_eq!(changes, ["~ 1:8 OprApp `a + b` -> 1:8 OprApp `a * b`"]);
+    }
+
+    #[test]
+    fn insert_and_delete() {
+        let old = "main =\n    x = 1\n    x + 1\n";
+        let new = "main =\n    x = 1\n    y = x * 2\n    x + 1\n";
+        assert_eq!(run(old, new, None), ["+ 3:5 Assignment `y = x * 2`"]);
+        assert_eq!(run(new, old, None), ["- 3:5 Assignment `y = x * 2`"]);
+        // The application of `foo` to `x` becomes the function of the new application.
+        let changes = run("main = foo x", "main = foo x y", None);
+        assert_eq!(changes, ["+ 1:8 App `foo x y`", "> 1:8 App `foo x` -> 1:8 App `foo x`"]);
+    }
+
+    #[test]
+    fn update_in_place() {
+        let old = "main =\n    x = 1\n    y = 2\n";
+        let new = "main =\n    x = 1\n    z = 3\n";
+        let changes = run(old, new, None);
+        assert_eq!(changes, [
+            "~ 3:5 Ident `y` -> 3:5 Ident `z`",
+            "~ 3:9 Number `2` -> 3:9 Number `3`"
+        ]);
+    }
+
+    #[test]
+    fn similar_trees() {
+        let old = "main =\n    x = foo 1\n    x + 2\n";
+        let new = "main =\n    y = 5\n    x = foo 3\n    2 + x\n";
+        assert_eq!(run(old, new, None), [
+            "+ 2:5 Assignment `y = 5`",
+            "~ 2:13 Number `1` -> 3:13 Number `3`",
+            "> 3:5 Ident `x` -> 4:9 Ident `x`"
+        ]);
+    }
+
+    #[test]
+    fn moves() {
+        let old = "foo = 1 + 2\nbar = 3 + 4\n";
+        let new = "bar = 3 + 4\nfoo = 1 + 2\n";
+        let changes = run(old, new, None);
+        assert_eq!(changes.len(), 1);
+        assert!(changes[0].starts_with("> "), "{changes:?}");
+        let changes = run("main = foo a b", "main = bar (foo a b)", None);
+        assert_eq!(changes, [
+            "+ 1:8 App `bar (foo a b)`",
+            "> 1:8 App `foo a b` -> 1:13 App `foo a b`"
+        ]);
+    }
+
+    #[test]
+    fn stable_ids() {
+        let (old, new) = ("main = foo", "main = 42");
+        assert_eq!(run(old, new, None), ["+ 1:8 Number `42`", "- 1:8 Ident `foo`"]);
+        let id = Uuid::from_u128(1);
+        let mut old_ids = IdMap::default();
+        old_ids.insert(Span::new(7, 3), id);
+        let mut new_ids = IdMap::default();
+        new_ids.insert(Span::new(7, 2), id);
+        let changes = run(old, new, Some((&old_ids, &new_ids)));
+        assert_eq!(changes, ["~ 1:8 Ident `foo` -> 1:8 Number `42`"]);
+    }
+
+    #[test]
+    fn json() {
+        let schema = Schema::new();
+        let parser = enso_parser::Parser::new();
+        let (old, new) = ("main = 1", "main = 2");
+        let (old_tree, new_tree) = (parser.run(old), parser.run(new));
+        let old = Document::new(&schema, &old_tree, old);
+        let new = Document::new(&schema, &new_tree, new);
+        let changes = diff(&schema, Version::new(&old), Version::new(&new));
+        let json = serde_json::to_value(changes).unwrap();
+        assert_eq!(json[0]["change"], "update");
+        assert_eq!(json[0]["old"]["kind"], "Number");
+        assert_eq!(json[0]["new"]["code"], "2");
+        assert_eq!(json[0]["new"]["range"]["start"]["col16"], 7);
+    }
+}
@@ -0,0 +1,479 @@
+//! A generic representation of syntax trees, derived from the reflection metadata of [`Tree`].
+//!
+//! A [`Document`] represents a tree as a hierarchy of [`Node`]s, each with the name of its type and
+//! its named fields, as described by a [`Schema`]. Tools that inspect trees in terms of this
+//! representation, such as [queries](crate::query) and [diffs](crate::diff), need no changes when
+//! the definition of the tree evolves.
+//!
+//! A document is built by following the [`Reflect`] definitions of the Rust types through the
+//! serde representation of the tree, in which values are identified by the names of their fields
+//! and variants. The locations of trees and tokens are obtained from the tree with an
+//! [`ItemVisitor`], rather than from the fields representing them.
+
+use enso_metamodel::meta;
+use enso_metamodel::meta::TypeGraph;
+use enso_metamodel::meta::TypeId;
+use enso_metamodel::rust;
+use enso_metamodel::rust::ReferencedTypes;
+use enso_parser::source::code::Location;
+use enso_parser::syntax::item;
+use enso_parser::syntax::tree::ItemVisitable;
+use enso_parser::syntax::tree::ItemVisitor;
+use enso_parser::syntax::tree::Visitor;
+use enso_parser::syntax::Tree;
+use enso_reflect::Reflect;
+use serde_json::Value as Json;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::iter;
+use std::ops::Range;
+use std::rc::Rc;
+
+
+
+// ==============
+// === Schema ===
+// ==============
+
+/// The kinds of nodes, and their fields, derived from the reflection metadata of [`Tree`].
+#[derive(Debug)]
+pub struct Schema {
+    graph:        TypeGraph,
+    tree:         TypeId,
+    kinds:        BTreeMap<String, Vec<TypeId>>,
+    /// The queryable fields defined by each kind, excluding those of its ancestors.
+    own_fields:   HashMap<TypeId, Vec<Rc<str>>>,
+    /// The Rust definitions of the types reachable from [`Tree`].
+    types:        BTreeMap<rust::TypeId, rust::TypeData>,
+    rust_to_meta: BTreeMap<rust::TypeId, TypeId>,
+    rust_tree:    rust::TypeId,
+}
+
+impl Schema {
+    /// Create the schema of the current definition of [`Tree`].
+    pub fn new() -> Self {
+        let rust_tree = Tree::reflect().id;
+        let types = rust_types(Tree::reflect());
+        let (graph, rust_to_meta) = rust::to_meta(Tree::reflect());
+        let tree = rust_to_meta[&rust_tree];
+        let mut kinds: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for (id, ty) in graph.types.iter() {
+            if ty.data.fields().is_none() {
+                continue;
+            }
+            let name = ty.name.to_pascal_case();
+            if let Some(parent) = ty.parent {
+                let parent = graph[parent].name.to_pascal_case();
+                kinds.entry(format!("{parent}.{name}")).or_default().push(id);
+            }
+            kinds.entry(name).or_default().push(id);
+        }
+        let mut own_fields: HashMap<_, Vec<_>> = HashMap::new();
+        for ty in types.values() {
+            match &ty.data {
+                rust::Data::Struct(rust::Struct { fields, transparent: false }) => {
+                    let id = rust_to_meta[&ty.id];
+                    own_fields.entry(id).or_default().extend(field_names(fields));
+                }
+                rust::Data::Enum(rust::Enum { variants }) => {
+                    let variants = variants.iter().enumerate();
+                    for (index, variant) in variants.filter(|(_, variant)| !variant.inline) {
+                        let id = graph[rust_to_meta[&ty.id]].discriminants[&index];
+                        own_fields.entry(id).or_default().extend(field_names(&variant.fields));
+                    }
+                }
+                _ => {}
+            }
+        }
+        Self { graph, tree, kinds, own_fields, types, rust_to_meta, rust_tree }
+    }
+
+    /// Return the type a kind refers to.
+    pub(crate) fn kind(&self, name: &str) -> Result<TypeId, String> {
+        let ids = self.kinds.get(name).ok_or_else(|| format!("Unknown kind: `{name}`."))?;
+        match ids[..] {
+            [id] => Ok(id),
+            _ => {
+                let variant = ids.iter().find(|id| self.graph[*id].parent == Some(self.tree));
+                let message = || {
+                    format!(
+                        "Ambiguous kind: `{name}`. Qualify it with the name of its parent type."
+                    )
+                };
+                variant.copied().ok_or_else(message)
+            }
+        }
+    }
+
+    /// The name of the kind, qualified if the unqualified name refers to another type.
+    pub fn kind_name(&self, id: TypeId) -> String {
+        let ty = &self.graph[id];
+        let name = ty.name.to_pascal_case();
+        match ty.parent {
+            Some(parent) if self.kind(&name) != Ok(id) =>
+                format!("{}.{name}", self.graph[parent].name.to_pascal_case()),
+            _ => name,
+        }
+    }
+
+    pub(crate) fn is_a(&self, id: TypeId, kind: TypeId) -> bool {
+        self.graph.hierarchy(id).contains(&kind)
+    }
+
+    /// Whether the type is a variant of [`Tree`].
+    pub fn is_tree(&self, id: TypeId) -> bool {
+        self.graph[id].parent == Some(self.tree)
+    }
+
+    /// The names of the fields of the type that can be queried: its own, and those of its
+    /// ancestors, except for the internal details of their representation.
+    pub(crate) fn fields(&self, id: TypeId) -> Vec<String> {
+        let hierarchy = self.graph.hierarchy(id);
+        let fields = hierarchy.iter().rev().flat_map(|id| self.own_fields.get(id));
+        fields.flatten().map(|field| field.to_string()).collect()
+    }
+
+    /// The names of the fields of the type, or of any of its subtypes.
+    pub(crate) fn fields_of_subtypes(&self, id: Option<TypeId>) -> BTreeSet<String> {
+        let types = self.graph.types.iter().map(|(id, _)| id);
+        let subtypes = types.filter(|subtype| id.map_or(true, |id| self.is_a(*subtype, id)));
+        subtypes.flat_map(|id| self.fields(id)).collect()
+    }
+
+    /// Describe the kinds that can be queried, with their fields.
+    pub fn kinds(&self) -> Vec<(String, Vec<String>)> {
+        let types = self.graph.types.iter().filter(|(_, ty)| ty.data.fields().is_some());
+        let mut kinds: Vec<_> =
+            types.map(|(id, _)| (self.kind_name(id), self.fields(id))).collect();
+        kinds.sort();
+        kinds
+    }
+}
+
+impl Default for Schema {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Collect the definitions of the type and of the types it refers to.
+fn rust_types(root: rust::TypeData) -> BTreeMap<rust::TypeId, rust::TypeData> {
+    let mut types = BTreeMap::new();
+    let mut to_visit = vec![root];
+    while let Some(ty) = to_visit.pop() {
+        let referenced = ty.referenced_types().into_iter();
+        let new = referenced.filter(|lazy| !types.contains_key(&lazy.id));
+        to_visit.extend(new.map(|lazy| lazy.evaluate()));
+        types.insert(ty.id, ty);
+    }
+    types
+}
+
+/// The names of the fields that can be queried. Hidden and flattened fields hold the internal
+/// details of the representation of the code, such as its location.
+fn field_names(fields: &rust::Fields) -> Vec<Rc<str>> {
+    let rust::Fields::Named(fields) = fields else { return vec![] };
+    let fields = fields.iter().filter(|field| !(field.hide || field.flatten || field.subtype));
+    fields.filter_map(field_name).collect()
+}
+
+/// The name of the field in the serde representation, which omits the prefix of a raw identifier.
+fn serde_name(field: &rust::NamedField) -> &str {
+    field.name.trim_start_matches("r#")
+}
+
+fn field_name(field: &rust::NamedField) -> Option<Rc<str>> {
+    let name = field.rename.as_deref().unwrap_or(serde_name(field));
+    meta::FieldName::from_snake_case(name).to_snake_case().map(Rc::from)
+}
+
+
+
+// ================
+// === Document ===
+// ================
+
+/// A syntax tree, in the generic representation.
+#[derive(Debug)]
+pub struct Document<'s> {
+    code:  &'s str,
+    root:  Value,
+    nodes: Vec<NodeInfo>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct NodeInfo {
+    type_:  TypeId,
+    parent: Option<usize>,
+}
+
+/// A value in a [`Document`].
+#[derive(Debug)]
+#[allow(missing_docs)]
+pub enum Value {
+    Node(Node),
+    Sequence(Vec<Value>),
+    /// An absent optional value.
+    None,
+    Bool(bool),
+    Number(i64),
+    String(String),
+}
+
+/// An instance of a type with fields.
+#[derive(Debug)]
+pub struct Node {
+    index:      usize,
+    /// The type of the node.
+    pub type_:  TypeId,
+    /// The fields of the node that can be queried, by name.
+    pub fields: Vec<(Rc<str>, Value)>,
+    /// The source code of the node, if it is a tree or a token.
+    pub range:  Option<Range<Location>>,
+}
+
+impl Node {
+    pub(crate) fn field(&self, name: &str) -> Option<&Value> {
+        self.fields.iter().find(|(field, _)| &**field == name).map(|(_, value)| value)
+    }
+}
+
+impl Value {
+    /// Return the range of the source code of the value, if it contains any trees or tokens.
+    pub fn range(&self) -> Option<Range<Location>> {
+        let children = match self {
+            Value::Node(Node { range: Some(range), .. }) => return Some(range.clone()),
+            Value::Node(node) => node.fields.iter().map(|(_, value)| value).collect(),
+            Value::Sequence(values) => values.iter().collect(),
+            _ => vec![],
+        };
+        let mut ranges = children.into_iter().filter_map(Value::range);
+        let first = ranges.next()?;
+        let end = ranges.last().map_or(first.end, |last| last.end);
+        Some(first.start..end)
+    }
+
+    /// Call the function for each node within the value, including the value itself.
+    pub(crate) fn for_each_node<'a>(&'a self, f: &mut impl FnMut(&'a Value, &'a Node)) {
+        match self {
+            Value::Node(node) => {
+                f(self, node);
+                node.fields.iter().for_each(|(_, value)| value.for_each_node(f));
+            }
+            Value::Sequence(values) => values.iter().for_each(|value| value.for_each_node(f)),
+            _ => {}
+        }
+    }
+
+    pub(crate) fn is_absent(&self) -> bool {
+        match self {
+            Value::None => true,
+            Value::Sequence(values) => values.is_empty(),
+            _ => false,
+        }
+    }
+}
+
+impl<'s> Document<'s> {
+    /// Convert the tree to the generic representation. The tree must have been parsed from
+    /// the given code.
+    pub fn new(schema: &Schema, tree: &Tree, code: &'s str) -> Self {
+        let json = serde_json::to_value(tree).expect("A tree can be represented as JSON.");
+        let mut ranges = Ranges::default();
+        tree.visit_item(&mut ranges);
+        let mut builder = Builder { schema, ranges: ranges.0.into_iter(), nodes: vec![] };
+        let root = builder.value(&json, schema.rust_tree, None);
+        assert!(builder.ranges.next().is_none(), "Every tree and token is part of the document.");
+        Self { code, root, nodes: builder.nodes }
+    }
+
+    /// The root of the tree.
+    pub fn root(&self) -> &Value {
+        &self.root
+    }
+
+    /// The source code of the value, or the text of a primitive value.
+    pub fn text(&self, value: &Value) -> Cow<'s, str> {
+        match value {
+            Value::Bool(value) => value.to_string().into(),
+            Value::Number(value) => value.to_string().into(),
+            Value::String(value) => value.clone().into(),
+            _ => value.range().map_or("".into(), |range| {
+                self.code[range.start.utf8 as usize..range.end.utf8 as usize].into()
+            }),
+        }
+    }
+
+    pub(crate) fn ancestors(&self, node: &Node) -> impl Iterator<Item = TypeId> + '_ {
+        let parents = iter::successors(self.nodes[node.index].parent, |i| self.nodes[*i].parent);
+        parents.map(|i| self.nodes[i].type_)
+    }
+}
+
+
+// === Building ===
+
+/// The locations of the trees and tokens of a tree, in the order they are visited.
+#[derive(Debug, Default)]
+struct Ranges(Vec<Range<Location>>);
+
+impl Visitor for Ranges {}
+impl<'s, 'a> ItemVisitor<'s, 'a> for Ranges {
+    fn visit_item(&mut self, item: item::Ref<'s, 'a>) -> bool {
+        self.0.push(match item {
+            item::Ref::Tree(tree) => tree.span.range(),
+            item::Ref::Token(token) => token.code.range(),
+        });
+        true
+    }
+}
+
+/// Reads the serde representation of a tree, using the Rust definitions of its types to interpret
+/// it. The fields of the values are visited in the same order as the trees and tokens are visited
+/// by [`Ranges`], so each tree or token takes the next of the ranges.
+struct Builder<'a> {
+    schema: &'a Schema,
+    ranges: std::vec::IntoIter<Range<Location>>,
+    nodes:  Vec<NodeInfo>,
+}
+
+impl<'a> Builder<'a> {
+    fn value(&mut self, json: &Json, id: rust::TypeId, parent: Option<usize>) -> Value {
+        match &self.schema.types[&id].data {
+            rust::Data::Primitive(primitive) => self.primitive(json, primitive, parent),
+            rust::Data::Struct(rust::Struct { fields, transparent: true }) => {
+                let json = match fields {
+                    rust::Fields::Named(fields) => &json[serde_name(&fields[0])],
+                    _ => json,
+                };
+                self.value(json, fields.as_wrapped_type().unwrap().id, parent)
+            }
+            _ => Value::Node(self.node(json, id, parent)),
+        }
+    }
+
+    fn node(&mut self, json: &Json, id: rust::TypeId, parent: Option<usize>) -> Node {
+        let schema = self.schema;
+        let index = self.nodes.len();
+        self.nodes.push(NodeInfo { type_: schema.rust_to_meta[&id], parent });
+        let mut fields = vec![];
+        let mut range = None;
+        match &schema.types[&id].data {
+            rust::Data::Struct(rust::Struct { fields: rust_fields, .. }) => {
+                // Only trees and tokens have subtypes.
+                if let rust::Fields::Named(named) = rust_fields
+                    && named.iter().any(|field| field.subtype)
+                {
+                    range = self.ranges.next();
+                }
+                self.fields(json, rust_fields, index, &mut fields);
+            }
+            _ => self.subtype(json, id, index, &mut fields),
+        }
+        Node { index, type_: self.nodes[index].type_, fields, range }
+    }
+
+    /// Add the fields of a struct or variant to the node.
+    fn fields(
+        &mut self,
+        json: &Json,
+        fields: &rust::Fields,
+        index: usize,
+        out: &mut Vec<(Rc<str>, Value)>,
+    ) {
+        match fields {
+            rust::Fields::Named(fields) =>
+                for field in fields {
+                    let json = &json[serde_name(field)];
+                    if field.subtype {
+                        self.subtype(json, field.type_.id, index, out);
+                    } else if !(field.hide || field.flatten) {
+                        let value = self.value(json, field.type_.id, Some(index));
+                        out.extend(field_name(field).map(|name| (name, value)));
+                    }
+                },
+            // Unnamed fields cannot be queried, but they may contain trees or tokens.
+            rust::Fields::Unnamed(fields) if fields.len() == 1 => {
+                self.value(json, fields[0].type_.id, Some(index));
+            }
+            rust::Fields::Unnamed(fields) =>
+                for (i, field) in fields.iter().enumerate() {
+                    self.value(&json[i], field.type_.id, Some(index));
+                },
+            rust::Fields::Unit => {}
+        }
+    }
+
+    /// Refine the type of the node to the concrete type of a value of the given type, and add the
+    /// fields of the value to the node.
+    fn subtype(
+        &mut self,
+        json: &Json,
+        id: rust::TypeId,
+        index: usize,
+        out: &mut Vec<(Rc<str>, Value)>,
+    ) {
+        match &self.schema.types[&id].data {
+            rust::Data::Enum(rust::Enum { variants }) => {
+                let (name, json) = match json {
+                    Json::Object(object) if object.len() == 1 => object.iter().next().unwrap(),
+                    Json::String(name) => (name, &Json::Null),
+                    _ => panic!("Expected a variant of `{}`: {json}", self.schema.types[&id].name),
+                };
+                let position = variants.iter().position(|variant| variant.ident == *name);
+                let variant = position.unwrap_or_else(|| panic!("Unknown variant: `{name}`."));
+                let type_ = &mut self.nodes[index].type_;
+                *type_ = self.schema.graph[*type_].discriminants[&variant];
+                let variant = &variants[variant];
+                match variant.inline {
+                    true => {
+                        let wrapped = variant.fields.as_wrapped_type().unwrap();
+                        self.subtype(json, wrapped.id, index, out)
+                    }
+                    false => self.fields(json, &variant.fields, index, out),
+                }
+            }
+            rust::Data::Struct(rust::Struct { fields, .. }) => {
+                self.nodes[index].type_ = self.schema.rust_to_meta[&id];
+                self.fields(json, fields, index, out)
+            }
+            rust::Data::Primitive(_) => {}
+        }
+    }
+
+    fn primitive(
+        &mut self,
+        json: &Json,
+        primitive: &rust::Primitive,
+        parent: Option<usize>,
+    ) -> Value {
+        use rust::Primitive;
+        match primitive {
+            Primitive::Bool => Value::Bool(json.as_bool().unwrap_or_default()),
+            Primitive::Usize | Primitive::U32 | Primitive::U64 | Primitive::I32 => {
+                let number = json.as_i64().or_else(|| json.as_u64().map(|n| n as i64));
+                Value::Number(number.unwrap_or_default())
+            }
+            // A character may be represented by its code point.
+            Primitive::Char => Value::String(match json {
+                Json::String(text) => text.clone(),
+                _ => json.as_u64().and_then(|n| char::from_u32(n as u32)).into_iter().collect(),
+            }),
+            Primitive::String => Value::String(json.as_str().unwrap_or_default().to_owned()),
+            Primitive::Vec(t0) => {
+                let values = json.as_array().into_iter().flatten();
+                Value::Sequence(values.map(|json| self.value(json, t0.id, parent)).collect())
+            }
+            Primitive::Option(t0) => match json {
+                Json::Null => Value::None,
+                _ => self.value(json, t0.id, parent),
+            },
+            Primitive::Result(t0, t1) => match json.get("Ok") {
+                Some(ok) => self.value(ok, t0.id, parent),
+                None => self.value(&json["Err"], t1.id, parent),
+            },
+        }
+    }
+}
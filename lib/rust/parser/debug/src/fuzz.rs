@@ -0,0 +1,381 @@
+//! Property testing of the parser's lossless round-trip guarantee.
+//!
+//! For any input, the parser must produce a tree that:
+//! - Prints back to exactly the input ([`enso_parser::syntax::Tree::code`]).
+//! - Has consistent spans, covering every character of the input exactly once ([`validate_spans`]).
+//! - Contains no internal errors, and is produced without panicking.
+//!
+//! Inputs are produced by [`TokenSoup`], a deterministic generator of Enso-like token sequences;
+//! the same checks are used by the `cargo-fuzz` targets in the `fuzz` directory of this crate.
+//! Inputs that violate an invariant are [`minimize`]d, and can be added to the regression cases in
+//! `tests/parse.rs` with [`insert_regression`].
+
+use crate::validate_spans;
+
+use enso_parser::source::code::debug::LocationCheck;
+use enso_parser::syntax::tree;
+use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
+use std::fmt;
+use std::panic;
+
+
+
+// =================
+// === Violation ===
+// =================
+
+/// An invariant of the parser that does not hold for some input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Violation {
+    /// The parser panicked.
+    Panic(String),
+    /// The tree does not print back to the input.
+    CodeMismatch {
+        /// The code of the tree.
+        printed: String,
+    },
+    /// The spans of the tree are not consistent with each other, or with the input.
+    InvalidSpans(String),
+    /// The tree contains an internal error.
+    InternalError(String),
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::Panic(message) => write!(f, "Parser panicked: {message}"),
+            Violation::CodeMismatch { printed } =>
+                write!(f, "Tree does not reproduce the input; printed: {printed:?}"),
+            Violation::InvalidSpans(message) => write!(f, "Invalid spans: {message}"),
+            Violation::InternalError(message) => write!(f, "Internal error: {message}"),
+        }
+    }
+}
+
+/// Check the round-trip invariants for the given input.
+pub fn check(code: &str) -> Result<(), Violation> {
+    check_with(&enso_parser::Parser::new(), code)
+}
+
+/// Check the round-trip invariants for the given input, parsed with the given parser.
+pub fn check_with(parser: &enso_parser::Parser, code: &str) -> Result<(), Violation> {
+    let ast = catch_panic(|| parser.run(code)).map_err(Violation::Panic)?;
+    let printed = ast.code();
+    if printed != code {
+        return Err(Violation::CodeMismatch { printed });
+    }
+    catch_panic(|| {
+        let expected_span = 0..(code.encode_utf16().count() as u32);
+        let mut locations = LocationCheck::new();
+        validate_spans(&ast, expected_span, &mut locations);
+        locations.check(code);
+    })
+    .map_err(Violation::InvalidSpans)?;
+    let mut internal_error = None;
+    ast.visit_trees(|tree| {
+        if let tree::Variant::Invalid(invalid) = &*tree.variant
+            && invalid.error.code == tree::ErrorCode::Internal
+        {
+            internal_error.get_or_insert_with(|| invalid.error.message.to_string());
+        }
+    });
+    match internal_error {
+        Some(message) => Err(Violation::InternalError(message)),
+        None => Ok(()),
+    }
+}
+
+fn catch_panic<T>(f: impl FnOnce() -> T) -> Result<T, String> {
+    panic::catch_unwind(panic::AssertUnwindSafe(f)).map_err(|payload| {
+        let message = payload.downcast_ref::<&str>().map(|s| s.to_string());
+        let message = message.or_else(|| payload.downcast_ref::<String>().cloned());
+        message.unwrap_or_default()
+    })
+}
+
+
+
+// ==================
+// === Token soup ===
+// ==================
+
+/// Fragments of Enso-like code that the generated inputs are composed of.
+const FRAGMENTS: &[&str] = &[
+    // Identifiers.
+    "foo",
+    "Bar",
+    "x1",
+    "_",
+    "a_b",
+    "self",
+    "Self",
+    "ą",
+    "🦀",
+    // Macro keywords.
+    "if",
+    "then",
+    "else",
+    "case",
+    "of",
+    "type",
+    "import",
+    "from",
+    "export",
+    "all",
+    "hiding",
+    "polyglot",
+    "java",
+    "as",
+    "private",
+    "foreign",
+    "js",
+    "->",
+    // Operators.
+    "+",
+    "-",
+    "*",
+    "/",
+    ".",
+    "..",
+    "...",
+    "=",
+    "==",
+    ":",
+    "~",
+    "@",
+    "|",
+    "&",
+    "!",
+    "?",
+    ",",
+    ";",
+    "$",
+    "^",
+    "%",
+    "<|",
+    "|>",
+    ">>",
+    "<<",
+    "<-",
+    "\\",
+    // Delimiters.
+    "(",
+    ")",
+    "[",
+    "]",
+    "{",
+    "}",
+    // Numbers.
+    "0",
+    "42",
+    "1.5",
+    "0x1F",
+    "0b101",
+    "0o17",
+    "1_000",
+    "1e10",
+    "2.5e-3",
+    // Text.
+    "\"",
+    "'",
+    "'''",
+    "\"\"\"",
+    "`",
+    "\\n",
+    "\\u{41}",
+    "\\u{110000}",
+    "\\x",
+    // Comments.
+    "#",
+    "##",
+    // Whitespace and layout.
+    " ",
+    "  ",
+    "\t",
+    "\n",
+    "\n    ",
+    "\n  ",
+    "\n\n",
+    "\r\n",
+    "\r",
+    "\u{FEFF}",
+];
+
+/// Deterministic generator of inputs composed of random Enso-like tokens.
+#[derive(Debug)]
+pub struct TokenSoup {
+    rng:           ChaCha8Rng,
+    max_fragments: usize,
+}
+
+impl TokenSoup {
+    /// Create a generator producing inputs determined by the given seed.
+    pub fn new(seed: u64) -> Self {
+        Self { rng: ChaCha8Rng::seed_from_u64(seed), max_fragments: 32 }
+    }
+
+    /// Set the maximum number of fragments an input is composed of.
+    pub fn with_max_fragments(self, max_fragments: usize) -> Self {
+        Self { max_fragments, ..self }
+    }
+
+    /// Generate the next input.
+    pub fn generate(&mut self) -> String {
+        let fragments = self.rng.gen_range(1..=self.max_fragments);
+        let mut code = String::new();
+        for _ in 0..fragments {
+            code.push_str(FRAGMENTS.choose(&mut self.rng).unwrap());
+            if self.rng.gen_bool(0.3) {
+                code.push(' ');
+            }
+        }
+        code
+    }
+}
+
+impl Iterator for TokenSoup {
+    type Item = String;
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.generate())
+    }
+}
+
+
+
+// ====================
+// === Minimization ===
+// ====================
+
+/// Find a small input that still fails, by removing parts of the given failing input for as long
+/// as `fails` holds for the result.
+pub fn minimize(input: &str, mut fails: impl FnMut(&str) -> bool) -> String {
+    let mut chars: Vec<char> = input.chars().collect();
+    let mut chunk = chars.len().div_ceil(2);
+    while chunk > 0 {
+        let mut removed = false;
+        let mut start = 0;
+        while start < chars.len() {
+            let end = (start + chunk).min(chars.len());
+            let candidate: String = chars[..start].iter().chain(&chars[end..]).collect();
+            if fails(&candidate) {
+                chars.drain(start..end);
+                removed = true;
+            } else {
+                start += chunk;
+            }
+        }
+        if !removed {
+            chunk /= 2;
+        }
+    }
+    chars.into_iter().collect()
+}
+
+/// Minimize an input violating an invariant, while preserving the kind of the violation.
+pub fn minimize_violation(input: &str, violation: &Violation) -> String {
+    let kind = std::mem::discriminant(violation);
+    minimize(input, |code| check(code).is_err_and(|found| std::mem::discriminant(&found) == kind))
+}
+
+
+
+// ===========
+// === Run ===
+// ===========
+
+/// An input for which an invariant does not hold.
+#[derive(Clone, Debug)]
+pub struct Failure {
+    /// The seed of the [`TokenSoup`] that generated the input.
+    pub seed:      u64,
+    /// The generated input.
+    pub input:     String,
+    /// A minimal input violating the same invariant.
+    pub minimized: String,
+    /// The violation found for the minimized input.
+    pub violation: Violation,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self { seed, input, minimized, violation } = self;
+        write!(f, "seed {seed}: {violation}\n    input: {input:?}\n    minimized: {minimized:?}")
+    }
+}
+
+/// Check one input generated from each of the seeds in the given range. Returns the distinct
+/// minimized failures.
+pub fn run(seeds: std::ops::Range<u64>) -> Vec<Failure> {
+    let mut failures: Vec<Failure> = vec![];
+    for seed in seeds {
+        let input = TokenSoup::new(seed).generate();
+        if let Err(violation) = check(&input) {
+            let minimized = minimize_violation(&input, &violation);
+            let violation = check(&minimized).err().unwrap_or(violation);
+            if !failures.iter().any(|failure| failure.minimized == minimized) {
+                failures.push(Failure { seed, input, minimized, violation });
+            }
+        }
+    }
+    failures
+}
+
+
+
+// ===================
+// === Regressions ===
+// ===================
+
+/// The line of `tests/parse.rs` above which new regression cases are inserted.
+pub const REGRESSION_MARKER: &str = "// Fuzzing regressions are inserted above this line.";
+
+/// Add the input to the regression cases in the given source of `tests/parse.rs`. Returns [`None`]
+/// if the source does not contain the [`REGRESSION_MARKER`], or already contains the case.
+pub fn insert_regression(source: &str, case: &str) -> Option<String> {
+    let literal = format!("{case:?},");
+    let marker = source.find(REGRESSION_MARKER)?;
+    let line_start = source[..marker].rfind('\n').map_or(0, |i| i + 1);
+    let indent = &source[line_start..marker];
+    let regressions = &source[..line_start];
+    if regressions.lines().any(|line| line.trim() == literal) {
+        return None;
+    }
+    Some(format!("{regressions}{indent}{literal}\n{}", &source[line_start..]))
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minimize_to_failing_character() {
+        let minimized = minimize("foo (bar) baz", |code| code.contains('('));
+        assert_eq!(minimized, "(");
+    }
+
+    #[test]
+    fn token_soup_is_deterministic() {
+        let first: Vec<_> = TokenSoup::new(7).take(4).collect();
+        let second: Vec<_> = TokenSoup::new(7).take(4).collect();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn insert_regression_case() {
+        let source =
+            format!("const CASES: &[&str] = &[\n    \"a\",\n    {REGRESSION_MARKER}\n];\n");
+        let updated = insert_regression(&source, "b\n").unwrap();
+        let expected = format!(
+            "const CASES: &[&str] = &[\n    \"a\",\n    \"b\\n\",\n    {REGRESSION_MARKER}\n];\n"
+        );
+        assert_eq!(updated, expected);
+        assert_eq!(insert_regression(&updated, "b\n"), None);
+    }
+}
@@ -14,6 +14,7 @@
 #![warn(unused_qualifications)]
 
 use enso_metamodel_lexpr::ToSExpr;
+use enso_parser::project::SourceFile;
 use enso_parser::source::code::debug::LocationCheck;
 use enso_reflect::Reflect;
 use lexpr::Value;
@@ -21,6 +22,35 @@ use std::collections::HashSet;
 
 
 
+// ==============
+// === Export ===
+// ==============
+
+pub mod diff;
+pub mod document;
+pub mod fuzz;
+pub mod query;
+
+
+
+// ===============
+// === Sources ===
+// ===============
+
+/// Read the sources named on the command line of a tool (see [`SourceFile::from_paths`]). If no
+/// paths are given, the source is read from standard input, and named `<stdin>`.
+pub fn read_sources(paths: Vec<String>) -> std::io::Result<Vec<SourceFile>> {
+    if !paths.is_empty() {
+        return SourceFile::from_paths(paths);
+    }
+    use std::io::Read;
+    let mut contents = String::new();
+    std::io::stdin().read_to_string(&mut contents)?;
+    Ok(vec![SourceFile { path: "<stdin>".into(), contents }])
+}
+
+
+
 // =====================
 // === S-expressions ===
 // =====================
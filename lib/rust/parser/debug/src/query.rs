@@ -0,0 +1,713 @@
+//! A query language matching syntax trees by their structure.
+//!
+//! Queries are S-expressions in the style of tree-sitter queries. A pattern names the kind of node
+//! it matches, and constrains the values of its fields:
+//!
+//! ```text
+//! ((Function name: (Ident) @name returns: (_)) @function
+//!  (#not-has-parent? @function Documented))
+//! ```
+//!
+//! The kinds and fields are those of the AST types, as described by their [`Reflect`] metadata
+//! (see [`Schema`]), so queries are checked against the current definition of the tree, and the
+//! language needs no changes when it evolves. A kind is the name of a type reachable from
+//! [`Tree`]: a variant of the tree (`Function`), a supporting type (`ArgumentDefinition`), or a
+//! token type. A name shared by several types refers to the variant of [`Tree`]; others can be
+//! qualified by the name of their parent type, as in `Token.Ident`. A kind also matches the
+//! subtypes of the type it names, so `(Token)` matches any token. Fields are named as in the Rust
+//! definitions, except that the `type_` fields are named `type`.
+//!
+//! # Syntax
+//!
+//! - `(Kind constraint...)` matches a node of the kind; `(_ constraint...)` matches any node. The
+//!   constraints are:
+//!   - `field: pattern` requires the field to be present and match the pattern. When the field
+//!     holds a sequence, any of its elements may match.
+//!   - `!field` requires the field to be absent: `None`, or an empty sequence.
+//!   - A nested pattern without a field name requires some node within the node to match it.
+//! - `_` matches any value that is present.
+//! - `"text"` matches a value whose source code, or primitive value, is the given text.
+//! - Any pattern may be followed by captures, `@name`, binding the value it matches.
+//! - Predicates, which constrain the captured values, can be written within the parentheses of any
+//!   pattern, or in a group with the pattern: `((Kind) @capture (#predicate? ...))`.
+//!   - `(#eq? @capture "text")`, `(#eq? @capture @other)`: the source code of the values is equal.
+//!   - `(#match? @capture "regex")`: the source code of the value matches the regular expression.
+//!   - `(#has-parent? @capture Kind...)`: the nearest node enclosing the captured node is of one of
+//!     the kinds.
+//!   - `(#has-ancestor? @capture Kind...)`: some node enclosing the captured node is of one of the
+//!     kinds.
+//!   - Each predicate can be negated with the prefix `not-`, as in `#not-eq?`.
+//! - `;` starts a comment, which extends to the end of the line.
+
+use crate::document::Document;
+use crate::document::Schema;
+use crate::document::Value;
+use enso_metamodel::meta::TypeId;
+use std::fmt;
+
+
+
+// =============
+// === Query ===
+// =============
+
+/// A compiled query, consisting of any number of patterns.
+#[derive(Debug)]
+pub struct Query<'a> {
+    schema:   &'a Schema,
+    patterns: Vec<(Pattern, Vec<Predicate>)>,
+    captures: Vec<String>,
+}
+
+#[derive(Debug)]
+struct Pattern {
+    matcher:  Matcher,
+    captures: Vec<usize>,
+}
+
+#[derive(Debug)]
+enum Matcher {
+    Any,
+    Text(String),
+    Node {
+        kind:        Option<TypeId>,
+        /// The constraints on the fields; `None` requires the field to be absent.
+        fields:      Vec<(String, Option<Pattern>)>,
+        descendants: Vec<Pattern>,
+    },
+}
+
+#[derive(Debug)]
+struct Predicate {
+    negated:   bool,
+    capture:   usize,
+    condition: Condition,
+}
+
+#[derive(Debug)]
+enum Condition {
+    EqText(String),
+    EqCapture(usize),
+    Match(regex::Regex),
+    HasParent(Vec<TypeId>),
+    HasAncestor(Vec<TypeId>),
+}
+
+/// A match of a pattern of a [`Query`].
+#[derive(Debug)]
+pub struct Match<'q, 'd> {
+    /// The index of the pattern within the query.
+    pub pattern:  usize,
+    /// The node matched by the pattern.
+    pub node:     &'d Value,
+    /// The values bound by the captures of the pattern, with the names of the captures.
+    pub captures: Vec<(&'q str, &'d Value)>,
+}
+
+impl<'a> Query<'a> {
+    /// Compile the query, checking its kinds and fields against the schema.
+    pub fn new(schema: &'a Schema, source: &str) -> Result<Self, Error> {
+        let tokens = lex(source)?;
+        let mut parser = Parser {
+            schema,
+            source,
+            tokens: &tokens,
+            pos: 0,
+            captures: vec![],
+            defined: vec![],
+            used: vec![],
+        };
+        let mut patterns = vec![];
+        while parser.pos < tokens.len() {
+            patterns.push(parser.top_level()?);
+        }
+        Ok(Self { schema, patterns, captures: parser.captures })
+    }
+
+    /// Return the matches of the patterns in the document, ordered by the position of the matched
+    /// nodes, and then by the order of the patterns.
+    pub fn matches<'q, 'd>(&'q self, document: &'d Document) -> Vec<Match<'q, 'd>> {
+        let mut matches = vec![];
+        document.root().for_each_node(&mut |value, _| {
+            for (index, (pattern, predicates)) in self.patterns.iter().enumerate() {
+                let mut captures = vec![];
+                if self.match_value(pattern, value, document, &mut captures)
+                    && predicates.iter().all(|p| self.check(p, &captures, document))
+                {
+                    let captures =
+                        captures.into_iter().map(|(i, value)| (self.captures[i].as_str(), value));
+                    matches.push(Match {
+                        pattern:  index,
+                        node:     value,
+                        captures: captures.collect(),
+                    });
+                }
+            }
+        });
+        matches
+    }
+
+    fn match_value<'d>(
+        &self,
+        pattern: &Pattern,
+        value: &'d Value,
+        document: &Document,
+        captures: &mut Vec<(usize, &'d Value)>,
+    ) -> bool {
+        let start = captures.len();
+        let matched = match value {
+            Value::None => false,
+            Value::Sequence(values) =>
+                return values
+                    .iter()
+                    .any(|value| self.match_value(pattern, value, document, captures)),
+            _ => match &pattern.matcher {
+                Matcher::Any => true,
+                Matcher::Text(text) => document.text(value) == text.as_str(),
+                Matcher::Node { kind, fields, descendants } => match value {
+                    Value::Node(node) =>
+                        kind.map_or(true, |kind| self.schema.is_a(node.type_, kind))
+                            && fields.iter().all(|(name, pattern)| {
+                                match (node.field(name), pattern) {
+                                    (Some(value), Some(pattern)) =>
+                                        self.match_value(pattern, value, document, captures),
+                                    (None, Some(_)) => false,
+                                    (Some(value), None) => value.is_absent(),
+                                    (None, None) => true,
+                                }
+                            })
+                            && descendants.iter().all(|pattern| {
+                                node.fields.iter().any(|(_, value)| {
+                                    self.match_descendant(pattern, value, document, captures)
+                                })
+                            }),
+                    _ => false,
+                },
+            },
+        };
+        if matched {
+            captures.extend(pattern.captures.iter().map(|capture| (*capture, value)));
+        } else {
+            captures.truncate(start);
+        }
+        matched
+    }
+
+    /// Whether the value, or any value within it, is a node matching the pattern.
+    fn match_descendant<'d>(
+        &self,
+        pattern: &Pattern,
+        value: &'d Value,
+        document: &Document,
+        captures: &mut Vec<(usize, &'d Value)>,
+    ) -> bool {
+        match value {
+            Value::Node(node) =>
+                self.match_value(pattern, value, document, captures)
+                    || node
+                        .fields
+                        .iter()
+                        .any(|(_, value)| self.match_descendant(pattern, value, document, captures)),
+            Value::Sequence(values) =>
+                values.iter().any(|value| self.match_descendant(pattern, value, document, captures)),
+            _ => false,
+        }
+    }
+
+    fn check(
+        &self,
+        predicate: &Predicate,
+        captures: &[(usize, &Value)],
+        document: &Document,
+    ) -> bool {
+        let captured = |index| captures.iter().find(|(i, _)| *i == index).map(|(_, value)| *value);
+        let Some(value) = captured(predicate.capture) else { return false };
+        let is_any =
+            |types: &[TypeId], ty: TypeId| types.iter().any(|kind| self.schema.is_a(ty, *kind));
+        let holds = match &predicate.condition {
+            Condition::EqText(text) => document.text(value) == text.as_str(),
+            Condition::EqCapture(other) => match captured(*other) {
+                Some(other) => document.text(value) == document.text(other),
+                None => return false,
+            },
+            Condition::Match(regex) => regex.is_match(&document.text(value)),
+            Condition::HasParent(kinds) => match value {
+                Value::Node(node) =>
+                    document.ancestors(node).next().is_some_and(|ty| is_any(kinds, ty)),
+                _ => false,
+            },
+            Condition::HasAncestor(kinds) => match value {
+                Value::Node(node) => document.ancestors(node).any(|ty| is_any(kinds, ty)),
+                _ => false,
+            },
+        };
+        holds != predicate.negated
+    }
+}
+
+
+
+// =============
+// === Error ===
+// =============
+
+/// An error in the text of a query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    /// The line of the query the error was found at, starting from 1.
+    pub line:    usize,
+    /// The column of the query the error was found at, in characters, starting from 1.
+    pub column:  usize,
+    /// Description of the error.
+    pub message: String,
+}
+
+impl Error {
+    fn new(source: &str, offset: usize, message: impl Into<String>) -> Self {
+        let before = &source[..offset];
+        let line = before.matches('\n').count() + 1;
+        let column = before.rsplit('\n').next().unwrap_or_default().chars().count() + 1;
+        Self { line, column, message: message.into() }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+
+
+// =============
+// === Lexer ===
+// =============
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Open,
+    Close,
+    Colon,
+    Bang,
+    Capture(String),
+    Predicate(String),
+    String(String),
+    Name(String),
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | '-' | '?')
+}
+
+fn lex(source: &str) -> Result<Vec<(usize, Token)>, Error> {
+    let mut tokens = vec![];
+    let mut chars = source.char_indices().peekable();
+    while let Some((offset, c)) = chars.next() {
+        let mut take_word = |mut word: String| {
+            while let Some((_, c)) = chars.next_if(|(_, c)| is_word(*c)) {
+                word.push(c);
+            }
+            word
+        };
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            ';' => {
+                while chars.next_if(|(_, c)| *c != '\n').is_some() {}
+                continue;
+            }
+            '(' => Token::Open,
+            ')' => Token::Close,
+            ':' => Token::Colon,
+            '!' => Token::Bang,
+            '@' | '#' => {
+                let name = take_word(String::new());
+                if name.is_empty() {
+                    return Err(Error::new(
+                        source,
+                        offset,
+                        format!("Expected a name after `{c}`."),
+                    ));
+                }
+                if c == '@' {
+                    Token::Capture(name)
+                } else {
+                    Token::Predicate(name)
+                }
+            }
+            '"' => {
+                let mut text = String::new();
+                loop {
+                    match chars.next().map(|(_, c)| c) {
+                        Some('"') => break,
+                        Some('\\') => match chars.next().map(|(_, c)| c) {
+                            Some('n') => text.push('\n'),
+                            Some('t') => text.push('\t'),
+                            Some(c) => text.push(c),
+                            None => break,
+                        },
+                        Some(c) => text.push(c),
+                        None => return Err(Error::new(source, offset, "Unterminated string.")),
+                    }
+                }
+                Token::String(text)
+            }
+            c if is_word(c) => Token::Name(take_word(c.to_string())),
+            c => return Err(Error::new(source, offset, format!("Unexpected character: `{c}`."))),
+        };
+        tokens.push((offset, token));
+    }
+    Ok(tokens)
+}
+
+
+
+// ==============
+// === Parser ===
+// ==============
+
+struct Parser<'a, 's> {
+    schema:   &'a Schema,
+    source:   &'s str,
+    tokens:   &'a [(usize, Token)],
+    pos:      usize,
+    captures: Vec<String>,
+    /// The captures defined by the pattern being parsed.
+    defined:  Vec<usize>,
+    /// The captures referred to by the predicates of the pattern being parsed, and where.
+    used:     Vec<(usize, usize)>,
+}
+
+impl<'a, 's> Parser<'a, 's> {
+    /// Parse a pattern at the top level of the query, with its predicates.
+    fn top_level(&mut self) -> Result<(Pattern, Vec<Predicate>), Error> {
+        self.defined.clear();
+        self.used.clear();
+        let mut predicates = vec![];
+        let pattern = self.pattern(&mut predicates)?;
+        if let Some((capture, offset)) =
+            self.used.iter().find(|(capture, _)| !self.defined.contains(capture))
+        {
+            let message = format!("Undefined capture: `@{}`.", self.captures[*capture]);
+            return Err(Error::new(self.source, *offset, message));
+        }
+        Ok((pattern, predicates))
+    }
+
+    fn pattern(&mut self, predicates: &mut Vec<Predicate>) -> Result<Pattern, Error> {
+        let mut pattern = match self.next() {
+            Some(Token::Name(name)) if name == "_" => Pattern::new(Matcher::Any),
+            Some(Token::String(text)) => Pattern::new(Matcher::Text(text.clone())),
+            Some(Token::Open) if self.peek() == Some(&Token::Open) => self.group(predicates)?,
+            Some(Token::Open) => Pattern::new(self.node(predicates)?),
+            _ => {
+                self.pos -= 1;
+                return Err(self.error("Expected a pattern."));
+            }
+        };
+        while let Some(Token::Capture(name)) = self.peek() {
+            let index = match self.captures.iter().position(|capture| capture == name) {
+                Some(index) => index,
+                None => {
+                    self.captures.push(name.clone());
+                    self.captures.len() - 1
+                }
+            };
+            pattern.captures.push(index);
+            self.defined.push(index);
+            self.pos += 1;
+        }
+        Ok(pattern)
+    }
+
+    /// Parse a pattern grouped with predicates, after the opening parenthesis.
+    fn group(&mut self, predicates: &mut Vec<Predicate>) -> Result<Pattern, Error> {
+        let pattern = self.pattern(predicates)?;
+        loop {
+            match self.next() {
+                Some(Token::Close) => return Ok(pattern),
+                Some(Token::Open) if matches!(self.peek(), Some(Token::Predicate(_))) =>
+                    predicates.push(self.predicate()?),
+                _ => {
+                    self.pos -= 1;
+                    return Err(self.error("Expected a predicate, or `)`."));
+                }
+            }
+        }
+    }
+
+    /// Parse a node pattern, after the opening parenthesis.
+    fn node(&mut self, predicates: &mut Vec<Predicate>) -> Result<Matcher, Error> {
+        let kind = match self.next() {
+            Some(Token::Name(name)) if name == "_" => None,
+            Some(Token::Name(name)) => {
+                let name = name.clone();
+                Some(self.schema.kind(&name).map_err(|message| self.error_before(message))?)
+            }
+            _ => {
+                self.pos -= 1;
+                return Err(self.error("Expected a kind, or `_`."));
+            }
+        };
+        let mut fields = vec![];
+        let mut descendants = vec![];
+        loop {
+            match self.peek() {
+                Some(Token::Close) => {
+                    self.pos += 1;
+                    return Ok(Matcher::Node { kind, fields, descendants });
+                }
+                Some(Token::Bang) => {
+                    self.pos += 1;
+                    let name = self.field(kind)?;
+                    fields.push((name, None));
+                }
+                Some(Token::Name(name)) if name != "_" => {
+                    let name = self.field(kind)?;
+                    if self.next() != Some(&Token::Colon) {
+                        self.pos -= 1;
+                        return Err(self.error(format!("Expected `:` after the field `{name}`.")));
+                    }
+                    fields.push((name, Some(self.pattern(predicates)?)));
+                }
+                Some(Token::Open)
+                    if matches!(self.tokens.get(self.pos + 1), Some((_, Token::Predicate(_)))) =>
+                {
+                    self.pos += 1;
+                    predicates.push(self.predicate()?);
+                }
+                Some(_) => descendants.push(self.pattern(predicates)?),
+                None => return Err(self.error("Expected `)`.")),
+            }
+        }
+    }
+
+    /// Parse the name of a field of the given kind of node.
+    fn field(&mut self, kind: Option<TypeId>) -> Result<String, Error> {
+        let Some(Token::Name(name)) = self.next() else {
+            self.pos -= 1;
+            return Err(self.error("Expected a field name."));
+        };
+        let name = name.clone();
+        let fields = self.schema.fields_of_subtypes(kind);
+        if fields.contains(&name) {
+            return Ok(name);
+        }
+        let message = match kind {
+            Some(kind) => {
+                let fields = fields.iter().map(|field| format!("`{field}`")).collect::<Vec<_>>();
+                let kind = self.schema.kind_name(kind);
+                match fields.is_empty() {
+                    true => format!("`{kind}` has no fields."),
+                    false => format!(
+                        "`{kind}` has no field `{name}`. Its fields are: {}.",
+                        fields.join(", ")
+                    ),
+                }
+            }
+            None => format!("No kind has a field `{name}`."),
+        };
+        Err(self.error_before(message))
+    }
+
+    /// Parse a predicate, after the opening parenthesis.
+    fn predicate(&mut self) -> Result<Predicate, Error> {
+        let Some(Token::Predicate(name)) = self.next() else { unreachable!() };
+        let name = name.clone();
+        let (negated, base) = match name.strip_prefix("not-") {
+            Some(base) => (true, base),
+            None => (false, name.as_str()),
+        };
+        let capture = match self.next() {
+            Some(Token::Capture(capture)) => self.capture(capture.clone()),
+            _ => {
+                self.pos -= 1;
+                return Err(self.error("Expected a capture."));
+            }
+        };
+        let condition = match base {
+            "eq?" => match self.next() {
+                Some(Token::String(text)) => Condition::EqText(text.clone()),
+                Some(Token::Capture(other)) => Condition::EqCapture(self.capture(other.clone())),
+                _ => {
+                    self.pos -= 1;
+                    return Err(self.error("Expected a string, or a capture."));
+                }
+            },
+            "match?" => match self.next() {
+                Some(Token::String(regex)) => {
+                    let regex = regex::Regex::new(regex);
+                    Condition::Match(regex.map_err(|e| self.error_before(e.to_string()))?)
+                }
+                _ => {
+                    self.pos -= 1;
+                    return Err(self.error("Expected a regular expression."));
+                }
+            },
+            "has-parent?" | "has-ancestor?" => {
+                let mut kinds = vec![];
+                while let Some(Token::Name(kind)) = self.peek() {
+                    let kind = kind.clone();
+                    self.pos += 1;
+                    kinds.push(self.schema.kind(&kind).map_err(|e| self.error_before(e))?);
+                }
+                if kinds.is_empty() {
+                    return Err(self.error("Expected a kind."));
+                }
+                match base {
+                    "has-parent?" => Condition::HasParent(kinds),
+                    _ => Condition::HasAncestor(kinds),
+                }
+            }
+            _ => {
+                self.pos -= 2;
+                return Err(self.error(format!("Unknown predicate: `#{name}`.")));
+            }
+        };
+        if self.next() != Some(&Token::Close) {
+            self.pos -= 1;
+            return Err(self.error("Expected `)`."));
+        }
+        Ok(Predicate { negated, capture, condition })
+    }
+
+    /// Return the index of a capture referred to by a predicate.
+    fn capture(&mut self, name: String) -> usize {
+        let index = match self.captures.iter().position(|capture| *capture == name) {
+            Some(index) => index,
+            None => {
+                self.captures.push(name);
+                self.captures.len() - 1
+            }
+        };
+        self.used.push((index, self.tokens[self.pos - 1].0));
+        index
+    }
+
+    fn next(&mut self) -> Option<&'a Token> {
+        let token = self.tokens.get(self.pos).map(|(_, token)| token);
+        self.pos += 1;
+        token
+    }
+
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.pos).map(|(_, token)| token)
+    }
+
+    /// An error at the next token.
+    fn error(&self, message: impl Into<String>) -> Error {
+        let offset = self.tokens.get(self.pos).map_or(self.source.len(), |(offset, _)| *offset);
+        Error::new(self.source, offset, message)
+    }
+
+    /// An error at the last token consumed.
+    fn error_before(&self, message: impl Into<String>) -> Error {
+        Error::new(self.source, self.tokens[self.pos - 1].0, message)
+    }
+}
+
+impl Pattern {
+    fn new(matcher: Matcher) -> Self {
+        Self { matcher, captures: vec![] }
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Return the code of the captures of each match.
+    fn run(query: &str, code: &str) -> Vec<Vec<(String, String)>> {
+        let schema = Schema::new();
+        let query = Query::new(&schema, query).unwrap_or_else(|e| panic!("{e}"));
+        let tree = enso_parser::Parser::new().run(code);
+        let document = Document::new(&schema, &tree, code);
+        let captures = |m: Match| {
+            let capture = |(name, value)| (format!("@{name}"), document.text(value).into_owned());
+            m.captures.into_iter().map(capture).collect()
+        };
+        query.matches(&document).into_iter().map(captures).collect()
+    }
+
+    fn error(query: &str) -> String {
+        Query::new(&Schema::new(), query).unwrap_err().to_string()
+    }
+
+    fn captures<const N: usize>(values: [(&str, &str); N]) -> Vec<(String, String)> {
+        values.iter().map(|(name, code)| (name.to_string(), code.to_string())).collect()
+    }
+
+    #[test]
+    fn undocumented_functions_with_return_types() {
+        let code = [
+            "## Documented.",
+            "f x -> Integer = x",
+            "g x -> Integer = x",
+            "h x = x",
+            "type T",
+            "    ## Documented.",
+            "    @x Widget",
+            "    m self -> Text = 'm'",
+            "    @x Widget",
+            "    n self -> Text = 'n'",
+        ]
+        .join("\n");
+        let query = "((Function name: (_) @name returns: (_)) @function
+                      (#not-has-parent? @function Documented Annotated))
+                     ; Annotated functions are checked by the documentation of the annotation.
+                     ((Annotated expression: (Function name: (_) @name returns: (_))) @function
+                      (#not-has-parent? @function Documented))";
+        let names: Vec<_> =
+            run(query, &code).into_iter().map(|captures| captures[0].1.clone()).collect();
+        assert_eq!(names, ["g", "n"]);
+    }
+
+    #[test]
+    fn fields_and_captures() {
+        let code = "main =\n    x = foo a b\n    bar (x + 1) c\n";
+        assert_eq!(run("(Assignment pattern: (Ident) @var expr: (_) @value)", code), [captures([
+            ("@var", "x"),
+            ("@value", "foo a b"),
+        ])]);
+        assert_eq!(run("(App func: \"bar\" arg: (_) @arg)", code), [captures([(
+            "@arg", "(x + 1)"
+        )])]);
+        assert_eq!(run("(OprApp opr: (Token.Operator) @opr)", code), [captures([("@opr", "+")])]);
+        assert_eq!(run("(Function !args body: (_ (OprApp) @opr))", code), [captures([(
+            "@opr", "x + 1"
+        )])]);
+        assert_eq!(run("((Ident) @id (#match? @id \"^[ab]$\"))", code).len(), 2);
+        assert_eq!(run("((Ident) @id (#has-ancestor? @id Group))", code).len(), 1);
+        assert_eq!(run("(App func: (App func: (_) @f) arg: (_) @b (#eq? @f \"foo\"))", code), [
+            captures([("@f", "foo"), ("@b", "b")])
+        ]);
+    }
+
+    #[test]
+    fn sequences() {
+        let code = "f (a = 1) b (c : Integer) = a\n";
+        let query = "(Function args: (ArgumentDefinition pattern: (_) @arg default: (_)))";
+        assert_eq!(run(query, code), [captures([("@arg", "a")])]);
+        let query = "(ArgumentDefinition pattern: (_) @arg type: (_) @type)";
+        assert_eq!(run(query, code), [captures([("@arg", "c"), ("@type", ": Integer")])]);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(error("(Fnction)"), "1:2: Unknown kind: `Fnction`.");
+        assert!(error("(Function nme: (_))").starts_with("1:11: `Function` has no field `nme`."));
+        assert_eq!(error("(_ nme: (_))"), "1:4: No kind has a field `nme`.");
+        assert_eq!(error("(Ident token)"), "1:13: Expected `:` after the field `token`.");
+        assert_eq!(error("((Ident) @x (#eq? @y \"a\"))"), "1:19: Undefined capture: `@y`.");
+        assert_eq!(error("((Ident) @x (#same? @x \"a\"))"), "1:14: Unknown predicate: `#same?`.");
+        assert_eq!(error("(Ident\n  \"x)"), "2:3: Unterminated string.");
+        assert_eq!(error("(Ident"), "1:7: Expected `)`.");
+    }
+}
@@ -218,6 +218,14 @@ fn type_definition_no_body() {
          (() (Ident column_names) (":" (App (Ident Vector) (Ident Text))) ())) #()));
 }
 
+#[test]
+fn type_definition_without_name() {
+    test!("type-", (Invalid));
+    test!("type - x", (Invalid));
+    test!("type '\n", (Invalid)());
+    expect_error("type-", enso_parser::syntax::tree::ErrorCode::ExpectedTypeName);
+}
+
 #[test]
 fn type_constructors() {
     let code = [
@@ -792,6 +800,13 @@ fn unary_operator_at_end_of_expression() {
     expect_invalid_node("foo ~");
 }
 
+#[test]
+fn unary_operator_before_binary_operator() {
+    test!("a..", (App (Ident a) (Invalid)));
+    test!("a.. + b", (OprApp (App (Ident a) (Invalid)) (Ok "+") (Ident b)));
+    test!("n..%", (OprSectionBoundary 1 (OprApp (App (Ident n) (Invalid)) (Ok "%") ())));
+}
+
 #[test]
 fn unspaced_operator_sequence() {
     // Add a negated value.
@@ -995,6 +1010,26 @@ fn metadata_parsing() {
     let _meta: enso_parser::metadata::Metadata = meta.unwrap();
 }
 
+#[test]
+fn metadata_round_trip() {
+    use enso_parser::metadata::*;
+    let file = metadata::ORDERS_WITH_METADATA;
+    let (section, code) = parse_section(file).unwrap();
+    let mut section = section.unwrap();
+    assert_eq!(attach(code, Some(&section)), file);
+    let properties = section.properties.as_mut().unwrap();
+    let (&id, _) = properties.ide.as_ref().unwrap().node.as_ref().unwrap().iter().next().unwrap();
+    properties.ide_mut().node_mut(id).position = Some(ide::Position::new(1.0, 2.0));
+    let edited = attach(code, Some(&section));
+    let (reparsed, reparsed_code) = parse_section(&edited).unwrap();
+    assert_eq!(reparsed_code, code);
+    let reparsed = reparsed.unwrap();
+    assert_eq!(reparsed.id_map, section.id_map);
+    assert_eq!(reparsed.to_string(), section.to_string());
+    let node = reparsed.properties.unwrap().ide.unwrap().node(id).cloned().unwrap();
+    assert_eq!(node.position, Some(ide::Position::new(1.0, 2.0)));
+}
+
 
 // === Type annotations and signatures ===
 
@@ -1125,6 +1160,9 @@ fn interpolated_literals_in_inline_text() {
           (Section "."))));
     test!(r#"'` SpliceWithLeadingWhitespace`'"#,
         (TextLiteral #((Splice (Ident SpliceWithLeadingWhitespace)))));
+    test!(r#"'`SpliceWithTrailingWhitespace `'"#,
+        (TextLiteral #((Splice (Ident SpliceWithTrailingWhitespace)))));
+    test!(r#"'a` x `b'"#, (TextLiteral #((Section "a") (Splice (Ident x)) (Section "b"))));
     test!(r#"'String with \n escape'"#,
         (TextLiteral #((Section "String with ") (Escape 0x0A) (Section " escape"))));
     test!(r#"'\x0Aescape'"#, (TextLiteral #((Escape 0x0A) (Section "escape"))));
@@ -1142,6 +1180,13 @@ fn interpolated_literals_in_multiline_text() {
         (TextLiteral #((Splice (Ident splice)) (Section " at start")))];
     test(code, expected);
 
+    let code = r#"'''
+    `splice `  after"#;
+    #[rustfmt::skip]
+    let expected = block![
+        (TextLiteral #((Splice (Ident splice)) (Section "  after")))];
+    test(code, expected);
+
     let code = r#"'''
     text with a `splice`
     and some \u000Aescapes\'"#;
@@ -1425,6 +1470,17 @@ fn trailing_whitespace() {
     cases.into_iter().for_each(|(code, expected)| test(code, expected));
 }
 
+#[test]
+fn blank_first_line() {
+    let cases = [
+        (" ", block![()()]),
+        (" \nfoo", block![() (Ident foo)]),
+        ("  \n  \nfoo", block![() () (Ident foo)]),
+    ];
+    cases.into_iter().for_each(|(code, expected)| test(code, expected));
+    expect_invalid_node(" \n foo");
+}
+
 
 // === Annotations ===
 
@@ -1637,6 +1693,189 @@ fn invalid_unspaced_operator_sequence() {
     expect_multiple_operator_error("=-");
 }
 
+#[test]
+fn error_codes() {
+    use enso_parser::syntax::tree::ErrorCode::*;
+    expect_error("type", ExpectedTypeName);
+    expect_error("import", IncompleteImport);
+    expect_error("export", IncompleteExport);
+    expect_error("import Foo hiding X,", MalformedSequence);
+    expect_error("(", UnmatchedDelimiter);
+    expect_error("]", UnmatchedDelimiter);
+    expect_error("`", UnexpectedToken);
+    expect_error("foo = 1, 2", InvalidSpecialOperator);
+    expect_error("foo = case x of\n 4", InvalidCase);
+    expect_error("foreign 4", ForeignLanguageExpected);
+    expect_error("foreign js foo = 4", ForeignBodyNotText);
+    expect_error("x = ..foo", AutoscopeNonConstructor);
+    expect_error("case x of\n    ..True -> y", AutoscopeInPattern);
+    expect_error("1 x = 2", InvalidAssignment);
+    expect_error("    x", ExpectedExpressionBeforeBlock);
+    expect_error("foreign js", ForeignNameExpected);
+    expect_error("foreign js 4", ForeignFunctionExpected);
+    expect_error("import Foo as", ExpectedTokens);
+    expect_error("x = ..(a)", AutoscopeNonIdentifier);
+    expect_error("x :", IncompleteTypeAnnotation);
+    expect_error("x = y ~", MissingOperand);
+    expect_error("from Foo import all What_Is_This_Doing_Here hiding Bar", UnexpectedMacroTokens);
+}
+
+#[test]
+fn error_spans() {
+    use enso_parser::syntax::tree::ErrorCode;
+    let code = "x = 1~y";
+    let (error, start) = find_error(code, ErrorCode::SpaceRequired);
+    let operator = error.primary.range(start);
+    assert_eq!(&code[operator.start.utf8 as usize..operator.end.utf8 as usize], "~");
+    let fix = error.fix.expect("Expected a suggested fix.");
+    let insertion = fix.span.range(start);
+    assert_eq!(insertion.start, insertion.end);
+    assert_eq!(insertion.start, operator.start);
+    assert_eq!(fix.replacement, " ");
+}
+
+
+
+// === User-Defined Macros ===
+
+fn parser_with_user_macros() -> enso_parser::Parser {
+    use enso_parser::macros::pattern::*;
+    use enso_parser::macros::user::*;
+    let mut parser = enso_parser::Parser::new();
+    let with = Definition::expression("with", everything(), Template::Tokens).segment(
+        "do",
+        everything().named("action"),
+        [Fragment::var("action")],
+    );
+    parser.register_macro(with).unwrap();
+    let define = Definition::statement(
+        "define",
+        identifier().named("name") >> many(identifier().named("arg")),
+        [Fragment::var("name"), Fragment::group([Fragment::repeat([Fragment::var("arg")])])],
+    )
+    .segment("means", everything().named("body"), [Fragment::var("body")]);
+    parser.register_macro(define).unwrap();
+    parser
+}
+
+#[test]
+fn user_defined_macros() {
+    let parser = parser_with_user_macros();
+    test_with(&parser, "with file do read file", block![
+        (MultiSegmentApp #(((Ident with) (Ident file)) ((Ident do) (App (Ident read) (Ident file)))))
+    ]);
+    test_with(&parser, "x = with a b do c", block![
+        (Assignment (Ident x) "=" (MultiSegmentApp #(((Ident with) (App (Ident a) (Ident b)))
+                                                ((Ident do) (Ident c)))))
+    ]);
+    test_with(&parser, "define f a b means a + b", block![
+        (MultiSegmentApp #(((Ident define) (App (Ident f) (App (Ident a) (Ident b))))
+                           ((Ident means) (OprApp (Ident a) (Ok "+") (Ident b)))))
+    ]);
+    test_with(&parser, "define f means 1", block![
+        (MultiSegmentApp #(((Ident define) (Ident f)) ((Ident means) (Number () "1" ()))))
+    ]);
+    test_with(&parser, "x = define", block![(Assignment (Ident x) "=" (Ident define))]);
+    test_with(&parser, "if a then with b do c", block![
+        (MultiSegmentApp #(((Ident if) (Ident a))
+                           ((Ident then) (MultiSegmentApp #(((Ident with) (Ident b))
+                                                            ((Ident do) (Ident c)))))))
+    ]);
+}
+
+#[test]
+fn user_defined_macro_registration_errors() {
+    use enso_parser::macros::pattern::*;
+    use enso_parser::macros::user::*;
+    let register = |definition| enso_parser::Parser::new().register_macro(definition).unwrap_err();
+    let conflict = register(Definition::expression("if", everything(), Template::Tokens));
+    assert!(
+        matches!(&conflict, RegistrationError::HeaderConflict { header, existing }
+        if header == "if" && existing.starts_with("if ... then ...")),
+        "{conflict}"
+    );
+    let conflict =
+        register(Definition::expression("unless", everything(), Template::Tokens).segment(
+            "then",
+            everything(),
+            Template::Tokens,
+        ));
+    assert!(
+        matches!(&conflict, RegistrationError::HeaderConflict { header, .. } if header == "then")
+    );
+    let conflict = register(Definition::statement("type", everything(), Template::Tokens));
+    assert!(matches!(conflict, RegistrationError::HeaderConflict { .. }));
+    let invalid = register(Definition::expression("with x", everything(), Template::Tokens));
+    assert_eq!(invalid, RegistrationError::InvalidHeader { header: "with x".into() });
+    let invalid = register(Definition::expression("42", everything(), Template::Tokens));
+    assert_eq!(invalid, RegistrationError::InvalidHeader { header: "42".into() });
+    let unbound = register(Definition::expression("with", everything(), [Fragment::var("x")]));
+    assert!(matches!(unbound, RegistrationError::UnsupportedPattern {
+        issue: PatternIssue::UnboundTokens,
+        ..
+    }));
+    let expected = expected("Expected an identifier.", identifier().named("a"));
+    let expected = register(Definition::expression("with", expected, [Fragment::var("a")]));
+    assert!(matches!(expected, RegistrationError::UnsupportedPattern {
+        issue: PatternIssue::BindingInExpected,
+        ..
+    }));
+    let mismatch = register(Definition::expression(
+        "with",
+        identifier().named("a") >> identifier().named("b"),
+        [Fragment::var("b"), Fragment::var("a")],
+    ));
+    assert_eq!(mismatch, RegistrationError::TemplateMismatch {
+        header:   "with".into(),
+        expected: "$a $b".into(),
+        found:    "$b $a".into(),
+    });
+    let mut parser = enso_parser::Parser::new();
+    let with = || Definition::expression("with", everything(), Template::Tokens);
+    parser.register_macro(with()).unwrap();
+    let duplicate = parser.register_macro(with()).unwrap_err();
+    assert_eq!(duplicate, RegistrationError::HeaderConflict {
+        header:   "with".into(),
+        existing: "with ...".into(),
+    });
+}
+
+
+
+// === Fuzzing Regressions ===
+
+/// Inputs for which the fuzzer (`cargo run -p enso-parser-debug --bin fuzz`) found a violation of
+/// the parser's round-trip invariants, minimized. With `--update-regressions`, the fuzzer adds any
+/// new failures to this list.
+#[rustfmt::skip]
+const FUZZ_REGRESSIONS: &[&str] = &[
+    "type-",
+    "type '\n",
+    "a..",
+    "n..%",
+    "1< \n ",
+    "'''` ",
+    "'`x `",
+    " ",
+    // Fuzzing regressions are inserted above this line.
+];
+
+#[test]
+fn fuzz_regressions() {
+    for code in FUZZ_REGRESSIONS {
+        if let Err(violation) = enso_parser_debug::fuzz::check(code) {
+            panic!("{code:?}: {violation}");
+        }
+    }
+}
+
+#[test]
+fn token_soup_round_trip() {
+    let failures = enso_parser_debug::fuzz::run(0..1000);
+    let report: Vec<_> = failures.iter().map(|failure| failure.to_string()).collect();
+    assert!(failures.is_empty(), "{}", report.join("\n"));
+}
+
 
 
 // ====================
@@ -1673,14 +1912,26 @@ fn expect_tree_representing_code(code: &str, ast: &enso_parser::syntax::Tree) {
 ///   example, a `token::Number` may be represented like: `sexp![10]`, and a `token::Ident` may look
 ///   like `sexp![foo]`.
 fn test(code: &str, expect: lexpr::Value) {
-    let ast = parse(code);
+    test_with(&enso_parser::Parser::new(), code, expect)
+}
+
+/// Like [`test`], but using the given parser.
+fn test_with(parser: &enso_parser::Parser, code: &str, expect: lexpr::Value) {
+    let ast = parse_with(parser, code);
     let ast_s_expr = to_s_expr(&ast, code);
     assert_eq!(ast_s_expr.to_string(), expect.to_string(), "{:?}", &ast);
     expect_tree_representing_code(code, &ast);
 }
 
 fn parse(code: &str) -> enso_parser::syntax::tree::Tree {
-    let ast = enso_parser::Parser::new().run(code);
+    parse_with(&enso_parser::Parser::new(), code)
+}
+
+fn parse_with<'s>(
+    parser: &enso_parser::Parser,
+    code: &'s str,
+) -> enso_parser::syntax::tree::Tree<'s> {
+    let ast = parser.run(code);
     let expected_span = 0..(code.encode_utf16().count() as u32);
     let mut locations = enso_parser::source::code::debug::LocationCheck::new();
     enso_parser_debug::validate_spans(&ast, expected_span, &mut locations);
@@ -1727,6 +1978,33 @@ fn expect_multiple_operator_error(code: &str) {
     assert!(errors.multiple_operator, "{:?}", enso_parser::Parser::new().run(code));
 }
 
+/// Returns the first error with the given code reported for the input, and the location of the
+/// start of the `Invalid` node it is attached to.
+fn find_error(
+    code: &str,
+    expected: enso_parser::syntax::tree::ErrorCode,
+) -> (enso_parser::syntax::tree::Error, enso_parser::source::code::Location) {
+    let ast = parse(code);
+    expect_tree_representing_code(code, &ast);
+    let found = core::cell::RefCell::new(vec![]);
+    ast.visit_trees(|tree| {
+        if let enso_parser::syntax::tree::Variant::Invalid(invalid) = &*tree.variant {
+            found.borrow_mut().push((invalid.error.clone(), tree.span.range().start));
+        }
+    });
+    let found = found.into_inner();
+    let codes: Vec<_> = found.iter().map(|(error, _)| error.code).collect();
+    found
+        .into_iter()
+        .find(|(error, _)| error.code == expected)
+        .unwrap_or_else(|| panic!("Expected {expected:?} in {code:?}, found: {codes:?}"))
+}
+
+/// Checks that an input reports an error with the given code.
+fn expect_error(code: &str, expected: enso_parser::syntax::tree::ErrorCode) {
+    find_error(code, expected);
+}
+
 /// Check that the input can be parsed, and doesn't yield any `Invalid` nodes.
 fn expect_valid(code: &str) {
     let errors = Errors::collect(code);
@@ -0,0 +1,107 @@
+//! Rendering the dependency graph with GraphViz.
+
+use crate::EdgeKind;
+use crate::Graph;
+
+use enso_metamodel::graphviz::Dot;
+use enso_metamodel::graphviz::DotCluster;
+use enso_metamodel::graphviz::DotEdge;
+use enso_metamodel::graphviz::DotNode;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+
+
+
+// ================
+// === Graphviz ===
+// ================
+
+/// Renders the graph in the DOT language. The modules of each library are grouped in a cluster;
+/// each external library is a single node. Repeated edges between the same modules are drawn once,
+/// and the edges within a cycle are highlighted.
+impl std::fmt::Display for Graph {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let module_attrs = vec![
+            format!("style=filled"),
+            format!("fillcolor={:?}", "#255C99"),
+            format!("fontcolor={:?}", "white"),
+            format!("shape=box"),
+        ];
+        let external_attrs = vec![
+            format!("style=filled"),
+            format!("fillcolor={:?}", "#262626"),
+            format!("fontcolor={:?}", "white"),
+            format!("shape=box3d"),
+        ];
+        let cluster_attrs = vec![format!("style=filled"), format!("fillcolor={:?}", "#E6EDF5")];
+        let cycle_color = "#B3001B";
+        let import_edge_attrs = vec![];
+        let export_edge_attrs = vec![format!("color={:?}", "#7EA3CC"), format!("arrowhead=dot")];
+        let import_cycle_edge_attrs = vec![format!("color={cycle_color:?}"), format!("penwidth=2")];
+        let export_cycle_edge_attrs =
+            vec![format!("color={cycle_color:?}"), format!("penwidth=2"), format!("arrowhead=dot")];
+        let mut components = HashMap::new();
+        for (index, cycle) in self.cycles.iter().enumerate() {
+            for module in &cycle.modules {
+                components.insert((cycle.kind, module.as_str()), index);
+            }
+        }
+        let in_cycle = |kind: EdgeKind, from: &str, to: &str| {
+            let kinds = match kind {
+                EdgeKind::Export => &[EdgeKind::Export, EdgeKind::Import][..],
+                EdgeKind::Import => &[EdgeKind::Import][..],
+            };
+            kinds.iter().any(|&kind| {
+                let from = components.get(&(kind, from));
+                from.is_some() && from == components.get(&(kind, to))
+            })
+        };
+        let mut libraries: BTreeMap<&str, Vec<_>> = BTreeMap::new();
+        for module in &self.modules {
+            libraries.entry(&module.library).or_default().push(module);
+        }
+        let mut dot = Dot::new("modules");
+        for (library, modules) in &libraries {
+            let mut nodes = vec![];
+            for module in modules {
+                let mut attrs;
+                let label;
+                if module.external {
+                    attrs = external_attrs.clone();
+                    label = module.name.as_str();
+                } else {
+                    attrs = module_attrs.clone();
+                    let relative = module.name.strip_prefix(&module.library);
+                    label =
+                        relative.and_then(|name| name.strip_prefix('.')).unwrap_or(&module.name);
+                }
+                attrs.push(format!("label={label:?}"));
+                nodes.push(DotNode { id: module.name.clone(), attrs });
+            }
+            if modules.iter().any(|module| !module.external) {
+                let mut attrs = cluster_attrs.clone();
+                attrs.push(format!("label={library:?}"));
+                dot.clusters.push(DotCluster { attrs, nodes });
+            } else {
+                dot.nodes.extend(nodes);
+            }
+        }
+        let mut drawn = BTreeSet::new();
+        for edge in &self.edges {
+            let (from, to) = (edge.from.as_str(), edge.to.as_str());
+            if !drawn.insert((from, to, edge.kind)) {
+                continue;
+            }
+            let attrs = match (edge.kind, in_cycle(edge.kind, from, to)) {
+                (EdgeKind::Import, false) => &import_edge_attrs,
+                (EdgeKind::Export, false) => &export_edge_attrs,
+                (EdgeKind::Import, true) => &import_cycle_edge_attrs,
+                (EdgeKind::Export, true) => &export_cycle_edge_attrs,
+            };
+            let (from, to, attrs) = (from.to_owned(), to.to_owned(), attrs.clone());
+            dot.edges.push(DotEdge { from, to, attrs });
+        }
+        write!(f, "{dot}")
+    }
+}
@@ -0,0 +1,758 @@
+//! The dependency graph of the modules of Enso libraries, derived from their imports and exports.
+//!
+//! A [`Builder`] collects the modules of one or more libraries: [`Builder::add_library`] parses all
+//! the sources of a [`Library`], and [`Builder::add_module`] adds a module that has already been
+//! parsed. [`Builder::build`] then resolves the target of every import and export statement to a
+//! module, and produces a [`Graph`] with an edge for each statement, along with the [`Problem`]s
+//! found:
+//! - modules that import or export each other in a cycle; the engine otherwise only reports these
+//!   when it loads the modules;
+//! - imports of names the module never uses, and imports or exports that are repeated;
+//! - imports and exports of modules that do not exist in a library that was added.
+//!
+//! The modules of libraries that were not added are represented by a single external node for each
+//! such library. The graph can be serialized to JSON, and its [`Display`](std::fmt::Display)
+//! implementation renders it in the GraphViz DOT language.
+
+// === Features ===
+#![feature(let_chains)]
+// === Non-Standard Linter Configuration ===
+#![allow(clippy::option_map_unit_fn)]
+#![allow(clippy::precedence)]
+#![allow(dead_code)]
+#![deny(unconditional_recursion)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(unused_qualifications)]
+
+use enso_parser::outline;
+use enso_parser::project;
+use enso_parser::project::Library;
+use enso_parser::source::code::Location;
+use enso_parser::syntax::tree;
+use enso_parser::syntax::tree::error::Severity;
+use enso_parser::syntax::Tree;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::num::NonZeroUsize;
+use std::ops::Range;
+use std::path::PathBuf;
+
+
+
+// ==============
+// === Export ===
+// ==============
+
+mod graphviz;
+
+
+
+// =============
+// === Graph ===
+// =============
+
+/// The dependencies between the modules of a set of libraries.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Graph {
+    /// The modules, ordered by name. Each library that is referred to, but was not added to the
+    /// [`Builder`], is represented by an external module named by the library.
+    pub modules:  Vec<Module>,
+    /// An edge for each import or export statement whose target was found, ordered by the module
+    /// containing the statement.
+    pub edges:    Vec<Edge>,
+    /// The groups of modules that depend on each other.
+    pub cycles:   Vec<Cycle>,
+    /// The problems found, ordered by module and location.
+    pub problems: Vec<Problem>,
+}
+
+/// A node of the [`Graph`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Module {
+    /// The qualified name of the module.
+    pub name:     String,
+    /// The qualified name of the library containing the module.
+    pub library:  String,
+    /// The source file of the module; absent for an external library, and for a directory that
+    /// has no source file of its own.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path:     Option<PathBuf>,
+    /// Whether this node stands for all the modules of a library that was not added to the
+    /// [`Builder`].
+    pub external: bool,
+}
+
+/// The kind of statement an [`Edge`] is derived from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EdgeKind {
+    /// An `import` statement.
+    Import,
+    /// An `export` statement.
+    Export,
+}
+
+/// A dependency of a module on another, due to an import or export statement.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Edge {
+    /// The module containing the statement.
+    pub from:  String,
+    /// The module imported or exported, or containing the entity imported or exported.
+    pub to:    String,
+    /// The kind of statement.
+    pub kind:  EdgeKind,
+    /// The location of the statement.
+    pub range: Range<Location>,
+}
+
+/// A strongly connected component of the graph: a group of modules each of which depends,
+/// directly or indirectly, on all the others.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Cycle {
+    /// [`EdgeKind::Export`] if the modules export each other; otherwise, the dependencies include
+    /// imports.
+    pub kind:    EdgeKind,
+    /// The modules of the component, ordered by name.
+    pub modules: Vec<String>,
+}
+
+/// The kind of a [`Problem`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProblemKind {
+    /// Modules export each other in a cycle.
+    ExportCycle,
+    /// Modules depend on each other in a cycle that includes imports.
+    ImportCycle,
+    /// The target of an import or export is not a module of a library that was added, nor an
+    /// entity defined or exported by one.
+    MissingModule,
+    /// A statement is identical to an earlier one in the same module.
+    Duplicate,
+    /// A name bound by an import is never used.
+    UnusedImport,
+}
+
+impl ProblemKind {
+    /// How serious a problem of this kind is.
+    pub fn severity(self) -> Severity {
+        match self {
+            ProblemKind::ExportCycle | ProblemKind::MissingModule => Severity::Error,
+            ProblemKind::ImportCycle | ProblemKind::Duplicate | ProblemKind::UnusedImport =>
+                Severity::Warning,
+        }
+    }
+}
+
+/// A problem found in the imports or exports of a module.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Problem {
+    /// Identifies the kind of problem.
+    pub kind:     ProblemKind,
+    /// How serious the problem is.
+    pub severity: Severity,
+    /// The module containing the statement the problem is reported at.
+    pub module:   String,
+    /// The source file of the module.
+    pub path:     PathBuf,
+    /// The location of the statement.
+    pub range:    Range<Location>,
+    /// Human-readable description of the problem.
+    pub message:  String,
+}
+
+
+
+// ===============
+// === Builder ===
+// ===============
+
+/// The name given to a library whose package file does not specify its name; this matches how its
+/// modules refer to each other (`project.Module`).
+pub const UNNAMED_LIBRARY: &str = "project";
+
+/// Collects the modules of a set of libraries, to build their [`Graph`].
+#[derive(Debug, Default)]
+pub struct Builder {
+    libraries:   BTreeSet<String>,
+    modules:     BTreeMap<String, Source>,
+    /// The names of the directories containing modules, mapped to their libraries. A directory is
+    /// a module, even if it has no source file of its own.
+    directories: BTreeMap<String, String>,
+}
+
+/// What the builder needs to know about a module.
+#[derive(Debug)]
+struct Source {
+    library:     String,
+    path:        PathBuf,
+    outline:     outline::Outline,
+    /// The identifiers occurring in the module, outside of its import and export statements.
+    identifiers: HashSet<String>,
+}
+
+impl Builder {
+    /// Constructor.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse all the modules of the library, using the given number of threads, and add them.
+    pub fn add_library(&mut self, library: &Library, threads: NonZeroUsize) -> std::io::Result<()> {
+        let name = library.name.clone().unwrap_or_else(|| UNNAMED_LIBRARY.to_owned());
+        let library = Library { root: library.root.clone(), name: Some(name.clone()) };
+        let src = library.sources_dir();
+        let sources = project::SourceFile::discover(&src)?;
+        let parser = enso_parser::Parser::new();
+        let batch = project::parse(&parser, &sources, threads);
+        for file in &batch.files {
+            let module = library.module_name(file.path.strip_prefix(&src).unwrap_or(file.path));
+            self.add_module(&name, module, file.path.to_owned(), &file.tree);
+        }
+        Ok(())
+    }
+
+    /// Add a module of the named library. If a module of the same name was already added, it is
+    /// replaced.
+    pub fn add_module(&mut self, library: &str, module: String, path: PathBuf, tree: &Tree) {
+        self.libraries.insert(library.to_owned());
+        let outline = outline::extract(tree, Some(library));
+        let identifiers = identifiers(tree);
+        let mut directory = module.as_str();
+        while let Some((parent, _)) = directory.rsplit_once('.')
+            && parent.len() > library.len()
+        {
+            self.directories.insert(parent.to_owned(), library.to_owned());
+            directory = parent;
+        }
+        let library = library.to_owned();
+        self.modules.insert(module, Source { library, path, outline, identifiers });
+    }
+
+    /// Resolve the imports and exports of the modules, and find the problems in them.
+    pub fn build(&self) -> Graph {
+        let mut graph = Graph::default();
+        let mut external = BTreeSet::new();
+        for (module, source) in &self.modules {
+            let mut statements = BTreeSet::new();
+            let imports = source.outline.imports.iter().map(|import| (EdgeKind::Import, import));
+            let exports = source.outline.exports.iter().map(|export| (EdgeKind::Export, export));
+            for (kind, statement) in imports.chain(exports) {
+                if statement.polyglot.is_some() {
+                    continue;
+                }
+                let problem = |kind: ProblemKind, message: String| Problem {
+                    kind,
+                    severity: kind.severity(),
+                    module: module.clone(),
+                    path: source.path.clone(),
+                    range: statement.range.clone(),
+                    message,
+                };
+                let (target, entity) = match self.resolve(&statement.module) {
+                    Target::Module(target) => (target.to_owned(), false),
+                    Target::Entity(target) => (target.to_owned(), true),
+                    Target::External(library) => {
+                        external.insert(library.clone());
+                        (library, false)
+                    }
+                    Target::Missing(library) => {
+                        let message = format!(
+                            "`{}` is not a module of `{library}`, nor an entity defined or \
+                             exported by one.",
+                            statement.module
+                        );
+                        graph.problems.push(problem(ProblemKind::MissingModule, message));
+                        continue;
+                    }
+                };
+                if !statements.insert((kind, normalized(statement))) {
+                    let message = format!("Repeated {} of `{}`.", kind.verb(), statement.module);
+                    graph.problems.push(problem(ProblemKind::Duplicate, message));
+                } else if kind == EdgeKind::Import {
+                    let module = (!entity).then_some(target.as_str());
+                    let unused = self.unused_names(source, statement, module);
+                    if !unused.is_empty() {
+                        let names: Vec<_> = unused.iter().map(|name| format!("`{name}`")).collect();
+                        let message = format!("Unused import of {}.", names.join(", "));
+                        graph.problems.push(problem(ProblemKind::UnusedImport, message));
+                    }
+                }
+                // A module may refer to its own entities, e.g. to export the constructors of a
+                // type.
+                if entity && target == *module {
+                    continue;
+                }
+                let from = module.clone();
+                graph.edges.push(Edge { from, to: target, kind, range: statement.range.clone() });
+            }
+        }
+        let modules = self.modules.iter().map(|(name, source)| Module {
+            name:     name.clone(),
+            library:  source.library.clone(),
+            path:     Some(source.path.clone()),
+            external: false,
+        });
+        let directories =
+            self.directories.iter().filter(|(name, _)| !self.modules.contains_key(*name));
+        let directories = directories.map(|(name, library)| Module {
+            name:     name.clone(),
+            library:  library.clone(),
+            path:     None,
+            external: false,
+        });
+        let external = external.into_iter().map(|library| Module {
+            name:     library.clone(),
+            library:  library.clone(),
+            path:     None,
+            external: true,
+        });
+        graph.modules = modules.chain(directories).chain(external).collect();
+        graph.modules.sort_by(|a, b| a.name.cmp(&b.name));
+        self.find_cycles(&mut graph);
+        let key = |problem: &Problem| (problem.path.clone(), problem.range.start, problem.kind);
+        graph.problems.sort_by_key(key);
+        graph
+    }
+}
+
+
+// === Resolution ===
+
+/// The module an import or export refers to.
+#[derive(Debug, PartialEq, Eq)]
+enum Target<'a> {
+    /// A module that was added, or a directory containing one.
+    Module(&'a str),
+    /// An entity defined or exported by the module, which was added.
+    Entity(&'a str),
+    /// A module of the named library, which was not added.
+    External(String),
+    /// No module of the named library, which was added.
+    Missing(&'a str),
+}
+
+impl Builder {
+    /// Find the module a qualified name refers to: the module of that name, or the module defining
+    /// or exporting the entity of that name, such as a type.
+    fn resolve<'a>(&'a self, name: &'a str) -> Target<'a> {
+        // The `Main` module of a library is named by the library itself.
+        let main = |library: &'a String| {
+            (name.strip_prefix(library.as_str())? == ".Main").then_some(library.as_str())
+        };
+        let name = self.libraries.iter().find_map(main).unwrap_or(name);
+        if let Some((directory, _)) = self.directories.get_key_value(name)
+            && !self.modules.contains_key(name)
+        {
+            return Target::Module(directory);
+        }
+        let mut prefix = name;
+        loop {
+            if let Some((module, source)) = self.modules.get_key_value(prefix) {
+                let rest = name[prefix.len()..].strip_prefix('.');
+                match rest {
+                    None => return Target::Module(module),
+                    Some(rest) if !rest.contains('.') && provides(source, rest) =>
+                        return Target::Entity(module),
+                    Some(_) => break,
+                }
+            }
+            match prefix.rsplit_once('.') {
+                Some((parent, _)) => prefix = parent,
+                None => break,
+            }
+        }
+        let in_library = |library: &&String| {
+            name.strip_prefix(library.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+        };
+        match self.libraries.iter().find(in_library) {
+            Some(library) => Target::Missing(library),
+            None => Target::External(name.split('.').take(2).collect::<Vec<_>>().join(".")),
+        }
+    }
+
+    /// The names bound by an import that never occur in the module, and are not exported by it
+    /// either. Imports of all the entities of a module are never considered unused, nor are imports
+    /// of a whole module that defines extension methods, as they may be needed to bring the
+    /// methods into scope. The module is given if the import refers to a module rather than an
+    /// entity.
+    fn unused_names<'a>(
+        &self,
+        source: &Source,
+        import: &'a outline::Import,
+        module: Option<&str>,
+    ) -> Vec<&'a str> {
+        if import.all {
+            return vec![];
+        }
+        let bound: Vec<&str> = match &import.alias {
+            Some(alias) => vec![alias.as_str()],
+            None if import.names.is_empty() => vec![last_segment(&import.module)],
+            None => import.names.iter().map(|name| last_segment(name)).collect(),
+        };
+        let extends =
+            |module: &Source| module.outline.functions.iter().any(|f| f.name.contains('.'));
+        if import.names.is_empty()
+            && let Some(module) = module
+            && self.modules.get(module).is_some_and(extends)
+        {
+            return vec![];
+        }
+        let used = |name: &&str| source.identifiers.contains(*name) || provides(source, name);
+        bound.into_iter().filter(|name| !used(name)).collect()
+    }
+}
+
+/// Whether the module defines or exports an entity of the given name.
+fn provides(source: &Source, name: &str) -> bool {
+    let outline = &source.outline;
+    outline.types.iter().any(|type_| type_.name == name)
+        || outline.functions.iter().any(|function| function.name == name)
+        || outline.exports.iter().any(|export| {
+            export.all
+                || export.alias.as_deref() == Some(name)
+                || export.alias.is_none()
+                    && match export.names.is_empty() {
+                        true => last_segment(&export.module) == name,
+                        false => export.names.iter().any(|export| last_segment(export) == name),
+                    }
+        })
+}
+
+/// The parts of a statement that determine its meaning, for detecting repeated statements.
+fn normalized(
+    statement: &outline::Import,
+) -> (&str, BTreeSet<&str>, bool, BTreeSet<&str>, Option<&str>) {
+    let names = statement.names.iter().map(String::as_str).collect();
+    let hiding = statement.hiding.iter().map(String::as_str).collect();
+    (&statement.module, names, statement.all, hiding, statement.alias.as_deref())
+}
+
+fn last_segment(name: &str) -> &str {
+    name.rsplit('.').next().unwrap_or(name)
+}
+
+/// The identifiers occurring in the module, outside of its import and export statements.
+fn identifiers(tree: &Tree) -> HashSet<String> {
+    let mut identifiers = HashSet::new();
+    let tree::Variant::BodyBlock(block) = &*tree.variant else { return identifiers };
+    for statement in block.statements.iter().filter_map(|line| line.expression.as_ref()) {
+        if matches!(&*statement.variant, tree::Variant::Import(_) | tree::Variant::Export(_)) {
+            continue;
+        }
+        let mut visit = |tree: &Tree| {
+            if let tree::Variant::Ident(ident) = &*tree.variant {
+                identifiers.insert(ident.token.code.repr.0.to_string());
+            }
+        };
+        visit(statement);
+        statement.visit_trees(&mut visit);
+    }
+    identifiers
+}
+
+impl EdgeKind {
+    fn verb(self) -> &'static str {
+        match self {
+            EdgeKind::Import => "import",
+            EdgeKind::Export => "export",
+        }
+    }
+}
+
+
+// === Cycles ===
+
+impl Builder {
+    /// Find the strongly connected components of the graph formed by the exports alone, and of the
+    /// graph formed by all the edges, and report each as a cycle. A component of the second graph
+    /// is only reported if it is not also a component of the first.
+    fn find_cycles(&self, graph: &mut Graph) {
+        let mut export_cycles = BTreeSet::new();
+        for kind in [EdgeKind::Export, EdgeKind::Import] {
+            let edges =
+                graph.edges.iter().filter(|edge| kind == EdgeKind::Import || edge.kind == kind);
+            let edges: Vec<_> = edges.filter(|edge| self.modules.contains_key(&edge.to)).collect();
+            let mut successors: BTreeMap<&str, Vec<&Edge>> = BTreeMap::new();
+            for edge in &edges {
+                successors.entry(&edge.from).or_default().push(edge);
+            }
+            for modules in components(&successors) {
+                let looped = |module: &&str| {
+                    let edges = successors.get(module).into_iter().flatten();
+                    edges.into_iter().any(|edge| edge.to == *module)
+                };
+                if modules.len() == 1 && !modules.first().is_some_and(looped) {
+                    continue;
+                }
+                let names: Vec<String> = modules.iter().map(|module| module.to_string()).collect();
+                if kind == EdgeKind::Export {
+                    export_cycles.insert(names.clone());
+                } else if export_cycles.contains(&names) {
+                    continue;
+                }
+                let members: HashSet<&str> = modules.iter().copied().collect();
+                let path = shortest_cycle(&successors, &members, modules[0]);
+                let first = path[0];
+                let source = &self.modules[first.from.as_str()];
+                let mut steps = vec![first.from.as_str()];
+                steps.extend(path.iter().map(|edge| edge.to.as_str()));
+                let (problem, verb) = match kind {
+                    EdgeKind::Export => (ProblemKind::ExportCycle, "export"),
+                    EdgeKind::Import => (ProblemKind::ImportCycle, "depend on"),
+                };
+                let message = match modules.len() {
+                    1 => format!("`{}` must not {verb} itself.", first.from),
+                    n => format!(
+                        "{n} modules {verb} each other in a cycle, e.g. {}.",
+                        steps
+                            .iter()
+                            .map(|step| format!("`{step}`"))
+                            .collect::<Vec<_>>()
+                            .join(" -> ")
+                    ),
+                };
+                graph.problems.push(Problem {
+                    kind: problem,
+                    severity: problem.severity(),
+                    module: first.from.clone(),
+                    path: source.path.clone(),
+                    range: first.range.clone(),
+                    message,
+                });
+                graph.cycles.push(Cycle { kind, modules: names });
+            }
+        }
+    }
+}
+
+/// The strongly connected components of the graph, each ordered by name, computed with Tarjan's
+/// algorithm.
+fn components<'a>(successors: &BTreeMap<&'a str, Vec<&'a Edge>>) -> Vec<Vec<&'a str>> {
+    #[derive(Default)]
+    struct State<'a> {
+        index:      HashMap<&'a str, usize>,
+        lowlink:    HashMap<&'a str, usize>,
+        stack:      Vec<&'a str>,
+        on_stack:   HashSet<&'a str>,
+        components: Vec<Vec<&'a str>>,
+    }
+    // The graph of the standard library is deep enough to exhaust the stack of a recursive
+    // implementation, so the recursion is simulated with an explicit stack of frames, each holding
+    // a node and the position of the next of its successors to visit.
+    let mut state = State::default();
+    for &root in successors.keys() {
+        if state.index.contains_key(root) {
+            continue;
+        }
+        let mut frames = vec![(root, 0)];
+        while let Some(&mut (node, ref mut next)) = frames.last_mut() {
+            if *next == 0 && !state.index.contains_key(node) {
+                let index = state.index.len();
+                state.index.insert(node, index);
+                state.lowlink.insert(node, index);
+                state.stack.push(node);
+                state.on_stack.insert(node);
+            }
+            let edges = successors.get(node).map_or(&[][..], |edges| &edges[..]);
+            if let Some(edge) = edges.get(*next) {
+                *next += 1;
+                let target = edge.to.as_str();
+                if !state.index.contains_key(target) {
+                    frames.push((target, 0));
+                } else if state.on_stack.contains(target) {
+                    let lowlink = state.lowlink[node].min(state.index[target]);
+                    state.lowlink.insert(node, lowlink);
+                }
+                continue;
+            }
+            frames.pop();
+            if let Some(&(parent, _)) = frames.last() {
+                let lowlink = state.lowlink[parent].min(state.lowlink[node]);
+                state.lowlink.insert(parent, lowlink);
+            }
+            if state.lowlink[node] == state.index[node] {
+                let mut component = vec![];
+                while let Some(member) = state.stack.pop() {
+                    state.on_stack.remove(member);
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                component.sort_unstable();
+                state.components.push(component);
+            }
+        }
+    }
+    state.components.sort();
+    state.components
+}
+
+/// The edges of a shortest path from the module back to itself, within the given component.
+fn shortest_cycle<'a>(
+    successors: &BTreeMap<&'a str, Vec<&'a Edge>>,
+    component: &HashSet<&str>,
+    start: &'a str,
+) -> Vec<&'a Edge> {
+    let mut reached: HashMap<&str, &Edge> = HashMap::new();
+    let mut queue = VecDeque::from([start]);
+    while let Some(node) = queue.pop_front() {
+        for &edge in successors.get(node).into_iter().flatten() {
+            let target = edge.to.as_str();
+            if !component.contains(target) || reached.contains_key(target) {
+                continue;
+            }
+            reached.insert(target, edge);
+            if target == start {
+                let mut path = vec![edge];
+                while path.last().unwrap().from != start {
+                    path.push(reached[path.last().unwrap().from.as_str()]);
+                }
+                path.reverse();
+                return path;
+            }
+            queue.push_back(target);
+        }
+    }
+    unreachable!("Every module of a cycle is reachable from the others.")
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build the graph of a library named `Acme.Lib`, from the paths and code of its modules.
+    fn build(modules: &[(&str, &str)]) -> Graph {
+        let parser = enso_parser::Parser::new();
+        let library = Library { root: PathBuf::from("Lib"), name: Some("Acme.Lib".into()) };
+        let mut builder = Builder::new();
+        for (path, code) in modules {
+            let tree = parser.run(code);
+            let module = library.module_name(path.as_ref());
+            builder.add_module("Acme.Lib", module, PathBuf::from(path), &tree);
+        }
+        builder.build()
+    }
+
+    fn problems(graph: &Graph) -> Vec<(ProblemKind, &str, u32)> {
+        let problems = graph.problems.iter();
+        problems.map(|p| (p.kind, p.module.as_str(), p.range.start.line)).collect()
+    }
+
+    #[test]
+    fn edges() {
+        let graph = build(&[
+            ("Main.enso", "from project.Data.Table export Table\nexport project.Errors"),
+            ("Data/Table.enso", "import Standard.Base.Data.Vector\ntype Table\nf = Vector.new"),
+            ("Errors.enso", "import project.Data.Table.Table\nfrom project import all\nf = Table"),
+        ]);
+        let edges: Vec<_> =
+            graph.edges.iter().map(|e| (e.from.as_str(), e.to.as_str(), e.kind)).collect();
+        assert_eq!(edges, [
+            ("Acme.Lib", "Acme.Lib.Data.Table", EdgeKind::Export),
+            ("Acme.Lib", "Acme.Lib.Errors", EdgeKind::Export),
+            ("Acme.Lib.Data.Table", "Standard.Base", EdgeKind::Import),
+            ("Acme.Lib.Errors", "Acme.Lib.Data.Table", EdgeKind::Import),
+            ("Acme.Lib.Errors", "Acme.Lib", EdgeKind::Import),
+        ]);
+        let modules: Vec<_> = graph.modules.iter().map(|m| (m.name.as_str(), m.external)).collect();
+        assert_eq!(modules, [
+            ("Acme.Lib", false),
+            ("Acme.Lib.Data", false),
+            ("Acme.Lib.Data.Table", false),
+            ("Acme.Lib.Errors", false),
+            ("Standard.Base", true),
+        ]);
+        assert_eq!(graph.cycles, [Cycle {
+            kind:    EdgeKind::Import,
+            modules: vec!["Acme.Lib".into(), "Acme.Lib.Errors".into()],
+        }]);
+        assert_eq!(problems(&graph), [(ProblemKind::ImportCycle, "Acme.Lib", 1)]);
+    }
+
+    #[test]
+    fn resolution() {
+        let graph = build(&[
+            ("Main.enso", "export project.Util.Helper\nfrom project.Util export nothing"),
+            ("Util.enso", "import project.Nowhere\nimport project.Util.helper\nhelper = Nowhere"),
+            ("Data/Table.enso", "import project.Data\nx = Data"),
+        ]);
+        assert_eq!(problems(&graph), [
+            (ProblemKind::MissingModule, "Acme.Lib", 0),
+            (ProblemKind::MissingModule, "Acme.Lib.Util", 0),
+        ]);
+        let edges: Vec<_> = graph.edges.iter().map(|edge| edge.to.as_str()).collect();
+        assert_eq!(edges, ["Acme.Lib.Util", "Acme.Lib.Data"]);
+    }
+
+    #[test]
+    fn export_cycles() {
+        let graph = build(&[
+            ("A.enso", "export project.B"),
+            ("B.enso", "export project.C"),
+            ("C.enso", "from project.A export all\nimport project.B\nx = B"),
+            ("D.enso", "import project.D\nx = D"),
+        ]);
+        let cycles: Vec<_> = graph.cycles.iter().map(|c| (c.kind, c.modules.len())).collect();
+        assert_eq!(cycles, [(EdgeKind::Export, 3), (EdgeKind::Import, 1)]);
+        assert_eq!(problems(&graph), [
+            (ProblemKind::ExportCycle, "Acme.Lib.A", 0),
+            (ProblemKind::ImportCycle, "Acme.Lib.D", 0),
+        ]);
+        assert_eq!(
+            graph.problems[0].message,
+            "3 modules export each other in a cycle, e.g. `Acme.Lib.A` -> `Acme.Lib.B` -> \
+             `Acme.Lib.C` -> `Acme.Lib.A`."
+        );
+    }
+
+    #[test]
+    fn unused_and_repeated_imports() {
+        let code = [
+            "import Standard.Base.Data.Vector.Vector",
+            "import Standard.Base.Data.Text.Text as T",
+            "from Standard.Base.Data.Maybe import Maybe, Nothing",
+            "from Standard.Base import all",
+            "import Standard.Base.Data.Vector.Vector",
+            "import project.Extensions",
+            "import project.Helpers",
+            "",
+            "f (x : Vector) = Maybe.Some x",
+        ];
+        let graph = build(&[
+            ("Main.enso", &code.join("\n")),
+            ("Extensions.enso", "Integer.twice self = self * 2"),
+            ("Helpers.enso", "help = 1"),
+        ]);
+        assert_eq!(problems(&graph), [
+            (ProblemKind::UnusedImport, "Acme.Lib", 1),
+            (ProblemKind::UnusedImport, "Acme.Lib", 2),
+            (ProblemKind::Duplicate, "Acme.Lib", 4),
+            (ProblemKind::UnusedImport, "Acme.Lib", 6),
+        ]);
+        assert_eq!(graph.problems[1].message, "Unused import of `Nothing`.");
+    }
+
+    #[test]
+    fn json() {
+        let graph = build(&[("Main.enso", "import project.Main")]);
+        let json = serde_json::to_value(graph).unwrap();
+        assert_eq!(json["edges"][0]["kind"], "import");
+        assert_eq!(json["cycles"][0]["modules"], serde_json::json!(["Acme.Lib"]));
+        assert_eq!(json["problems"][0]["kind"], "import_cycle");
+        assert_eq!(json["problems"][0]["severity"], "Warning");
+    }
+}
@@ -0,0 +1,106 @@
+//! Builds the dependency graph of the modules of Enso libraries, and reports the problems found in
+//! their imports and exports (see [`enso_dependency_graph`]).
+//!
+//! Usage: `enso-dependency-graph [--json|--dot] [--stdlib=DIR] [--threads=N] ROOT...`
+//!
+//! Each root is the directory of a library, containing its `package.yaml`, or a directory
+//! containing libraries, such as `distribution/lib`. The libraries found under the directory given
+//! with `--stdlib` are used to resolve imports; of their modules, only those the other libraries
+//! depend on directly are included in the output, and their problems are not reported. By default,
+//! each problem is printed on a line, with its location; with `--json`, the graph is written as
+//! JSON instead, and with `--dot`, it is rendered in the GraphViz DOT language. Exits with a
+//! non-zero status if any of the problems is an error.
+
+// === Non-Standard Linter Configuration ===
+#![allow(clippy::option_map_unit_fn)]
+#![allow(clippy::precedence)]
+#![allow(dead_code)]
+#![deny(unconditional_recursion)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(unused_qualifications)]
+
+use enso_dependency_graph::Builder;
+use enso_dependency_graph::UNNAMED_LIBRARY;
+use enso_parser::project;
+use enso_parser::project::Library;
+use enso_parser::syntax::tree::error::Severity;
+use std::collections::HashSet;
+use std::path::Path;
+
+
+
+fn main() {
+    let (flags, roots): (Vec<_>, Vec<_>) =
+        std::env::args().skip(1).partition(|arg| arg.starts_with("--"));
+    let mut threads = project::default_threads();
+    let mut stdlib = None;
+    let mut format = None;
+    for flag in &flags {
+        if let Some(value) = flag.strip_prefix("--threads=") {
+            threads = value.parse().expect("Invalid number of threads.");
+        } else if let Some(value) = flag.strip_prefix("--stdlib=") {
+            stdlib = Some(value.to_owned());
+        } else if flag == "--json" || flag == "--dot" {
+            format = Some(flag.as_str());
+        } else {
+            panic!("Unexpected argument: {flag}");
+        }
+    }
+    if roots.is_empty() {
+        panic!("Usage: enso-dependency-graph [--json|--dot] [--stdlib=DIR] [--threads=N] ROOT...");
+    }
+    let libraries: Vec<_> = roots.iter().flat_map(|root| libraries(Path::new(root))).collect();
+    let reported: HashSet<_> = libraries
+        .iter()
+        .map(|library| library.name.clone().unwrap_or_else(|| UNNAMED_LIBRARY.to_owned()))
+        .collect();
+    let mut builder = Builder::new();
+    if let Some(stdlib) = stdlib {
+        for library in Library::discover(stdlib).unwrap() {
+            if !library.name.as_ref().is_some_and(|name| reported.contains(name)) {
+                builder.add_library(&library, threads).unwrap();
+            }
+        }
+    }
+    for library in &libraries {
+        builder.add_library(library, threads).unwrap();
+    }
+    let mut graph = builder.build();
+    let modules = graph.modules.iter().filter(|module| reported.contains(&module.library));
+    let modules: HashSet<_> = modules.map(|module| module.name.clone()).collect();
+    graph.problems.retain(|problem| modules.contains(&problem.module));
+    graph.cycles.retain(|cycle| cycle.modules.iter().any(|module| modules.contains(module)));
+    graph.edges.retain(|edge| modules.contains(&edge.from));
+    let targets: HashSet<_> = graph.edges.iter().map(|edge| edge.to.clone()).collect();
+    graph.modules.retain(|module| modules.contains(&module.name) || targets.contains(&module.name));
+    match format {
+        Some("--json") => {
+            serde_json::to_writer_pretty(std::io::stdout(), &graph).unwrap();
+            println!();
+        }
+        Some(_) => print!("{graph}"),
+        None =>
+            for problem in &graph.problems {
+                let start = problem.range.start;
+                let severity = match problem.severity {
+                    Severity::Error => "error",
+                    Severity::Warning => "warning",
+                };
+                let path = problem.path.display();
+                let (line, col) = (start.line + 1, start.col16 + 1);
+                println!("{path}:{line}:{col}: {severity}: {}", problem.message);
+            },
+    }
+    if graph.problems.iter().any(|problem| problem.severity == Severity::Error) {
+        std::process::exit(1);
+    }
+}
+
+/// The library at the given directory, or the libraries found under it.
+fn libraries(root: &Path) -> Vec<Library> {
+    match root.join(project::PACKAGE_FILE).is_file() || root.join("src").is_dir() {
+        true => vec![Library::open(root)],
+        false => Library::discover(root).unwrap(),
+    }
+}
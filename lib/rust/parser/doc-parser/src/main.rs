@@ -13,7 +13,6 @@ use enso_doc_parser::*;
 use enso_parser::prelude::*;
 
 use enso_parser::syntax::tree::DocComment;
-use enso_parser::syntax::tree::TextElement;
 
 
 // ====================================
@@ -67,31 +66,7 @@ fn extract_docs(_filename: &str, mut code: &str) -> Vec<String> {
 /// Return the contents of the comment, with leading whitespace, the `##` token, and following
 /// empty lines removed; newlines will be normalized.
 pub fn content(node: &DocComment) -> String {
-    let mut buf = String::new();
-    for element in &node.elements {
-        match element {
-            TextElement::Section { text } => buf.push_str(&text.code.repr),
-            TextElement::Newline { .. } => buf.push('\n'),
-            TextElement::Escape {
-                token:
-                    token @ enso_parser::syntax::token::TextEscape {
-                        variant: enso_parser::syntax::token::variant::TextEscape { value },
-                        ..
-                    },
-            } => {
-                if let Some(c) = value.to_char() {
-                    buf.push(c);
-                } else {
-                    // Invalid escape character, or unpaired surrogate that can't be represented in
-                    // a Rust string.
-                    buf.push_str(**token.code)
-                }
-            }
-            // Unreachable.
-            TextElement::Splice { .. } => continue,
-        }
-    }
-    buf
+    node.content()
 }
 
 /// Lex the given documentation, and return the sequence of tokens.
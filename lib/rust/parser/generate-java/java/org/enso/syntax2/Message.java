@@ -16,6 +16,30 @@ final class Message {
     metadata = metadataIn;
   }
 
+  /**
+   * Read the header identifying the format of the message, and check that it matches the format
+   * the deserialization code was generated for.
+   */
+  void checkHeader(int version, long typeGraphHash) {
+    if (buffer.remaining() < 12) {
+      throw new FormatException("Message is too short to contain a header");
+    }
+    int foundVersion = get32();
+    long foundHash = get64();
+    if (foundVersion != version || foundHash != typeGraphHash) {
+      throw new FormatException(
+          "Expected format version "
+              + version
+              + " with type graph hash "
+              + Long.toHexString(typeGraphHash)
+              + ", found version "
+              + foundVersion
+              + " with hash "
+              + Long.toHexString(foundHash)
+              + ". The parser library does not match its generated Java bindings.");
+    }
+  }
+
   long get64() {
     return buffer.getLong();
   }
@@ -5,6 +5,8 @@ import java.net.URISyntaxException;
 import java.nio.ByteBuffer;
 import java.nio.ByteOrder;
 import java.nio.charset.StandardCharsets;
+import java.util.ArrayList;
+import java.util.List;
 
 public final class Parser implements AutoCloseable {
   static {
@@ -64,6 +66,8 @@ public final class Parser implements AutoCloseable {
 
   private static native ByteBuffer parseInput(long state, ByteBuffer input);
 
+  private static native ByteBuffer parseInputBatch(long state, ByteBuffer inputs);
+
   private static native long getLastInputBase(long state);
 
   private static native long getMetadata(long state);
@@ -86,7 +90,39 @@ public final class Parser implements AutoCloseable {
     var metadata = getMetadata(state);
     serializedTree.order(ByteOrder.LITTLE_ENDIAN);
     var message = new Message(serializedTree, input, base, metadata);
-    return Tree.deserialize(message);
+    return Tree.deserializeMessage(message);
+  }
+
+  public List<Tree> parseBatch(List<? extends CharSequence> inputs) {
+    var inputBytes = new ArrayList<byte[]>(inputs.size());
+    var size = 4;
+    for (var input : inputs) {
+      var bytes = input.toString().getBytes(StandardCharsets.UTF_8);
+      inputBytes.add(bytes);
+      size += 4 + bytes.length;
+    }
+    ByteBuffer inputBuf = ByteBuffer.allocateDirect(size);
+    inputBuf.order(ByteOrder.LITTLE_ENDIAN);
+    inputBuf.putInt(inputBytes.size());
+    for (var bytes : inputBytes) {
+      inputBuf.putInt(bytes.length);
+    }
+    for (var bytes : inputBytes) {
+      inputBuf.put(bytes);
+    }
+    var output = parseInputBatch(state, inputBuf);
+    output.order(ByteOrder.LITTLE_ENDIAN);
+    var trees = new ArrayList<Tree>(inputs.size());
+    for (var input : inputs) {
+      var base = output.getLong();
+      var metadata = output.getLong();
+      var length = (int) output.getLong();
+      var serializedTree = output.slice().limit(length).order(ByteOrder.LITTLE_ENDIAN);
+      output.position(output.position() + length);
+      var message = new Message(serializedTree, input, base, metadata);
+      trees.add(Tree.deserializeMessage(message));
+    }
+    return trees;
   }
 
   @Override
@@ -59,7 +59,7 @@ fn main() {
     println!("            CharSequence context = \"\";");
     println!("            Message message = new Message(buffer, context, 0, 0);");
     println!("            try {{");
-    println!("                Tree tree = Tree.deserialize(message);");
+    println!("                Tree tree = Tree.deserializeMessage(message);");
     println!("                System.out.println(\"- pass\");");
     println!("            }} catch (RuntimeException e) {{");
     println!("                System.out.println(\"- fail:\");");
@@ -73,7 +73,7 @@ fn main() {
     println!("            CharSequence context = \"\";");
     println!("            Message message = new Message(buffer, context, 0, 0);");
     println!("            try {{");
-    println!("                Tree tree = Tree.deserialize(message);");
+    println!("                Tree tree = Tree.deserializeMessage(message);");
     println!("                System.out.println(\"- fail: accepted\");");
     println!("                result = 1;");
     println!("            }} catch ({serialization}.FormatException e) {{");
@@ -42,12 +42,30 @@ pub const EITHER_TYPE: &str = "org.enso.syntax2.Either";
 use enso_parser::syntax;
 
 /// Generate accept/reject test case set for the parser types rooted at `syntax::Tree`.
+///
+/// Each case begins with a serialization [`Header`](enso_parser::serialization::Header). In
+/// addition to the cases rejected for their contents, an accepted case is included in the rejected
+/// set with each of: an unexpected format version, an unexpected type graph hash, and no header.
 pub fn generate_testcases() -> meta::serialization::TestCases {
+    use enso_parser::serialization::Header;
+    use enso_parser::serialization::FORMAT_VERSION;
     let root = syntax::Tree::reflect();
     let root_id = root.id;
     let (graph, rust_to_meta) = enso_metamodel::rust::to_meta(root);
     let root = rust_to_meta[&root_id];
-    meta::serialization::testcases(&graph, root)
+    let mut cases = meta::serialization::testcases(&graph, root);
+    let header = Header::new(FORMAT_VERSION);
+    let with_header = |header: Header, case: &[u8]| [&header.to_bytes()[..], case].concat();
+    let accepted = cases.accept[0].clone();
+    for case in cases.accept.iter_mut().chain(cases.reject.iter_mut()) {
+        *case = with_header(header, case);
+    }
+    let old_version = Header { version: FORMAT_VERSION.wrapping_sub(1), ..header };
+    let old_types = Header { type_graph_hash: !header.type_graph_hash, ..header };
+    cases.reject.push(with_header(old_version, &accepted));
+    cases.reject.push(with_header(old_types, &accepted));
+    cases.reject.push(accepted);
+    cases
 }
 
 
@@ -31,6 +31,8 @@ pub fn derive(graph: &mut TypeGraph, tree: ClassId, token: ClassId) {
     graph[token].methods.push(impl_whitespace_getter(WHITESPACE_GETTER));
     graph[tree].methods.push(impl_getter(CODE_GETTER));
     graph[tree].methods.push(impl_whitespace_getter(WHITESPACE_GETTER));
+    let tree_type = syntax::Type::named(&graph[tree].name);
+    graph[tree].methods.push(impl_deserialize_message(tree_type));
 }
 
 
@@ -167,6 +169,24 @@ fn end_code_token() -> impl for<'a> Fn(MaterializerInput<'a>) -> String + 'stati
 }
 
 
+/// Deserialize a tree from the output of the parser, after checking that it begins with the
+/// serialization header of the types the code was generated from.
+fn impl_deserialize_message(tree: syntax::Type) -> Method {
+    let header =
+        enso_parser::serialization::Header::new(enso_parser::serialization::FORMAT_VERSION);
+    let version = header.version;
+    let hash = header.type_graph_hash;
+    let mut method = syntax::Method::new("deserializeMessage", tree);
+    method.static_ = true;
+    method.visibility = None;
+    let message_ty = syntax::Type::named(format!("{}.Message", crate::SERIALIZATION_SUPPORT));
+    method.arguments = vec![(message_ty, "message".to_owned())];
+    method.body =
+        format!("message.checkHeader({version}, 0x{hash:016x}L);\nreturn deserialize(message);\n");
+    Method::Raw(method)
+}
+
+
 // === Source Code Getters ===
 
 fn impl_getter(name: &str) -> Method {
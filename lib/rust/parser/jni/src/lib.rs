@@ -15,6 +15,7 @@ use jni::objects::JByteBuffer;
 use jni::objects::JClass;
 use jni::sys::jobject;
 use jni::JNIEnv;
+use std::sync::OnceLock;
 
 
 
@@ -29,8 +30,8 @@ use jni::JNIEnv;
 ///
 /// The state MUST be a value returned by `allocState` that has not been passed to `freeState`.
 /// The input buffer contents MUST be valid UTF-8.
-/// The contents of the returned buffer MUST not be accessed after another call to `parseInput`, or
-/// a call to `freeState`.
+/// The contents of the returned buffer MUST not be accessed after another call to `parseInput` or
+/// `parseInputBatch`, or a call to `freeState`.
 #[allow(unsafe_code)]
 #[no_mangle]
 pub extern "system" fn Java_org_enso_syntax2_Parser_parseInput(
@@ -40,40 +41,64 @@ pub extern "system" fn Java_org_enso_syntax2_Parser_parseInput(
     input: JByteBuffer,
 ) -> jobject {
     let state = unsafe { &mut *(state as usize as *mut State) };
-    let direct_allocated = "Internal Error: ByteBuffer must be direct-allocated.";
-    let input = env.get_direct_buffer_address(input).expect(direct_allocated);
-    let input = if cfg!(debug_assertions) {
-        std::str::from_utf8(input).unwrap()
-    } else {
-        unsafe { std::str::from_utf8_unchecked(input) }
-    };
-    let mut code = input;
-    let mut meta = None;
-    if let Some((meta_, code_)) = enso_parser::metadata::parse(input) {
-        match meta_ {
-            Ok(meta_) => meta = Some(meta_),
-            Err(e) => eprintln!("Ignoring invalid metadata: {e}."),
-        }
-        code = code_;
+    let input = bytes_to_str(direct_buffer(&env, input));
+    state.clear();
+    let parsed = state.parse(input);
+    state.base = parsed.base;
+    let result = env.new_direct_byte_buffer(&mut state.output);
+    result.unwrap().into_inner()
+}
+
+/// Parse a batch of inputs, reusing the parser state for each of them. Returns the serialized
+/// representations of the parse trees. The caller is responsible for freeing the memory associated
+/// with the returned buffer.
+///
+/// The input buffer contains: the number of inputs, as a little-endian `u32`; the length in bytes
+/// of each input, as a little-endian `u32`; then the concatenated inputs.
+///
+/// The output buffer contains, for each input: the `base` parameter to pass to the `Message`
+/// class; the metadata pointer, as would be returned by `getMetadata`; the length in bytes of the
+/// serialized tree; all as little-endian `u64`s, followed by the serialized tree.
+///
+/// # Safety
+///
+/// The state MUST be a value returned by `allocState` that has not been passed to `freeState`.
+/// The input buffer MUST have the layout described above, and each input MUST be valid UTF-8.
+/// The contents of the returned buffer, and the metadata pointers it contains, MUST not be
+/// accessed after another call to `parseInput` or `parseInputBatch`, or a call to `freeState`.
+#[allow(unsafe_code)]
+#[no_mangle]
+pub extern "system" fn Java_org_enso_syntax2_Parser_parseInputBatch(
+    env: JNIEnv,
+    _class: JClass,
+    state: u64,
+    input: JByteBuffer,
+) -> jobject {
+    let state = unsafe { &mut *(state as usize as *mut State) };
+    let input = direct_buffer(&env, input);
+    let read_u32 = |bytes: &[u8]| u32::from_le_bytes(bytes.try_into().unwrap()) as usize;
+    let count = read_u32(&input[..4]);
+    let (lengths, mut inputs) = input[4..].split_at(count * 4);
+    state.clear();
+    for length in lengths.chunks_exact(4) {
+        let (module, rest) = inputs.split_at(read_u32(length));
+        inputs = rest;
+        let header_start = state.output.len();
+        state.output.extend_from_slice(&[0; 3 * 8]);
+        let parsed = state.parse(bytes_to_str(module));
+        let tree_length = (state.output.len() - header_start - 3 * 8) as u64;
+        let header = [parsed.base, parsed.metadata, tree_length];
+        let header = header.iter().flat_map(|value| value.to_le_bytes());
+        state.output.splice(header_start..header_start + 3 * 8, header);
+        state.base = parsed.base;
     }
-    state.base = str::as_ptr(code) as usize as u64;
-    let tree = enso_parser::Parser::new().run(code);
-    state.output = match enso_parser::serialization::serialize_tree(&tree) {
-        Ok(tree) => tree,
-        // `Tree` does not contain any types with fallible `serialize` implementations, so this
-        // cannot fail.
-        Err(_) => {
-            debug_assert!(false);
-            default()
-        }
-    };
-    state.metadata = meta;
     let result = env.new_direct_byte_buffer(&mut state.output);
     result.unwrap().into_inner()
 }
 
 /// Return the `base` parameter to pass to the `Message` class along with the other output of the
-/// most recent call to `parseInput`.
+/// most recent call to `parseInput`. After a call to `parseInputBatch`, this is the `base` of the
+/// last input of the batch.
 ///
 /// # Safety
 ///
@@ -90,7 +115,8 @@ pub extern "system" fn Java_org_enso_syntax2_Parser_getLastInputBase(
     state.base
 }
 
-/// Return the metadata associated with the most recent parse.
+/// Return the metadata associated with the most recent parse. After a call to `parseInputBatch`,
+/// this is the metadata of the last input of the batch.
 ///
 /// # Safety
 ///
@@ -104,13 +130,7 @@ pub extern "system" fn Java_org_enso_syntax2_Parser_getMetadata(
     state: u64,
 ) -> u64 {
     let state = unsafe { &mut *(state as usize as *mut State) };
-    match &state.metadata {
-        Some(metadata) => {
-            let metadata: *const _ = metadata;
-            metadata as usize as u64
-        }
-        None => 0,
-    }
+    state.metadata.last().map_or(0, |metadata| metadata_ptr(metadata))
 }
 
 /// Allocate a new parser state object. The returned value should be passed to `freeState` when no
@@ -148,8 +168,8 @@ pub extern "system" fn Java_org_enso_syntax2_Parser_freeState(
 /// # Safety
 ///
 /// The `metadata` pointer MUST be 0, or a value returned by `Parser.getMetadata`. If it is the
-/// latter, `parser.parseInput` MUST NOT have been called since the call to `getMetadata` that
-/// returned the value.
+/// latter, `parser.parseInput` or `parser.parseInputBatch` MUST NOT have been called since the call
+/// that returned the value.
 #[allow(unsafe_code)]
 #[no_mangle]
 pub extern "system" fn Java_org_enso_syntax2_Parser_getUuidHigh(
@@ -167,8 +187,8 @@ pub extern "system" fn Java_org_enso_syntax2_Parser_getUuidHigh(
 /// # Safety
 ///
 /// The `metadata` pointer MUST be 0, or a value returned by `Parser.getMetadata`. If it is the
-/// latter, `parser.parseInput` MUST NOT have been called since the call to `getMetadata` that
-/// returned the value.
+/// latter, `parser.parseInput` or `parser.parseInputBatch` MUST NOT have been called since the call
+/// that returned the value.
 #[allow(unsafe_code)]
 #[no_mangle]
 pub extern "system" fn Java_org_enso_syntax2_Parser_getUuidLow(
@@ -186,12 +206,9 @@ fn get_uuid(metadata: u64, code_offset: u64, code_length: u64) -> (u64, u64) {
     if metadata == 0 {
         return (0, 0);
     }
-    let metadata = unsafe { &*(metadata as usize as *const enso_parser::metadata::Metadata) };
+    let metadata = unsafe { &*(metadata as usize as *const ModuleIds) };
     let data = metadata.get_uuid(code_offset as usize, code_length as usize);
-    match data {
-        Some(uuid) => uuid.as_u64_pair(),
-        None => (0, 0),
-    }
+    data.unwrap_or((0, 0))
 }
 
 
@@ -200,9 +217,99 @@ fn get_uuid(metadata: u64, code_offset: u64, code_length: u64) -> (u64, u64) {
 // === Parser state ===
 // ====================
 
+/// State retained between calls, so that the parser and its buffers can be reused.
 #[derive(Default, Debug)]
 struct State {
+    parser:   enso_parser::Parser,
+    tokens:   enso_parser::lexer::TokenBuffer,
     base:     u64,
     output:   Vec<u8>,
-    metadata: Option<enso_parser::metadata::Metadata>,
+    /// The node IDs of each module in the output. The values are boxed so that pointers to them
+    /// remain valid while more modules are parsed.
+    #[allow(clippy::vec_box)]
+    metadata: Vec<Box<ModuleIds>>,
+}
+
+/// The IDs of the nodes of a module parsed into a [`State`]'s output. IDs are only assigned when
+/// one is first requested, so that parsing modules whose IDs are not used doesn't pay for them.
+#[derive(Debug)]
+struct ModuleIds {
+    /// The code of the module, without its metadata section.
+    code:   String,
+    /// The IDs found in the module's metadata section.
+    id_map: enso_parser::metadata::IdMap,
+    /// An ID for every node of the module; see [`enso_parser::syntax::tree::id`].
+    ids:    OnceLock<enso_parser::metadata::Metadata>,
+}
+
+impl ModuleIds {
+    /// Return the high and low bits of the ID of the node at the given location, if any.
+    fn get_uuid(&self, index: usize, size: usize) -> Option<(u64, u64)> {
+        let ids = self.ids.get_or_init(|| {
+            static PARSER: OnceLock<enso_parser::Parser> = OnceLock::new();
+            let parser = PARSER.get_or_init(default);
+            let (_, ids) = parser.run_with_ids(&self.code, &self.id_map);
+            enso_parser::metadata::Metadata::from(&ids)
+        });
+        ids.get_uuid(index, size).map(|uuid| uuid.as_u64_pair())
+    }
+}
+
+/// Information about a module parsed into a [`State`]'s output.
+#[derive(Debug, Copy, Clone)]
+struct Parsed {
+    base:     u64,
+    metadata: u64,
+}
+
+impl State {
+    /// Discard the output of previous calls.
+    fn clear(&mut self) {
+        self.base = 0;
+        self.output.clear();
+        self.metadata.clear();
+    }
+
+    /// Parse the module, appending its serialized tree to the output.
+    fn parse(&mut self, input: &str) -> Parsed {
+        let mut code = input;
+        let mut meta = None;
+        if let Some((meta_, code_)) = enso_parser::metadata::parse(input) {
+            match meta_ {
+                Ok(meta_) => meta = Some(meta_),
+                Err(e) => eprintln!("Ignoring invalid metadata: {e}."),
+            }
+            code = code_;
+        }
+        let base = str::as_ptr(code) as usize as u64;
+        let tree = self.parser.run_with_buffer(code, &mut self.tokens);
+        let id_map = meta.as_ref().map(enso_parser::metadata::IdMap::from).unwrap_or_default();
+        let meta = Box::new(ModuleIds { code: code.to_owned(), id_map, ids: default() });
+        // `Tree` does not contain any types with fallible `serialize` implementations, so this
+        // cannot fail.
+        let serialized = enso_parser::serialization::serialize_tree_into(&tree, &mut self.output);
+        debug_assert!(serialized.is_ok());
+        let metadata = metadata_ptr(&meta);
+        self.metadata.push(meta);
+        Parsed { base, metadata }
+    }
+}
+
+fn metadata_ptr(metadata: &ModuleIds) -> u64 {
+    let metadata: *const _ = metadata;
+    metadata as usize as u64
+}
+
+#[allow(unsafe_code)]
+fn bytes_to_str(input: &[u8]) -> &str {
+    if cfg!(debug_assertions) {
+        std::str::from_utf8(input).unwrap()
+    } else {
+        unsafe { std::str::from_utf8_unchecked(input) }
+    }
+}
+
+fn direct_buffer<'e>(env: &'e JNIEnv, buffer: JByteBuffer) -> &'e [u8] {
+    let direct_allocated = "Internal Error: ByteBuffer must be direct-allocated.";
+    env.get_direct_buffer_address(buffer).expect(direct_allocated)
 }
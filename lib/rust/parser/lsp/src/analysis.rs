@@ -0,0 +1,520 @@
+//! The editor features, computed from the syntax tree of a document.
+//!
+//! Each function produces the JSON representation of the result of the corresponding request of
+//! the protocol. Lines are numbered from 0, and columns are counted in UTF-16 code units, as in the
+//! [`Location`]s of the parser.
+
+use enso_prelude::*;
+
+use enso_doc_parser::DocSection;
+use enso_doc_parser::Mark;
+use enso_parser::outline;
+use enso_parser::outline::Outline;
+use enso_parser::source::code::Location;
+use enso_parser::syntax::item;
+use enso_parser::syntax::token;
+use enso_parser::syntax::tree;
+use enso_parser::syntax::tree::error::Severity;
+use enso_parser::syntax::tree::ItemVisitable;
+use enso_parser::syntax::tree::ItemVisitor;
+use enso_parser::syntax::Tree;
+use serde_json::json;
+use serde_json::Value;
+
+
+
+// ================
+// === Position ===
+// ================
+
+/// A position in a document: a line, and a column within it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+    /// The line number, starting from 0.
+    pub line:      u32,
+    /// The offset from the start of the line, in UTF-16 code units.
+    pub character: u32,
+}
+
+impl Position {
+    /// Read a position in the representation of the protocol.
+    pub fn from_json(value: &Value) -> Option<Self> {
+        let line = value.get("line")?.as_u64()?.try_into().ok()?;
+        let character = value.get("character")?.as_u64()?.try_into().ok()?;
+        Some(Self { line, character })
+    }
+
+    fn to_json(self) -> Value {
+        json!({ "line": self.line, "character": self.character })
+    }
+}
+
+impl From<Location> for Position {
+    fn from(location: Location) -> Self {
+        Self { line: location.line, character: location.col16 }
+    }
+}
+
+fn range_to_json(range: &Range<Location>) -> Value {
+    json!({
+        "start": Position::from(range.start).to_json(),
+        "end": Position::from(range.end).to_json(),
+    })
+}
+
+/// Whether the position is within the range, or at either of its ends.
+fn touches(range: &Range<Location>, position: Position) -> bool {
+    Position::from(range.start) <= position && position <= Position::from(range.end)
+}
+
+/// The last line containing any of the code in the range.
+fn last_line(range: &Range<Location>) -> u32 {
+    match range.end {
+        end if end.col16 == 0 && end.line > range.start.line => end.line - 1,
+        end => end.line,
+    }
+}
+
+
+
+// ===================
+// === Diagnostics ===
+// ===================
+
+/// The syntax errors in the document identified by the URI.
+pub fn diagnostics(tree: &Tree, uri: &str) -> Vec<Value> {
+    let diagnostics = enso_parser::diagnostics::collect(tree);
+    let diagnostic_to_json = |diagnostic: enso_parser::diagnostics::Diagnostic| {
+        let mut message = diagnostic.message;
+        for note in &diagnostic.notes {
+            message.push_str("\nnote: ");
+            message.push_str(note);
+        }
+        let severity = match diagnostic.severity {
+            Severity::Error => 1,
+            Severity::Warning => 2,
+        };
+        let mut result = json!({
+            "range": range_to_json(&diagnostic.primary),
+            "severity": severity,
+            "code": diagnostic.code.code(),
+            "source": "enso",
+            "message": message,
+        });
+        if !diagnostic.labels.is_empty() {
+            let related = diagnostic.labels.iter().map(|(range, message)| {
+                json!({
+                    "location": { "uri": uri, "range": range_to_json(range) },
+                    "message": message,
+                })
+            });
+            result["relatedInformation"] = related.collect();
+        }
+        result
+    };
+    diagnostics.into_iter().map(diagnostic_to_json).collect()
+}
+
+
+
+// ========================
+// === Document Symbols ===
+// ========================
+
+/// The kinds of symbols reported, as numbered by the protocol.
+mod symbol_kind {
+    pub const MODULE: u32 = 2;
+    pub const CLASS: u32 = 5;
+    pub const METHOD: u32 = 6;
+    pub const CONSTRUCTOR: u32 = 9;
+    pub const FUNCTION: u32 = 12;
+}
+
+/// The hierarchy of the definitions in the module: its imports and exports, types (containing their
+/// constructors and methods), and functions.
+pub fn document_symbols(tree: &Tree) -> Vec<Value> {
+    let Outline { imports, exports, types, functions, .. } = outline::extract(tree, None);
+    let mut symbols = vec![];
+    for (statements, detail) in [(imports, "import"), (exports, "export")] {
+        for statement in statements {
+            let range = &statement.range;
+            let (start, mut symbol) =
+                symbol(&statement.module, symbol_kind::MODULE, range, range, default());
+            symbol["detail"] = detail.into();
+            symbols.push((start, symbol));
+        }
+    }
+    for type_ in &types {
+        let constructors = type_.constructors.iter().map(|def| {
+            symbol(&def.name, symbol_kind::CONSTRUCTOR, &def.range, &def.name_range, default())
+        });
+        let methods = type_.methods.iter().map(|def| function_symbol(def, true));
+        let children = in_order(constructors.chain(methods).collect());
+        let kind = symbol_kind::CLASS;
+        symbols.push(symbol(&type_.name, kind, &type_.range, &type_.name_range, children));
+    }
+    symbols.extend(functions.iter().map(|def| function_symbol(def, false)));
+    in_order(symbols)
+}
+
+fn function_symbol(def: &outline::Function, in_type: bool) -> (Location, Value) {
+    let is_method = in_type || def.name.contains('.');
+    let kind = if is_method { symbol_kind::METHOD } else { symbol_kind::FUNCTION };
+    symbol(&def.name, kind, &def.range, &def.name_range, default())
+}
+
+/// A symbol, and the location it starts at.
+fn symbol(
+    name: &str,
+    kind: u32,
+    range: &Range<Location>,
+    selection: &Range<Location>,
+    children: Vec<Value>,
+) -> (Location, Value) {
+    let symbol = json!({
+        "name": name,
+        "kind": kind,
+        "range": range_to_json(range),
+        "selectionRange": range_to_json(selection),
+        "children": children,
+    });
+    (range.start, symbol)
+}
+
+/// The symbols, in the order of their definitions in the document.
+fn in_order(mut symbols: Vec<(Location, Value)>) -> Vec<Value> {
+    symbols.sort_by_key(|(start, _)| *start);
+    symbols.into_iter().map(|(_, symbol)| symbol).collect()
+}
+
+
+
+// =====================
+// === Folding Range ===
+// =====================
+
+/// The regions of the document that can be folded: blocks, multi-line documentation comments, and
+/// groups of imports and exports.
+pub fn folding_ranges(tree: &Tree) -> Vec<Value> {
+    let mut folds = vec![];
+    tree.visit_trees(|node| match &*node.variant {
+        tree::Variant::BodyBlock(_) if std::ptr::eq(node, tree) => {}
+        tree::Variant::BodyBlock(_)
+        | tree::Variant::ArgumentBlockApplication(_)
+        | tree::Variant::OperatorBlockApplication(_)
+        | tree::Variant::TypeDef(_)
+        | tree::Variant::CaseOf(_) => {
+            let range = node.span.range();
+            folds.push((range.start.line, last_line(&range), None));
+        }
+        tree::Variant::Documented(documented) => {
+            let start = documented.documentation.open.code.range().start.line;
+            let lines =
+                documented.documentation.elements.iter().filter_map(|element| match element {
+                    tree::TextElement::Section { text } => Some(text.code.range().start.line),
+                    tree::TextElement::Newline { newline } => Some(newline.code.range().start.line),
+                    _ => None,
+                });
+            folds.push((start, lines.max().unwrap_or(start), Some("comment")));
+        }
+        _ => {}
+    });
+    if let tree::Variant::BodyBlock(block) = &*tree.variant {
+        let mut imports: Option<Range<Location>> = None;
+        let statements = block.statements.iter().filter_map(|line| line.expression.as_ref());
+        for statement in statements.map(Some).chain([None]) {
+            match statement.map(|statement| (&*statement.variant, statement.span.range())) {
+                Some((tree::Variant::Import(_) | tree::Variant::Export(_), range)) =>
+                    match &mut imports {
+                        Some(imports) => imports.end = range.end,
+                        None => imports = Some(range),
+                    },
+                _ =>
+                    if let Some(range) = imports.take() {
+                        folds.push((range.start.line, last_line(&range), Some("imports")));
+                    },
+            }
+        }
+    }
+    folds.retain(|(start, end, _)| start < end);
+    folds.sort_by_key(|(start, _, _)| *start);
+    folds.dedup_by_key(|(start, _, _)| *start);
+    let fold_to_json = |(start, end, kind): (u32, u32, Option<&str>)| {
+        let mut fold = json!({ "startLine": start, "endLine": end });
+        if let Some(kind) = kind {
+            fold["kind"] = kind.into();
+        }
+        fold
+    };
+    folds.into_iter().map(fold_to_json).collect()
+}
+
+
+
+// =======================
+// === Selection Range ===
+// =======================
+
+/// The ranges of the syntactic elements containing the position, from the innermost outwards, as a
+/// linked list of selection ranges.
+pub fn selection_range(tree: &Tree, position: Position) -> Value {
+    let (trees, token) = path(tree, position);
+    let mut ranges: Vec<_> = trees.iter().map(|tree| tree.span.range()).collect();
+    ranges.extend(token.map(|token| token.code.range()));
+    ranges.dedup();
+    let mut selection = Value::Null;
+    for range in ranges {
+        let parent = mem::take(&mut selection);
+        selection = json!({ "range": range_to_json(&range) });
+        if !parent.is_null() {
+            selection["parent"] = parent;
+        }
+    }
+    selection
+}
+
+/// Return the trees containing the position, from the root inwards, and the token at the position.
+///
+/// When the position is at the boundary of two elements, it is considered to be within the element
+/// it starts.
+fn path<'s, 'a>(
+    tree: &'a Tree<'s>,
+    position: Position,
+) -> (Vec<&'a Tree<'s>>, Option<token::Ref<'s, 'a>>) {
+    let mut trees = vec![tree];
+    let mut current = tree;
+    loop {
+        let mut candidates = children(current).into_iter().filter(|child| {
+            let range = match child {
+                item::Ref::Tree(tree) => tree.span.range(),
+                item::Ref::Token(token) if matches!(token.data, token::Variant::Newline(_)) =>
+                    return false,
+                item::Ref::Token(token) => token.code.range(),
+            };
+            range.start != range.end && touches(&range, position)
+        });
+        let first = candidates.next();
+        let child = candidates.next().or(first);
+        match child {
+            Some(item::Ref::Tree(tree)) => {
+                trees.push(tree);
+                current = tree;
+            }
+            Some(item::Ref::Token(token)) => return (trees, Some(token)),
+            None => return (trees, None),
+        }
+    }
+}
+
+fn children<'s, 'a>(tree: &'a Tree<'s>) -> Vec<item::Ref<'s, 'a>> {
+    let mut collector = Children { items: default() };
+    tree.variant.visit_item(&mut collector);
+    collector.items
+}
+
+struct Children<'s, 'a> {
+    items: Vec<item::Ref<'s, 'a>>,
+}
+
+impl<'s, 'a> tree::Visitor for Children<'s, 'a> {}
+impl<'s, 'a> ItemVisitor<'s, 'a> for Children<'s, 'a> {
+    fn visit_item(&mut self, item: item::Ref<'s, 'a>) -> bool {
+        self.items.push(item);
+        false
+    }
+}
+
+
+
+// =============
+// === Hover ===
+// =============
+
+/// The documentation of the entity named by the identifier at the position, if the module defines
+/// an entity with that name and documents it.
+pub fn hover(tree: &Tree, position: Position) -> Option<Value> {
+    let (_, token) = path(tree, position);
+    let token = token.filter(|token| matches!(token.data, token::Variant::Ident(_)))?;
+    let outline = outline::extract(tree, None);
+    let sections = enso_doc_parser::parse(documentation(&outline, token.code.repr.0)?);
+    Some(json!({
+        "contents": { "kind": "markdown", "value": markdown(&sections) },
+        "range": range_to_json(&token.code.range()),
+    }))
+}
+
+/// The documentation of the first entity of the outline with the given unqualified name.
+fn documentation<'a>(outline: &'a Outline, name: &str) -> Option<&'a str> {
+    let types = outline.types.iter();
+    let members = types.clone().flat_map(|type_| {
+        let constructors = type_.constructors.iter().map(|def| (&def.name, &def.attributes));
+        constructors.chain(type_.methods.iter().map(|def| (&def.name, &def.attributes)))
+    });
+    let types = types.map(|def| (&def.name, &def.attributes));
+    let functions = outline.functions.iter().map(|def| (&def.name, &def.attributes));
+    let mut definitions = types.chain(members).chain(functions);
+    definitions.find_map(|(defined, attributes)| {
+        let unqualified = defined.rsplit('.').next() == Some(name);
+        attributes.documentation.as_deref().filter(|_| unqualified)
+    })
+}
+
+/// Render the documentation as Markdown. The text of the sections is HTML, which Markdown allows.
+fn markdown(sections: &[DocSection]) -> String {
+    let lines = |lines: Vec<String>| lines.join("\n");
+    let section_to_markdown = |section: &DocSection| match section {
+        DocSection::Tag { tag, body } =>
+            format!("**{}** {body}", tag.to_str()).trim_end().to_owned(),
+        DocSection::Paragraph { body } => body.clone(),
+        DocSection::List { items } => lines(items.iter().map(|item| format!("- {item}")).collect()),
+        // The list of arguments follows the `Arguments` key, which is its own section.
+        DocSection::Arguments { args } =>
+            lines(args.iter().map(|arg| format!("- `{}`: {}", arg.name, arg.description)).collect()),
+        DocSection::Keyed { key, body } => format!("**{key}**\n\n{body}").trim_end().to_owned(),
+        DocSection::Marked { mark, header, body } => {
+            let title = header.as_deref().unwrap_or(match mark {
+                Mark::Important => "Important",
+                Mark::Info => "Info",
+                Mark::Example => "Example",
+            });
+            format!("**{title}**\n\n{body}")
+        }
+    };
+    sections.iter().map(section_to_markdown).collect::<Vec<_>>().join("\n\n")
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(code: &str) -> Tree {
+        enso_parser::Parser::new().run(code)
+    }
+
+    fn names(symbols: &[Value]) -> Vec<(String, u64)> {
+        let symbol =
+            |s: &Value| (s["name"].as_str().unwrap().to_owned(), s["kind"].as_u64().unwrap());
+        symbols.iter().map(symbol).collect()
+    }
+
+    #[test]
+    fn symbols() {
+        let code = [
+            "import Standard.Base.Data.Vector",
+            "from Standard.Base import all",
+            "## A type.",
+            "type T",
+            "    A x",
+            "    B",
+            "    @x Widget",
+            "    m self x = x",
+            "    n = 1",
+            "T.ext self = 0",
+            "main = 42",
+            "f x = x",
+        ]
+        .join("\n");
+        let symbols = document_symbols(&parse(&code));
+        assert_eq!(names(&symbols), [
+            ("Standard.Base.Data.Vector".into(), 2),
+            ("Standard.Base".into(), 2),
+            ("T".into(), 5),
+            ("T.ext".into(), 6),
+            ("main".into(), 12),
+            ("f".into(), 12),
+        ]);
+        let members = symbols[2]["children"].as_array().unwrap();
+        assert_eq!(names(members), [
+            ("A".into(), 9),
+            ("B".into(), 9),
+            ("m".into(), 6),
+            ("n".into(), 6),
+        ]);
+        assert_eq!(symbols[1]["detail"], "import");
+        assert_eq!(symbols[2]["range"]["start"], json!({ "line": 2, "character": 0 }));
+        assert_eq!(symbols[2]["selectionRange"]["start"], json!({ "line": 3, "character": 5 }));
+    }
+
+    #[test]
+    fn folds() {
+        let code = [
+            "import A",
+            "import B",
+            "## Some",
+            "   docs.",
+            "type T",
+            "    A",
+            "    f x =",
+            "        x",
+            "main =",
+            "    y = case 1 of",
+            "        _ -> 0",
+            "    y",
+        ]
+        .join("\n");
+        let folds = folding_ranges(&parse(&code));
+        let fold = |f: &Value| {
+            let kind = f["kind"].as_str().map(str::to_owned);
+            (f["startLine"].as_u64().unwrap(), f["endLine"].as_u64().unwrap(), kind)
+        };
+        assert_eq!(folds.iter().map(fold).collect::<Vec<_>>(), [
+            (0, 1, Some("imports".into())),
+            (2, 3, Some("comment".into())),
+            (4, 7, None),
+            (6, 7, None),
+            (8, 11, None),
+            (9, 10, None),
+        ]);
+    }
+
+    #[test]
+    fn selection() {
+        let code = "main =\n    foo (bar baz)";
+        let tree = parse(code);
+        let mut selection = &selection_range(&tree, Position { line: 1, character: 14 });
+        let mut ranges = vec![];
+        while !selection.is_null() {
+            let range = &selection["range"];
+            ranges.push((range["start"]["character"].clone(), range["end"]["character"].clone()));
+            selection = &selection["parent"];
+        }
+        let column = |n: u64| Value::from(n);
+        assert_eq!(ranges[..3], [
+            (column(13), column(16)),
+            (column(9), column(16)),
+            (column(8), column(17)),
+        ]);
+    }
+
+    #[test]
+    fn hover_documentation() {
+        let code = [
+            "## Adds one.",
+            "",
+            "   Arguments:",
+            "   - x: A number.",
+            "   > Example",
+            "     Add one to two.",
+            "",
+            "         inc 2",
+            "inc x = x + 1",
+            "main = inc 2",
+        ]
+        .join("\n");
+        let tree = parse(&code);
+        let hover = hover(&tree, Position { line: 9, character: 8 }).unwrap();
+        let text = hover["contents"]["value"].as_str().unwrap();
+        assert!(text.contains("Adds one."), "{text}");
+        assert!(text.contains("- `x`: A number."), "{text}");
+        assert!(text.contains("**Example**\n\n<p>Add one to two."), "{text}");
+        assert_eq!(text.matches("**Arguments**").count(), 1, "{text}");
+        assert_eq!(hover["range"]["start"], json!({ "line": 9, "character": 7 }));
+        assert_eq!(super::hover(&tree, Position { line: 9, character: 4 }), None);
+    }
+}
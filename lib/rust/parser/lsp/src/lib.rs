@@ -0,0 +1,249 @@
+//! A language server for Enso, providing the editor features that can be derived from the syntax of
+//! a module alone.
+//!
+//! The [`Server`] implements the Language Server Protocol over a pair of byte streams. It keeps the
+//! text of the open documents, reports their syntax errors whenever they change, and answers each
+//! request by parsing the document and analyzing the tree (see [`analysis`]). No information is
+//! shared between documents, and no semantic analysis is performed.
+
+// === Features ===
+#![feature(let_chains)]
+// === Non-Standard Linter Configuration ===
+#![allow(clippy::option_map_unit_fn)]
+#![allow(clippy::precedence)]
+#![allow(dead_code)]
+#![deny(unconditional_recursion)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(unused_qualifications)]
+
+use enso_prelude::*;
+
+use enso_parser::semantic_tokens;
+use enso_parser::syntax::Tree;
+use rpc::ResponseError;
+use serde_json::json;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io;
+use std::io::BufRead;
+use std::io::Write;
+
+
+
+// ==============
+// === Export ===
+// ==============
+
+pub mod analysis;
+pub mod rpc;
+
+
+
+// ==============
+// === Server ===
+// ==============
+
+/// A language server, writing its responses and notifications to the given output.
+#[derive(Debug)]
+pub struct Server<W> {
+    output:      W,
+    parser:      enso_parser::Parser,
+    documents:   HashMap<String, String>,
+    initialized: bool,
+    shutdown:    bool,
+}
+
+impl<W: Write> Server<W> {
+    /// Constructor.
+    pub fn new(output: W) -> Self {
+        let parser = enso_parser::Parser::new();
+        Self { output, parser, documents: default(), initialized: false, shutdown: false }
+    }
+
+    /// Handle the messages read from the input, until the client sends the `exit` notification or
+    /// the input ends. Returns the exit code of the server, which indicates whether the client
+    /// requested a shutdown before the server exited.
+    pub fn run(&mut self, input: &mut impl BufRead) -> io::Result<i32> {
+        while let Some(content) = rpc::read_message(input)? {
+            match serde_json::from_str(&content) {
+                Ok(message) =>
+                    if let Some(exit_code) = self.handle(message)? {
+                        return Ok(exit_code);
+                    },
+                Err(e) => {
+                    let error = ResponseError::new(ResponseError::PARSE_ERROR, e.to_string());
+                    self.send(&rpc::error_response(Value::Null, &error))?;
+                }
+            }
+        }
+        Ok(1)
+    }
+
+    fn handle(&mut self, message: Value) -> io::Result<Option<i32>> {
+        let id = message.get("id").cloned();
+        let params = message.get("params").unwrap_or(&Value::Null);
+        match (message.get("method").and_then(Value::as_str), id) {
+            (Some(method), Some(id)) => {
+                let response = match self.request(method, params) {
+                    Ok(result) => rpc::response(id, result),
+                    Err(error) => rpc::error_response(id, &error),
+                };
+                self.send(&response)?;
+            }
+            (Some(method), None) => return self.notification(method, params),
+            // A response; the server sends no requests, so there is nothing to do with it.
+            (None, Some(_)) => {}
+            (None, None) => {
+                let error = ResponseError::new(ResponseError::INVALID_REQUEST, "Missing method.");
+                self.send(&rpc::error_response(Value::Null, &error))?;
+            }
+        }
+        Ok(None)
+    }
+
+    fn request(&mut self, method: &str, params: &Value) -> Result<Value, ResponseError> {
+        if self.shutdown {
+            let message = "The server has been shut down.";
+            return Err(ResponseError::new(ResponseError::INVALID_REQUEST, message));
+        }
+        if !self.initialized && method != "initialize" {
+            let message = "The server has not been initialized.";
+            return Err(ResponseError::new(ResponseError::SERVER_NOT_INITIALIZED, message));
+        }
+        match method {
+            "initialize" => {
+                self.initialized = true;
+                Ok(initialize_result())
+            }
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Value::Null)
+            }
+            "textDocument/documentSymbol" =>
+                self.analyze(params, |tree| analysis::document_symbols(tree).into()),
+            "textDocument/foldingRange" =>
+                self.analyze(params, |tree| analysis::folding_ranges(tree).into()),
+            "textDocument/selectionRange" => {
+                let positions = params.get("positions").and_then(Value::as_array);
+                let positions = positions.map(|positions| {
+                    positions.iter().map(analysis::Position::from_json).collect::<Option<Vec<_>>>()
+                });
+                let positions = positions.flatten().ok_or_else(|| invalid_params("positions"))?;
+                self.analyze(params, |tree| {
+                    positions.iter().map(|p| analysis::selection_range(tree, *p)).collect()
+                })
+            }
+            "textDocument/hover" => {
+                let position = params.get("position").and_then(analysis::Position::from_json);
+                let position = position.ok_or_else(|| invalid_params("position"))?;
+                self.analyze(params, |tree| analysis::hover(tree, position).into())
+            }
+            "textDocument/semanticTokens/full" => self.analyze(params, |tree| {
+                let tokens = semantic_tokens::collect(tree);
+                json!({ "data": semantic_tokens::encode(&tokens) })
+            }),
+            _ => {
+                let message = format!("Unsupported method: `{method}`.");
+                Err(ResponseError::new(ResponseError::METHOD_NOT_FOUND, message))
+            }
+        }
+    }
+
+    fn notification(&mut self, method: &str, params: &Value) -> io::Result<Option<i32>> {
+        if method == "exit" {
+            return Ok(Some(if self.shutdown { 0 } else { 1 }));
+        }
+        if !self.initialized || self.shutdown {
+            return Ok(None);
+        }
+        let document = params.get("textDocument");
+        let uri = document.and_then(|document| document.get("uri")).and_then(Value::as_str);
+        match (method, uri) {
+            ("textDocument/didOpen", Some(uri)) => {
+                let text = document.and_then(|document| document.get("text")?.as_str());
+                if let Some(text) = text {
+                    self.documents.insert(uri.to_owned(), text.to_owned());
+                    self.publish_diagnostics(uri)?;
+                }
+            }
+            ("textDocument/didChange", Some(uri)) => {
+                // The server requests full synchronization, so every change replaces the text.
+                let changes = params.get("contentChanges").and_then(Value::as_array);
+                let text = changes.and_then(|changes| changes.last()?.get("text")?.as_str());
+                if let Some(text) = text
+                    && let Some(document) = self.documents.get_mut(uri)
+                {
+                    text.clone_into(document);
+                    self.publish_diagnostics(uri)?;
+                }
+            }
+            ("textDocument/didClose", Some(uri)) =>
+                if self.documents.remove(uri).is_some() {
+                    self.publish_diagnostics(uri)?;
+                },
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    /// Parse the document identified by the parameters of a request, and analyze its tree.
+    fn analyze(
+        &self,
+        params: &Value,
+        f: impl FnOnce(&Tree) -> Value,
+    ) -> Result<Value, ResponseError> {
+        let document = params.get("textDocument");
+        let uri = document.and_then(|document| document.get("uri")).and_then(Value::as_str);
+        let uri = uri.ok_or_else(|| invalid_params("textDocument"))?;
+        let text = self.documents.get(uri).ok_or_else(|| {
+            let message = format!("The document is not open: `{uri}`.");
+            ResponseError::new(ResponseError::INVALID_PARAMS, message)
+        })?;
+        Ok(f(&self.parser.run(code(text))))
+    }
+
+    /// Report the syntax errors of the document, or clear them if it has been closed.
+    fn publish_diagnostics(&mut self, uri: &str) -> io::Result<()> {
+        let diagnostics = match self.documents.get(uri) {
+            Some(text) => analysis::diagnostics(&self.parser.run(code(text)), uri),
+            None => default(),
+        };
+        let params = json!({ "uri": uri, "diagnostics": diagnostics });
+        let notification = rpc::notification("textDocument/publishDiagnostics", params);
+        self.send(&notification)
+    }
+
+    fn send(&mut self, message: &Value) -> io::Result<()> {
+        rpc::write_message(&mut self.output, message)
+    }
+}
+
+/// The code of the document, without its metadata section.
+fn code(text: &str) -> &str {
+    enso_parser::metadata::extract(text).0
+}
+
+fn invalid_params(name: &str) -> ResponseError {
+    let message = format!("Missing or invalid parameter: `{name}`.");
+    ResponseError::new(ResponseError::INVALID_PARAMS, message)
+}
+
+fn initialize_result() -> Value {
+    let token_types = semantic_tokens::Kind::ALL.map(semantic_tokens::Kind::name);
+    let token_modifiers = semantic_tokens::Modifier::ALL.map(semantic_tokens::Modifier::name);
+    json!({
+        "capabilities": {
+            "textDocumentSync": { "openClose": true, "change": 1 },
+            "documentSymbolProvider": true,
+            "foldingRangeProvider": true,
+            "selectionRangeProvider": true,
+            "hoverProvider": true,
+            "semanticTokensProvider": {
+                "legend": { "tokenTypes": token_types, "tokenModifiers": token_modifiers },
+                "full": true,
+            },
+        },
+        "serverInfo": { "name": "enso-parser-lsp", "version": env!("CARGO_PKG_VERSION") },
+    })
+}
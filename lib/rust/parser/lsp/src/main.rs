@@ -0,0 +1,22 @@
+//! Runs the language server, communicating with the client over standard input and output.
+
+// === Non-Standard Linter Configuration ===
+#![allow(clippy::option_map_unit_fn)]
+#![allow(clippy::precedence)]
+#![allow(dead_code)]
+#![deny(unconditional_recursion)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(unused_qualifications)]
+
+
+
+fn main() {
+    let mut input = std::io::stdin().lock();
+    let mut server = enso_parser_lsp::Server::new(std::io::stdout().lock());
+    let exit_code = server.run(&mut input).unwrap_or_else(|error| {
+        eprintln!("{error}");
+        1
+    });
+    std::process::exit(exit_code);
+}
@@ -0,0 +1,136 @@
+//! The base protocol of the Language Server Protocol: JSON-RPC 2.0 messages, each preceded by a
+//! header specifying its length.
+
+use serde_json::json;
+use serde_json::Value;
+use std::io;
+use std::io::BufRead;
+use std::io::Write;
+
+
+
+// ================
+// === Messages ===
+// ================
+
+/// Read the content of the next message. Returns `None` if the input ends before a message starts.
+pub fn read_message(input: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut length = None;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if input.read_line(&mut line)? == 0 {
+            if length.is_none() {
+                return Ok(None);
+            }
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        let header = line.trim_end_matches(['\r', '\n']);
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.trim().eq_ignore_ascii_case("Content-Length")
+        {
+            let value = value.trim().parse::<usize>();
+            length = Some(value.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?);
+        }
+    }
+    let length = length.ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, "Message header lacks `Content-Length`.")
+    })?;
+    let mut content = vec![0; length];
+    input.read_exact(&mut content)?;
+    String::from_utf8(content).map(Some).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Write the message, preceded by its header, and flush the output.
+pub fn write_message(output: &mut impl Write, message: &Value) -> io::Result<()> {
+    let content = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{content}", content.len())?;
+    output.flush()
+}
+
+/// A successful response to the request with the given ID.
+pub fn response(id: Value, result: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+/// A response reporting that the request with the given ID failed.
+pub fn error_response(id: Value, error: &ResponseError) -> Value {
+    let ResponseError { code, message } = error;
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+/// A notification, which is a message that is not answered.
+pub fn notification(method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "method": method, "params": params })
+}
+
+
+
+// =====================
+// === ResponseError ===
+// =====================
+
+/// The reason a request failed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResponseError {
+    /// One of the error codes defined by JSON-RPC or the protocol.
+    pub code:    i64,
+    /// Human-readable description of the error.
+    pub message: String,
+}
+
+impl ResponseError {
+    /// The message is not valid JSON.
+    pub const PARSE_ERROR: i64 = -32700;
+    /// The message is not a valid request.
+    pub const INVALID_REQUEST: i64 = -32600;
+    /// The method is not supported by the server.
+    pub const METHOD_NOT_FOUND: i64 = -32601;
+    /// The parameters of the request are invalid.
+    pub const INVALID_PARAMS: i64 = -32602;
+    /// A request other than `initialize` was received before the server was initialized.
+    pub const SERVER_NOT_INITIALIZED: i64 = -32002;
+
+    /// Constructor.
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let message = notification("initialized", json!({}));
+        let mut buffer = vec![];
+        write_message(&mut buffer, &message).unwrap();
+        write_message(&mut buffer, &message).unwrap();
+        let mut input = &buffer[..];
+        for _ in 0..2 {
+            let content = read_message(&mut input).unwrap().unwrap();
+            assert_eq!(serde_json::from_str::<Value>(&content).unwrap(), message);
+        }
+        assert_eq!(read_message(&mut input).unwrap(), None);
+    }
+
+    #[test]
+    fn headers() {
+        let input = "content-length: 2\r\nContent-Type: application/vscode-jsonrpc\r\n\r\n{}";
+        assert_eq!(read_message(&mut input.as_bytes()).unwrap().as_deref(), Some("{}"));
+        let input = "Content-Type: application/vscode-jsonrpc\r\n\r\n{}";
+        assert!(read_message(&mut input.as_bytes()).is_err());
+        let input = "Content-Length: 4\r\n\r\n{}";
+        assert!(read_message(&mut input.as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,171 @@
+//! Tests of the language server executable, scripting sessions over its standard input.
+
+// === Non-Standard Linter Configuration ===
+#![allow(clippy::option_map_unit_fn)]
+#![allow(clippy::precedence)]
+#![allow(dead_code)]
+#![deny(unconditional_recursion)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(unused_qualifications)]
+
+use enso_parser_lsp::rpc;
+use serde_json::json;
+use serde_json::Value;
+use std::io::Write;
+use std::process::Command;
+use std::process::Stdio;
+
+
+
+const URI: &str = "file:///project/src/Main.enso";
+
+/// Send the messages to a new server process, and return its exit code and the messages it sent.
+fn session(messages: &[Value]) -> (Option<i32>, Vec<Value>) {
+    let mut server = Command::new(env!("CARGO_BIN_EXE_enso-parser-lsp"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut input = vec![];
+    for message in messages {
+        rpc::write_message(&mut input, message).unwrap();
+    }
+    server.stdin.take().unwrap().write_all(&input).unwrap();
+    let output = server.wait_with_output().unwrap();
+    let mut stdout = &output.stdout[..];
+    let mut received = vec![];
+    while let Some(content) = rpc::read_message(&mut stdout).unwrap() {
+        received.push(serde_json::from_str(&content).unwrap());
+    }
+    (output.status.code(), received)
+}
+
+fn request(id: u64, method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+}
+
+fn text_document_request(id: u64, method: &str, mut params: Value) -> Value {
+    params["textDocument"] = json!({ "uri": URI });
+    request(id, method, params)
+}
+
+fn result(messages: &[Value], id: u64) -> &Value {
+    let response = messages.iter().find(|message| message["id"] == id).unwrap();
+    &response["result"]
+}
+
+fn diagnostics(messages: &[Value]) -> Vec<&Value> {
+    let method = "textDocument/publishDiagnostics";
+    let notifications = messages.iter().filter(|message| message["method"] == method);
+    notifications.map(|notification| &notification["params"]["diagnostics"]).collect()
+}
+
+#[test]
+fn session_lifecycle() {
+    let code = [
+        "## A point.",
+        "type Point",
+        "    Value x y",
+        "",
+        "    ## The sum of the coordinates.",
+        "    sum self = self.x + self.y",
+        "",
+        "main =",
+        "    p = Point.Value 1 2",
+        "    p.sum",
+    ]
+    .join("\n");
+    let (exit_code, messages) = session(&[
+        request(1, "initialize", json!({ "capabilities": {} })),
+        rpc::notification("initialized", json!({})),
+        rpc::notification(
+            "textDocument/didOpen",
+            json!({ "textDocument": { "uri": URI, "languageId": "enso", "version": 1, "text": code } }),
+        ),
+        text_document_request(2, "textDocument/documentSymbol", json!({})),
+        text_document_request(
+            3,
+            "textDocument/hover",
+            json!({ "position": { "line": 9, "character": 6 } }),
+        ),
+        text_document_request(4, "textDocument/foldingRange", json!({})),
+        text_document_request(
+            5,
+            "textDocument/selectionRange",
+            json!({ "positions": [{ "line": 8, "character": 15 }] }),
+        ),
+        text_document_request(6, "textDocument/semanticTokens/full", json!({})),
+        rpc::notification(
+            "textDocument/didChange",
+            json!({
+                "textDocument": { "uri": URI, "version": 2 },
+                "contentChanges": [{ "text": "main = (1 +" }],
+            }),
+        ),
+        rpc::notification("textDocument/didClose", json!({ "textDocument": { "uri": URI } })),
+        request(7, "shutdown", Value::Null),
+        rpc::notification("exit", Value::Null),
+    ]);
+    assert_eq!(exit_code, Some(0));
+    let capabilities = &result(&messages, 1)["capabilities"];
+    assert_eq!(capabilities["documentSymbolProvider"], true);
+    assert_eq!(capabilities["textDocumentSync"]["change"], 1);
+
+    let symbols = result(&messages, 2).as_array().unwrap();
+    assert_eq!(symbols.len(), 2);
+    assert_eq!(symbols[0]["name"], "Point");
+    let members = symbols[0]["children"].as_array().unwrap();
+    let member_names: Vec<_> = members.iter().map(|member| &member["name"]).collect();
+    assert_eq!(member_names, ["Value", "sum"]);
+    assert_eq!(symbols[1]["name"], "main");
+
+    let hover = result(&messages, 3);
+    assert_eq!(hover["contents"]["kind"], "markdown");
+    let text = hover["contents"]["value"].as_str().unwrap();
+    assert!(text.contains("The sum of the coordinates."), "{text}");
+
+    let folds = result(&messages, 4).as_array().unwrap();
+    let fold_lines = |fold: &Value| (fold["startLine"].clone(), fold["endLine"].clone());
+    assert!(folds.iter().map(fold_lines).any(|lines| lines == (json!(7), json!(9))));
+
+    let selection = &result(&messages, 5)[0];
+    assert_eq!(selection["range"]["start"], json!({ "line": 8, "character": 14 }));
+    assert_eq!(selection["range"]["end"], json!({ "line": 8, "character": 19 }));
+    assert!(selection["parent"].is_object());
+
+    let tokens = result(&messages, 6)["data"].as_array().unwrap();
+    assert_eq!(tokens.len() % 5, 0);
+    assert!(!tokens.is_empty());
+
+    assert_eq!(result(&messages, 7), &Value::Null);
+    let published = diagnostics(&messages);
+    assert_eq!(published.len(), 3);
+    assert_eq!(published[0], &json!([]));
+    let errors = published[1].as_array().unwrap();
+    assert!(!errors.is_empty());
+    assert_eq!(errors[0]["severity"], 1);
+    assert_eq!(errors[0]["source"], "enso");
+    assert_eq!(published[2], &json!([]));
+}
+
+#[test]
+fn protocol_errors() {
+    let (exit_code, messages) = session(&[
+        text_document_request(1, "textDocument/documentSymbol", json!({})),
+        request(2, "initialize", json!({ "capabilities": {} })),
+        request(3, "workspace/symbol", json!({ "query": "" })),
+        text_document_request(4, "textDocument/documentSymbol", json!({})),
+        request(5, "textDocument/hover", json!({})),
+        rpc::notification("exit", Value::Null),
+    ]);
+    assert_eq!(exit_code, Some(1));
+    let error = |id: u64| {
+        let response = messages.iter().find(|message| message["id"] == id).unwrap();
+        response["error"]["code"].as_i64().unwrap()
+    };
+    assert_eq!(error(1), rpc::ResponseError::SERVER_NOT_INITIALIZED);
+    assert_eq!(error(3), rpc::ResponseError::METHOD_NOT_FOUND);
+    assert_eq!(error(4), rpc::ResponseError::INVALID_PARAMS);
+    assert_eq!(error(5), rpc::ResponseError::INVALID_PARAMS);
+}
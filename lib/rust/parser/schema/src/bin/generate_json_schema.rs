@@ -0,0 +1,31 @@
+//! Generate a JSON Schema describing the JSON representation of `enso-parser`'s AST, as produced
+//! by `enso_parser::serialization::tree_to_json`.
+//!
+//! The schema is emitted to standard output. With the argument `--openapi`, an OpenAPI document
+//! defining the AST types as component schemas is emitted instead.
+
+// === Non-Standard Linter Configuration ===
+#![deny(unconditional_recursion)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(unused_qualifications)]
+
+use enso_parser::serialization;
+
+
+
+// ==============================
+// === JSON Schema Generation ===
+// ==============================
+
+fn main() {
+    let document = match std::env::args().nth(1).as_deref() {
+        None => serialization::tree_json_schema(),
+        Some("--openapi") => serialization::tree_openapi(),
+        Some(argument) => {
+            eprintln!("Unexpected argument: {argument}. Usage: generate_json_schema [--openapi]");
+            std::process::exit(1);
+        }
+    };
+    serde_json::to_writer_pretty(std::io::stdout(), &document).unwrap()
+}
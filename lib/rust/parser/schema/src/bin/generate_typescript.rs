@@ -0,0 +1,82 @@
+//! Generate TypeScript bindings for `enso-parser`'s AST types, reading the format produced by
+//! `enso_parser::format`.
+//!
+//! The generated module is emitted to standard output. It imports its runtime support from the
+//! module given as an argument (default: `../parserSupport`).
+
+// === Non-Standard Linter Configuration ===
+#![allow(clippy::option_map_unit_fn)]
+#![allow(clippy::precedence)]
+#![allow(dead_code)]
+#![deny(unconditional_recursion)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(unused_qualifications)]
+
+use enso_metamodel::rust;
+use enso_metamodel::typescript;
+use enso_metamodel::typescript::syntax;
+use enso_reflect::Reflect;
+
+
+
+// =============================
+// === TypeScript Generation ===
+// =============================
+
+/// Field names used by the GUI that differ from the names derived from the Rust types.
+const RENAMES: &[(&str, &str)] = &[
+    ("constructor_", "ident"),
+    ("type_", "typeNode"),
+    // In `Tree`s:
+    ("spanLeftOffsetCodeStartUtf16", "whitespaceStartInCodeParsed"),
+    ("spanLeftOffsetCodeLenUtf16", "whitespaceLengthInCodeParsed"),
+    ("spanCodeLengthUtf16", "childrenLengthInCodeParsed"),
+    // In `Token`s:
+    ("leftOffsetCodeStartUtf16", "whitespaceStartInCodeBuffer"),
+    ("leftOffsetCodeLenUtf16", "whitespaceLengthInCodeBuffer"),
+    ("codeLenUtf16", "lengthInCodeBuffer"),
+    ("codeStartUtf16", "startInCodeBuffer"),
+];
+
+fn main() {
+    let support = std::env::args().nth(1).unwrap_or_else(|| "../parserSupport".to_owned());
+    let (graph, _) = rust::to_meta(enso_parser::syntax::Tree::reflect());
+    let (graph, _) = typescript::from_meta(&graph);
+    let graph = typescript::transform::optional_to_undefined(graph);
+    let mut graph = typescript::transform::rename_fields(graph, RENAMES.iter().copied());
+    typescript::format::derive(&mut graph);
+    let mut module = typescript::to_syntax(&graph, &support);
+    module.header.push("*** THIS FILE GENERATED BY `generate_typescript` ***".to_owned());
+    module.imports[0].names.push(("checkHeader".to_owned(), false));
+    module.items.extend(header_check());
+    print!("{module}");
+}
+
+/// Define the header that serialized data is expected to begin with, and a function that reads the
+/// root of the serialized tree after checking the header.
+fn header_check() -> Vec<syntax::Item> {
+    let header = enso_parser::serialization::Header::new(enso_parser::format::FORMAT_VERSION);
+    let version = syntax::Const {
+        export: true,
+        name:   "FORMAT_VERSION".to_owned(),
+        value:  format!("{} as const", header.version),
+    };
+    let hash = syntax::Const {
+        export: true,
+        name:   "TYPE_GRAPH_HASH".to_owned(),
+        value:  format!("0x{:016x}n as const", header.type_graph_hash),
+    };
+    let mut deserialize = syntax::Function::new("deserializeTree", syntax::Type::named("Tree"));
+    deserialize.arguments = vec![("view".to_owned(), syntax::Type::named("DataView"))];
+    deserialize.body = [
+        "checkHeader(view, FORMAT_VERSION, TYPE_GRAPH_HASH)",
+        "return Tree.read(view, view.byteLength - 4)",
+    ]
+    .join("\n");
+    vec![
+        syntax::Item::Const(version),
+        syntax::Item::Const(hash),
+        syntax::Item::Function(deserialize),
+    ]
+}
@@ -0,0 +1,54 @@
+//! Compare two schemas produced by `enso-parser-schema`, and report whether the serialized layout
+//! of the newer one is backward-compatible with readers generated from the older one.
+//!
+//! Usage: `schema_diff [--json] OLD [NEW]`
+//!
+//! `OLD` and `NEW` are paths to schema JSON files; if `NEW` is not provided, the schema of the
+//! current parser is used. The changes are printed as a changelog-style summary, or with `--json`
+//! as a JSON object. Exits with a non-zero status if any change is breaking.
+
+// === Non-Standard Linter Configuration ===
+#![allow(clippy::option_map_unit_fn)]
+#![allow(clippy::precedence)]
+#![allow(dead_code)]
+#![deny(unconditional_recursion)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(unused_qualifications)]
+
+use enso_parser_schema::compat;
+use enso_parser_schema::Schema;
+
+
+
+fn main() {
+    let (flags, paths): (Vec<_>, Vec<_>) =
+        std::env::args().skip(1).partition(|arg| arg.starts_with("--"));
+    let mut json = false;
+    for flag in &flags {
+        match flag.as_str() {
+            "--json" => json = true,
+            _ => panic!("Unexpected argument: {flag}"),
+        }
+    }
+    let read = |path: &String| -> Schema {
+        let file = std::fs::File::open(path).unwrap_or_else(|e| panic!("{path}: {e}"));
+        serde_json::from_reader(std::io::BufReader::new(file)).unwrap()
+    };
+    let (old, new) = match &paths[..] {
+        [old] => (read(old), enso_parser_schema::schema()),
+        [old, new] => (read(old), read(new)),
+        _ => panic!("Expected one or two schema paths."),
+    };
+    let changes = compat::compare(&old, &new);
+    let breaking = changes.iter().any(|change| change.is_breaking());
+    if json {
+        let output = serde_json::json!({ "compatible": !breaking, "changes": changes });
+        println!("{output}");
+    } else {
+        print!("{}", compat::changelog(&changes));
+    }
+    if breaking {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,582 @@
+//! Comparison of two versions of a [`Schema`], to determine whether readers generated from the
+//! older schema can still read data serialized according to the newer one.
+//!
+//! Type IDs are arbitrary, so types are matched between the schemas by their qualified names: the
+//! name of a type, prefixed by the names of its ancestors (e.g. `tree::app`). A change is
+//! *breaking* if existing readers would misinterpret data in the new layout: a field that moved,
+//! was removed, or changed type; a variant that was removed or given a different discriminant; or
+//! a type stored inline in other objects that changed size. Additions to the layout are
+//! compatible: existing readers ignore new fields, and reject only the data that uses a new
+//! variant.
+//!
+//! However, readers check the [`Header`] that serialized data begins with, and reject data with any
+//! other header. The header includes a hash of the type graph, which changes with any change to the
+//! type definitions; so unless readers are regenerated, a change to the header is breaking even if
+//! all the layout changes accompanying it are compatible.
+
+use crate::*;
+
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+
+
+// ===============
+// === Compare ===
+// ===============
+
+/// Return the changes between the `old` and `new` schemas: a change to the header, if any, followed
+/// by the changes to types, ordered by the name of the type they affect.
+pub fn compare(old: &Schema, new: &Schema) -> Vec<Change> {
+    let mut changes = vec![];
+    if old.header != new.header {
+        let (old, new) = (old.header.clone(), new.header.clone());
+        changes.push(Change { type_name: None, kind: ChangeKind::ChangedHeader { old, new } });
+    }
+    let old = Names::new(old);
+    let new = Names::new(new);
+    let inline = inline_types(old.schema);
+    let mut type_names: Vec<_> = old.ids.keys().chain(new.ids.keys()).collect();
+    type_names.sort();
+    type_names.dedup();
+    for type_name in type_names {
+        let mut change = |kind| changes.push(Change { type_name: Some(type_name.clone()), kind });
+        match (old.ids.get(type_name), new.ids.get(type_name)) {
+            (Some(id), None) if !old.is_variant(id) => change(ChangeKind::RemovedType),
+            (None, Some(id)) if !new.is_variant(id) => change(ChangeKind::AddedType),
+            (Some(old_id), Some(new_id)) => {
+                let context = TypeChanges { old: &old, new: &new, old_id, new_id };
+                let inline = inline.contains(old_id);
+                context.compare(inline).into_iter().for_each(change);
+            }
+            _ => {}
+        }
+    }
+    changes
+}
+
+/// Return a changelog-style summary of the changes, stating whether the layout is compatible.
+pub fn changelog(changes: &[Change]) -> String {
+    let (breaking, compatible): (Vec<_>, Vec<_>) =
+        changes.iter().partition(|change| change.is_breaking());
+    let mut out = match breaking.len() {
+        0 if compatible.is_empty() => return "The serialized layout is unchanged.\n".to_owned(),
+        0 => "The serialized layout is backward-compatible.\n".to_owned(),
+        1 => "The serialized layout is not backward-compatible (1 breaking change).\n".to_owned(),
+        n => format!("The serialized layout is not backward-compatible ({n} breaking changes).\n"),
+    };
+    for (heading, changes) in [("Breaking changes", breaking), ("Compatible changes", compatible)] {
+        if !changes.is_empty() {
+            out += &format!("\n### {heading}\n\n");
+            changes.iter().for_each(|change| out += &format!("- {change}\n"));
+        }
+    }
+    out
+}
+
+
+
+// ==============
+// === Change ===
+// ==============
+
+/// A difference between two schemas.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct Change {
+    /// The qualified name of the affected type, or `None` for a change to the header.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub type_name: Option<String>,
+    /// What changed.
+    #[serde(flatten)]
+    pub kind:      ChangeKind,
+}
+
+/// The kinds of [`Change`]s.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum ChangeKind {
+    /// The header that serialized data begins with changed.
+    ChangedHeader {
+        /// The old header, if any.
+        old: Option<Header>,
+        /// The new header, if any.
+        new: Option<Header>,
+    },
+    /// A type was added.
+    AddedType,
+    /// A type was removed.
+    RemovedType,
+    /// The type's parent changed.
+    ChangedParent {
+        /// The qualified name of the old parent, if any.
+        old: Option<String>,
+        /// The qualified name of the new parent, if any.
+        new: Option<String>,
+    },
+    /// The type changed between being abstract (having variants identified by discriminants) and
+    /// being concrete.
+    ChangedAbstract {
+        /// Whether the type is now abstract.
+        is_abstract: bool,
+    },
+    /// A variant was added to an abstract type.
+    AddedVariant {
+        /// The name of the variant.
+        variant:      String,
+        /// The variant's discriminant.
+        discriminant: u32,
+    },
+    /// A variant was removed from an abstract type.
+    RemovedVariant {
+        /// The name of the variant.
+        variant:      String,
+        /// The variant's discriminant.
+        discriminant: u32,
+    },
+    /// A variant is identified by a different discriminant.
+    ReorderedDiscriminant {
+        /// The name of the variant.
+        variant: String,
+        /// The old discriminant.
+        old:     u32,
+        /// The new discriminant.
+        new:     u32,
+    },
+    /// A field was added.
+    AddedField {
+        /// The name of the field.
+        field:  String,
+        /// The field's type.
+        r#type: String,
+    },
+    /// A field was removed.
+    RemovedField {
+        /// The name of the field.
+        field: String,
+    },
+    /// A field has a different type.
+    ChangedFieldType {
+        /// The name of the field.
+        field: String,
+        /// The old type.
+        old:   String,
+        /// The new type.
+        new:   String,
+    },
+    /// A field is located at a different offset.
+    MovedField {
+        /// The name of the field.
+        field: String,
+        /// The old offset.
+        old:   usize,
+        /// The new offset.
+        new:   usize,
+    },
+    /// The number of bytes the type occupies changed.
+    ChangedSize {
+        /// The old size.
+        old:    usize,
+        /// The new size.
+        new:    usize,
+        /// Whether the type is stored inline in other objects (as a field or sequence element), so
+        /// that its size determines the layout of other data.
+        inline: bool,
+    },
+}
+
+impl Change {
+    /// Whether readers of the old layout would misinterpret data in the new layout.
+    pub fn is_breaking(&self) -> bool {
+        match &self.kind {
+            ChangeKind::AddedType
+            | ChangeKind::AddedVariant { .. }
+            | ChangeKind::AddedField { .. } => false,
+            ChangeKind::ChangedSize { inline, .. } => *inline,
+            _ => true,
+        }
+    }
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = |name: &Option<String>| match name {
+            Some(name) => format!("`{name}`"),
+            None => "none".to_owned(),
+        };
+        let header = |header: &Option<Header>| match header {
+            Some(Header { version, type_graph_hash }) =>
+                format!("version {version} with type graph hash {type_graph_hash}"),
+            None => "none".to_owned(),
+        };
+        if let Some(type_name) = &self.type_name {
+            write!(f, "`{type_name}`: ")?;
+        }
+        match &self.kind {
+            ChangeKind::ChangedHeader { old, new } =>
+                write!(f, "changed header from {} to {}", header(old), header(new)),
+            ChangeKind::AddedType => write!(f, "added type"),
+            ChangeKind::RemovedType => write!(f, "removed type"),
+            ChangeKind::ChangedParent { old, new } =>
+                write!(f, "changed parent from {} to {}", name(old), name(new)),
+            ChangeKind::ChangedAbstract { is_abstract: true } => write!(f, "became abstract"),
+            ChangeKind::ChangedAbstract { is_abstract: false } => write!(f, "became concrete"),
+            ChangeKind::AddedVariant { variant, discriminant } =>
+                write!(f, "added variant `{variant}` (discriminant {discriminant})"),
+            ChangeKind::RemovedVariant { variant, discriminant } =>
+                write!(f, "removed variant `{variant}` (discriminant {discriminant})"),
+            ChangeKind::ReorderedDiscriminant { variant, old, new } =>
+                write!(f, "changed discriminant of variant `{variant}` from {old} to {new}"),
+            ChangeKind::AddedField { field, r#type } =>
+                write!(f, "added field `{field}` of type `{type}`"),
+            ChangeKind::RemovedField { field } => write!(f, "removed field `{field}`"),
+            ChangeKind::ChangedFieldType { field, old, new } =>
+                write!(f, "changed type of field `{field}` from `{old}` to `{new}`"),
+            ChangeKind::MovedField { field, old, new } =>
+                write!(f, "moved field `{field}` from offset {old} to {new}"),
+            ChangeKind::ChangedSize { old, new, inline } => {
+                write!(f, "changed size from {old} to {new} bytes")?;
+                if *inline {
+                    write!(f, " (stored inline in other objects)")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+
+
+// ======================
+// === Implementation ===
+// ======================
+
+/// A schema, with the qualified names of its types.
+struct Names<'s> {
+    schema: &'s Schema,
+    names:  HashMap<&'s TypeId, String>,
+    ids:    BTreeMap<String, &'s TypeId>,
+}
+
+impl<'s> Names<'s> {
+    fn new(schema: &'s Schema) -> Self {
+        let qualified_name = |mut id: &'s TypeId| {
+            let mut names = vec![];
+            loop {
+                let ty = &schema.types[id];
+                names.push(ty.name.as_ref());
+                match &ty.parent {
+                    Some(parent) => id = parent,
+                    None => break,
+                }
+            }
+            names.reverse();
+            names.join("::")
+        };
+        let names: HashMap<_, _> = schema.types.keys().map(|id| (id, qualified_name(id))).collect();
+        let ids = names.iter().map(|(id, name)| (name.clone(), *id)).collect();
+        Self { schema, names, ids }
+    }
+
+    /// Whether the type is a variant of an abstract type. Variants are compared as part of their
+    /// parent.
+    fn is_variant(&self, id: &TypeId) -> bool {
+        let parent = self.schema.types[id].parent.as_ref();
+        parent.map_or(false, |parent| self.schema.serialization[parent].discriminants.is_some())
+    }
+
+    fn type_ref(&self, ty: &TypeRef) -> String {
+        match ty {
+            TypeRef::Type { id } => self.names[id].clone(),
+            TypeRef::Primitive { r#type } => format!("{type:?}").to_lowercase(),
+            TypeRef::Sequence { r#type } => format!("Vec<{}>", self.type_ref(r#type)),
+            TypeRef::Option { r#type } => format!("Option<{}>", self.type_ref(r#type)),
+            TypeRef::Result { r#type0, r#type1 } =>
+                format!("Result<{}, {}>", self.type_ref(r#type0), self.type_ref(r#type1)),
+        }
+    }
+
+    /// The type's fields, with their offsets and types.
+    fn fields(&self, id: &TypeId) -> BTreeMap<&'s str, (usize, String)> {
+        let fields = &self.schema.types[id].fields;
+        let layout = &self.schema.serialization[id];
+        let field = |(name, offset): &'s (FieldName, usize)| {
+            (name.0.as_ref(), (*offset, self.type_ref(&fields[name])))
+        };
+        layout.fields.iter().map(field).collect()
+    }
+
+    /// The type's variants, by discriminant.
+    fn variants(&self, id: &TypeId) -> Option<BTreeMap<u32, &str>> {
+        let discriminants = self.schema.serialization[id].discriminants.as_ref()?;
+        Some(
+            discriminants
+                .iter()
+                .map(|(key, id)| (key.0, self.schema.types[id].name.as_ref()))
+                .collect(),
+        )
+    }
+}
+
+/// Return the types whose encoding is stored in the fields or sequence elements of other objects.
+/// Values of any other type are stored behind a reference, so their size does not affect the
+/// layout of other data.
+fn inline_types(schema: &Schema) -> HashSet<&TypeId> {
+    fn visit<'s>(ty: &'s TypeRef, boxed: bool, out: &mut HashSet<&'s TypeId>) {
+        match ty {
+            TypeRef::Type { id } if !boxed => {
+                out.insert(id);
+            }
+            TypeRef::Type { .. } | TypeRef::Primitive { .. } => {}
+            TypeRef::Sequence { r#type } => visit(r#type, false, out),
+            TypeRef::Option { r#type } => visit(r#type, true, out),
+            TypeRef::Result { r#type0, r#type1 } => {
+                visit(r#type0, true, out);
+                visit(r#type1, true, out);
+            }
+        }
+    }
+    let mut out = HashSet::new();
+    let fields = schema.types.values().flat_map(|ty| ty.fields.values());
+    fields.for_each(|ty| visit(ty, false, &mut out));
+    out
+}
+
+/// Compares the versions of a type present in both schemas.
+struct TypeChanges<'a, 's> {
+    old:    &'a Names<'s>,
+    new:    &'a Names<'s>,
+    old_id: &'s TypeId,
+    new_id: &'s TypeId,
+}
+
+impl<'a, 's> TypeChanges<'a, 's> {
+    fn compare(&self, inline: bool) -> Vec<ChangeKind> {
+        let mut changes = vec![];
+        let parent = |names: &Names, id: &TypeId| {
+            names.schema.types[id].parent.as_ref().map(|parent| names.names[parent].clone())
+        };
+        let (old_parent, new_parent) =
+            (parent(self.old, self.old_id), parent(self.new, self.new_id));
+        if old_parent != new_parent {
+            changes.push(ChangeKind::ChangedParent { old: old_parent, new: new_parent });
+        }
+        match (self.old.variants(self.old_id), self.new.variants(self.new_id)) {
+            (Some(old), Some(new)) => self.compare_variants(&old, &new, &mut changes),
+            (None, None) => {}
+            (_, new) => changes.push(ChangeKind::ChangedAbstract { is_abstract: new.is_some() }),
+        }
+        self.compare_fields(&mut changes);
+        let old_size = self.old.schema.serialization[self.old_id].size;
+        let new_size = self.new.schema.serialization[self.new_id].size;
+        if old_size != new_size {
+            changes.push(ChangeKind::ChangedSize { old: old_size, new: new_size, inline });
+        }
+        changes
+    }
+
+    fn compare_variants(
+        &self,
+        old: &BTreeMap<u32, &str>,
+        new: &BTreeMap<u32, &str>,
+        changes: &mut Vec<ChangeKind>,
+    ) {
+        let by_name = |variants: &BTreeMap<u32, &str>| -> HashMap<String, u32> {
+            variants.iter().map(|(discriminant, name)| (name.to_string(), *discriminant)).collect()
+        };
+        let (old_by_name, new_by_name) = (by_name(old), by_name(new));
+        for (&discriminant, &variant) in old {
+            let variant = variant.to_owned();
+            match new_by_name.get(&variant) {
+                None => changes.push(ChangeKind::RemovedVariant { variant, discriminant }),
+                Some(&new) if new != discriminant => changes
+                    .push(ChangeKind::ReorderedDiscriminant { variant, old: discriminant, new }),
+                Some(_) => {}
+            }
+        }
+        for (&discriminant, &variant) in new {
+            if !old_by_name.contains_key(variant) {
+                let variant = variant.to_owned();
+                changes.push(ChangeKind::AddedVariant { variant, discriminant });
+            }
+        }
+    }
+
+    fn compare_fields(&self, changes: &mut Vec<ChangeKind>) {
+        let old = self.old.fields(self.old_id);
+        let new = self.new.fields(self.new_id);
+        for (name, (old_offset, old_type)) in &old {
+            let field = name.to_string();
+            match new.get(name) {
+                None => changes.push(ChangeKind::RemovedField { field }),
+                Some((new_offset, new_type)) => {
+                    if old_type != new_type {
+                        let (old, new) = (old_type.clone(), new_type.clone());
+                        changes.push(ChangeKind::ChangedFieldType {
+                            field: field.clone(),
+                            old,
+                            new,
+                        });
+                    }
+                    if old_offset != new_offset {
+                        let (old, new) = (*old_offset, *new_offset);
+                        changes.push(ChangeKind::MovedField { field, old, new });
+                    }
+                }
+            }
+        }
+        for (name, (_, r#type)) in &new {
+            if !old.contains_key(name) {
+                let field = name.to_string();
+                changes.push(ChangeKind::AddedField { field, r#type: r#type.clone() });
+            }
+        }
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn type_id(schema: &Schema, name: &str) -> TypeId {
+        Names::new(schema).ids[name].clone()
+    }
+
+    #[test]
+    fn unchanged() {
+        assert_eq!(compare(&schema(), &schema()), vec![]);
+        assert_eq!(changelog(&[]), "The serialized layout is unchanged.\n");
+    }
+
+    #[test]
+    fn compatible_changes() {
+        let old = schema();
+        let mut new = schema();
+        // Add a field to the end of a variant, which is only referred to by pointers.
+        let app = type_id(&new, "tree::app");
+        let field = FieldName("label".into());
+        let u32_type = TypeRef::Primitive { r#type: Primitive::U32 };
+        new.types.get_mut(&app).unwrap().fields.insert(field.clone(), u32_type);
+        let layout = new.serialization.get_mut(&app).unwrap();
+        layout.fields.push((field, layout.size));
+        layout.size += 4;
+        // Add a variant.
+        let tree = type_id(&new, "tree");
+        let variant = TypeId("new_variant".into());
+        let name = "frobnicate".into();
+        new.types.insert(variant.clone(), Type {
+            name,
+            fields: HashMap::new(),
+            parent: Some(tree.clone()),
+        });
+        let layout = Layout {
+            fields:        vec![],
+            discriminants: None,
+            size:          old.serialization[&app].size,
+        };
+        new.serialization.insert(variant.clone(), layout);
+        let discriminants =
+            new.serialization.get_mut(&tree).unwrap().discriminants.as_mut().unwrap();
+        let discriminant = discriminants.keys().last().unwrap().0 + 1;
+        discriminants.insert(Discriminant(discriminant), variant);
+        let changes = compare(&old, &new);
+        assert_eq!(changes, vec![
+            Change {
+                type_name: Some("tree".into()),
+                kind:      ChangeKind::AddedVariant { variant: "frobnicate".into(), discriminant },
+            },
+            Change {
+                type_name: Some("tree::app".into()),
+                kind:      ChangeKind::AddedField { field: "label".into(), r#type: "u32".into() },
+            },
+            Change {
+                type_name: Some("tree::app".into()),
+                kind:      ChangeKind::ChangedSize { old: 60, new: 64, inline: false },
+            },
+        ]);
+        assert!(changelog(&changes).starts_with("The serialized layout is backward-compatible.\n"));
+        // The header of data serialized with the new definitions changes with the type graph, so
+        // readers of the old layout reject it.
+        let header = new.header.as_mut().unwrap();
+        header.type_graph_hash =
+            format!("{:016x}", !u64::from_str_radix(&header.type_graph_hash, 16).unwrap());
+        let changes = compare(&old, &new);
+        let change = &changes[0];
+        assert_eq!(change.type_name, None);
+        let expected =
+            ChangeKind::ChangedHeader { old: old.header.clone(), new: new.header.clone() };
+        assert_eq!(change.kind, expected);
+        assert!(change.is_breaking());
+        assert!(changes[1..].iter().all(|change| !change.is_breaking()));
+        let changelog = changelog(&changes);
+        assert!(changelog.starts_with("The serialized layout is not backward-compatible (1 "));
+        assert!(changelog.contains("- changed header from version "));
+    }
+
+    #[test]
+    fn breaking_changes() {
+        let old = schema();
+        let mut new = schema();
+        // Remove a field.
+        let app = type_id(&new, "tree::app");
+        new.types.get_mut(&app).unwrap().fields.retain(|name, _| name.0.as_ref() != "arg");
+        new.serialization
+            .get_mut(&app)
+            .unwrap()
+            .fields
+            .retain(|(name, _)| name.0.as_ref() != "arg");
+        // Swap two discriminants.
+        let tree = type_id(&new, "tree");
+        let discriminants =
+            new.serialization.get_mut(&tree).unwrap().discriminants.as_mut().unwrap();
+        let first = discriminants.remove(&Discriminant(0)).unwrap();
+        let second = discriminants.insert(Discriminant(1), first).unwrap();
+        discriminants.insert(Discriminant(0), second);
+        // Change the type of a field stored inline in other objects.
+        let ident = type_id(&new, "token::ident");
+        let lift_level = FieldName("lift_level".into());
+        let u64_type = TypeRef::Primitive { r#type: Primitive::U64 };
+        new.types.get_mut(&ident).unwrap().fields.insert(lift_level, u64_type);
+        new.serialization.get_mut(&ident).unwrap().size += 4;
+        let changes = compare(&old, &new);
+        let kinds: Vec<_> = changes
+            .iter()
+            .map(|change| (change.type_name.as_deref().unwrap(), &change.kind))
+            .collect();
+        let names = Names::new(&old);
+        let variant = |discriminant| {
+            let discriminants = old.serialization[names.ids["tree"]].discriminants.as_ref();
+            let id = &discriminants.unwrap()[&Discriminant(discriminant)];
+            old.types[id].name.to_string()
+        };
+        assert_eq!(kinds, vec![
+            ("token::ident", &ChangeKind::ChangedFieldType {
+                field: "lift_level".into(),
+                old:   "u32".into(),
+                new:   "u64".into(),
+            }),
+            ("token::ident", &ChangeKind::ChangedSize { old: 83, new: 87, inline: true }),
+            ("tree", &ChangeKind::ReorderedDiscriminant {
+                variant: variant(0),
+                old:     0,
+                new:     1,
+            }),
+            ("tree", &ChangeKind::ReorderedDiscriminant {
+                variant: variant(1),
+                old:     1,
+                new:     0,
+            }),
+            ("tree::app", &ChangeKind::RemovedField { field: "arg".into() }),
+        ]);
+        assert!(changes.iter().all(|change| change.is_breaking()));
+        let changelog = changelog(&changes);
+        assert!(changelog.starts_with("The serialized layout is not backward-compatible (5 "));
+        assert!(changelog.contains("- `tree::app`: removed field `arg`\n"));
+    }
+}
@@ -17,19 +17,35 @@ use std::collections::HashMap;
 use std::rc::Rc;
 
 
+// ==============
+// === Export ===
+// ==============
+
+pub mod compat;
+pub mod view;
+
+
 
 // ===================
 // === Entry Point ===
 // ===================
 
 /// Return a serializable [`Schema`] describing the parser types.
-pub fn schema() -> impl serde::Serialize {
-    let (graph, _) = enso_metamodel::rust::to_meta(enso_parser::syntax::Tree::reflect());
+pub fn schema() -> Schema {
+    tree_schema().0
+}
+
+/// Return a [`Schema`] describing the parser types, and the ID of the root `Tree` type within it.
+fn tree_schema() -> (Schema, TypeId) {
+    let tree = enso_parser::syntax::Tree::reflect();
+    let (graph, rust_to_meta) = enso_metamodel::rust::to_meta(tree.clone());
     let Types { types, ids } = types(&graph);
     let serialization = serialization(&graph)
         .filter_map(|(k, v)| ids.get(&k).map(|k| (k.clone(), v.map_ids(|k| ids[&k].clone()))))
         .collect();
-    Schema { types, serialization }
+    let root = ids[&rust_to_meta[&tree.id]].clone();
+    let header = Header::current();
+    (Schema { types, serialization, header: Some(header) }, root)
 }
 
 
@@ -45,6 +61,32 @@ pub struct Schema {
     pub types:         HashMap<TypeId, Type>,
     /// Serialization information for the types.
     pub serialization: HashMap<TypeId, Layout>,
+    /// The header that serialized data begins with. Schemas produced by older versions of this
+    /// crate do not include this.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub header:        Option<Header>,
+}
+
+/// Identifies data serialized according to the schema; see [`enso_parser::serialization::Header`].
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Header {
+    /// The version of the format.
+    pub version:         u32,
+    /// The hash of the type graph, as 16 hexadecimal digits. It is represented as a string because
+    /// JSON numbers cannot represent all 64-bit values exactly in all readers.
+    pub type_graph_hash: String,
+}
+
+impl Header {
+    /// The header of data serialized by the current version of the parser.
+    pub fn current() -> Self {
+        let header = enso_parser::serialization::Header::new(enso_parser::format::FORMAT_VERSION);
+        Self {
+            version:         header.version,
+            type_graph_hash: format!("{:016x}", header.type_graph_hash),
+        }
+    }
 }
 
 
@@ -358,3 +400,35 @@ impl<Id> Layout<Id> {
         Layout { fields, discriminants, size }
     }
 }
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use enso_metamodel::typescript;
+
+    /// The readers derived by the TypeScript backend must agree with the schema on the layout of
+    /// every type.
+    #[test]
+    fn typescript_layout_matches_schema() {
+        let (graph, _) = enso_metamodel::rust::to_meta(enso_parser::syntax::Tree::reflect());
+        let (typescript, meta_to_ts) = typescript::from_meta(&graph);
+        let layout = typescript::format::Layout::new(&typescript);
+        for (id, expected) in serialization(&graph) {
+            if graph[id].data.fields().is_none() {
+                continue;
+            }
+            let ty = &typescript[meta_to_ts[&id]];
+            assert_eq!(layout.size(&typescript, meta_to_ts[&id]), expected.size, "{}", ty.name);
+            assert_eq!(ty.fields.len(), expected.fields.len());
+            for (field, (_, offset)) in ty.fields.iter().zip(&expected.fields) {
+                assert_eq!(layout.offset(field.id()), *offset, "{}.{}", ty.name, field.name);
+            }
+        }
+    }
+}
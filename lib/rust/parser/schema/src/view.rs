@@ -0,0 +1,655 @@
+//! Zero-copy navigation of data in the binary format produced by [`enso_parser::format`].
+//!
+//! A [`Reader`] holds the [`Schema`] of the parser types, which determines where each field of each
+//! type is located. Given a serialized buffer, it produces lightweight views of the values in the
+//! buffer; a value is only decoded when it is accessed, so that a tool can navigate to the parts
+//! of a tree it is interested in without deserializing (or allocating) the rest.
+//!
+//! # Example
+//!
+//! ```
+//! use enso_parser_schema::view::Reader;
+//!
+//! let code = "main = 42";
+//! let tree = enso_parser::Parser::new().run(code);
+//! let data = enso_parser::format::serialize(&tree).unwrap();
+//! let reader = Reader::new();
+//! let root = reader.tree(&data).unwrap();
+//! assert_eq!(root.type_name(), "body_block");
+//! let statements = root.get("statements").unwrap().as_sequence().unwrap();
+//! assert_eq!(statements.len(), 1);
+//! ```
+
+use crate::*;
+
+use std::fmt::Debug;
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+
+
+// ==============
+// === Reader ===
+// ==============
+
+/// Interprets data in the binary format, according to a [`Schema`].
+#[derive(Debug)]
+pub struct Reader {
+    schema: Schema,
+    root:   TypeRef,
+    header: Option<enso_parser::serialization::Header>,
+}
+
+impl Reader {
+    /// Return a reader for serialized parser trees.
+    pub fn new() -> Self {
+        let (schema, root) = tree_schema();
+        Self::from_schema(schema, root)
+    }
+
+    /// Return a reader for data with the given schema, where a serialized buffer contains a value
+    /// of the `root` type. If the schema specifies a [`Header`], data that does not begin with it
+    /// will be rejected.
+    pub fn from_schema(schema: Schema, root: TypeId) -> Self {
+        let header = schema.header.as_ref().map(|header| {
+            let type_graph_hash = u64::from_str_radix(&header.type_graph_hash, 16)
+                .expect("Schema header has an invalid type graph hash.");
+            enso_parser::serialization::Header { version: header.version, type_graph_hash }
+        });
+        Self { schema, root: TypeRef::Type { id: root }, header }
+    }
+
+    /// The schema this reader interprets data with.
+    pub fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    /// Return a view of the value of the root type serialized in the buffer.
+    pub fn root<'a>(&'a self, data: &'a [u8]) -> Result<Value<'a>> {
+        if let Some(header) = self.header {
+            header.check(data).map_err(Error::HeaderMismatch)?;
+        }
+        let size = self.size(&self.root);
+        let address =
+            data.len().checked_sub(size).ok_or(Error::OutOfBounds { address: 0, size })?;
+        Buffer { reader: self, data }.value(&self.root, address)
+    }
+
+    /// Return a view of the tree serialized in the buffer. The reader must have been created with
+    /// [`Reader::new`], or with a schema whose root type is defined in the schema.
+    pub fn tree<'a>(&'a self, data: &'a [u8]) -> Result<Object<'a>> {
+        match self.root(data)? {
+            Value::Object(object) => Ok(object),
+            _ => Err(Error::RootNotAnObject),
+        }
+    }
+
+    fn layout(&self, id: &TypeId) -> &Layout {
+        &self.schema.serialization[id]
+    }
+
+    /// The number of bytes a value of the type occupies within a containing object.
+    fn size(&self, ty: &TypeRef) -> usize {
+        match ty {
+            TypeRef::Type { id } => self.layout(id).size,
+            TypeRef::Primitive { r#type: Primitive::Bool } => 1,
+            TypeRef::Primitive { r#type: Primitive::U32 | Primitive::I32 | Primitive::Char } => 4,
+            TypeRef::Primitive { r#type: Primitive::U64 | Primitive::I64 } => 8,
+            TypeRef::Option { .. } => 1 + POINTER,
+            TypeRef::Primitive { r#type: Primitive::String }
+            | TypeRef::Sequence { .. }
+            | TypeRef::Result { .. } => POINTER,
+        }
+    }
+}
+
+impl Default for Reader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+
+// =============
+// === Views ===
+// =============
+
+/// A decoded value. Values of compound types are views referring to their data in the buffer.
+#[derive(Debug, Copy, Clone)]
+pub enum Value<'a> {
+    /// A boolean.
+    Bool(bool),
+    /// A 32-bit unsigned integer.
+    U32(u32),
+    /// A 64-bit unsigned integer.
+    U64(u64),
+    /// A 32-bit signed integer.
+    I32(i32),
+    /// A 64-bit signed integer.
+    I64(i64),
+    /// A unicode codepoint.
+    Char(char),
+    /// A string, borrowed from the buffer.
+    String(&'a str),
+    /// An object of a type defined in the schema.
+    Object(Object<'a>),
+    /// A sequence of values.
+    Sequence(Sequence<'a>),
+    /// An optional value.
+    Option(Option<Lazy<'a>>),
+    /// A value indicating success or error.
+    Result(std::result::Result<Lazy<'a>, Lazy<'a>>),
+}
+
+impl<'a> Value<'a> {
+    /// If the value is an object, return it.
+    pub fn as_object(self) -> Option<Object<'a>> {
+        match self {
+            Value::Object(object) => Some(object),
+            _ => None,
+        }
+    }
+
+    /// If the value is a sequence, return it.
+    pub fn as_sequence(self) -> Option<Sequence<'a>> {
+        match self {
+            Value::Sequence(sequence) => Some(sequence),
+            _ => None,
+        }
+    }
+
+    /// If the value is optional, return its value if present.
+    pub fn as_option(self) -> Option<Option<Lazy<'a>>> {
+        match self {
+            Value::Option(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// If the value is a string, return it.
+    pub fn as_str(self) -> Option<&'a str> {
+        match self {
+            Value::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// If the value is a 32-bit unsigned integer, return it.
+    pub fn as_u32(self) -> Option<u32> {
+        match self {
+            Value::U32(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+
+// === Lazy ===
+
+/// A value that has been located in the buffer, but not yet decoded.
+#[derive(Debug, Copy, Clone)]
+pub struct Lazy<'a> {
+    buffer:  Buffer<'a>,
+    ty:      &'a TypeRef,
+    address: usize,
+}
+
+impl<'a> Lazy<'a> {
+    /// The type of the value.
+    pub fn type_ref(&self) -> &'a TypeRef {
+        self.ty
+    }
+
+    /// Decode the value. Compound values are not decoded beyond determining their location.
+    pub fn value(&self) -> Result<Value<'a>> {
+        self.buffer.value(self.ty, self.address)
+    }
+}
+
+
+// === Object ===
+
+/// A view of an object of a type defined in the schema.
+#[derive(Debug, Copy, Clone)]
+pub struct Object<'a> {
+    buffer:  Buffer<'a>,
+    id:      &'a TypeId,
+    address: usize,
+}
+
+impl<'a> Object<'a> {
+    /// The object's type. If the object was found in a field of an abstract type, this is the
+    /// concrete type identified by its discriminant.
+    pub fn ty(&self) -> &'a Type {
+        &self.buffer.reader.schema.types[self.id]
+    }
+
+    /// The location of the object in the data.
+    pub fn address(&self) -> usize {
+        self.address
+    }
+
+    /// The name of the object's type.
+    pub fn type_name(&self) -> &'a str {
+        &self.ty().name
+    }
+
+    /// The type the object's type inherits fields from, if any.
+    pub fn parent(&self) -> Option<&'a Type> {
+        let types = &self.buffer.reader.schema.types;
+        self.ty().parent.as_ref().map(|parent| &types[parent])
+    }
+
+    /// The object's fields, including inherited fields, in the order they are serialized.
+    pub fn fields(&self) -> impl Iterator<Item = (&'a str, Lazy<'a>)> + 'a {
+        let Self { buffer, address, .. } = *self;
+        let types = &buffer.reader.schema.types;
+        let mut hierarchy = vec![self.id];
+        while let Some(parent) = &types[*hierarchy.last().unwrap()].parent {
+            hierarchy.push(parent);
+        }
+        hierarchy.into_iter().rev().flat_map(move |id| {
+            let fields = &types[id].fields;
+            buffer.reader.layout(id).fields.iter().map(move |(name, offset)| {
+                let ty = &fields[name];
+                (&*name.0, Lazy { buffer, ty, address: address + offset })
+            })
+        })
+    }
+
+    /// Return the field with the given name, if the object has such a field.
+    pub fn field(&self, name: &str) -> Option<Lazy<'a>> {
+        self.fields().find(|(field, _)| *field == name).map(|(_, value)| value)
+    }
+
+    /// Decode the field with the given name.
+    pub fn get(&self, name: &str) -> Result<Value<'a>> {
+        let field = self.field(name).ok_or_else(|| Error::NoSuchField {
+            type_name: self.type_name().to_owned(),
+            field:     name.to_owned(),
+        })?;
+        field.value()
+    }
+}
+
+
+// === Sequence ===
+
+/// A view of a sequence of values.
+#[derive(Debug, Copy, Clone)]
+pub struct Sequence<'a> {
+    buffer:  Buffer<'a>,
+    element: &'a TypeRef,
+    address: usize,
+    len:     usize,
+}
+
+impl<'a> Sequence<'a> {
+    /// The number of elements.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the sequence has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Return the element at the given index, if it is in bounds.
+    pub fn get(&self, index: usize) -> Option<Lazy<'a>> {
+        let Self { buffer, element: ty, address, len } = *self;
+        if index >= len {
+            return None;
+        }
+        let offset = index.checked_mul(buffer.reader.size(ty))?;
+        let address = address.checked_add(offset)?;
+        Some(Lazy { buffer, ty, address })
+    }
+
+    /// Iterate over the elements.
+    pub fn iter(&self) -> impl Iterator<Item = Lazy<'a>> + 'a {
+        let sequence = *self;
+        (0..self.len).map(move |i| sequence.get(i).unwrap())
+    }
+}
+
+
+
+// ==============
+// === Buffer ===
+// ==============
+
+/// Serialized data, with the information needed to interpret it.
+#[derive(Copy, Clone)]
+struct Buffer<'a> {
+    reader: &'a Reader,
+    data:   &'a [u8],
+}
+
+impl<'a> Buffer<'a> {
+    fn bytes<const N: usize>(self, address: usize) -> Result<[u8; N]> {
+        let bytes = address.checked_add(N).and_then(|end| self.data.get(address..end));
+        let bytes = bytes.ok_or(Error::OutOfBounds { address, size: N })?;
+        Ok(bytes.try_into().unwrap())
+    }
+
+    fn u32(self, address: usize) -> Result<u32> {
+        self.bytes(address).map(u32::from_le_bytes)
+    }
+
+    fn pointer(self, address: usize) -> Result<usize> {
+        self.u32(address).map(|pointer| pointer as usize)
+    }
+
+    fn value(self, ty: &'a TypeRef, address: usize) -> Result<Value<'a>> {
+        Ok(match ty {
+            TypeRef::Type { id } if self.reader.layout(id).discriminants.is_some() =>
+                Value::Object(self.abstract_object(id, address)?),
+            TypeRef::Type { id } => Value::Object(self.object(id, address)?),
+            TypeRef::Primitive { r#type } => self.primitive(*r#type, address)?,
+            TypeRef::Sequence { r#type } => {
+                let pointer = self.pointer(address)?;
+                let len = self.pointer(pointer)?;
+                let address = pointer.saturating_add(4);
+                let size = len.saturating_mul(self.reader.size(r#type));
+                if address.saturating_add(size) > self.data.len() {
+                    return Err(Error::OutOfBounds { address, size });
+                }
+                Value::Sequence(Sequence { buffer: self, element: r#type, address, len })
+            }
+            TypeRef::Option { r#type } => match self.bytes::<1>(address)? {
+                [0] => Value::Option(None),
+                [1] => {
+                    let address = self.pointer(address + 1)?;
+                    Value::Option(Some(Lazy { buffer: self, ty: r#type, address }))
+                }
+                [tag] => return Err(Error::InvalidTag { address, tag: tag.into() }),
+            },
+            TypeRef::Result { r#type0, r#type1 } => {
+                let pointer = self.pointer(address)?;
+                let address = pointer.saturating_add(4);
+                match self.u32(pointer)? {
+                    0 => Value::Result(Ok(Lazy { buffer: self, ty: r#type0, address })),
+                    1 => Value::Result(Err(Lazy { buffer: self, ty: r#type1, address })),
+                    tag => return Err(Error::InvalidTag { address: pointer, tag }),
+                }
+            }
+        })
+    }
+
+    fn primitive(self, ty: Primitive, address: usize) -> Result<Value<'a>> {
+        Ok(match ty {
+            Primitive::Bool => Value::Bool(self.bytes::<1>(address)? != [0]),
+            Primitive::U32 => Value::U32(self.u32(address)?),
+            Primitive::U64 => Value::U64(u64::from_le_bytes(self.bytes(address)?)),
+            Primitive::I32 => Value::I32(i32::from_le_bytes(self.bytes(address)?)),
+            Primitive::I64 => Value::I64(i64::from_le_bytes(self.bytes(address)?)),
+            Primitive::Char => {
+                let value = self.u32(address)?;
+                Value::Char(char::from_u32(value).ok_or(Error::InvalidChar(value))?)
+            }
+            Primitive::String => {
+                let pointer = self.pointer(address)?;
+                let len = self.pointer(pointer)?;
+                let address = pointer.saturating_add(4);
+                let bytes = address.checked_add(len).and_then(|end| self.data.get(address..end));
+                let bytes = bytes.ok_or(Error::OutOfBounds { address, size: len })?;
+                Value::String(
+                    std::str::from_utf8(bytes).map_err(|_| Error::InvalidUtf8 { address })?,
+                )
+            }
+        })
+    }
+
+    /// Return a view of an object of a concrete type located at the given address.
+    fn object(self, id: &'a TypeId, address: usize) -> Result<Object<'a>> {
+        let size = self.reader.layout(id).size;
+        if address.saturating_add(size) > self.data.len() {
+            return Err(Error::OutOfBounds { address, size });
+        }
+        Ok(Object { buffer: self, id, address })
+    }
+
+    /// Follow the reference at the given address to an object of a child type of the given type,
+    /// which is identified by the discriminant preceding the object's fields.
+    fn abstract_object(self, id: &'a TypeId, address: usize) -> Result<Object<'a>> {
+        let pointer = self.pointer(address)?;
+        let discriminant = self.u32(pointer)?;
+        let discriminants = self.reader.layout(id).discriminants.as_ref().unwrap();
+        let child = discriminants.get(&Discriminant(discriminant)).ok_or_else(|| {
+            let type_name = self.reader.schema.types[id].name.to_string();
+            Error::UnknownDiscriminant { type_name, discriminant }
+        })?;
+        self.object(child, pointer.saturating_add(4))
+    }
+}
+
+impl Debug for Buffer<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Buffer").field("len", &self.data.len()).finish()
+    }
+}
+
+
+
+// ====================
+// === Result Types ===
+// ====================
+
+/// Describes data that could not be interpreted according to the schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The data does not begin with the header the schema specifies, so it was serialized from
+    /// different type definitions, or in a different version of the format.
+    HeaderMismatch(enso_parser::serialization::HeaderMismatch),
+    /// A value extends past the end of the buffer.
+    OutOfBounds {
+        /// The location of the value.
+        address: usize,
+        /// The size of the value.
+        size:    usize,
+    },
+    /// An object of an abstract type has a discriminant that does not identify any of its child
+    /// types.
+    UnknownDiscriminant {
+        /// The name of the abstract type.
+        type_name:    String,
+        /// The discriminant found in the data.
+        discriminant: u32,
+    },
+    /// An optional value or result has a tag other than the two valid values.
+    InvalidTag {
+        /// The location of the tag.
+        address: usize,
+        /// The tag found in the data.
+        tag:     u32,
+    },
+    /// A string is not valid UTF-8.
+    InvalidUtf8 {
+        /// The location of the string's data.
+        address: usize,
+    },
+    /// A character value is not a unicode scalar value.
+    InvalidChar(u32),
+    /// A field was requested that the object's type does not have.
+    NoSuchField {
+        /// The name of the object's type.
+        type_name: String,
+        /// The requested field.
+        field:     String,
+    },
+    /// A tree was requested, but the schema's root type is not a type defined in the schema.
+    RootNotAnObject,
+}
+
+impl std::error::Error for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(self, f)
+    }
+}
+
+/// The result of reading a value.
+pub type Result<T> = std::result::Result<T, Error>;
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn serialize(code: &str) -> Vec<u8> {
+        let tree = enso_parser::Parser::new().run(code);
+        enso_parser::format::serialize(&tree).unwrap()
+    }
+
+    /// The source code of a token object, including its left offset.
+    fn token_code<'s>(code: &'s str, token: &Object) -> &'s str {
+        let start = u32_field(token, "left_offset_code_start_utf8");
+        let end = u32_field(token, "code_start_utf8") + u32_field(token, "code_repr_len");
+        &code[start..end]
+    }
+
+    /// The whitespace preceding a tree object.
+    fn tree_left_offset<'s>(code: &'s str, tree: &Object) -> &'s str {
+        let start = u32_field(tree, "span_left_offset_code_start_utf8");
+        &code[start..start + u32_field(tree, "span_left_offset_code_repr_len")]
+    }
+
+    fn u32_field(object: &Object, name: &str) -> usize {
+        object.get(name).unwrap().as_u32().unwrap() as usize
+    }
+
+    /// Visit all the tree and token objects reachable from the value, in the order of their
+    /// fields.
+    fn visit<'a>(value: Value<'a>, f: &mut impl FnMut(Object<'a>)) {
+        match value {
+            Value::Object(object) => {
+                if object.parent().is_some() {
+                    f(object);
+                }
+                for (_, field) in object.fields() {
+                    visit(field.value().unwrap(), f);
+                }
+            }
+            Value::Sequence(sequence) =>
+                sequence.iter().for_each(|element| visit(element.value().unwrap(), f)),
+            Value::Option(Some(value)) | Value::Result(Ok(value) | Err(value)) =>
+                visit(value.value().unwrap(), f),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn navigate_tree() {
+        let code = "main x = foo 42";
+        let data = serialize(code);
+        let reader = Reader::new();
+        let root = reader.tree(&data).unwrap();
+        assert_eq!(root.type_name(), "body_block");
+        assert_eq!(root.parent().unwrap().name.as_ref(), "tree");
+        let statements = root.get("statements").unwrap().as_sequence().unwrap();
+        assert_eq!(statements.len(), 1);
+        assert!(statements.get(1).is_none());
+        assert!(statements.get(usize::MAX).is_none());
+        let line = statements.get(0).unwrap().value().unwrap().as_object().unwrap();
+        let expression = line.get("expression").unwrap().as_option().unwrap().unwrap();
+        let function = expression.value().unwrap().as_object().unwrap();
+        assert_eq!(function.type_name(), "function");
+        let body = function.get("body").unwrap().as_option().unwrap().unwrap();
+        let app = body.value().unwrap().as_object().unwrap();
+        assert_eq!(app.type_name(), "app");
+        let arg = app.get("arg").unwrap().as_object().unwrap();
+        assert_eq!(arg.type_name(), "number");
+        let digits = arg.get("integer").unwrap().as_option().unwrap().unwrap();
+        let digits = digits.value().unwrap().as_object().unwrap();
+        assert_eq!(tree_left_offset(code, &arg), " ");
+        assert_eq!(token_code(code, &digits), "42");
+        assert!(matches!(app.get("no_such_field"), Err(Error::NoSuchField { .. })));
+    }
+
+    /// Check that the source code can be recovered from the tokens and offsets of a serialized
+    /// tree.
+    #[test]
+    fn round_trip() {
+        let code = [
+            "## Docs",
+            "type Maybe a",
+            "    Some (value : a)",
+            "    None",
+            "",
+            "    map self f = case self of",
+            "        Maybe.Some x -> Maybe.Some (f x)",
+            "        _ -> self",
+            "",
+            "from Standard.Base import all",
+            "main =",
+            "    text = 'Value: \\n\\u{1F600} `1 + 2`'",
+            "    xs = [1, 2.5, 0x1F] . map (x-> x * 2)",
+            "    if xs.is_empty then Nothing else xs.first",
+            "    foo (a = 1) ... _",
+            "    \"\"\"",
+            "        Multiline",
+            "        text",
+            "    Ω = '",
+        ]
+        .join("\n");
+        let data = serialize(&code);
+        let reader = Reader::new();
+        let root = reader.tree(&data).unwrap();
+        let mut recovered = String::new();
+        visit(Value::Object(root), &mut |object| match &*object.parent().unwrap().name {
+            "tree" => recovered += tree_left_offset(&code, &object),
+            "token" => recovered += token_code(&code, &object),
+            _ => {}
+        });
+        assert_eq!(recovered, code);
+    }
+
+    #[test]
+    fn node_ids() {
+        let code = "main =\n    x = foo bar\n    x + 1";
+        let tree = enso_parser::Parser::new().run(code);
+        let ids = enso_parser::syntax::tree::id::assign(&tree, &Default::default());
+        let data = enso_parser::format::serialize_tree_with_ids(&tree, &ids).unwrap();
+        let (data, table) = enso_parser::format::split_node_ids(&data).unwrap();
+        let table: HashMap<_, _> = table.into_iter().collect();
+        let reader = Reader::new();
+        let root = reader.tree(data).unwrap();
+        let mut trees = 0;
+        visit(Value::Object(root), &mut |object| {
+            if object.parent().unwrap().name.as_ref() == "tree" {
+                trees += 1;
+                let start = u32_field(&object, "span_left_offset_code_start_utf16")
+                    + u32_field(&object, "span_left_offset_code_len_utf16");
+                let len = u32_field(&object, "span_code_length_utf16");
+                let span = enso_parser::metadata::Span::new(start, len);
+                assert_eq!(table.get(&(object.address() as u32)), ids.get(span).as_ref());
+            }
+        });
+        assert_eq!(trees, table.len());
+    }
+
+    #[test]
+    fn invalid_data() {
+        let reader = Reader::new();
+        assert!(matches!(reader.tree(&[]), Err(Error::HeaderMismatch(_))));
+        let mut data = serialize("foo");
+        let len = data.len();
+        data[len - 4..].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(reader.tree(&data), Err(Error::OutOfBounds { .. })));
+        let mut data = serialize("foo");
+        let root = u32::from_le_bytes(data[len - 4..].try_into().unwrap()) as usize;
+        data[root..root + 4].copy_from_slice(&1000u32.to_le_bytes());
+        assert!(matches!(reader.tree(&data), Err(Error::UnknownDiscriminant { .. })));
+        let mut data = serialize("foo");
+        data[0] ^= 1;
+        assert!(matches!(reader.tree(&data), Err(Error::HeaderMismatch(_))));
+    }
+}
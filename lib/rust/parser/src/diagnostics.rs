@@ -0,0 +1,534 @@
+//! Reporting of the syntax errors found in a parsed [`Tree`].
+//!
+//! [`collect`] gathers the problems in a tree as [`Diagnostic`]s, with spans resolved to absolute
+//! source locations. A [`Renderer`] formats them as human-readable reports, in the style of
+//! `rustc`: each report shows the relevant source lines with a line-number gutter, and underlines
+//! the code the problem refers to. [`to_json`] produces the same information in a machine-readable
+//! form.
+
+use crate::prelude::*;
+
+use std::fmt::Write;
+
+use crate::source::code::Location;
+use crate::syntax::tree;
+use crate::syntax::tree::error::Severity;
+use crate::syntax::tree::ErrorCode;
+use crate::syntax::Tree;
+
+
+
+// ==================
+// === Diagnostic ===
+// ==================
+
+/// A problem found in the source code.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// Identifies the kind of problem.
+    pub code:     ErrorCode,
+    /// How serious the problem is.
+    pub severity: Severity,
+    /// Human-readable description of the problem.
+    pub message:  String,
+    /// The code the problem is reported at.
+    pub primary:  Range<Location>,
+    /// Other code related to the problem, with explanations.
+    pub labels:   Vec<(Range<Location>, String)>,
+    /// Additional explanations.
+    pub notes:    Vec<String>,
+    /// A suggested edit: the code to replace, its replacement, and a description of the edit.
+    pub fix:      Option<(Range<Location>, String, String)>,
+}
+
+impl Diagnostic {
+    /// The parts of the diagnostic that are shown in a report.
+    pub fn report(&self) -> Report {
+        Report {
+            severity: self.severity,
+            code:     self.code.code(),
+            message:  &self.message,
+            primary:  &self.primary,
+            labels:   &self.labels,
+            notes:    &self.notes,
+            fix:      self.fix.as_ref(),
+        }
+    }
+
+    fn from_error(error: &tree::Error, node_start: Location) -> Self {
+        let tree::Error { code, severity, message, primary, labels, notes, fix } = error;
+        Self {
+            code:     *code,
+            severity: *severity,
+            message:  message.to_string(),
+            primary:  primary.range(node_start),
+            labels:   labels
+                .iter()
+                .map(|label| (label.span.range(node_start), label.message.to_string()))
+                .collect(),
+            notes:    notes.iter().map(|note| note.to_string()).collect(),
+            fix:      fix.as_ref().map(|fix| {
+                let range = fix.span.range(node_start);
+                (range, fix.replacement.to_string(), fix.description.to_string())
+            }),
+        }
+    }
+
+    fn invalid_escape(token: &crate::syntax::token::TextEscape) -> Self {
+        let code = ErrorCode::InvalidEscapeSequence;
+        Self {
+            code,
+            severity: code.severity(),
+            message: format!("Invalid escape sequence: `{}`.", token.code),
+            primary: token.code.range(),
+            labels: default(),
+            notes: default(),
+            fix: default(),
+        }
+    }
+}
+
+/// Return the problems found in the given tree, ordered by location.
+pub fn collect(tree: &Tree) -> Vec<Diagnostic> {
+    let diagnostics = RefCell::new(vec![]);
+    let check = |tree: &Tree| match &*tree.variant {
+        tree::Variant::Invalid(invalid) => {
+            let diagnostic = Diagnostic::from_error(&invalid.error, tree.span.range().start);
+            diagnostics.borrow_mut().push(diagnostic);
+        }
+        tree::Variant::TextLiteral(text) =>
+            for element in &text.elements {
+                if let tree::TextElement::Escape { token } = element
+                    && token.variant.value.is_none()
+                {
+                    diagnostics.borrow_mut().push(Diagnostic::invalid_escape(token));
+                }
+            },
+        _ => (),
+    };
+    check(tree);
+    tree.visit_trees(check);
+    let mut diagnostics = diagnostics.into_inner();
+    diagnostics.sort_by_key(|diagnostic| (diagnostic.primary.start, diagnostic.primary.end));
+    diagnostics
+}
+
+
+
+// ================
+// === Renderer ===
+// ================
+
+/// A problem to be reported by a [`Renderer`]. This is the content of a [`Diagnostic`], with the
+/// kind of problem identified by a string; other tools can report their findings in the same format
+/// by producing it.
+#[derive(Debug, Clone, Copy)]
+pub struct Report<'a> {
+    /// How serious the problem is.
+    pub severity: Severity,
+    /// Identifies the kind of problem.
+    pub code:     &'a str,
+    /// Human-readable description of the problem.
+    pub message:  &'a str,
+    /// The code the problem is reported at.
+    pub primary:  &'a Range<Location>,
+    /// Other code related to the problem, with explanations.
+    pub labels:   &'a [(Range<Location>, String)],
+    /// Additional explanations.
+    pub notes:    &'a [String],
+    /// A suggested edit: the code to replace, its replacement, and a description of the edit.
+    pub fix:      Option<&'a (Range<Location>, String, String)>,
+}
+
+/// Formats [`Diagnostic`]s as reports quoting the source code they refer to.
+#[derive(Debug, Clone)]
+pub struct Renderer<'a> {
+    path:  &'a str,
+    lines: Vec<&'a str>,
+    color: bool,
+}
+
+impl<'a> Renderer<'a> {
+    /// Create a renderer for diagnostics in the given source, which will be identified in reports
+    /// by the given path.
+    pub fn new(path: &'a str, source: &'a str) -> Self {
+        let lines = split_lines(source);
+        Self { path, lines, color: false }
+    }
+
+    /// Set whether ANSI escape codes are used to style the output.
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Render a report of each diagnostic, separated by empty lines.
+    pub fn render_all(&self, diagnostics: &[Diagnostic]) -> String {
+        let mut out = String::new();
+        for (i, diagnostic) in diagnostics.iter().enumerate() {
+            if i != 0 {
+                out.push('\n');
+            }
+            out.push_str(&self.render(diagnostic));
+        }
+        out
+    }
+
+    /// Render a report of the diagnostic.
+    pub fn render(&self, diagnostic: &Diagnostic) -> String {
+        self.render_report(diagnostic.report())
+    }
+
+    /// Render a report of a problem.
+    pub fn render_report(&self, report: Report) -> String {
+        let mut annotations =
+            vec![Annotation { range: report.primary.clone(), message: None, primary: true }];
+        annotations.extend(report.labels.iter().map(|(range, message)| Annotation {
+            range:   range.clone(),
+            message: Some(message.as_str()),
+            primary: false,
+        }));
+        let mut lines: Vec<_> = annotations.iter().flat_map(|a| a.displayed_lines()).collect();
+        lines.sort_unstable();
+        lines.dedup();
+        lines.retain(|&line| line < self.lines.len());
+        let last_line = lines.last().copied().unwrap_or_default();
+        let width = (last_line + 1).to_string().len();
+        let pad = " ".repeat(width);
+        let severity = match report.severity {
+            Severity::Error => self.style(Style::Error, "error"),
+            Severity::Warning => self.style(Style::Warning, "warning"),
+        };
+        let mut out = String::new();
+        let code = self.style(Style::from(report.severity), &format!("[{}]", report.code));
+        let message = self.style(Style::Emphasis, report.message);
+        writeln!(out, "{severity}{code}: {message}").unwrap();
+        let start = report.primary.start;
+        let arrow = self.style(Style::Gutter, "-->");
+        let (line, column) = (start.line + 1, start.col16 + 1);
+        writeln!(out, "{pad}{arrow} {}:{line}:{column}", self.path).unwrap();
+        let bar = self.style(Style::Gutter, "|");
+        writeln!(out, "{pad} {bar}").unwrap();
+        let mut previous = None;
+        for line in lines {
+            if let Some(previous) = previous
+                && line > previous + 1
+            {
+                writeln!(out, "{}", self.style(Style::Gutter, "...")).unwrap();
+            }
+            previous = Some(line);
+            let text = self.lines[line];
+            let number = self.style(Style::Gutter, &format!("{:>width$}", line + 1));
+            writeln!(out, "{number} {bar} {text}").unwrap();
+            for annotation in &annotations {
+                if let Some((start, end)) = annotation.columns(line, text) {
+                    let style = match annotation.primary {
+                        true => Style::from(report.severity),
+                        false => Style::Gutter,
+                    };
+                    let marker = if annotation.primary { "^" } else { "-" };
+                    let underline = marker.repeat((end - start).max(1));
+                    let mut underline = format!("{}{underline}", " ".repeat(start));
+                    if let Some(message) = annotation.message
+                        && line == annotation.range.end.line as usize
+                    {
+                        underline = format!("{underline} {message}");
+                    }
+                    writeln!(out, "{pad} {bar} {}", self.style(style, &underline)).unwrap();
+                }
+            }
+        }
+        let equals = self.style(Style::Gutter, "=");
+        for note in report.notes {
+            writeln!(out, "{pad} {equals} {}: {note}", self.style(Style::Emphasis, "note"))
+                .unwrap();
+        }
+        if let Some((_, replacement, description)) = report.fix {
+            let help = self.style(Style::Emphasis, "help");
+            writeln!(out, "{pad} {equals} {help}: {description}: `{replacement}`").unwrap();
+        }
+        out
+    }
+
+    fn style(&self, style: Style, text: &str) -> String {
+        if !self.color {
+            return text.to_owned();
+        }
+        let code = match style {
+            Style::Error => "1;31",
+            Style::Warning => "1;33",
+            Style::Gutter => "1;34",
+            Style::Emphasis => "1",
+        };
+        format!("\x1b[{code}m{text}\x1b[0m")
+    }
+}
+
+/// Split source code into lines, recognizing the same line terminators as [`Location::line`].
+fn split_lines(source: &str) -> Vec<&str> {
+    let mut lines = vec![];
+    let mut start = 0;
+    let mut chars = source.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if c == '\r' || c == '\n' {
+            lines.push(&source[start..i]);
+            if c == '\r'
+                && let Some((_, '\n')) = chars.peek()
+            {
+                chars.next();
+                start = i + 2;
+            } else {
+                start = i + 1;
+            }
+        }
+    }
+    lines.push(&source[start..]);
+    lines
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Style {
+    Error,
+    Warning,
+    Gutter,
+    Emphasis,
+}
+
+impl From<Severity> for Style {
+    fn from(severity: Severity) -> Self {
+        match severity {
+            Severity::Error => Style::Error,
+            Severity::Warning => Style::Warning,
+        }
+    }
+}
+
+
+// === Annotation ===
+
+/// A range of code to be underlined in a report.
+#[derive(Debug)]
+struct Annotation<'a> {
+    range:   Range<Location>,
+    message: Option<&'a str>,
+    primary: bool,
+}
+
+/// Annotations spanning more lines than this are shown by their first and last lines only.
+const MAX_ANNOTATED_LINES: u32 = 4;
+
+impl<'a> Annotation<'a> {
+    /// The source lines to display for this annotation.
+    fn displayed_lines(&self) -> Vec<usize> {
+        let (first, last) = (self.range.start.line, self.range.end.line);
+        let lines: Vec<_> = if last - first < MAX_ANNOTATED_LINES {
+            (first..=last).collect()
+        } else {
+            vec![first, last]
+        };
+        lines.into_iter().map(|line| line as usize).collect()
+    }
+
+    /// The range of characters underlined in the given line, if the annotation covers it.
+    fn columns(&self, line: usize, text: &str) -> Option<(usize, usize)> {
+        let Range { start, end } = &self.range;
+        let line = u32::try_from(line).unwrap();
+        if !(start.line..=end.line).contains(&line) {
+            return None;
+        }
+        let start = if line == start.line { char_index(text, start.col16) } else { 0 };
+        let end = if line == end.line { char_index(text, end.col16) } else { text.chars().count() };
+        Some((start, end.max(start)))
+    }
+}
+
+/// Convert an offset in UTF-16 code units into an index of characters within the line.
+fn char_index(text: &str, col16: u32) -> usize {
+    let mut offset16 = 0;
+    for (i, c) in text.chars().enumerate() {
+        if offset16 >= col16 {
+            return i;
+        }
+        offset16 += c.len_utf16() as u32;
+    }
+    text.chars().count()
+}
+
+
+
+// ============
+// === JSON ===
+// ============
+
+/// Represent the diagnostics as JSON. Lines and columns are zero-based; columns are measured in
+/// UTF-16 code units.
+pub fn to_json(path: &str, diagnostics: &[Diagnostic]) -> serde_json::Value {
+    let diagnostics = diagnostics.iter().map(|diagnostic| {
+        let mut json = report_to_json(path, diagnostic.report());
+        json["kind"] = format!("{:?}", diagnostic.code).into();
+        json
+    });
+    serde_json::Value::Array(diagnostics.collect())
+}
+
+/// Represent a problem as JSON, in the format used by [`to_json`].
+pub fn report_to_json(path: &str, report: Report) -> serde_json::Value {
+    let severity = match report.severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+    };
+    let labels: Vec<_> = report.labels.iter().map(label_to_json).collect();
+    let fix = report.fix.map(fix_to_json);
+    serde_json::json!({
+        "path": path,
+        "code": report.code,
+        "severity": severity,
+        "message": report.message,
+        "range": range_to_json(report.primary),
+        "labels": labels,
+        "notes": report.notes,
+        "fix": fix,
+    })
+}
+
+fn label_to_json((range, message): &(Range<Location>, String)) -> serde_json::Value {
+    serde_json::json!({ "range": range_to_json(range), "message": message })
+}
+
+fn fix_to_json(
+    (range, replacement, description): &(Range<Location>, String, String),
+) -> serde_json::Value {
+    serde_json::json!({
+        "range": range_to_json(range),
+        "replacement": replacement,
+        "description": description,
+    })
+}
+
+fn range_to_json(range: &Range<Location>) -> serde_json::Value {
+    let start = location_to_json(&range.start);
+    let end = location_to_json(&range.end);
+    serde_json::json!({ "start": start, "end": end })
+}
+
+fn location_to_json(location: &Location) -> serde_json::Value {
+    serde_json::json!({ "line": location.line, "column": location.col16 })
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(code: &str) -> String {
+        let tree = crate::Parser::new().run(code);
+        Renderer::new("test.enso", code).render_all(&collect(&tree))
+    }
+
+    #[test]
+    fn render_primary_span_and_fix() {
+        let expected = [
+            "error[E0021]: Space required between term and unary-operator expression.",
+            " --> test.enso:2:6",
+            "  |",
+            "2 | x = 1~y",
+            "  |      ^",
+            "  = help: Insert a space: ` `",
+            "",
+        ];
+        assert_eq!(render("main =\nx = 1~y"), expected.join("\n"));
+    }
+
+    #[test]
+    fn render_invalid_escape() {
+        let code = "x = 'a\\u{110000}b'";
+        let expected = [
+            "warning[E0027]: Invalid escape sequence: `\\u{110000}`.",
+            " --> test.enso:1:7",
+            "  |",
+            "1 | x = 'a\\u{110000}b'",
+            "  |       ^^^^^^^^^^",
+            "",
+        ];
+        assert_eq!(render(code), expected.join("\n"));
+        let diagnostics = collect(&crate::Parser::new().run(code));
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn render_multiline_source() {
+        let code = "foo =\r\n    x = (\r\n    y";
+        let rendered = render(code);
+        assert!(rendered.contains("2 |     x = (\n  |         ^\n"), "{rendered}");
+    }
+
+    #[test]
+    fn render_labels() {
+        let code = "a\nb\nc\nd\nfoo bar";
+        let location = |line, col16| Location { utf8: 0, utf16: 0, line, col16 };
+        let diagnostic = Diagnostic {
+            code:     ErrorCode::InvalidAssignment,
+            severity: Severity::Warning,
+            message:  "Message.".into(),
+            primary:  location(4, 4)..location(4, 7),
+            labels:   vec![(location(0, 0)..location(0, 1), "Label.".into())],
+            notes:    vec!["Note.".into()],
+            fix:      None,
+        };
+        let expected = [
+            "warning[E0026]: Message.",
+            " --> test.enso:5:5",
+            "  |",
+            "1 | a",
+            "  | - Label.",
+            "...",
+            "5 | foo bar",
+            "  |     ^^^",
+            "  = note: Note.",
+            "",
+        ];
+        assert_eq!(Renderer::new("test.enso", code).render(&diagnostic), expected.join("\n"));
+    }
+
+    #[test]
+    fn render_color() {
+        let code = "x = ]";
+        let tree = crate::Parser::new().run(code);
+        let rendered = Renderer::new("a", code).with_color(true).render_all(&collect(&tree));
+        assert!(rendered.starts_with("\x1b[1;31merror\x1b[0m"), "{rendered:?}");
+    }
+
+    #[test]
+    fn json() {
+        let code = "x = ]";
+        let tree = crate::Parser::new().run(code);
+        let json = to_json("a.enso", &collect(&tree));
+        let expected = serde_json::json!([{
+            "path": "a.enso",
+            "code": "E0002",
+            "kind": "UnmatchedDelimiter",
+            "severity": "error",
+            "message": "Unmatched delimiter.",
+            "range": {
+                "start": { "line": 0, "column": 4 },
+                "end": { "line": 0, "column": 5 },
+            },
+            "labels": [],
+            "notes": [],
+            "fix": {
+                "range": {
+                    "start": { "line": 0, "column": 4 },
+                    "end": { "line": 0, "column": 5 },
+                },
+                "replacement": "",
+                "description": "Remove the unmatched delimiter",
+            },
+        }]);
+        assert_eq!(json, expected);
+    }
+}
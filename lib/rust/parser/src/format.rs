@@ -21,6 +21,28 @@
 //!   performance, and a recursion-free implementation that would allow arbitrarily-deep trees.
 //! - ❌ Rejected: Use the `len` hints provided by `serde` to pre-allocate objects of the correct
 //!   size: The requirement that every field have the same size representation would be too onerous.
+//!
+//! # Reading
+//!
+//! The layout of each type is described by the schema generated by the `enso-parser-schema` crate,
+//! which also provides a zero-copy reader for Rust (`enso_parser_schema::view`).
+//! The data begins with a [`Header`]; a reader should check that it matches the header of the
+//! types the reader was generated from, and reject the data otherwise.
+//!
+//! # Node IDs
+//!
+//! [`serialize_tree_with_ids`] appends a table of the IDs of the tree's nodes to the data, keyed by
+//! the addresses of the nodes' objects; see there for its layout. [`split_node_ids`] separates the
+//! table from the data, which can then be read like the output of [`serialize`].
+
+use crate::metadata::IdMap;
+use crate::metadata::Span as IdSpan;
+use crate::serialization::Header;
+use crate::syntax::item;
+use crate::syntax::tree::ItemVisitable;
+use crate::syntax::tree::ItemVisitor;
+use crate::syntax::tree::Visitor;
+use crate::syntax::Tree;
 
 use serde::ser;
 use serde::ser::SerializeSeq;
@@ -28,6 +50,7 @@ use serde::Serialize;
 use std::fmt::Debug;
 use std::fmt::Display;
 use std::fmt::Formatter;
+use uuid::Uuid;
 
 
 
@@ -40,24 +63,94 @@ use std::fmt::Formatter;
 /// [`test::wasm::test_infinite_recursion`]).
 const RECURSION_LIMIT: usize = 1024;
 
+/// The version of the binary format. This must be changed when the format changes in a way not
+/// reflected in the types being serialized. Versions are allocated from the same sequence as
+/// [`serialization::FORMAT_VERSION`](crate::serialization::FORMAT_VERSION), so that the header
+/// also distinguishes this format from that one; a new version takes the next unused number.
+pub const FORMAT_VERSION: u32 = 2;
+
 /// If enabled, logs debugging info to stderr.
 const DEBUG: bool = false;
 
+/// The size of an entry of the table of node IDs produced by [`serialize_tree_with_ids`].
+const ID_ENTRY_SIZE: usize = 4 + 8 + 8;
+
 
 
 // =================
 // === Serialize ===
 // =================
 
-/// Generate a binary representation of the value.
+/// Generate a binary representation of the value. The output begins with a [`Header`] identifying
+/// the format version and the parser's types; addresses in the output are relative to the start of
+/// the output, including the header.
 pub fn serialize<T: Serialize>(value: T) -> Result<Vec<u8>> {
-    let mut serializer = Serializer::new();
-    value.serialize(&mut serializer)?;
-    serializer.heap.append(&mut serializer.stack);
-    debug_assert_eq!(serializer.recursion_depth, 0);
-    debug_assert_eq!(serializer.object_depth, 0);
-    debug_assert_eq!(&serializer.parent_structs, &[]);
-    Ok(serializer.heap)
+    Ok(Serializer::new().run(value)?.heap)
+}
+
+/// Generate a binary representation of the tree, like [`serialize`], followed by a table of the IDs
+/// of its nodes. Each node receives the ID associated with its location in the given map (see
+/// [`crate::syntax::tree::id::assign`]); nodes whose locations are not in the map are not included
+/// in the table.
+///
+/// The table consists of an entry for each node, in order of address: the address of the node's
+/// object (the location of its fields, which follow its discriminant), as a little-endian `u32`;
+/// and the node's ID, as the high and then the low 64 bits of the
+/// UUID, each a little-endian `u64`. The entries are followed by their number, as a little-endian
+/// `u32`.
+pub fn serialize_tree_with_ids(tree: &Tree, ids: &IdMap) -> Result<Vec<u8>> {
+    let serializer = Serializer { tree_addresses: Some(vec![]), ..Serializer::new() };
+    let mut serializer = serializer.run(tree)?;
+    let addresses = serializer.tree_addresses.take().unwrap_or_default();
+    let mut spans = PostOrderSpans::default();
+    tree.visit_item(&mut spans);
+    debug_assert_eq!(addresses.len(), spans.0.len());
+    let mut output = serializer.heap;
+    let mut count = 0u32;
+    for (address, span) in addresses.into_iter().zip(spans.0) {
+        if let Some(id) = ids.get(span) {
+            let (high, low) = id.as_u64_pair();
+            output.extend_from_slice(&address.to_le_bytes());
+            output.extend_from_slice(&high.to_le_bytes());
+            output.extend_from_slice(&low.to_le_bytes());
+            count += 1;
+        }
+    }
+    output.extend_from_slice(&count.to_le_bytes());
+    Ok(output)
+}
+
+/// Separate the output of [`serialize_tree_with_ids`] into the representation of the tree, as
+/// produced by [`serialize`], and the IDs of its nodes, by the addresses of the nodes' objects.
+/// Returns [`None`] if the data is too short to contain the table it specifies.
+pub fn split_node_ids(data: &[u8]) -> Option<(&[u8], Vec<(u32, Uuid)>)> {
+    let u32_at = |bytes: &[u8], i: usize| u32::from_le_bytes(bytes[i..i + 4].try_into().unwrap());
+    let u64_at = |bytes: &[u8], i: usize| u64::from_le_bytes(bytes[i..i + 8].try_into().unwrap());
+    let count_start = data.len().checked_sub(4)?;
+    let count = u32_at(data, count_start) as usize;
+    let table_start = count_start.checked_sub(count.checked_mul(ID_ENTRY_SIZE)?)?;
+    let (tree, table) = data[..count_start].split_at(table_start);
+    let ids = table
+        .chunks_exact(ID_ENTRY_SIZE)
+        .map(|entry| (u32_at(entry, 0), Uuid::from_u64_pair(u64_at(entry, 4), u64_at(entry, 12))))
+        .collect();
+    Some((tree, ids))
+}
+
+/// Collects the locations of the nodes of a tree, in post-order: the order in which the
+/// [`Serializer`] builds their objects.
+#[derive(Debug, Default)]
+struct PostOrderSpans(Vec<IdSpan>);
+
+impl Visitor for PostOrderSpans {}
+impl<'s, 'a> ItemVisitor<'s, 'a> for PostOrderSpans {
+    fn visit_item(&mut self, item: item::Ref<'s, 'a>) -> bool {
+        if let item::Ref::Tree(tree) = item {
+            tree.variant.visit_item(self);
+            self.0.push(IdSpan::of_tree(tree));
+        }
+        false
+    }
 }
 
 
@@ -76,6 +169,8 @@ pub struct Serializer {
     recursion_depth: usize,
     object_depth:    usize,
     parent_structs:  Vec<ParentStruct>,
+    /// If set, the address of every `Tree` object is recorded, in the order the objects are built.
+    tree_addresses:  Option<Vec<u32>>,
 }
 
 impl Serializer {
@@ -84,6 +179,17 @@ impl Serializer {
         Self::default()
     }
 
+    /// Write the header and the representation of the value.
+    fn run<T: Serialize>(mut self, value: T) -> Result<Self> {
+        self.heap.extend_from_slice(&Header::new(FORMAT_VERSION).to_bytes());
+        value.serialize(&mut self)?;
+        self.heap.append(&mut self.stack);
+        debug_assert_eq!(self.recursion_depth, 0);
+        debug_assert_eq!(self.object_depth, 0);
+        debug_assert_eq!(&self.parent_structs, &[]);
+        Ok(self)
+    }
+
     fn object_serializer(&mut self) -> Result<ObjectSerializer> {
         if self.recursion_depth < RECURSION_LIMIT {
             self.recursion_depth += 1;
@@ -132,8 +238,7 @@ impl<'a> ObjectSerializer<'a> {
 struct ParentStruct {
     object_depth_inside: usize,
     begin:               usize,
-    // Useful for debugging.
-    _name:               &'static str,
+    name:                &'static str,
 }
 
 
@@ -273,7 +378,7 @@ impl<'a> ser::Serializer for &'a mut Serializer {
             && ancestor.object_depth_inside == self.object_depth
         {
             let parent_start = ancestor.begin;
-            let _ancestor_name = ancestor._name;
+            let ancestor_name = ancestor.name;
             // Add the child's fields to the stack (following the parent's fields).
             value.serialize(&mut *self)?;
             // Build the object on the heap.
@@ -282,7 +387,13 @@ impl<'a> ser::Serializer for &'a mut Serializer {
             self.heap.extend(self.stack.drain(parent_start..));
             let end_address = self.heap.len();
             if DEBUG {
-                eprintln!(">> {address}-{end_address} [{_ancestor_name}::{variant}]");
+                eprintln!(">> {address}-{end_address} [{ancestor_name}::{variant}]");
+            }
+            if ancestor_name == "Tree"
+                && let Some(addresses) = &mut self.tree_addresses
+            {
+                let fields = address + variant_index.to_le_bytes().len();
+                addresses.push(u32::try_from(fields).unwrap());
             }
             self.serialize_u32(u32::try_from(address).unwrap())?;
         } else {
@@ -336,7 +447,7 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         if matches!(name, "Tree" | "Token") {
             let object_depth_inside = self.object_depth;
             let begin = self.stack.len();
-            self.parent_structs.push(ParentStruct { object_depth_inside, begin, _name: name });
+            self.parent_structs.push(ParentStruct { object_depth_inside, begin, name });
         }
         Ok(self)
     }
@@ -538,6 +649,28 @@ mod test {
         assert!(super::serialize(Cyclic::new()).is_err());
     }
 
+    #[test]
+    fn node_id_table() {
+        let tree = crate::Parser::new().run("main = foo bar");
+        let data = super::serialize(&tree).unwrap();
+        let no_ids = crate::metadata::IdMap::default();
+        let mut with_ids = super::serialize_tree_with_ids(&tree, &no_ids).unwrap();
+        assert_eq!(super::split_node_ids(&with_ids), Some((&data[..], vec![])));
+        let ids = crate::syntax::tree::id::assign(&tree, &no_ids);
+        with_ids = super::serialize_tree_with_ids(&tree, &ids).unwrap();
+        let (tree_data, table) = super::split_node_ids(&with_ids).unwrap();
+        assert_eq!(tree_data, data);
+        // Nested nodes with the same location share an ID.
+        let distinct: std::collections::HashSet<_> = table.iter().map(|(_, id)| *id).collect();
+        assert_eq!(distinct, ids.iter().map(|(_, id)| id).collect());
+        assert!(table.windows(2).all(|entries| entries[0].0 < entries[1].0));
+        // The root is the last object built, and is referred to by the last field of the data.
+        let root = u32::from_le_bytes(data[data.len() - 4..].try_into().unwrap()) + 4;
+        let root_span = crate::metadata::Span::of_tree(&tree);
+        assert_eq!(table.last(), Some(&(root, ids.get(root_span).unwrap())));
+        assert_eq!(super::split_node_ids(&with_ids[..3]), None);
+    }
+
     #[cfg(target_arch = "wasm32")]
     mod wasm {
 
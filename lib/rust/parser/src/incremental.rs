@@ -0,0 +1,642 @@
+//! Incremental reparsing. After a small edit to a module, most of its top-level lines are
+//! unaffected; this module reuses them from the previous tree instead of resolving them again.
+//!
+//! The edited source is always lexed in full, so that the token stream is exactly the one that
+//! [`Parser::run`] would produce. Macro resolution and operator precedence resolution, however, are
+//! only run on a *window* of top-level lines around the edit. The window is bounded by lines that
+//! start at column 0 with an expression: at such a line, every block has been closed, and no
+//! multiline text or documentation literal can continue. The lines outside the window are taken
+//! from the old tree and moved to their new locations.
+//!
+//! The result is always equal to the result of parsing the new source with [`Parser::run`]; when
+//! it is not possible to determine a window for which that is guaranteed within a few attempts, the
+//! window is extended up to the end of the module.
+
+use crate::prelude::*;
+
+use crate::lexer;
+use crate::macros;
+use crate::source::code::Location;
+use crate::source::*;
+use crate::syntax::token;
+use crate::syntax::tree::*;
+use crate::syntax::Token;
+use crate::Parser;
+
+
+
+// ============
+// === Edit ===
+// ============
+
+/// A change to source code: the text in the given byte range is replaced by new text.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Edit {
+    /// The replaced range of the old source, in UTF-8 code units (bytes).
+    pub range: Range<usize>,
+    /// The text inserted in place of the replaced range.
+    pub text:  String,
+}
+
+impl Edit {
+    /// Constructor.
+    pub fn new(range: Range<usize>, text: impl Into<String>) -> Self {
+        let text = text.into();
+        Self { range, text }
+    }
+
+    /// Return the result of applying this edit to the given source.
+    pub fn apply(&self, code: &str) -> String {
+        let mut code = code.to_owned();
+        code.replace_range(self.range.clone(), &self.text);
+        code
+    }
+
+    /// The end of the inserted text, in UTF-8 code units of the new source.
+    fn new_end(&self) -> usize {
+        self.range.start + self.text.len()
+    }
+}
+
+
+
+// ===============
+// === Reparse ===
+// ===============
+
+/// The maximum number of windows that are resolved before falling back to resolving the rest of
+/// the module. Each attempt resolves the whole window, so without a limit, a long run of lines
+/// that cannot end a window would make reparsing quadratic in the number of lines.
+const MAX_WINDOW_ATTEMPTS: usize = 4;
+
+/// Parse `code`, which must be the result of applying `edit` to `old_code`; `old_tree` must be the
+/// result of parsing `old_code`. See the module documentation for details.
+pub fn reparse<'s>(
+    parser: &Parser,
+    old_tree: Tree<'_>,
+    old_code: &str,
+    edit: &Edit,
+    code: &'s str,
+) -> Tree<'s> {
+    debug_assert_eq!(edit.apply(old_code), code);
+    let Tree {
+        span: old_span,
+        variant: box Variant::BodyBlock(BodyBlock { statements: mut old_lines }),
+    } = old_tree
+    else {
+        return parser.run(code);
+    };
+    let tokens = lexer::run(code);
+    if tokens.internal_error.is_some() {
+        return parser.run(code);
+    }
+    let tokens = tokens.value;
+    // The window starts at the last line that begins before the edit, and is not affected by it.
+    let start_line = (1..old_lines.len())
+        .rev()
+        .find(|&i| line_content_start(&old_lines[i]).is_some_and(|p| p < edit.range.start))
+        .unwrap_or_default();
+    let start_token = match start_line {
+        0 => 0,
+        i => match find_newline(&tokens, 0, old_lines[i].newline.code.range().start.utf8) {
+            Some(index) => index,
+            None => return parser.run(code),
+        },
+    };
+    let old_window_start = usize_from(old_lines[start_line].newline.code.range().start.utf8);
+    let new_window_start = old_window_start;
+    let mut window = None;
+    let mut depth = Depth { index: start_token, depth: 0 };
+    let mut attempts = 0;
+    for (end_line, line) in old_lines.iter().enumerate().skip(start_line + 1) {
+        let old_end = usize_from(line.newline.code.range().start.utf8);
+        if line_content_start(line).is_none() || old_end <= edit.range.end {
+            continue;
+        }
+        let new_end = old_end - edit.range.end + edit.new_end();
+        // A splice can carry lexer state across lines; a window containing one is not reliably
+        // bounded by its lines.
+        if old_code[old_window_start..old_end].contains('`')
+            || code[new_window_start..new_end].contains('`')
+        {
+            break;
+        }
+        let Some(end_token) = find_newline(&tokens, start_token, u32_from(new_end)) else {
+            continue;
+        };
+        if depth.advance_to(&tokens, end_token) != 0 {
+            continue;
+        }
+        if attempts == MAX_WINDOW_ATTEMPTS {
+            break;
+        }
+        attempts += 1;
+        let (offset, lines) = resolve_window(parser, &tokens[start_token..end_token], start_line);
+        if lines.last().is_some_and(is_dangling_prefix) {
+            continue;
+        }
+        window = Some((offset, lines, end_line, end_token));
+        break;
+    }
+    let (window_offset, window_lines, end) = match window {
+        Some((offset, lines, end_line, end_token)) => (offset, lines, Some((end_line, end_token))),
+        None => {
+            let (offset, lines) = resolve_window(parser, &tokens[start_token..], start_line);
+            (offset, lines, None)
+        }
+    };
+    let suffix = end.map(|(end_line, end_token)| (old_lines.split_off(end_line), end_token));
+    old_lines.truncate(start_line);
+    let unmoved = Relocation::identity(code);
+    let mut lines = old_lines.relocate(&unmoved);
+    lines.extend(window_lines);
+    if let Some((suffix, end_token)) = suffix {
+        let mut suffix = suffix.into_iter();
+        let block::Line { newline: old_newline, expression } = suffix.next().unwrap();
+        let newline = tokens[end_token].clone().with_variant(token::variant::Newline());
+        let from = old_newline.code.range().end;
+        let to = newline.code.range().end;
+        let moved = Relocation { source: code, from, to };
+        lines.push(block::Line { newline, expression: expression.relocate(&moved) });
+        lines.extend(suffix.map(|line| line.relocate(&moved)));
+    }
+    // The block construction moves the left offsets of the elements preceding the first code in
+    // the module to the block. Offsets that were moved out of reused lines are taken from the block
+    // that contained them; the newline token of the window's first line is an exception, as it is
+    // always taken from the token stream, and therefore moved again.
+    let mut left_offset = match start_line {
+        0 => window_offset,
+        _ => old_span.left_offset.relocate(&unmoved),
+    };
+    let mut tree = Tree::body_block(lines);
+    let new_offset = mem::take(&mut tree.span.left_offset);
+    if start_line == 0 || new_offset.is_empty() {
+        left_offset += new_offset;
+    }
+    tree.span.left_offset = left_offset;
+    tree
+}
+
+/// If the line begins at column 0 with an expression, return the location of its first character.
+/// Such a line cannot be a continuation of any preceding line.
+fn line_content_start(line: &block::Line) -> Option<usize> {
+    let expression = line.expression.as_ref()?;
+    let is_line_break = line.newline.code.starts_with(['\n', '\r']);
+    let is_unindented = expression.span.left_offset.is_empty();
+    (is_line_break && is_unindented)
+        .then(|| usize_from(expression.span.left_offset.code.range().end.utf8))
+}
+
+/// Find the `Newline` token whose code starts at the given location.
+fn find_newline(tokens: &[Token], from: usize, location: u32) -> Option<usize> {
+    let tokens_from = &tokens[from..];
+    let first = tokens_from.partition_point(|t| t.code.range().start.utf8 < location);
+    tokens_from[first..]
+        .iter()
+        .take_while(|t| t.code.range().start.utf8 == location)
+        .position(|t| matches!(t.variant, token::Variant::Newline(_)))
+        .map(|i| from + first + i)
+}
+
+/// Run the macro resolver and precedence resolver on the tokens of a window, and return its lines,
+/// along with the left offset of the block containing them.
+fn resolve_window<'s>(
+    parser: &Parser,
+    tokens: &[Token<'s>],
+    start_line: usize,
+) -> (Offset<'s>, Vec<block::Line<'s>>) {
+    let mut resolver = macros::resolver::Resolver::new_statement();
+    let tree = resolver.run(&parser.macros, tokens.iter().cloned());
+    let Tree { span, variant: box Variant::BodyBlock(BodyBlock { mut statements }) } = tree else {
+        unreachable!()
+    };
+    // Unless the window starts at the beginning of the module, its tokens start with the newline
+    // token of its first line; the empty line preceding that is not part of the window. Because
+    // that line is empty, the block construction has moved the left offset of the newline token to
+    // the block; it is restored from the token stream.
+    if start_line != 0 {
+        statements.remove(0);
+        let newline = tokens[0].clone().with_variant(token::variant::Newline());
+        statements[0].newline = newline;
+    }
+    (span.left_offset, statements)
+}
+
+/// Return whether the line is a prefix (such as an annotation or a documentation comment) that was
+/// not applied to any expression, because there were no lines after it. If there had been, it
+/// would have been applied to the next expression. Consecutive prefixes are nested, so the
+/// innermost one is checked.
+fn is_dangling_prefix(line: &block::Line) -> bool {
+    let mut expression = line.expression.as_ref();
+    while let Some(tree) = expression {
+        expression = match &*tree.variant {
+            Variant::Annotated(Annotated { expression, .. })
+            | Variant::AnnotatedBuiltin(AnnotatedBuiltin { expression, .. })
+            | Variant::Documented(Documented { expression, .. }) => expression.as_ref(),
+            _ => return false,
+        };
+    }
+    line.expression.is_some()
+}
+
+/// Tracks the block nesting depth at a position in a token stream.
+#[derive(Debug)]
+struct Depth {
+    index: usize,
+    depth: i32,
+}
+
+impl Depth {
+    fn advance_to(&mut self, tokens: &[Token], index: usize) -> i32 {
+        for token in &tokens[self.index..index] {
+            match token.variant {
+                token::Variant::BlockStart(_) => self.depth += 1,
+                token::Variant::BlockEnd(_) => self.depth -= 1,
+                _ => (),
+            }
+        }
+        self.index = index;
+        self.depth
+    }
+}
+
+fn usize_from(x: u32) -> usize {
+    usize::try_from(x).unwrap()
+}
+
+fn u32_from(x: usize) -> u32 {
+    u32::try_from(x).unwrap()
+}
+
+
+
+// ==================
+// === Relocation ===
+// ==================
+
+/// Describes how to move syntax elements from one source to another source containing the same
+/// text at a different location. Every location at or after `from` is moved to the corresponding
+/// location relative to `to`.
+#[derive(Debug, Clone, Copy)]
+struct Relocation<'t> {
+    source: &'t str,
+    from:   Location,
+    to:     Location,
+}
+
+impl<'t> Relocation<'t> {
+    /// Rebind elements to a new source, without moving them.
+    fn identity(source: &'t str) -> Self {
+        Self { source, from: default(), to: default() }
+    }
+
+    fn location(&self, location: Location) -> Location {
+        let Self { from, to, .. } = *self;
+        Location {
+            utf8:  location.utf8 - from.utf8 + to.utf8,
+            utf16: location.utf16 - from.utf16 + to.utf16,
+            line:  location.line - from.line + to.line,
+            col16: match location.line == from.line {
+                true => location.col16 - from.col16 + to.col16,
+                false => location.col16,
+            },
+        }
+    }
+
+    fn code(&self, code: Code) -> Code<'t> {
+        // Some error-recovery paths leave tokens taken with `mem::take` in the tree; such tokens
+        // are empty, and have no location in the source.
+        if code.is_empty() && code.range().start == default() {
+            return Code::empty_without_location();
+        }
+        let start = self.location(code.range().start);
+        let begin = usize_from(start.utf8);
+        let repr = &self.source[begin..begin + code.len()];
+        debug_assert_eq!(repr, &**code.repr);
+        Code::from_str_at_location(repr, start)
+    }
+}
+
+/// A syntax element that can be moved to another source. See [`Relocation`].
+trait Relocate {
+    type Output<'t>;
+    fn relocate<'t>(self, to: &Relocation<'t>) -> Self::Output<'t>;
+}
+
+
+// === Source elements ===
+
+impl<'s> Relocate for Offset<'s> {
+    type Output<'t> = Offset<'t>;
+    fn relocate<'t>(self, to: &Relocation<'t>) -> Self::Output<'t> {
+        Offset(self.visible, to.code(self.code))
+    }
+}
+
+impl<'s> Relocate for Span<'s> {
+    type Output<'t> = Span<'t>;
+    fn relocate<'t>(self, to: &Relocation<'t>) -> Self::Output<'t> {
+        Span { left_offset: self.left_offset.relocate(to), code_length: self.code_length }
+    }
+}
+
+impl<'s, T> Relocate for Token<'s, T> {
+    type Output<'t> = Token<'t, T>;
+    fn relocate<'t>(self, to: &Relocation<'t>) -> Self::Output<'t> {
+        Token(self.left_offset.relocate(to), to.code(self.code), self.variant)
+    }
+}
+
+impl<'s> Relocate for Tree<'s> {
+    type Output<'t> = Tree<'t>;
+    fn relocate<'t>(self, to: &Relocation<'t>) -> Self::Output<'t> {
+        let variant = Box::new((*self.variant).relocate(to));
+        Tree { span: self.span.relocate(to), variant }
+    }
+}
+
+
+// === Containers ===
+
+impl<T: Relocate> Relocate for Option<T> {
+    type Output<'t> = Option<T::Output<'t>>;
+    fn relocate<'t>(self, to: &Relocation<'t>) -> Self::Output<'t> {
+        self.map(|t| t.relocate(to))
+    }
+}
+
+impl<T: Relocate, E: Relocate> Relocate for Result<T, E> {
+    type Output<'t> = Result<T::Output<'t>, E::Output<'t>>;
+    fn relocate<'t>(self, to: &Relocation<'t>) -> Self::Output<'t> {
+        self.map(|t| t.relocate(to)).map_err(|e| e.relocate(to))
+    }
+}
+
+impl<T: Relocate> Relocate for Vec<T> {
+    type Output<'t> = Vec<T::Output<'t>>;
+    fn relocate<'t>(self, to: &Relocation<'t>) -> Self::Output<'t> {
+        self.into_iter().map(|t| t.relocate(to)).collect()
+    }
+}
+
+impl<T: Relocate> Relocate for NonEmptyVec<T> {
+    type Output<'t> = NonEmptyVec<T::Output<'t>>;
+    fn relocate<'t>(self, to: &Relocation<'t>) -> Self::Output<'t> {
+        self.mapped(|t| t.relocate(to))
+    }
+}
+
+
+// === Values without source references ===
+
+macro_rules! relocate_as_is {
+    ($($ty:ty),* $(,)?) => {$(
+        impl Relocate for $ty {
+            type Output<'t> = $ty;
+            fn relocate<'t>(self, _: &Relocation<'t>) -> Self::Output<'t> {
+                self
+            }
+        }
+    )*};
+}
+
+relocate_as_is!(bool, u32, Error);
+
+
+// === Tree elements ===
+
+macro_rules! relocate_fields {
+    ($($ty:ident { $($field:ident),* $(,)? })*) => {$(
+        impl<'s> Relocate for $ty<'s> {
+            type Output<'t> = $ty<'t>;
+            fn relocate<'t>(self, to: &Relocation<'t>) -> Self::Output<'t> {
+                $ty { $($field: self.$field.relocate(to)),* }
+            }
+        }
+    )*};
+}
+
+relocate_fields! {
+    ArgumentDefinitionLine { newline, argument }
+    DocComment { open, elements, newlines }
+    FractionalDigits { dot, digits }
+    ArgumentDefinition { open, open2, suspension, pattern, type_, close2, default, close }
+    ArgumentDefault { equals, expression }
+    ArgumentType { operator, type_ }
+    ReturnSpecification { arrow, r#type }
+    CaseLine { newline, case }
+    Case { documentation, pattern, arrow, expression }
+    MultipleOperatorError { operators }
+    MultiSegmentAppSegment { header, body }
+    OperatorDelimitedTree { operator, body }
+}
+
+mod block_elements {
+    use super::*;
+    use crate::syntax::tree::block::*;
+
+    relocate_fields! {
+        Line { newline, expression }
+        OperatorLine { newline, expression }
+        OperatorBlockExpression { operator, expression }
+    }
+}
+
+impl<'s> Relocate for TextElement<'s> {
+    type Output<'t> = TextElement<'t>;
+    fn relocate<'t>(self, to: &Relocation<'t>) -> Self::Output<'t> {
+        match self {
+            TextElement::Section { text } => TextElement::Section { text: text.relocate(to) },
+            TextElement::Escape { token } => TextElement::Escape { token: token.relocate(to) },
+            TextElement::Newline { newline } =>
+                TextElement::Newline { newline: newline.relocate(to) },
+            TextElement::Splice { open, expression, close } => TextElement::Splice {
+                open:       open.relocate(to),
+                expression: expression.relocate(to),
+                close:      close.relocate(to),
+            },
+        }
+    }
+}
+
+macro_rules! generate_relocate_impls {
+    (
+        $(#$enum_meta:tt)*
+        pub enum $enum:ident<'s> {
+            $(
+                $(#$variant_meta:tt)*
+                $variant:ident $({$($(#$field_meta:tt)* pub $field:ident : $field_ty:ty),* $(,)? })?
+            ),* $(,)?
+        }
+    ) => {
+        impl<'s> Relocate for $enum<'s> {
+            type Output<'t> = $enum<'t>;
+            fn relocate<'t>(self, to: &Relocation<'t>) -> Self::Output<'t> {
+                match self {
+                    $($enum::$variant(node) => $enum::$variant(node.relocate(to)),)*
+                }
+            }
+        }
+        $(
+            impl<'s> Relocate for $variant<'s> {
+                type Output<'t> = $variant<'t>;
+                fn relocate<'t>(self, to: &Relocation<'t>) -> Self::Output<'t> {
+                    $variant { $($($field: self.$field.relocate(to)),*)? }
+                }
+            }
+        )*
+    };
+}
+
+crate::with_ast_definition!(generate_relocate_impls());
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+#[cfg(not(target_arch = "wasm32"))]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+    use rand_chacha::ChaCha8Rng;
+
+    /// Lines used to generate sources. They are chosen to exercise constructs that span multiple
+    /// lines.
+    const LINES: &[&str] = &[
+        "main =",
+        "x = 1",
+        "foo a b = a + b",
+        "Some value",
+        "## Documentation.",
+        "@Builtin_Type",
+        "@annotation x",
+        "bar = foo 2 . to_text",
+        "case x of",
+        "1 -> 2",
+        "if x then y else z",
+        "raw = \"text\"",
+        "block = '''",
+        "multiline text",
+        "import Standard.Base.Data.Vector",
+        "from Standard.Base import all",
+        "# Comment.",
+        "f = x -> x + 1",
+        "list = [1, 2, 3]",
+        "private",
+        "",
+        "x",
+        "+ 1",
+        "value : Integer",
+    ];
+
+    /// Fragments inserted by edits within a line.
+    const FRAGMENTS: &[&str] = &["x", "1", " ", "    ", "\n", "\r\n", "=", " + ", "(", ")", "'"];
+
+    fn random_code(rng: &mut ChaCha8Rng, lines: usize) -> String {
+        let mut code = String::new();
+        let mut indent = 0usize;
+        for _ in 0..lines {
+            // Indent:
+            // 1/4 chance of increasing.
+            // 1/4 chance of decreasing.
+            // 1/2 chance of leaving unchanged.
+            match rng.gen_range(0..4) {
+                0u32 => indent = indent.saturating_sub(1),
+                1 => indent += 1,
+                _ => (),
+            }
+            code.push_str(&"    ".repeat(indent));
+            code.push_str(LINES[rng.gen_range(0..LINES.len())]);
+            code.push('\n');
+        }
+        code
+    }
+
+    fn random_line(rng: &mut ChaCha8Rng) -> String {
+        let indent = "    ".repeat(rng.gen_range(0..3));
+        format!("{indent}{}\n", LINES[rng.gen_range(0..LINES.len())])
+    }
+
+    fn random_edit(rng: &mut ChaCha8Rng, code: &str) -> Edit {
+        let line_starts: Vec<_> = [0]
+            .into_iter()
+            .chain(code.match_indices('\n').map(|(i, _)| i + 1))
+            .filter(|&i| i < code.len())
+            .collect();
+        match rng.gen_range(0..4) {
+            // Insert a line.
+            0u32 => {
+                let start = line_starts.get(rng.gen_range(0..=line_starts.len())).copied();
+                let start = start.unwrap_or(code.len());
+                Edit::new(start..start, random_line(rng))
+            }
+            // Delete a line.
+            1 if !line_starts.is_empty() => {
+                let start = line_starts[rng.gen_range(0..line_starts.len())];
+                let end = code[start..].find('\n').map_or(code.len(), |i| start + i + 1);
+                Edit::new(start..end, "")
+            }
+            // Edit within a line.
+            _ => {
+                let start = rng.gen_range(0..=code.len());
+                let end = (start + rng.gen_range(0..3)).min(code.len());
+                let text = match rng.gen_range(0..3) {
+                    0u32 => "",
+                    _ => FRAGMENTS[rng.gen_range(0..FRAGMENTS.len())],
+                };
+                Edit::new(start..end, text)
+            }
+        }
+    }
+
+    fn check_edits(parser: &Parser, rng: &mut ChaCha8Rng, code: &str, tree: Tree, edits: usize) {
+        if edits == 0 {
+            return;
+        }
+        let edit = random_edit(rng, code);
+        let new_code = edit.apply(code);
+        let expected = parser.run(&new_code);
+        let tree = parser.reparse(tree, code, &edit, &new_code);
+        assert_eq!(tree, expected, "code: {code:?}, edit: {edit:?}");
+        check_edits(parser, rng, &new_code, tree, edits - 1);
+    }
+
+    #[test]
+    fn reparse_randomized_edits() {
+        let parser = Parser::new();
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        for _ in 0..1_000 {
+            let lines = rng.gen_range(0..30);
+            let code = random_code(&mut rng, lines);
+            let tree = parser.run(&code);
+            check_edits(&parser, &mut rng, &code, tree, 10);
+        }
+    }
+
+    #[test]
+    fn reparse_edit_in_block() {
+        let parser = Parser::new();
+        let code = "main =\n    x = 1\n    x\n\nfoo a = a + 1\n\nbar = foo 2\n";
+        let tree = parser.run(code);
+        let edit = Edit::new(21..22, "y");
+        let new_code = edit.apply(code);
+        let expected = parser.run(&new_code);
+        assert_eq!(parser.reparse(tree, code, &edit, &new_code), expected);
+    }
+
+    #[test]
+    fn reparse_before_dangling_prefixes() {
+        let parser = Parser::new();
+        // Every window ending in the annotations ends with a dangling prefix, so the window is
+        // extended until the limit on attempts is reached.
+        let code = format!("x = 1\n{}", "@annotation x\n".repeat(20));
+        let tree = parser.run(&code);
+        let edit = Edit::new(4..5, "2");
+        let new_code = edit.apply(&code);
+        let expected = parser.run(&new_code);
+        assert_eq!(parser.reparse(tree, &code, &edit, &new_code), expected);
+    }
+}
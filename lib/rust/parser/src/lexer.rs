@@ -88,13 +88,15 @@ pub struct Lexer<'s> {
     state:         LexerState,
     input:         &'s str,
     iterator:      str::CharIndices<'s>,
+    /// The offset in `input` of the text `iterator` iterates over, in UTF-8 code units.
+    iterator_base: u32,
     output:        Vec<Token<'s>>,
     /// Memory for storing tokens, reused as an optimization.
     token_storage: VecAllocation<Token<'s>>,
 }
 
 /// Internal state of the [`Lexer`].
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 #[allow(missing_docs)]
 pub struct LexerState {
     current_char: Option<char>,
@@ -130,12 +132,34 @@ struct Mark<'s> {
 impl<'s> Lexer<'s> {
     /// Constructor.
     pub fn new(input: &'s str) -> Self {
+        Self::new_with_buffer(input, &mut default())
+    }
+
+    /// Constructor. The output will be stored in memory taken from the given buffer.
+    pub fn new_with_buffer(input: &'s str, buffer: &mut TokenBuffer) -> Self {
         let iterator = input.char_indices();
         let capacity = input.len() / AVERAGE_TOKEN_LEN;
-        let output = Vec::with_capacity(capacity);
+        let mut output = buffer.take();
+        output.reserve(capacity);
         let state = default();
         let token_storage = default();
-        Self { input, iterator, output, state, token_storage }.init()
+        let iterator_base = 0;
+        Self { input, iterator, iterator_base, output, state, token_storage }.init()
+    }
+
+    /// Constructor. The lexer will continue from the state captured in the snapshot; the input must
+    /// be unchanged up to and including the first character of the snapshot's line that is not
+    /// whitespace. See [`Snapshot`].
+    pub fn resume(input: &'s str, snapshot: &Snapshot) -> Self {
+        let location = snapshot.state.current_offset;
+        let iterator = input[usize_from(location.utf8)..].char_indices();
+        let iterator_base = location.utf8;
+        let output = default();
+        let mut state = snapshot.state.clone();
+        // The current character will be read from the new input.
+        state.current_char = None;
+        let token_storage = default();
+        Self { input, iterator, iterator_base, output, state, token_storage }.init()
     }
 
     fn init(mut self) -> Self {
@@ -152,7 +176,7 @@ impl<'s> Lexer<'s> {
             let prev = self.current_offset;
             let char_len16 = self.current_char.map_or(0, |c| c.len_utf16() as u32);
             self.current_offset = Location {
-                utf8:  u32_from(current_offset),
+                utf8:  self.iterator_base + u32_from(current_offset),
                 utf16: prev.utf16 + char_len16,
                 line:  prev.line,
                 col16: prev.col16 + char_len16,
@@ -997,10 +1021,10 @@ impl<'s> Lexer<'s> {
                 initial_indent = self.last_spaces_visible_offset.into();
             }
         }
-        self.text_content(None, text_type.is_interpolated(), State::MultilineText {
-            block_indent,
-            initial_indent,
-        });
+        let state = State::MultilineText { block_indent, initial_indent };
+        if let TextEndedAt::Splice = self.text_content(None, text_type.is_interpolated(), state) {
+            self.spaces_after_lexeme();
+        }
     }
 
     fn inline_quote(&mut self, quote_char: char, text_type: TextType) {
@@ -1009,17 +1033,21 @@ impl<'s> Lexer<'s> {
     }
 
     fn end_splice(&mut self, state: State) {
-        let splice_quote_start = self.mark_without_whitespace();
+        let splice_quote_start = self.mark();
         self.take_next();
         let splice_quote_end = self.mark_without_whitespace();
         let token =
             self.make_token(splice_quote_start, splice_quote_end, token::Variant::close_symbol());
         self.output.push(token);
         match state {
-            State::InlineText => self.inline_quote('\'', TextType::Interpolated),
-            State::MultilineText { .. } => {
-                self.text_content(None, true, state);
+            State::InlineText => {
+                self.inline_quote('\'', TextType::Interpolated);
+                self.spaces_after_lexeme();
             }
+            State::MultilineText { .. } =>
+                if let TextEndedAt::Splice = self.text_content(None, true, state) {
+                    self.spaces_after_lexeme();
+                },
         }
     }
 
@@ -1434,17 +1462,26 @@ impl<'s> Lexer<'s> {
     /// Run the lexer. Return non-hierarchical list of tokens (the token groups will be represented
     /// as start and end tokens).
     pub fn run(mut self) -> ParseResult<Vec<Token<'s>>> {
-        // If the first line is indented, open a block for it.
+        self.start();
+        while PARSERS.iter().any(|f| self.run_and_check_if_progressed(f)) {}
+        self.finish()
+    }
+
+    fn start(&mut self) {
+        // If the first line is indented, open a block for it. A line containing only whitespace
+        // doesn't open a block; its whitespace is attached to the following newline.
         self.spaces_after_lexeme();
         let first_block_indent = self.last_spaces_visible_offset;
-        if first_block_indent.width_in_spaces != 0 {
+        let is_blank = self.current_char.map_or(true, is_newline_char);
+        if first_block_indent.width_in_spaces != 0 && !is_blank {
             let start = Location::default();
             self.submit_token(token::block_start(Code::empty(start), Code::empty(start)).into());
             self.start_block(first_block_indent);
             self.submit_token(token::newline(Code::empty(start), Code::empty(start)).into());
         }
-        // Main parsing loop.
-        while PARSERS.iter().any(|f| self.run_and_check_if_progressed(f)) {}
+    }
+
+    fn finish(mut self) -> ParseResult<Vec<Token<'s>>> {
         // If any blocks were still open at EOF, close them.
         while self.end_block().is_some() {
             let block_end = self.marker_token(token::Variant::block_end());
@@ -1480,6 +1517,188 @@ pub fn run(input: &'_ str) -> ParseResult<Vec<Token<'_>>> {
     Lexer::new(input).run()
 }
 
+/// Run the lexer, storing the output in memory taken from the given buffer. See [`run`].
+pub fn run_with_buffer<'s>(
+    input: &'s str,
+    buffer: &mut TokenBuffer,
+) -> ParseResult<Vec<Token<'s>>> {
+    Lexer::new_with_buffer(input, buffer).run()
+}
+
+
+// === TokenBuffer ===
+
+/// The capacity needed for the output of the lexer, which can be allocated up front by runs of the
+/// lexer on later inputs, instead of growing the output as tokens are produced.
+///
+/// Unlike a [`VecAllocation`], the buffer is not tied to the lifetime of any input, so it keeps
+/// only the capacity of the output rather than its memory.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct TokenBuffer {
+    capacity: usize,
+}
+
+impl TokenBuffer {
+    /// Drop the tokens in the given `Vec`, remembering its capacity for the next call to `take`.
+    pub fn set_from(&mut self, tokens: Vec<Token<'_>>) {
+        self.capacity = self.capacity.max(tokens.capacity());
+    }
+
+    /// Return an empty `Vec`, with at least the capacity of any `Vec` passed to `set_from`.
+    pub fn take<'s>(&mut self) -> Vec<Token<'s>> {
+        Vec::with_capacity(self.capacity)
+    }
+}
+
+
+
+// ==================
+// === Resumption ===
+// ==================
+
+/// The state of the lexer at the start of a line, from which lexing can be resumed with
+/// [`Lexer::resume`].
+///
+/// A snapshot is taken when the lexer has consumed the indentation of a line and is about to read
+/// its first token. The tokens produced from that point depend only on the snapshot and on the
+/// input that follows; so, after an edit, the input can be re-lexed from the last snapshot before
+/// the edit. Once the lexer reaches a snapshot taken after the edit in the same state (see
+/// [`Snapshot::relocated`]), the rest of the output is the same as before the edit.
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    state:       LexerState,
+    token_index: usize,
+}
+
+impl Snapshot {
+    /// The location of the first character of the line that is not whitespace; lexing resumes
+    /// there.
+    pub fn location(&self) -> Location {
+        self.state.current_offset
+    }
+
+    /// The location of the start of the line.
+    pub fn line_start(&self) -> Location {
+        self.state.last_spaces_offset
+    }
+
+    /// The number of tokens the lexer produced before the snapshot was taken.
+    pub fn token_index(&self) -> usize {
+        self.token_index
+    }
+
+    /// Return the snapshot with its locations updated for an edit of the input, which replaced code
+    /// ending at `old_end` with code ending at `new_end`. The snapshot must have been taken after
+    /// the edited code: its line must start no earlier than `old_end`. The token index is not
+    /// changed.
+    pub fn relocated(&self, old_end: Location, new_end: Location) -> Self {
+        debug_assert!(self.line_start().utf8 >= old_end.utf8);
+        let relocate = |location: Location| Location {
+            utf8:  location.utf8 - old_end.utf8 + new_end.utf8,
+            utf16: location.utf16 - old_end.utf16 + new_end.utf16,
+            line:  location.line - old_end.line + new_end.line,
+            col16: match location.line == old_end.line {
+                true => location.col16 - old_end.col16 + new_end.col16,
+                false => location.col16,
+            },
+        };
+        let mut snapshot = self.clone();
+        snapshot.state.current_offset = relocate(self.state.current_offset);
+        snapshot.state.last_spaces_offset = relocate(self.state.last_spaces_offset);
+        snapshot
+    }
+
+    /// Return whether the lexer would produce the same output when resumed from either snapshot.
+    fn is_equivalent(&self, other: &Self) -> bool {
+        let a = &self.state;
+        let b = &other.state;
+        a.current_offset == b.current_offset
+            && a.last_spaces_offset == b.last_spaces_offset
+            && a.last_spaces_visible_offset == b.last_spaces_visible_offset
+            && a.current_block_indent == b.current_block_indent
+            && a.block_indent_stack == b.block_indent_stack
+            && a.stack == b.stack
+    }
+}
+
+/// The output of a resumable run of the lexer.
+#[derive(Debug)]
+pub struct Resumed<'s> {
+    /// The tokens produced. If the run converged, these are only the tokens up to the point of
+    /// convergence.
+    pub tokens:    ParseResult<Vec<Token<'s>>>,
+    /// The snapshots taken at the start of each line that was lexed.
+    pub snapshots: Vec<Snapshot>,
+    /// If lexing reached a state equivalent to one of the `previous` snapshots the run was given,
+    /// the index of that snapshot. Lexing stops there: the tokens following the snapshot in the
+    /// output it was taken from are the rest of the output.
+    pub converged: Option<usize>,
+}
+
+impl<'s> Lexer<'s> {
+    fn run_resumable(mut self, resumed: Option<&Snapshot>, previous: &[Snapshot]) -> Resumed<'s> {
+        let tokens_before = resumed.map_or(0, |snapshot| snapshot.token_index);
+        let mut last_line = resumed.map(|snapshot| snapshot.line_start().line);
+        let mut snapshots: Vec<Snapshot> = vec![];
+        let mut converged = None;
+        loop {
+            let line_start = self.last_spaces_offset;
+            if line_start.col16 == 0 && last_line.map_or(true, |line| line < line_start.line) {
+                last_line = Some(line_start.line);
+                let token_index = tokens_before + self.output.len();
+                let snapshot = Snapshot { state: self.state.clone(), token_index };
+                let location = snapshot.location().utf8;
+                let candidate = previous.partition_point(|s| s.location().utf8 < location);
+                if let Some(previous) = previous.get(candidate)
+                    && previous.is_equivalent(&snapshot)
+                {
+                    converged = Some(candidate);
+                    break;
+                }
+                snapshots.push(snapshot);
+            }
+            if !PARSERS.iter().any(|f| self.run_and_check_if_progressed(f)) {
+                break;
+            }
+        }
+        let tokens = match converged {
+            Some(_) => {
+                let internal_error = self.internal_error.take();
+                ParseResult { value: self.output, internal_error }
+            }
+            None => self.finish(),
+        };
+        Resumed { tokens, snapshots, converged }
+    }
+}
+
+/// Run the lexer, taking a [`Snapshot`] at the start of each line. See [`run`].
+pub fn run_with_snapshots(input: &'_ str) -> Resumed<'_> {
+    resume(input, None, &[])
+}
+
+/// Lex the input from the state in the given snapshot, or from the start if there is none, taking a
+/// [`Snapshot`] at the start of each line, until a state equivalent to one of the `previous`
+/// snapshots is reached.
+///
+/// To re-lex an edited input, resume from the last snapshot located before the start of the edit,
+/// and pass as `previous` the snapshots taken after the end of the edit, [relocated]. The output
+/// for the edited input is then the tokens that preceded the resumed snapshot, followed by the
+/// tokens produced by this function, followed by (if it converged) the tokens that followed the
+/// previous snapshot it converged with. The `previous` snapshots must be ordered by location.
+///
+/// [relocated]: Snapshot::relocated
+pub fn resume<'s>(input: &'s str, from: Option<&Snapshot>, previous: &[Snapshot]) -> Resumed<'s> {
+    match from {
+        Some(snapshot) => Lexer::resume(input, snapshot).run_resumable(from, previous),
+        None => {
+            let mut lexer = Lexer::new(input);
+            lexer.start();
+            lexer.run_resumable(None, previous)
+        }
+    }
+}
+
 
 
 // =============
@@ -1962,6 +2181,86 @@ mod tests {
         lex_and_validate_spans("Linux\n...");
         lex_and_validate_spans("Classic Mac OS\r...");
     }
+
+    /// Re-lex the input after replacing the given range with the replacement, resuming from the
+    /// snapshots of the lexer's run on the original input, and check that the result is the same as
+    /// lexing the edited input from the start. Return whether the lexer converged. Edits that
+    /// produce input the lexer fails on are ignored.
+    fn test_resume(input: &str, edit: Range<usize>, replacement: &str) -> bool {
+        fn describe(token: &Token, shift: i64) -> (String, String, Variant, i64) {
+            let location = i64::from(token.code.range().start.utf8) + shift;
+            let left_offset = token.left_offset.code.repr.to_string();
+            (left_offset, token.code.repr.to_string(), token.variant, location)
+        }
+        let location_of = |text: &str, offset| Location::default() + Length::of(&text[..offset]);
+        let before = run_with_snapshots(input);
+        let old_tokens = before.tokens.unwrap();
+        let edited = format!("{}{replacement}{}", &input[..edit.start], &input[edit.end..]);
+        let expected = run(&edited);
+        if expected.internal_error.is_some() {
+            return false;
+        }
+        let expected: Vec<_> = expected.value.iter().map(|t| describe(t, 0)).collect();
+        let old_end = location_of(input, edit.end);
+        let new_end = location_of(&edited, edit.start + replacement.len());
+        let snapshots = &before.snapshots;
+        let from = snapshots.iter().rev().find(|s| usize_from(s.location().utf8) < edit.start);
+        let previous: Vec<_> = snapshots
+            .iter()
+            .filter(|snapshot| snapshot.line_start().utf8 >= old_end.utf8)
+            .map(|snapshot| snapshot.relocated(old_end, new_end))
+            .collect();
+        let resumed = resume(&edited, from, &previous);
+        let prefix = from.map_or(0, |snapshot| snapshot.token_index());
+        let mut tokens: Vec<_> = old_tokens[..prefix].iter().map(|t| describe(t, 0)).collect();
+        tokens.extend(resumed.tokens.unwrap().iter().map(|t| describe(t, 0)));
+        if let Some(index) = resumed.converged {
+            let shift = i64::from(new_end.utf8) - i64::from(old_end.utf8);
+            let rest = &old_tokens[previous[index].token_index()..];
+            tokens.extend(rest.iter().map(|t| describe(t, shift)));
+        }
+        assert_eq!(tokens, expected, "Replacing {edit:?} in {input:?} with {replacement:?}.");
+        resumed.converged.is_some()
+    }
+
+    #[test]
+    fn test_resume_after_edits() {
+        let inputs = [
+            "main =\n    x = foo bar\n    y =\n        x + 1\n\n    y\n",
+            "type T\n    A\n    B x\n\n    f self = case self of\n        A -> 1\n        B x -> x\n",
+            "x = '''\n    text\n      more `splice\n  y` end\n\n    last\nz = 'ä'\n",
+            "  indented\n    block\n  back\nzero\n",
+            "## Doc\n   comment\nf = \"\"\"\n    raw\n# Comment\ng = 'a\\n'",
+        ];
+        let replacements = ["", "x", " ", "\n", "\n    ", "'''", "`", "=\n        y"];
+        let mut converged = 0;
+        for input in inputs {
+            let boundaries = (0..=input.len()).filter(|&i| input.is_char_boundary(i));
+            for start in boundaries {
+                let ends = (start..=input.len()).filter(|&i| input.is_char_boundary(i)).take(3);
+                for end in ends {
+                    for replacement in replacements {
+                        converged += test_resume(input, start..end, replacement) as usize;
+                    }
+                }
+            }
+        }
+        assert!(converged > 0);
+    }
+
+    #[test]
+    fn test_token_buffer_reuse() {
+        let mut buffer = TokenBuffer::default();
+        let first = "foo bar baz".to_owned();
+        let tokens = run_with_buffer(&first, &mut buffer).unwrap();
+        let capacity = tokens.capacity();
+        buffer.set_from(tokens);
+        drop(first);
+        let second = "x".to_owned();
+        let tokens = run_with_buffer(&second, &mut buffer).unwrap();
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens.capacity(), capacity);
+    }
 }
 
 
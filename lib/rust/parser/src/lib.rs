@@ -8,8 +8,9 @@
 //! # Building macro registry.
 //! Macros in Enso are a very powerful mechanism and are used to transform group of tokens into
 //! almost any statement. First, macros need to be discovered and registered. Currently, there is no
-//! real macro discovery process, as there is no support for user-defined macros. Instead, there is
-//! a set of hardcoded macros defined in the compiler.
+//! real macro discovery process. Instead, there is a set of hardcoded macros defined in the
+//! compiler, which can be extended with declaratively-defined macros registered at runtime with
+//! [`Parser::register_macro`] (see [`macros::user`]).
 //!
 //! Each macro defines one or more segments. Every segment starts with a predefined token and can
 //! contain any number of other tokens. For example, the macro `if ... then ... else ...` contains
@@ -98,10 +99,18 @@ use crate::prelude::*;
 // === Export ===
 // ==============
 
+pub mod diagnostics;
 pub mod format;
+pub mod incremental;
 pub mod lexer;
+pub mod lint;
 pub mod macros;
 pub mod metadata;
+pub mod outline;
+pub mod pretty;
+pub mod project;
+pub mod rewrite;
+pub mod semantic_tokens;
 pub mod serialization;
 pub mod source;
 pub mod syntax;
@@ -163,15 +172,66 @@ impl Parser {
 
     /// Main entry point.
     pub fn run<'s>(&self, code: &'s str) -> syntax::Tree<'s> {
-        let tokens = lexer::run(code);
+        self.run_with_buffer(code, &mut default())
+    }
+
+    /// Parse the code, like [`Parser::run`], allocating memory for the tokens up front as recorded
+    /// by the given buffer. When parsing many inputs, passing the same buffer to every call avoids
+    /// repeatedly growing the token storage.
+    pub fn run_with_buffer<'s>(
+        &self,
+        code: &'s str,
+        buffer: &mut lexer::TokenBuffer,
+    ) -> syntax::Tree<'s> {
+        let mut tokens = lexer::run_with_buffer(code, buffer);
         let mut resolver = macros::resolver::Resolver::new_statement();
-        let result = tokens.map(|tokens| resolver.run(&self.macros, tokens));
-        let value = result.value;
-        if let Some(error) = result.internal_error {
-            return value.with_error(format!("Internal error: {error}"));
+        let value = resolver.run(&self.macros, tokens.value.drain(..));
+        buffer.set_from(tokens.value);
+        if let Some(error) = tokens.internal_error {
+            let message = format!("Internal error: {error}");
+            return value.with_error_from(|tree| {
+                use syntax::tree::*;
+                Error::new(ErrorCode::Internal, ErrorSpan::of_tree(tree)).with_message(message)
+            });
         }
         value
     }
+
+    /// Parse the code, like [`Parser::run`], and assign an ID to every node of the result: nodes
+    /// whose locations are found in the given map receive the associated IDs, and other nodes
+    /// receive deterministic fresh IDs. The IDs are returned in a map from the locations of the
+    /// nodes; see [`syntax::tree::id`].
+    pub fn run_with_ids<'s>(
+        &self,
+        code: &'s str,
+        id_map: &metadata::IdMap,
+    ) -> (syntax::Tree<'s>, metadata::IdMap) {
+        let tree = self.run(code);
+        let ids = syntax::tree::id::assign(&tree, id_map);
+        (tree, ids)
+    }
+
+    /// Register a user-defined macro. Fails if the definition is malformed, or if any of its
+    /// segment headers is already used by a registered macro, including the built-in ones.
+    pub fn register_macro(
+        &mut self,
+        definition: macros::user::Definition,
+    ) -> Result<(), macros::user::RegistrationError> {
+        definition.register(&mut self.macros)
+    }
+
+    /// Parse `code`, which is the result of applying `edit` to `old_code`, reusing the lines of
+    /// `old_tree` (the result of parsing `old_code`) that were not affected by the edit. The result
+    /// is the same as the result of [`Parser::run`].
+    pub fn reparse<'s>(
+        &self,
+        old_tree: syntax::Tree<'_>,
+        old_code: &str,
+        edit: &incremental::Edit,
+        code: &'s str,
+    ) -> syntax::Tree<'s> {
+        incremental::reparse(self, old_tree, old_code, edit, code)
+    }
 }
 
 impl Default for Parser {
@@ -200,7 +260,7 @@ fn expression_to_statement(mut tree: syntax::Tree<'_>) -> syntax::Tree<'_> {
             documented.expression = documented.expression.take().map(expression_to_statement);
         }
         Variant::ArgumentBlockApplication(ArgumentBlockApplication { lhs: None, .. }) => {
-            return tree.with_error("Expected expression before indented block.");
+            return tree.with_error(ErrorCode::ExpectedExpressionBeforeBlock);
         }
         Variant::TypeAnnotated(typed) => {
             tree.variant = Box::new(Variant::TypeSignature(TypeSignature {
@@ -263,7 +323,7 @@ fn expression_to_statement(mut tree: syntax::Tree<'_>) -> syntax::Tree<'_> {
                 }));
                 return tree;
             }
-            return tree.with_error("Invalid use of assignment operator `=`.");
+            return tree.with_error(ErrorCode::InvalidAssignment);
         }
         _ => (),
     }
@@ -308,7 +368,7 @@ fn expression_to_pattern(mut input: syntax::Tree<'_>) -> syntax::Tree<'_> {
         box Variant::TypeAnnotated(TypeAnnotated { expression, operator, type_ }) =>
             Tree::type_annotated(expression_to_pattern(expression), operator, type_),
         box Variant::AutoscopedIdentifier(_) =>
-            return input.with_error("The autoscope operator (..) cannot be used in a pattern."),
+            return input.with_error(ErrorCode::AutoscopeInPattern),
         _ => return input,
     };
     out.span.left_offset += input.span.left_offset;
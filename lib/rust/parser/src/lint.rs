@@ -0,0 +1,453 @@
+//! Checks for code that is valid, but likely to be a mistake or hard to read.
+//!
+//! Each check is a [`Rule`]: a type that is shown every node of a tree, and reports the problems it
+//! finds as [`Lint`]s, optionally with a fix. A [`Linter`] walks a tree, presenting each node to
+//! each enabled rule along with a [`Context`] giving access to the ancestors of the node. The
+//! [`Level`] of each rule can be configured: a rule can be disabled, or its findings reported as
+//! errors instead of warnings. The built-in rules are defined in [`rules`].
+//!
+//! Findings can be suppressed in the code, with a comment naming the rules:
+//! ```text
+//! x = foo a-> 1  # lint: allow(unused-lambda-argument)
+//! # lint: allow(shadowed-binding, if-without-else)
+//! y = if c then x
+//! ```
+//! A suppression comment at the end of a line applies to the findings reported on that line; a
+//! comment on a line of its own applies to the next line of code.
+
+use crate::prelude::*;
+
+use crate::diagnostics::Report;
+use crate::source::code::Location;
+use crate::syntax::item;
+use crate::syntax::token;
+use crate::syntax::tree;
+use crate::syntax::tree::error::Severity;
+use crate::syntax::tree::ItemVisitable;
+use crate::syntax::tree::ItemVisitor;
+use crate::syntax::Tree;
+
+use std::collections::HashMap;
+
+
+// ==============
+// === Export ===
+// ==============
+
+pub mod rules;
+
+
+
+// ============
+// === Rule ===
+// ============
+
+/// A check for a kind of problem.
+pub trait Rule: Send + Sync {
+    /// The name of the rule, in `kebab-case`, used to configure it and to suppress its findings.
+    fn name(&self) -> &'static str;
+    /// A one-line description of the problems the rule finds.
+    fn description(&self) -> &'static str;
+    /// The level of the rule, unless it is configured otherwise.
+    fn default_level(&self) -> Level {
+        Level::Warn
+    }
+    /// Examine a node of the tree, and report any problems found in it to the context.
+    fn check<'s>(&self, tree: &Tree<'s>, context: &mut Context<'s, '_>);
+}
+
+/// How the findings of a rule are reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    /// The rule is disabled.
+    Allow,
+    /// The findings of the rule are reported as warnings.
+    Warn,
+    /// The findings of the rule are reported as errors.
+    Deny,
+}
+
+impl Level {
+    /// The severity of the findings of a rule at this level, if it is enabled.
+    pub fn severity(self) -> Option<Severity> {
+        match self {
+            Level::Allow => None,
+            Level::Warn => Some(Severity::Warning),
+            Level::Deny => Some(Severity::Error),
+        }
+    }
+}
+
+
+
+// ============
+// === Lint ===
+// ============
+
+/// A problem found by a [`Rule`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Lint {
+    /// The name of the rule that found the problem.
+    pub rule:     &'static str,
+    /// How serious the problem is, according to the level of the rule.
+    pub severity: Severity,
+    /// Human-readable description of the problem.
+    pub message:  String,
+    /// The code the problem is reported at.
+    pub primary:  Range<Location>,
+    /// Other code related to the problem, with explanations.
+    pub labels:   Vec<(Range<Location>, String)>,
+    /// A suggested edit: the code to replace, its replacement, and a description of the edit.
+    pub fix:      Option<(Range<Location>, String, String)>,
+}
+
+impl Lint {
+    /// Add a label explaining the relevance of some other code to the problem.
+    pub fn with_label(&mut self, range: Range<Location>, message: impl Into<String>) -> &mut Self {
+        self.labels.push((range, message.into()));
+        self
+    }
+
+    /// Suggest replacing some code to fix the problem.
+    pub fn with_fix(
+        &mut self,
+        range: Range<Location>,
+        replacement: impl Into<String>,
+        description: impl Into<String>,
+    ) -> &mut Self {
+        self.fix = Some((range, replacement.into(), description.into()));
+        self
+    }
+
+    /// The parts of the lint that are shown in a report, for rendering with a
+    /// [`Renderer`](crate::diagnostics::Renderer).
+    pub fn report(&self) -> Report {
+        Report {
+            severity: self.severity,
+            code:     self.rule,
+            message:  &self.message,
+            primary:  &self.primary,
+            labels:   &self.labels,
+            notes:    &[],
+            fix:      self.fix.as_ref(),
+        }
+    }
+}
+
+/// Represent the lints as JSON, in the format of
+/// [`diagnostics::to_json`](crate::diagnostics::to_json), with the name of the rule as the code.
+pub fn to_json(path: &str, lints: &[Lint]) -> serde_json::Value {
+    let lints = lints.iter().map(|lint| crate::diagnostics::report_to_json(path, lint.report()));
+    serde_json::Value::Array(lints.collect())
+}
+
+/// Apply the fixes of the lints to the code they were found in. If the code replaced by a fix
+/// overlaps that of a fix earlier in the code, it is skipped; running the linter again will report
+/// the problem again, if it has not been fixed by the other edit.
+pub fn apply_fixes(code: &str, lints: &[Lint]) -> String {
+    let mut fixes: Vec<_> = lints.iter().filter_map(|lint| lint.fix.as_ref()).collect();
+    fixes.sort_by_key(|(range, _, _)| (range.start.utf8, range.end.utf8));
+    let mut out = String::with_capacity(code.len());
+    let mut end = 0;
+    for (range, replacement, _) in fixes {
+        let (start, stop) = (range.start.utf8 as usize, range.end.utf8 as usize);
+        if start < end {
+            continue;
+        }
+        out.push_str(&code[end..start]);
+        out.push_str(replacement);
+        end = stop;
+    }
+    out.push_str(&code[end..]);
+    out
+}
+
+
+
+// ===============
+// === Context ===
+// ===============
+
+/// The information available to a [`Rule`] when it examines a node, and the destination of its
+/// findings.
+#[derive(Debug)]
+pub struct Context<'s, 'a> {
+    ancestors: &'a [&'a Tree<'s>],
+    rule:      &'static str,
+    severity:  Severity,
+    lints:     &'a mut Vec<Lint>,
+}
+
+impl<'s, 'a> Context<'s, 'a> {
+    /// The node containing the node being examined.
+    pub fn parent(&self) -> Option<&'a Tree<'s>> {
+        self.ancestors.last().copied()
+    }
+
+    /// The nodes containing the node being examined, starting with its parent and ending with the
+    /// root of the tree.
+    pub fn ancestors(&self) -> impl Iterator<Item = &'a Tree<'s>> {
+        self.ancestors.iter().rev().copied()
+    }
+
+    /// The root of the tree; if the node being examined is the root, it has no ancestors, and
+    /// this returns `None`.
+    pub fn root(&self) -> Option<&'a Tree<'s>> {
+        self.ancestors.first().copied()
+    }
+
+    /// Report a problem in the given code.
+    pub fn report(&mut self, range: Range<Location>, message: impl Into<String>) -> &mut Lint {
+        self.lints.push(Lint {
+            rule:     self.rule,
+            severity: self.severity,
+            message:  message.into(),
+            primary:  range,
+            labels:   default(),
+            fix:      default(),
+        });
+        self.lints.last_mut().unwrap()
+    }
+}
+
+
+
+// ==============
+// === Linter ===
+// ==============
+
+/// Checks trees with a set of rules.
+pub struct Linter {
+    rules: Vec<(Box<dyn Rule>, Level)>,
+}
+
+impl Debug for Linter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rules = self.rules.iter().map(|(rule, level)| (rule.name(), level));
+        f.debug_map().entries(rules).finish()
+    }
+}
+
+impl Default for Linter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Linter {
+    /// Create a linter with the built-in rules, each at its default level.
+    pub fn new() -> Self {
+        let linter = Self { rules: default() };
+        rules::all().into_iter().fold(linter, Self::with_boxed_rule)
+    }
+
+    /// Create a linter without any rules.
+    pub fn empty() -> Self {
+        Self { rules: default() }
+    }
+
+    /// Add a rule, at its default level.
+    pub fn with_rule(self, rule: impl Rule + 'static) -> Self {
+        self.with_boxed_rule(Box::new(rule))
+    }
+
+    fn with_boxed_rule(mut self, rule: Box<dyn Rule>) -> Self {
+        let level = rule.default_level();
+        self.rules.push((rule, level));
+        self
+    }
+
+    /// Set the level of the named rule. Returns `None` if there is no such rule.
+    pub fn with_level(mut self, name: &str, level: Level) -> Option<Self> {
+        let (_, current) = self.rules.iter_mut().find(|(rule, _)| rule.name() == name)?;
+        *current = level;
+        Some(self)
+    }
+
+    /// The rules, with their levels.
+    pub fn rules(&self) -> impl Iterator<Item = (&dyn Rule, Level)> {
+        self.rules.iter().map(|(rule, level)| (rule.as_ref(), *level))
+    }
+
+    /// Check the tree with the enabled rules. Returns the findings that are not suppressed by
+    /// comments, ordered by location.
+    pub fn check(&self, tree: &Tree) -> Vec<Lint> {
+        let rules =
+            self.rules.iter().filter_map(|(rule, level)| Some((rule.as_ref(), level.severity()?)));
+        let mut walker = Walker {
+            rules:     rules.collect(),
+            ancestors: default(),
+            lints:     default(),
+            comments:  default(),
+            pending:   default(),
+            code_line: None,
+        };
+        tree.visit_item(&mut walker);
+        let Walker { mut lints, comments, .. } = walker;
+        lints.retain(|lint| {
+            let allowed = comments.get(&lint.primary.start.line);
+            !allowed.is_some_and(|rules| rules.iter().any(|rule| rule == lint.rule))
+        });
+        lints.sort_by_key(|lint| (lint.primary.start, lint.primary.end));
+        lints
+    }
+}
+
+
+// === Walker ===
+
+/// Presents each node to the rules, while keeping track of its ancestors and of suppression
+/// comments.
+struct Walker<'s, 'a> {
+    rules:     Vec<(&'a dyn Rule, Severity)>,
+    ancestors: Vec<&'a Tree<'s>>,
+    lints:     Vec<Lint>,
+    /// The names of the rules allowed by comments, by the line they apply to.
+    comments:  HashMap<u32, Vec<String>>,
+    /// Rules allowed by comments on their own lines, which apply to the next line of code.
+    pending:   Vec<String>,
+    /// The last line a token other than a line break was found on.
+    code_line: Option<u32>,
+}
+
+impl<'s, 'a> tree::Visitor for Walker<'s, 'a> {}
+impl<'s, 'a> ItemVisitor<'s, 'a> for Walker<'s, 'a> {
+    fn visit_item(&mut self, item: item::Ref<'s, 'a>) -> bool {
+        match item {
+            item::Ref::Tree(tree) => {
+                for &(rule, severity) in &self.rules {
+                    let ancestors = &self.ancestors[..];
+                    let lints = &mut self.lints;
+                    let rule_name = rule.name();
+                    let mut context = Context { ancestors, rule: rule_name, severity, lints };
+                    rule.check(tree, &mut context);
+                }
+                self.ancestors.push(tree);
+                tree.variant.visit_item(self);
+                self.ancestors.pop();
+            }
+            item::Ref::Token(token) => self.token(token),
+        }
+        false
+    }
+}
+
+impl<'s, 'a> Walker<'s, 'a> {
+    fn token(&mut self, token: token::Ref) {
+        let line = token.code.range().start.line;
+        let token::Variant::Newline(_) = token.data else {
+            if !token.code.is_empty() {
+                if !self.pending.is_empty() {
+                    self.comments.entry(line).or_default().append(&mut self.pending);
+                }
+                self.code_line = Some(line);
+            }
+            return;
+        };
+        let Some(rules) = allowed_rules(token.code) else { return };
+        match self.code_line == Some(line) {
+            true => self.comments.entry(line).or_default().extend(rules),
+            false => self.pending.extend(rules),
+        }
+    }
+}
+
+/// If the code of a line break is a suppression comment, return the names of the rules it allows.
+fn allowed_rules(comment: &str) -> Option<Vec<String>> {
+    let directive = comment.strip_prefix('#')?.trim().strip_prefix("lint:")?.trim();
+    let rules = directive.strip_prefix("allow")?.trim().strip_prefix('(')?.strip_suffix(')')?;
+    Some(rules.split(',').map(|rule| rule.trim().to_owned()).filter(|r| !r.is_empty()).collect())
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reports every identifier named `bad`.
+    struct Bad;
+
+    impl Rule for Bad {
+        fn name(&self) -> &'static str {
+            "bad"
+        }
+
+        fn description(&self) -> &'static str {
+            "Identifiers named `bad`."
+        }
+
+        fn check<'s>(&self, tree: &Tree<'s>, context: &mut Context<'s, '_>) {
+            if let tree::Variant::Ident(ident) = &*tree.variant
+                && ident.token.code.repr.0 == "bad"
+            {
+                let range = tree.span.range();
+                let parent = context.parent().map(|parent| format!("{:?}", parent.variant));
+                let parent = parent.unwrap_or_default();
+                let kind = parent.split_whitespace().next().unwrap_or_default().to_owned();
+                context
+                    .report(range.clone(), format!("In {kind}"))
+                    .with_fix(range, "good", "Rename");
+            }
+        }
+    }
+
+    fn check(linter: &Linter, code: &str) -> Vec<(u32, String)> {
+        let lints = linter.check(&crate::Parser::new().run(code));
+        lints.into_iter().map(|lint| (lint.primary.start.line, lint.message)).collect()
+    }
+
+    #[test]
+    fn context_and_suppression() {
+        let linter = Linter::empty().with_rule(Bad);
+        let code = [
+            "x = bad",
+            "y = bad  # lint: allow(bad)",
+            "# lint: allow(other, bad)",
+            "",
+            "z = f bad",
+            "# lint: allow(other)",
+            "w = bad",
+        ];
+        let lints = check(&linter, &code.join("\n"));
+        assert_eq!(lints, [(0, "In Assignment".to_owned()), (6, "In Assignment".to_owned())]);
+        assert_eq!(check(&linter.with_level("bad", Level::Allow).unwrap(), "x = bad"), []);
+    }
+
+    #[test]
+    fn fixes() {
+        let linter = Linter::empty().with_rule(Bad);
+        let code = "x = bad + bad\ny = bad";
+        let tree = crate::Parser::new().run(code);
+        let lints = linter.check(&tree);
+        assert_eq!(apply_fixes(code, &lints), "x = good + good\ny = good");
+        assert_eq!(lints[0].severity, Severity::Warning);
+        let linter = linter.with_level("bad", Level::Deny).unwrap();
+        assert_eq!(linter.check(&tree)[0].severity, Severity::Error);
+        assert!(Linter::new().with_level("bad", Level::Deny).is_none());
+    }
+
+    #[test]
+    fn report() {
+        let code = "x = bad";
+        let lints = Linter::empty().with_rule(Bad).check(&crate::Parser::new().run(code));
+        let renderer = crate::diagnostics::Renderer::new("test.enso", code);
+        let expected = [
+            "warning[bad]: In Assignment",
+            " --> test.enso:1:5",
+            "  |",
+            "1 | x = bad",
+            "  |     ^^^",
+            "  = help: Rename: `good`",
+            "",
+        ];
+        assert_eq!(renderer.render_report(lints[0].report()), expected.join("\n"));
+        let json = to_json("test.enso", &lints);
+        assert_eq!(json[0]["code"], "bad");
+        assert_eq!(json[0]["fix"]["replacement"], "good");
+    }
+}
@@ -0,0 +1,498 @@
+//! The built-in lint rules.
+
+use crate::prelude::*;
+
+use crate::lint::Context;
+use crate::lint::Level;
+use crate::lint::Rule;
+use crate::outline;
+use crate::source::code::Location;
+use crate::syntax::token;
+use crate::syntax::tree;
+use crate::syntax::Tree;
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+
+
+// ================
+// === Registry ===
+// ================
+
+/// All the built-in rules.
+pub fn all() -> Vec<Box<dyn Rule>> {
+    vec![
+        Box::new(UnusedLambdaArgument),
+        Box::new(ShadowedBinding),
+        Box::new(IfWithoutElse),
+        Box::new(DotSpacing),
+        Box::new(MissingDocs),
+    ]
+}
+
+
+
+// ==============================
+// === unused-lambda-argument ===
+// ==============================
+
+/// An argument of a lambda that is not used in its body. The fix replaces the argument with a
+/// wildcard.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UnusedLambdaArgument;
+
+impl Rule for UnusedLambdaArgument {
+    fn name(&self) -> &'static str {
+        "unused-lambda-argument"
+    }
+
+    fn description(&self) -> &'static str {
+        "Lambda arguments that are not used in the body of the lambda."
+    }
+
+    fn check<'s>(&self, tree: &Tree<'s>, context: &mut Context<'s, '_>) {
+        let Some((params, body)) = lambda(tree) else { return };
+        if in_type(tree, context) {
+            return;
+        }
+        let mut used = HashSet::new();
+        if let tree::Variant::Ident(ident) = &*body.variant {
+            used.insert(ident.token.code.repr.0);
+        }
+        body.visit_trees(|tree| {
+            if let tree::Variant::Ident(ident) = &*tree.variant {
+                used.insert(ident.token.code.repr.0);
+            }
+        });
+        for param in params {
+            let name = param.code.repr.0;
+            if !used.contains(name) {
+                let range = param.code.range();
+                context.report(range.clone(), format!("Unused lambda argument `{name}`")).with_fix(
+                    range,
+                    "_",
+                    "Replace the argument with a wildcard",
+                );
+            }
+        }
+    }
+}
+
+/// If the tree is a lambda (`x y -> body`), return its named arguments and its body.
+fn lambda<'s, 'a>(tree: &'a Tree<'s>) -> Option<(Vec<&'a token::Ident<'s>>, &'a Tree<'s>)> {
+    let tree::Variant::OprApp(tree::OprApp { lhs: Some(lhs), opr: Ok(opr), rhs: Some(rhs) }) =
+        &*tree.variant
+    else {
+        return None;
+    };
+    if opr.code.repr.0 != "->" {
+        return None;
+    }
+    let mut params = vec![];
+    lambda_params(lhs, &mut params)?;
+    Some((params, rhs))
+}
+
+/// Collect the names bound by the argument list of a lambda. Returns `None` if the tree is not a
+/// valid argument list.
+fn lambda_params<'s, 'a>(tree: &'a Tree<'s>, params: &mut Vec<&'a token::Ident<'s>>) -> Option<()> {
+    match &*tree.variant {
+        tree::Variant::Ident(ident) => {
+            let name = ident.token.code.repr.0;
+            if name.starts_with(|c: char| c.is_lowercase()) {
+                params.push(&ident.token);
+            }
+        }
+        tree::Variant::Wildcard(_) => (),
+        tree::Variant::App(app) => {
+            lambda_params(&app.func, params)?;
+            lambda_params(&app.arg, params)?;
+        }
+        tree::Variant::Group(tree::Group { body: Some(body), .. }) => lambda_params(body, params)?,
+        tree::Variant::TypeAnnotated(annotated) => lambda_params(&annotated.expression, params)?,
+        _ => return None,
+    }
+    Some(())
+}
+
+/// Whether the tree is part of a type expression, in which `->` denotes a function type rather
+/// than a lambda.
+fn in_type(tree: &Tree, context: &Context) -> bool {
+    let contains = |type_: &Tree| {
+        let (outer, inner) = (type_.span.range(), tree.span.range());
+        outer.start.utf8 <= inner.start.utf8 && inner.end.utf8 <= outer.end.utf8
+    };
+    let in_args = |args: &[tree::ArgumentDefinition]| {
+        args.iter().filter_map(|arg| arg.type_.as_ref()).any(|type_| contains(&type_.type_))
+    };
+    context.ancestors().any(|ancestor| match &*ancestor.variant {
+        tree::Variant::TypeSignature(signature) => contains(&signature.type_),
+        tree::Variant::TypeAnnotated(annotated) => contains(&annotated.type_),
+        tree::Variant::Function(function) =>
+            in_args(&function.args)
+                || function.returns.as_ref().is_some_and(|returns| contains(&returns.r#type)),
+        tree::Variant::ForeignFunction(function) => in_args(&function.args),
+        tree::Variant::TypeDef(def) => in_args(&def.params),
+        tree::Variant::ConstructorDefinition(def) =>
+            in_args(&def.arguments)
+                || def.block.iter().any(|line| {
+                    line.argument.as_ref().is_some_and(|arg| in_args(slice::from_ref(arg)))
+                }),
+        _ => false,
+    })
+}
+
+
+
+// ========================
+// === shadowed-binding ===
+// ========================
+
+/// A variable defined in a block, with the name of a variable already in scope: defined earlier in
+/// the same block or an enclosing block, or an argument of an enclosing function or lambda.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ShadowedBinding;
+
+impl Rule for ShadowedBinding {
+    fn name(&self) -> &'static str {
+        "shadowed-binding"
+    }
+
+    fn description(&self) -> &'static str {
+        "Variables defined in a block that shadow another variable in scope."
+    }
+
+    fn check<'s>(&self, tree: &Tree<'s>, context: &mut Context<'s, '_>) {
+        let tree::Variant::BodyBlock(block) = &*tree.variant else { return };
+        // The definitions at the top level of a module are not variables.
+        if context.parent().is_none() {
+            return;
+        }
+        let start = tree.span.range().start.utf8;
+        let mut scope: HashMap<&str, Range<Location>> = HashMap::new();
+        let ancestors: Vec<_> = context.ancestors().collect();
+        // Add the outermost scopes first, so that the nearest definition of each name is kept. The
+        // root block is skipped, as its definitions are not variables.
+        for ancestor in ancestors.iter().rev().skip(1) {
+            match &*ancestor.variant {
+                tree::Variant::BodyBlock(block) => {
+                    let statements =
+                        block.statements.iter().filter_map(|line| line.expression.as_ref());
+                    // Only the statements before the one containing the block are in scope.
+                    let statements =
+                        statements.take_while(|statement| statement.span.range().end.utf8 <= start);
+                    scope.extend(statements.filter_map(binding));
+                }
+                tree::Variant::Function(function) =>
+                    for arg in &function.args {
+                        if let tree::Variant::Ident(ident) = &*arg.pattern.variant {
+                            scope.insert(ident.token.code.repr.0, arg.pattern.span.range());
+                        }
+                    },
+                _ =>
+                    if let Some((params, body)) = lambda(ancestor)
+                        && body.span.range().start.utf8 <= start
+                    {
+                        for param in params {
+                            scope.insert(param.code.repr.0, param.code.range());
+                        }
+                    },
+            }
+        }
+        for expression in block.statements.iter().filter_map(|line| line.expression.as_ref()) {
+            let Some((name, range)) = binding(expression) else { continue };
+            if let Some(previous) = scope.insert(name, range.clone()) {
+                context
+                    .report(range, format!("`{name}` shadows a variable of the same name"))
+                    .with_label(previous, "The shadowed variable is defined here");
+            }
+        }
+    }
+}
+
+/// If the statement defines a variable, or a local function, return its name and the location of
+/// the name.
+fn binding<'s>(statement: &Tree<'s>) -> Option<(&'s str, Range<Location>)> {
+    let name = match &*statement.variant {
+        tree::Variant::Assignment(assignment) => &assignment.pattern,
+        tree::Variant::Function(function) => &function.name,
+        _ => return None,
+    };
+    match &*name.variant {
+        tree::Variant::Ident(ident) => Some((ident.token.code.repr.0, name.span.range())),
+        _ => None,
+    }
+}
+
+
+
+// =======================
+// === if-without-else ===
+// =======================
+
+/// An `if`-`then` expression without an `else` branch, used as a value rather than as a statement.
+/// When the condition is false, its value is `Nothing`; the fix makes that explicit.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IfWithoutElse;
+
+impl Rule for IfWithoutElse {
+    fn name(&self) -> &'static str {
+        "if-without-else"
+    }
+
+    fn description(&self) -> &'static str {
+        "`if`-`then` expressions without an `else` branch, whose value is used."
+    }
+
+    fn check<'s>(&self, tree: &Tree<'s>, context: &mut Context<'s, '_>) {
+        let tree::Variant::MultiSegmentApp(app) = &*tree.variant else { return };
+        let headers: Vec<_> =
+            app.segments.iter().map(|segment| segment.header.code.repr.0).collect();
+        if headers[..] != ["if", "then"] {
+            return;
+        }
+        // A statement may be preceded by a comment, which is parsed as its documentation.
+        let ancestors = context.ancestors().map(|ancestor| &*ancestor.variant);
+        let mut ancestors =
+            ancestors.skip_while(|ancestor| matches!(ancestor, tree::Variant::Documented(_)));
+        if matches!(ancestors.next(), Some(tree::Variant::BodyBlock(_))) {
+            return;
+        }
+        let range = tree.span.range();
+        let message = "The value of `if` without `else` is `Nothing` if the condition is false";
+        let lint = context.report(range.clone(), message);
+        // Adding a branch after a block would place it inside the last line of the block.
+        if range.start.line == range.end.line {
+            let end = range.end..range.end;
+            lint.with_fix(end, " else Nothing", "Add an `else` branch");
+        }
+    }
+}
+
+
+
+// ===================
+// === dot-spacing ===
+// ===================
+
+/// Whitespace on only one side of a `.` operator, as in `a. b`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DotSpacing;
+
+impl Rule for DotSpacing {
+    fn name(&self) -> &'static str {
+        "dot-spacing"
+    }
+
+    fn description(&self) -> &'static str {
+        "Whitespace on only one side of a `.` operator."
+    }
+
+    fn check<'s>(&self, tree: &Tree<'s>, context: &mut Context<'s, '_>) {
+        let (opr, after) = match &*tree.variant {
+            // `a . b`, `a .b` or `a. b`
+            tree::Variant::OprApp(tree::OprApp { lhs: Some(_), opr: Ok(opr), rhs: Some(rhs) }) =>
+                (opr, &rhs.span.left_offset),
+            // `a. b` is parsed as the application of the section `a.` to `b`.
+            tree::Variant::App(app) => match &*app.func.variant {
+                tree::Variant::OprApp(tree::OprApp { lhs: Some(_), opr: Ok(opr), rhs: None }) =>
+                    (opr, &app.arg.span.left_offset),
+                _ => return,
+            },
+            _ => return,
+        };
+        // `a .b` as an argument is the application of the section `.b`, and is not flagged.
+        let before = &opr.left_offset;
+        if opr.code.repr.0 != "."
+            || before.code.is_empty() == after.code.is_empty()
+            || before.code.contains('\n')
+            || after.code.contains('\n')
+        {
+            return;
+        }
+        let whitespace = if before.code.is_empty() { after } else { before };
+        let message = "Whitespace on only one side of `.`";
+        context.report(opr.code.range(), message).with_fix(
+            whitespace.code.range(),
+            "",
+            "Remove the whitespace",
+        );
+    }
+}
+
+
+
+// ====================
+// === missing-docs ===
+// ====================
+
+/// A function defined at the top level of a module, or in the body of a type, that is not private
+/// and has no documentation comment. Modules marked `private` and `main` functions are exempt.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MissingDocs;
+
+impl Rule for MissingDocs {
+    fn name(&self) -> &'static str {
+        "missing-docs"
+    }
+
+    fn description(&self) -> &'static str {
+        "Public functions without documentation comments."
+    }
+
+    fn default_level(&self) -> Level {
+        Level::Allow
+    }
+
+    fn check<'s>(&self, tree: &Tree<'s>, context: &mut Context<'s, '_>) {
+        if context.parent().is_some() {
+            return;
+        }
+        let outline = outline::extract(tree, None);
+        if outline.private {
+            return;
+        }
+        let methods = outline.types.iter().filter(|type_| !type_.attributes.private);
+        let methods = methods.flat_map(|type_| &type_.methods);
+        for function in outline.functions.iter().chain(methods) {
+            let attributes = &function.attributes;
+            if attributes.private || attributes.documentation.is_some() || function.name == "main" {
+                continue;
+            }
+            let message = format!("Public function `{}` is not documented", function.name);
+            context.report(function.name_range.clone(), message);
+        }
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lint::apply_fixes;
+    use crate::lint::Linter;
+
+    /// Check the code with only the given rule, at the warning level. Returns the lints, as the
+    /// linted code and the message of each, and the code with the fixes applied.
+    fn lint(rule: impl Rule + 'static, code: &str) -> (Vec<(String, String)>, String) {
+        let name = rule.name();
+        let linter = Linter::empty().with_rule(rule).with_level(name, Level::Warn).unwrap();
+        let lints = linter.check(&crate::Parser::new().run(code));
+        let fixed = apply_fixes(code, &lints);
+        let code = |range: &Range<Location>| {
+            code[range.start.utf8 as usize..range.end.utf8 as usize].to_owned()
+        };
+        let lints = lints.iter().map(|lint| (code(&lint.primary), lint.message.clone()));
+        (lints.collect(), fixed)
+    }
+
+    fn lint_code(rule: impl Rule + 'static, code: &str) -> Vec<String> {
+        lint(rule, code).0.into_iter().map(|(code, _)| code).collect()
+    }
+
+    #[test]
+    fn unused_lambda_argument() {
+        let code = [
+            "main =",
+            "    f = x-> 1",
+            "    g = \\a b -> a",
+            "    h = list.map (i -> i + 1)",
+            "    k = list.map (_ -> 1)",
+            "    m = (n : Integer) -> 2",
+            "    p = q -> r -> q",
+            "foo : (Integer -> Integer) -> Integer",
+            "foo (f : Integer -> Integer) -> Integer = f 1",
+        ];
+        let (lints, fixed) = lint(UnusedLambdaArgument, &code.join("\n"));
+        let lints: Vec<_> = lints.into_iter().map(|(code, _)| code).collect();
+        assert_eq!(lints, ["x", "b", "n", "r"]);
+        assert!(fixed.contains("f = _-> 1"));
+        assert!(fixed.contains("g = \\a _ -> a"));
+    }
+
+    #[test]
+    fn shadowed_binding() {
+        let code = [
+            "x = 1",
+            "foo a =",
+            "    x = 2",
+            "    a = 3",
+            "    y = 4",
+            "    z =",
+            "        y = 5",
+            "        w = 6",
+            "        f w = w",
+            "        w",
+            "    w = 7",
+            "    g = b -> ",
+            "        b = 8",
+            "        b",
+        ];
+        let (lints, _) = lint(ShadowedBinding, &code.join("\n"));
+        let lints: Vec<_> = lints.into_iter().map(|(code, _)| code).collect();
+        assert_eq!(lints, ["a", "y", "b"]);
+        let code = "foo =\n    x = 1\n    x = 2";
+        let linter = Linter::empty().with_rule(ShadowedBinding);
+        let lints = linter.check(&crate::Parser::new().run(code));
+        assert_eq!(lints[0].labels[0].0.start.line, 1);
+    }
+
+    #[test]
+    fn if_without_else() {
+        let code = [
+            "main =",
+            "    if c then log 1",
+            "    ## Comment",
+            "    if c then log 2",
+            "    x = if c then 1",
+            "    y = if c then 1 else 2",
+            "    z = f (if c then 1)",
+            "    w = if c then",
+            "        1",
+        ];
+        let (lints, fixed) = lint(IfWithoutElse, &code.join("\n"));
+        let lints: Vec<_> = lints.into_iter().map(|(code, _)| code).collect();
+        assert_eq!(lints, ["if c then 1", "if c then 1", "if c then\n        1"]);
+        assert!(fixed.contains("x = if c then 1 else Nothing\n"));
+        assert!(fixed.contains("z = f (if c then 1 else Nothing)\n"));
+        assert!(fixed.ends_with("w = if c then\n        1"));
+    }
+
+    #[test]
+    fn dot_spacing() {
+        let code = ["a = b. c", "d = e .f", "g = h . i", "j = k.l", "m = n.o. p", "q = r\n    . s"];
+        let (lints, fixed) = lint(DotSpacing, &code.join("\n"));
+        assert_eq!(lints.len(), 2);
+        assert_eq!(fixed, code.join("\n").replace(". c", ".c").replace(". p", ".p"));
+    }
+
+    #[test]
+    fn missing_docs() {
+        let code = [
+            "## Documented.",
+            "foo x = x",
+            "bar x = x",
+            "## Documented by its signature.",
+            "baz : Integer -> Integer",
+            "baz x = x",
+            "private qux x = x",
+            "main = bar 1",
+            "type T",
+            "    method self = 1",
+            "    ## Documented.",
+            "    other self = 1",
+            "private type U",
+            "    method self = 1",
+        ];
+        assert_eq!(lint_code(MissingDocs, &code.join("\n")), ["bar", "method"]);
+        assert_eq!(lint_code(MissingDocs, "private\n\nfoo x = x"), Vec::<String>::new());
+        let linter = Linter::new();
+        assert_eq!(linter.check(&crate::Parser::new().run("foo x = x")), []);
+    }
+}
@@ -7,7 +7,7 @@ use crate::prelude::*;
 
 use crate::syntax;
 
-use enso_data_structures::im_list;
+use std::sync::Arc;
 
 
 // ==============
@@ -18,6 +18,7 @@ pub mod built_in;
 pub mod expand;
 pub mod pattern;
 pub mod resolver;
+pub mod user;
 
 pub use pattern::Pattern;
 
@@ -39,16 +40,19 @@ pub use pattern::Pattern;
 #[derivative(Debug)]
 #[allow(missing_docs)]
 pub struct Definition<'a> {
-    pub segments: im_list::NonEmpty<SegmentDefinition<'a>>,
+    pub segments: NonEmptyVec<SegmentDefinition<'a>>,
     #[derivative(Debug = "ignore")]
-    pub body:     Rc<DefinitionBody>,
+    pub body:     Arc<DefinitionBody>,
 }
 
-/// A function that transforms matched macro tokens into [`syntax::Tree`].
+/// A function that transforms matched macro tokens into [`syntax::Tree`]. Definitions are shared
+/// by parsers, which can be used from multiple threads.
 pub type DefinitionBody = dyn for<'s, 'r> Fn(
-    pattern::MatchedSegments<'s>,
-    &'r mut syntax::operator::Precedence<'s>,
-) -> syntax::Tree<'s>;
+        pattern::MatchedSegments<'s>,
+        &'r mut syntax::operator::Precedence<'s>,
+    ) -> syntax::Tree<'s>
+    + Send
+    + Sync;
 
 
 
@@ -61,13 +65,14 @@ pub type DefinitionBody = dyn for<'s, 'r> Fn(
 #[derive(Clone, Debug)]
 #[allow(missing_docs)]
 pub struct SegmentDefinition<'a> {
-    pub header:  &'a str,
+    pub header:  Cow<'a, str>,
     pub pattern: Pattern,
 }
 
 impl<'a> SegmentDefinition<'a> {
     /// Constructor.
-    pub fn new(header: &'a str, pattern: Pattern) -> Self {
+    pub fn new(header: impl Into<Cow<'a, str>>, pattern: Pattern) -> Self {
+        let header = header.into();
         Self { header, pattern }
     }
 }
@@ -94,9 +99,9 @@ macro_rules! macro_definition {
     };
     (($($section:literal, $pattern:expr),* $(,)?) $body:expr) => {
         $crate::macros::Definition {
-            segments: im_list::NonEmpty::try_from(vec![
+            segments: $crate::prelude::NonEmptyVec::try_from(vec![
                 $($crate::macros::SegmentDefinition::new($section, $pattern)),*]).unwrap(),
-            body: Rc::new($body),
+            body: std::sync::Arc::new($body),
         }
     };
 }
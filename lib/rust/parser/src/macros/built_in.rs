@@ -5,6 +5,7 @@ use crate::macros::*;
 
 use crate::source::Code;
 use crate::syntax::operator;
+use crate::syntax::tree::ErrorCode;
 
 
 
@@ -135,7 +136,7 @@ fn import_body<'s>(
     }
     let import = syntax::Tree::import(polyglot, from, import.unwrap(), all, as_, hiding);
     if incomplete_import {
-        return import.with_error("Expected name or `all` keyword following `import` keyword.");
+        return import.with_error(ErrorCode::IncompleteImport);
     }
     import
 }
@@ -222,7 +223,7 @@ fn export_body<'s>(
     }
     let export = syntax::Tree::export(from, export.unwrap(), all, as_, hiding);
     if incomplete_export {
-        return export.with_error("Expected name or `all` keyword following `export` keyword.");
+        return export.with_error(ErrorCode::IncompleteExport);
     }
     export
 }
@@ -297,20 +298,26 @@ fn type_def_body<'s>(
     let segment = matched_segments.pop().0;
     let header = into_ident(segment.header);
     let mut tokens = segment.result.tokens();
-    let mut block = vec![];
-    if let Some(syntax::Item::Block(lines)) = tokens.last_mut() {
-        block = mem::take(lines);
-        tokens.pop();
-    }
-    let mut tokens = tokens.into_iter();
-    let name = match tokens.next() {
+    let name = match (!tokens.is_empty()).then(|| tokens.remove(0)) {
         Some(syntax::Item::Token(syntax::Token {
             left_offset,
             code,
             variant: syntax::token::Variant::Ident(ident),
         })) => syntax::Token(left_offset, code, ident),
-        _ => return Tree::ident(header).with_error("Expected identifier after `type` keyword."),
+        first => {
+            tokens.splice(0..0, first);
+            let tree = match precedence.resolve(tokens) {
+                Some(rest) => apply(Tree::ident(header), rest),
+                None => Tree::ident(header),
+            };
+            return tree.with_error(ErrorCode::ExpectedTypeName);
+        }
     };
+    let mut block = vec![];
+    if let Some(syntax::Item::Block(lines)) = tokens.last_mut() {
+        block = mem::take(lines);
+        tokens.pop();
+    }
     let params = precedence
         .resolve_non_section(tokens)
         .map(crate::collect_arguments_inclusive)
@@ -460,7 +467,7 @@ fn case_body<'s>(
     let (case_lines, any_invalid) = case_builder.finish();
     let tree = Tree::case_of(case_, expression, of_, case_lines);
     if any_invalid {
-        return tree.with_error("Invalid case expression.");
+        return tree.with_error(ErrorCode::InvalidCase);
     }
     tree
 }
@@ -676,7 +683,7 @@ fn sequence_tree<'s>(
         tree = Tree::opr_app(tree, Ok(operator), body.map(&mut f)).into();
     }
     if invalid {
-        tree = tree.map(|tree| tree.with_error("Malformed comma-delimited sequence."));
+        tree = tree.map(|tree| tree.with_error(ErrorCode::MalformedSequence));
     }
     tree
 }
@@ -761,32 +768,30 @@ fn try_foreign_body<'s>(
     keyword: syntax::token::Ident<'s>,
     tokens: impl IntoIterator<Item = syntax::Item<'s>>,
     precedence: &mut operator::Precedence<'s>,
-) -> Result<syntax::Tree<'s>, &'static str> {
+) -> Result<syntax::Tree<'s>, ErrorCode> {
     let mut tokens = tokens.into_iter();
     let language = tokens
         .next()
         .and_then(try_into_token)
         .and_then(try_token_into_ident)
-        .ok_or("Expected an identifier specifying foreign method's language.")?;
-    let expected_name = "Expected an identifier specifying foreign function's name.";
-    let function = precedence.resolve(tokens).ok_or(expected_name)?;
-    let expected_function = "Expected a function definition after foreign declaration.";
+        .ok_or(ErrorCode::ForeignLanguageExpected)?;
+    let function = precedence.resolve(tokens).ok_or(ErrorCode::ForeignNameExpected)?;
     let box syntax::tree::Variant::OprApp(syntax::tree::OprApp {
         lhs: Some(lhs),
         opr: Ok(equals),
         rhs: Some(body),
     }) = function.variant
     else {
-        return Err(expected_function);
+        return Err(ErrorCode::ForeignFunctionExpected);
     };
     if !equals.properties.is_assignment() {
-        return Err(expected_function);
+        return Err(ErrorCode::ForeignFunctionExpected);
     };
     if !matches!(body.variant, box syntax::tree::Variant::TextLiteral(_)) {
-        return Err("Expected a text literal as body of `foreign` declaration.");
+        return Err(ErrorCode::ForeignBodyNotText);
     }
     let (name, args) = crate::collect_arguments(lhs);
-    let mut name = try_tree_into_ident(name).ok_or(expected_name)?;
+    let mut name = try_tree_into_ident(name).ok_or(ErrorCode::ForeignNameExpected)?;
     name.left_offset += function.span.left_offset;
     Ok(syntax::Tree::foreign_function(keyword, language, name, args, equals, body))
 }
@@ -844,7 +849,7 @@ fn expect_ident(tree: syntax::Tree) -> syntax::Tree {
     if matches!(&*tree.variant, syntax::tree::Variant::Ident(_)) {
         tree
     } else {
-        tree.with_error("Expected identifier.")
+        tree.with_error(ErrorCode::ExpectedIdentifier)
     }
 }
 
@@ -852,7 +857,7 @@ fn expect_qualified(tree: syntax::Tree) -> syntax::Tree {
     if crate::is_qualified_name(&tree) {
         tree
     } else {
-        tree.with_error("Expected qualified name.")
+        tree.with_error(ErrorCode::ExpectedQualifiedName)
     }
 }
 
@@ -866,5 +871,5 @@ fn expected_nonempty(location: Code) -> syntax::Tree {
         false,
         false,
     ));
-    empty.with_error("Expected tokens.")
+    empty.with_error(ErrorCode::ExpectedTokens)
 }
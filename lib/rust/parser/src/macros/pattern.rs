@@ -6,6 +6,7 @@ use crate::prelude::*;
 use crate::syntax;
 
 use std::collections::VecDeque;
+use std::sync::Arc;
 
 
 
@@ -23,14 +24,14 @@ use std::collections::VecDeque;
 #[allow(missing_docs)]
 pub struct Pattern {
     #[deref]
-    pub data:                Rc<PatternData>,
+    pub data:                Arc<PatternData>,
     pub matches_empty_input: bool,
 }
 
 impl Pattern {
     /// Constructor.
     pub fn new(data: PatternData, matches_empty_input: bool) -> Self {
-        Self { data: Rc::new(data), matches_empty_input }
+        Self { data: Arc::new(data), matches_empty_input }
     }
 }
 
@@ -30,10 +30,11 @@ use crate::source::Code;
 use crate::syntax;
 use crate::syntax::token;
 use crate::syntax::token::Token;
+use crate::syntax::tree::ErrorCode;
 
-use enso_data_structures::im_list::List;
 use std::collections::HashMap;
 use std::collections::VecDeque;
+use std::sync::Arc;
 
 
 
@@ -57,14 +58,32 @@ impl MacroMap {
         let expression_result = || self.expression.get(key);
         (context == Context::Statement).then(statement_result).flatten().or_else(expression_result)
     }
+
+    /// Register a new macro definition in the given context.
+    pub fn register(&mut self, context: Context, definition: macros::Definition<'static>) {
+        match context {
+            Context::Expression => self.expression.register(definition),
+            Context::Statement => self.statement.register(definition),
+        }
+    }
+
+    /// Return a macro definition that has a segment with the given header, in any context.
+    pub fn definition_with_header(&self, header: &str) -> Option<&macros::Definition<'static>> {
+        let entries = self.expression.values().chain(self.statement.values()).flatten();
+        let mut definitions = entries.map(|entry| &*entry.definition);
+        definitions.find(|definition| definition.segments.iter().any(|s| s.header == header))
+    }
 }
 
 
 // === Context ===
 
+/// The context in which a macro can be used.
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
-enum Context {
+pub enum Context {
+    /// Anywhere in an expression.
     Expression,
+    /// Only at the beginning of a line.
     Statement,
 }
 
@@ -82,31 +101,29 @@ enum Context {
 /// case).
 #[derive(Default, Debug, Deref, DerefMut)]
 pub struct SegmentMap<'s> {
-    map: HashMap<&'s str, NonEmptyVec<SegmentEntry<'s>>>,
+    map: HashMap<Cow<'s, str>, NonEmptyVec<SegmentEntry<'s>>>,
 }
 
 /// Partially matched macro info. See docs of [`SegmentMap`] to learn more.
 #[derive(Clone, Debug)]
 #[allow(missing_docs)]
 pub struct SegmentEntry<'s> {
-    /// All the segment headers that are required for the macro definition to be used.
-    pub required_segments: List<macros::SegmentDefinition<'s>>,
+    /// The index in the definition's segments of the next segment that is required for the macro
+    /// definition to be used; if it is past the last segment, the definition is fully matched.
+    pub next_segment: usize,
     /// Definition of the macro that should be used when all the required segments will be matched.
     /// It contains [`Pattern`] definition for every segment that will be used after all the
     /// segment tokens are discovered.
-    pub definition:        Rc<macros::Definition<'s>>,
+    pub definition:   Arc<macros::Definition<'s>>,
 }
 
 
 impl<'a> SegmentMap<'a> {
     /// Register a new macro definition in this macro tree.
     pub fn register(&mut self, definition: macros::Definition<'a>) {
-        let header = definition.segments.head.header;
-        let entry = SegmentEntry {
-            required_segments: definition.segments.tail.clone(),
-            definition:        Rc::new(definition),
-        };
-        if let Some(node) = self.get_mut(header) {
+        let header = definition.segments.first().header.clone();
+        let entry = SegmentEntry { next_segment: 1, definition: Arc::new(definition) };
+        if let Some(node) = self.get_mut(&header) {
             node.push(entry);
         } else {
             self.insert(header, NonEmptyVec::singleton(entry));
@@ -295,7 +312,7 @@ impl<'s> Resolver<'s> {
         token: Token<'s>,
         context: Context,
     ) -> Step<'s> {
-        let repr = &**token.code;
+        let repr = **token.code;
         if !token.variant.can_start_macro_segment() {
             return Step::NormalToken(token.into());
         }
@@ -343,7 +360,7 @@ impl<'s> Resolver<'s> {
     }
 
     fn resolve_match(&mut self, macro_def: &macros::Definition, segments_start: usize) {
-        let mut def_segments = macro_def.segments.to_vec().into_iter().rev();
+        let mut def_segments = macro_def.segments.iter().rev();
         let segments = self.segments.drain(segments_start..).rev();
         let segments: NonEmptyVec<_> = segments.collect::<Vec<_>>().try_into().unwrap();
         let mut pattern_matched_segments = segments.mapped(|segment| {
@@ -389,7 +406,7 @@ impl<'s> Resolver<'s> {
                     Err(tokens) => tokens,
                 };
                 if let Some(excess) = self.precedence.resolve(excess) {
-                    let excess = excess.with_error("Unexpected tokens in macro invocation.");
+                    let excess = excess.with_error(ErrorCode::UnexpectedMacroTokens);
                     tokens.push(excess.into());
                 }
                 let body = self.precedence.resolve(tokens);
@@ -412,7 +429,8 @@ impl<'s> Resolver<'s> {
             items.push(segment.header.into());
         }
         let segment0 = self.segments.pop().unwrap();
-        let header0 = syntax::tree::to_ast(segment0.header).with_error("Invalid macro invocation.");
+        let header0 =
+            syntax::tree::to_ast(segment0.header).with_error(ErrorCode::InvalidMacroInvocation);
         items.extend(self.items.drain(segment0.items_start..).rev());
         self.items.push(header0.into());
         self.items.extend(items.into_iter().rev());
@@ -422,20 +440,20 @@ impl<'s> Resolver<'s> {
     /// new [`SegmentMap`]. If after moving to the next segment there is a macro definition that is
     /// fully matched, its definition will be recorded.
     fn move_to_next_segment(
-        matched_macro_def: &mut Option<Rc<macros::Definition<'s>>>,
+        matched_macro_def: &mut Option<Arc<macros::Definition<'s>>>,
         possible_segments: &[SegmentEntry<'s>],
     ) -> SegmentMap<'s> {
         *matched_macro_def = None;
         let mut new_section_tree = SegmentMap::default();
         for segment_entry in possible_segments {
-            if let Some(first) = segment_entry.required_segments.head() {
-                let tail = segment_entry.required_segments.tail().cloned().unwrap_or_default();
+            let next_segment = segment_entry.next_segment;
+            if let Some(first) = segment_entry.definition.segments.get(next_segment) {
                 let definition = segment_entry.definition.clone();
-                let entry = SegmentEntry { required_segments: tail, definition };
+                let entry = SegmentEntry { next_segment: next_segment + 1, definition };
                 if let Some(node) = new_section_tree.get_mut(&first.header) {
                     node.push(entry);
                 } else {
-                    new_section_tree.insert(first.header, NonEmptyVec::singleton(entry));
+                    new_section_tree.insert(first.header.clone(), NonEmptyVec::singleton(entry));
                 }
             } else {
                 *matched_macro_def = Some(segment_entry.definition.clone());
@@ -458,7 +476,7 @@ impl<'s> Resolver<'s> {
 #[derive(Debug)]
 struct PartiallyMatchedMacro<'s> {
     possible_next_segments: SegmentMap<'s>,
-    matched_macro_def:      Option<Rc<macros::Definition<'s>>>,
+    matched_macro_def:      Option<Arc<macros::Definition<'s>>>,
     /// Height in `segments` where this macro's resolved segments begin.
     segments_start:         usize,
 }
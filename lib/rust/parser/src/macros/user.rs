@@ -0,0 +1,504 @@
+//! User-defined macros. A user-defined macro is described declaratively: every segment has a
+//! header, a [`Pattern`] and a [`Template`] describing how the tokens matched by the pattern are
+//! arranged in the segment's body, so that new syntax can be prototyped without writing a Rust
+//! macro body function.
+//!
+//! For example, the `with ... do ...` macro can be defined as follows:
+//!
+//! ```text
+//! Definition::expression("with", everything() / "resource", [Fragment::var("resource")])
+//!     .segment("do", everything() / "action", [Fragment::var("action")])
+//! ```
+//!
+//! The result of a user-defined macro is a [`syntax::tree::MultiSegmentApp`]. As the parser is
+//! lossless, the template can only group the matched tokens, it cannot drop, duplicate or reorder
+//! them. This is checked when the macro is registered: the variables must be used by the template
+//! exactly once, in the order in which they are bound by the pattern.
+
+use crate::macros::pattern::*;
+use crate::prelude::*;
+
+use crate::lexer;
+use crate::macros;
+use crate::macros::expand::EnabledValidator;
+use crate::macros::expand::VarMapView;
+use crate::macros::resolver::Context;
+use crate::macros::resolver::MacroMap;
+use crate::syntax;
+use crate::syntax::token;
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+
+
+// ==================
+// === Definition ===
+// ==================
+
+/// Declarative definition of a macro, which can be registered in a [`crate::Parser`] at runtime.
+#[derive(Clone, Debug)]
+pub struct Definition {
+    context:  Context,
+    segments: NonEmptyVec<Segment>,
+}
+
+/// A segment of a user-defined macro.
+#[derive(Clone, Debug)]
+#[allow(missing_docs)]
+pub struct Segment {
+    pub header:   String,
+    pub pattern:  Pattern,
+    pub template: Template,
+}
+
+impl Definition {
+    /// Constructor of a macro that can occur anywhere in an expression.
+    pub fn expression(
+        header: impl Into<String>,
+        pattern: Pattern,
+        template: impl Into<Template>,
+    ) -> Self {
+        Self::new(Context::Expression, header, pattern, template)
+    }
+
+    /// Constructor of a macro that can only occur in statement context.
+    pub fn statement(
+        header: impl Into<String>,
+        pattern: Pattern,
+        template: impl Into<Template>,
+    ) -> Self {
+        Self::new(Context::Statement, header, pattern, template)
+    }
+
+    /// Constructor.
+    pub fn new(
+        context: Context,
+        header: impl Into<String>,
+        pattern: Pattern,
+        template: impl Into<Template>,
+    ) -> Self {
+        let segment = Segment { header: header.into(), pattern, template: template.into() };
+        Self { context, segments: NonEmptyVec::singleton(segment) }
+    }
+
+    /// Add a segment after the already defined ones.
+    pub fn segment(
+        mut self,
+        header: impl Into<String>,
+        pattern: Pattern,
+        template: impl Into<Template>,
+    ) -> Self {
+        let segment = Segment { header: header.into(), pattern, template: template.into() };
+        self.segments.push(segment);
+        self
+    }
+
+    /// The context in which the macro can be used.
+    pub fn context(&self) -> Context {
+        self.context
+    }
+
+    /// The segments of the macro.
+    pub fn segments(&self) -> &NonEmptyVec<Segment> {
+        &self.segments
+    }
+
+    /// Check the definition and add it to the given macros. Fails if the definition is malformed,
+    /// or if any of its segment headers is already used by one of the macros.
+    pub fn register(self, macros: &mut MacroMap) -> Result<(), RegistrationError> {
+        self.validate(macros)?;
+        let context = self.context;
+        macros.register(context, self.into_macro_definition());
+        Ok(())
+    }
+
+    fn validate(&self, macros: &MacroMap) -> Result<(), RegistrationError> {
+        for (index, segment) in self.segments.iter().enumerate() {
+            let header = &segment.header;
+            if !is_valid_header(header) {
+                return Err(RegistrationError::InvalidHeader { header: header.clone() });
+            }
+            if self.segments[..index].iter().any(|previous| &previous.header == header) {
+                return Err(RegistrationError::DuplicateHeader { header: header.clone() });
+            }
+            if let Some(existing) = macros.definition_with_header(header) {
+                let existing = describe(existing.segments.iter().map(|s| &*s.header));
+                return Err(RegistrationError::HeaderConflict { header: header.clone(), existing });
+            }
+            segment.validate_template()?;
+        }
+        Ok(())
+    }
+
+    fn into_macro_definition(self) -> macros::Definition<'static> {
+        let (segments, templates): (Vec<_>, Vec<_>) = self
+            .segments
+            .into_iter()
+            .map(|Segment { header, pattern, template }| {
+                (macros::SegmentDefinition::new(header, pattern), template)
+            })
+            .unzip();
+        let segments = NonEmptyVec::try_from(segments).unwrap();
+        let body: Arc<macros::DefinitionBody> =
+            Arc::new(move |segments, precedence| expand(&templates, segments, precedence));
+        macros::Definition { segments, body }
+    }
+}
+
+impl Segment {
+    fn validate_template(&self) -> Result<(), RegistrationError> {
+        let Template::Fragments(fragments) = &self.template else { return Ok(()) };
+        let header = &self.header;
+        let mut expected = vec![];
+        let mut names = HashSet::new();
+        pattern_shape(&self.pattern, &mut expected, &mut names, false).map_err(|issue| {
+            RegistrationError::UnsupportedPattern { header: header.clone(), issue }
+        })?;
+        let mut found = vec![];
+        template_shape(fragments, &mut found);
+        if expected != found {
+            let expected = Shape::describe(&expected);
+            let found = Shape::describe(&found);
+            return Err(RegistrationError::TemplateMismatch {
+                header: header.clone(),
+                expected,
+                found,
+            });
+        }
+        Ok(())
+    }
+}
+
+fn is_valid_header(header: &str) -> bool {
+    let tokens = lexer::run(header);
+    match &tokens.value[..] {
+        [token] if tokens.internal_error.is_none() => {
+            let is_header_variant =
+                matches!(token.variant, token::Variant::Ident(_) | token::Variant::Operator(_));
+            is_header_variant && token.left_offset.is_empty() && **token.code == header
+        }
+        _ => false,
+    }
+}
+
+/// Describe a macro by its segment headers, e.g. `if ... then ...`.
+fn describe<'a>(headers: impl IntoIterator<Item = &'a str>) -> String {
+    let headers: Vec<_> = headers.into_iter().map(|header| format!("{header} ...")).collect();
+    headers.join(" ")
+}
+
+
+
+// ================
+// === Template ===
+// ================
+
+/// Describes the body of a segment of a user-defined macro.
+#[derive(Clone, Debug, Default)]
+pub enum Template {
+    /// All the tokens matched by the segment's pattern, resolved as a single expression.
+    #[default]
+    Tokens,
+    /// The given fragments, resolved as a single expression. The fragments must use every
+    /// variable bound by the segment's pattern, in the order of binding.
+    Fragments(Vec<Fragment>),
+}
+
+impl From<Vec<Fragment>> for Template {
+    fn from(fragments: Vec<Fragment>) -> Self {
+        Self::Fragments(fragments)
+    }
+}
+
+impl<const N: usize> From<[Fragment; N]> for Template {
+    fn from(fragments: [Fragment; N]) -> Self {
+        Self::Fragments(fragments.into())
+    }
+}
+
+/// An element of a [`Template`].
+#[derive(Clone, Debug)]
+pub enum Fragment {
+    /// Splice the tokens bound to the variable with the given name. A variable bound by a
+    /// [`Pattern`] that is not matched (e.g. the second alternative of a matched
+    /// [`PatternData::Or`]) expands to nothing.
+    Var(String),
+    /// Expand the fragments and resolve the result as a single expression.
+    Group(Vec<Fragment>),
+    /// Expand the fragments once for every repetition of the corresponding
+    /// [`PatternData::Many`] pattern.
+    Repeat(Vec<Fragment>),
+}
+
+impl Fragment {
+    /// Constructor.
+    pub fn var(name: impl Into<String>) -> Self {
+        Self::Var(name.into())
+    }
+
+    /// Constructor.
+    pub fn group(fragments: impl IntoIterator<Item = Fragment>) -> Self {
+        Self::Group(fragments.into_iter().collect())
+    }
+
+    /// Constructor.
+    pub fn repeat(fragments: impl IntoIterator<Item = Fragment>) -> Self {
+        Self::Repeat(fragments.into_iter().collect())
+    }
+}
+
+
+// === Shape ===
+
+/// The variables bound by a pattern, or used by a template, with their repetition structure.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Shape {
+    Var(String),
+    Repeat(Vec<Shape>),
+}
+
+impl Shape {
+    fn describe(shapes: &[Shape]) -> String {
+        let items: Vec<_> = shapes
+            .iter()
+            .map(|shape| match shape {
+                Shape::Var(name) => format!("${name}"),
+                Shape::Repeat(shapes) => format!("$({})*", Self::describe(shapes)),
+            })
+            .collect();
+        items.join(" ")
+    }
+}
+
+fn pattern_shape(
+    pattern: &Pattern,
+    out: &mut Vec<Shape>,
+    names: &mut HashSet<String>,
+    in_repetition: bool,
+) -> Result<(), PatternIssue> {
+    match &*pattern.data {
+        PatternData::Nothing => Ok(()),
+        PatternData::Everything
+        | PatternData::Identifier
+        | PatternData::Block
+        | PatternData::NotBlock => Err(PatternIssue::UnboundTokens),
+        PatternData::Named(name, _) =>
+            if names.insert(name.clone()) {
+                out.push(Shape::Var(name.clone()));
+                Ok(())
+            } else {
+                Err(PatternIssue::DuplicateVariable(name.clone()))
+            },
+        PatternData::Expected(_, item) => {
+            let mut nested = vec![];
+            pattern_shape(item, &mut nested, names, in_repetition)?;
+            match nested.is_empty() {
+                true => Ok(()),
+                false => Err(PatternIssue::BindingInExpected),
+            }
+        }
+        PatternData::Seq(first, second) => {
+            pattern_shape(first, out, names, in_repetition)?;
+            pattern_shape(second, out, names, in_repetition)
+        }
+        PatternData::Or(first, second) => {
+            let bound_before = out.len();
+            pattern_shape(first, out, names, in_repetition)?;
+            pattern_shape(second, out, names, in_repetition)?;
+            if in_repetition && out.len() > bound_before {
+                return Err(PatternIssue::AlternativeInRepetition);
+            }
+            Ok(())
+        }
+        PatternData::Many(item) => {
+            if in_repetition {
+                return Err(PatternIssue::NestedRepetition);
+            }
+            let mut nested = vec![];
+            pattern_shape(item, &mut nested, names, true)?;
+            if !nested.is_empty() {
+                out.push(Shape::Repeat(nested));
+            }
+            Ok(())
+        }
+    }
+}
+
+fn template_shape(fragments: &[Fragment], out: &mut Vec<Shape>) {
+    for fragment in fragments {
+        match fragment {
+            Fragment::Var(name) => out.push(Shape::Var(name.clone())),
+            Fragment::Group(fragments) => template_shape(fragments, out),
+            Fragment::Repeat(fragments) => {
+                let mut nested = vec![];
+                template_shape(fragments, &mut nested);
+                out.push(Shape::Repeat(nested));
+            }
+        }
+    }
+}
+
+
+
+// =================
+// === Expansion ===
+// =================
+
+fn expand<'s>(
+    templates: &[Template],
+    segments: MatchedSegments<'s>,
+    precedence: &mut syntax::operator::Precedence<'s>,
+) -> syntax::Tree<'s> {
+    let mut templates = templates.iter();
+    let segments = segments.mapped(|segment| {
+        let header = segment.header;
+        let body = match templates.next() {
+            Some(Template::Fragments(fragments)) => {
+                let var_map = segment.result.into_var_map();
+                let mut expansion = Expansion::new(var_map.view());
+                let mut items = vec![];
+                expansion.expand(fragments, precedence, &mut items);
+                precedence.resolve(items)
+            }
+            Some(Template::Tokens) | None => precedence.resolve(segment.result.tokens()),
+        };
+        syntax::tree::MultiSegmentAppSegment { header, body }
+    });
+    syntax::Tree::multi_segment_app(segments)
+}
+
+/// The state of the expansion of a [`Template`] in one repetition scope.
+#[derive(Debug)]
+struct Expansion<'t, 's> {
+    view:    VarMapView<'t, 's, EnabledValidator>,
+    /// For every variable, the number of its bindings that have been expanded so far.
+    cursors: HashMap<String, usize>,
+}
+
+impl<'t, 's> Expansion<'t, 's> {
+    fn new(view: VarMapView<'t, 's, EnabledValidator>) -> Self {
+        Self { view, cursors: default() }
+    }
+
+    fn expand(
+        &mut self,
+        fragments: &[Fragment],
+        precedence: &mut syntax::operator::Precedence<'s>,
+        out: &mut Vec<syntax::Item<'s>>,
+    ) {
+        for fragment in fragments {
+            match fragment {
+                Fragment::Var(name) => {
+                    let bindings = self.view.query(name).unwrap_or_default();
+                    let cursor = self.cursors.entry(name.clone()).or_default();
+                    if let Some(items) = bindings.get(*cursor) {
+                        out.extend(items.iter().cloned());
+                        *cursor += 1;
+                    }
+                }
+                Fragment::Group(fragments) => {
+                    let mut items = vec![];
+                    self.expand(fragments, precedence, &mut items);
+                    out.extend(precedence.resolve(items).map(syntax::Item::from));
+                }
+                Fragment::Repeat(fragments) => {
+                    let mut nested = Expansion::new(self.view.nested());
+                    let mut first_var = vec![];
+                    template_shape(fragments, &mut first_var);
+                    let repetitions = match first_var.first() {
+                        Some(Shape::Var(name)) => nested.view.query(name).map_or(0, |b| b.len()),
+                        _ => 0,
+                    };
+                    for _ in 0..repetitions {
+                        nested.expand(fragments, precedence, out);
+                    }
+                }
+            }
+        }
+    }
+}
+
+
+
+// ==============
+// === Errors ===
+// ==============
+
+/// Describes why a user-defined macro could not be registered.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub enum RegistrationError {
+    /// The segment header is not a single identifier or operator token.
+    InvalidHeader { header: String },
+    /// The same header is used by more than one segment of the definition.
+    DuplicateHeader { header: String },
+    /// The segment header is already used by a registered macro (such as a built-in one).
+    HeaderConflict {
+        header:   String,
+        /// The headers of the registered macro, e.g. `if ... then ...`.
+        existing: String,
+    },
+    /// The pattern of the segment cannot be used with a [`Template::Fragments`] template.
+    UnsupportedPattern { header: String, issue: PatternIssue },
+    /// The template does not use the variables bound by the pattern exactly once, in order.
+    TemplateMismatch {
+        header:   String,
+        /// The variables bound by the pattern, e.g. `$name $($arg)*`.
+        expected: String,
+        /// The variables used by the template.
+        found:    String,
+    },
+}
+
+/// A property of a [`Pattern`] preventing the expansion of a [`Template::Fragments`] template.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PatternIssue {
+    /// The pattern consumes tokens that are not bound to a variable.
+    UnboundTokens,
+    /// The variable is bound more than once.
+    DuplicateVariable(String),
+    /// A [`PatternData::Many`] pattern contains another one binding variables.
+    NestedRepetition,
+    /// A [`PatternData::Many`] pattern contains alternatives binding variables.
+    AlternativeInRepetition,
+    /// A [`PatternData::Expected`] pattern contains one binding variables.
+    BindingInExpected,
+}
+
+impl Display for RegistrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidHeader { header } =>
+                write!(f, "`{header}` is not a valid macro segment header"),
+            Self::DuplicateHeader { header } =>
+                write!(f, "the segment header `{header}` is used more than once"),
+            Self::HeaderConflict { header, existing } =>
+                write!(f, "the segment header `{header}` is already used by the `{existing}` macro"),
+            Self::UnsupportedPattern { header, issue } =>
+                write!(f, "the pattern of the `{header}` segment cannot be expanded: {issue}"),
+            Self::TemplateMismatch { header, expected, found } => write!(
+                f,
+                "the template of the `{header}` segment must use the variables `{expected}`, \
+                 found `{found}`"
+            ),
+        }
+    }
+}
+
+impl Display for PatternIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnboundTokens => write!(f, "it matches tokens not bound to a variable"),
+            Self::DuplicateVariable(name) => write!(f, "the variable `{name}` is bound twice"),
+            Self::NestedRepetition => write!(f, "repetitions cannot be nested"),
+            Self::AlternativeInRepetition =>
+                write!(f, "alternatives binding variables cannot be repeated"),
+            Self::BindingInExpected =>
+                write!(f, "variables cannot be bound within an `expected` pattern"),
+        }
+    }
+}
+
+impl std::error::Error for RegistrationError {}
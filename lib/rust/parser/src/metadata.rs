@@ -1,13 +1,31 @@
 //! Data associated with a syntax tree.
 //!
 //! This data is currently represented as two lines containing one JSON value each, placed at the
-//! end of a file after a line containing exactly the text "#### METADATA ####".
+//! end of a file after a line containing exactly the text "#### METADATA ####". The first line is
+//! the [`IdMap`]; the second contains [`ide::Properties`].
+//!
+//! [`Metadata`] provides lookup of the IDs associated with nodes. A [`Section`] is a complete,
+//! editable representation of the metadata, which can be written back to text with [`attach`]; data
+//! that is not modified is written exactly as it was read.
+
+use crate::prelude::*;
+
+use crate::incremental::Edit;
+use crate::syntax::Tree;
 
 use std::collections::BTreeMap;
-use std::str::FromStr;
+use std::collections::HashMap;
 use uuid::Uuid;
 
 
+// ==============
+// === Export ===
+// ==============
+
+pub mod ide;
+pub mod json;
+
+
 
 const MARKER: &str = "\n\n\n#### METADATA ####\n";
 
@@ -47,6 +65,13 @@ impl From<MetadataFormat> for Metadata {
     }
 }
 
+impl From<&IdMap> for Metadata {
+    fn from(id_map: &IdMap) -> Self {
+        let id_map = id_map.iter().map(|(span, id)| (span.into(), id)).collect();
+        Self { id_map }
+    }
+}
+
 /// Split input source file into the code and the metadata section, if any was found.
 pub fn extract(input: &str) -> (&str, Option<&str>) {
     match input.rsplit_once(MARKER) {
@@ -62,6 +87,24 @@ pub fn parse(input: &str) -> Option<(Result, &str)> {
     Some((metadata.parse().map(|data: MetadataFormat| data.into()), code))
 }
 
+/// Given source code, if a metadata section is found: Attempt to parse it as a [`Section`]; return
+/// the result, and the non-metadata portion of the input.
+pub fn parse_section(input: &str) -> Option<(Result<Section>, &str)> {
+    let (code, metadata) = input.rsplit_once(MARKER)?;
+    Some((metadata.parse(), code))
+}
+
+/// Combine code with a metadata section; this is the inverse of [`parse_section`]. If there is no
+/// metadata, but the code contains the metadata marker, an empty metadata section is added, so that
+/// the code is not mistaken for metadata when it is read.
+pub fn attach(code: &str, section: Option<&Section>) -> String {
+    match section {
+        Some(section) => format!("{code}{MARKER}{section}"),
+        None if code.contains(MARKER.trim_end()) => format!("{code}{MARKER}"),
+        None => code.to_owned(),
+    }
+}
+
 /// Parse just the metadata section.
 pub fn parse_metadata(input: &str) -> Option<Vec<((usize, usize), Uuid)>> {
     Some(
@@ -93,9 +136,9 @@ impl FromStr for MetadataFormat {
 /// Identifies a span in the source code.
 #[derive(Debug, Copy, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 struct Location {
-    /// The beginning of the span, as a byte offset from the beginning of the file.
+    /// The beginning of the span, in UTF-16 code units from the beginning of the file.
     index: Number,
-    /// The length of the span, in bytes.
+    /// The length of the span, in UTF-16 code units.
     size:  Number,
 }
 
@@ -104,6 +147,268 @@ struct Number {
     value: usize,
 }
 
+impl From<Span> for Location {
+    fn from(span: Span) -> Self {
+        Self { index: Number { value: span.index }, size: Number { value: span.size } }
+    }
+}
+
+impl From<Location> for Span {
+    fn from(location: Location) -> Self {
+        Self { index: location.index.value, size: location.size.value }
+    }
+}
+
+
+
+// ============
+// === Span ===
+// ============
+
+/// Identifies the code of a node, by its location in the source. Locations are measured in UTF-16
+/// code units, as by the IDE.
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize
+)]
+#[serde(from = "Location", into = "Location")]
+pub struct Span {
+    /// The beginning of the span, in UTF-16 code units from the beginning of the file.
+    pub index: usize,
+    /// The length of the span, in UTF-16 code units.
+    pub size:  usize,
+}
+
+impl Span {
+    /// Constructor.
+    pub fn new(index: usize, size: usize) -> Self {
+        Self { index, size }
+    }
+
+    /// The offset of the end of the span.
+    pub fn end(&self) -> usize {
+        self.index + self.size
+    }
+
+    /// Return the location of the tree's code, excluding its leading whitespace.
+    pub fn of_tree(tree: &Tree) -> Self {
+        let range = tree.span.range();
+        let index = range.start.utf16 as usize;
+        Self::new(index, range.end.utf16 as usize - index)
+    }
+
+    /// Return the location of the same code, after the given edit is applied to `code`; returns
+    /// [`None`] if the edit changes the span's boundaries, or replaces all of its code.
+    ///
+    /// A span that contains the edited text is resized; a span following it is moved. Text
+    /// inserted exactly at the start of a span is considered to precede it, and text inserted at
+    /// its end to follow it.
+    pub fn remap(&self, edit: &Edit, code: &str) -> Option<Self> {
+        // The edit is measured in UTF-8 code units; convert it to the units of the span.
+        let utf16_len = |text: &str| text.encode_utf16().count();
+        let start = utf16_len(&code[..edit.range.start]);
+        let end = start + utf16_len(&code[edit.range.clone()]);
+        let delta = utf16_len(&edit.text) as isize - (end - start) as isize;
+        let shift = |offset: usize| offset.checked_add_signed(delta).unwrap();
+        let replaces_all = self.index == start && self.end() == end;
+        if self.end() <= start && !(self.size == 0 && start == end && self.index == start) {
+            Some(*self)
+        } else if self.index >= end {
+            Some(Self { index: shift(self.index), ..*self })
+        } else if self.index <= start && end <= self.end() && !replaces_all {
+            Some(Self { size: shift(self.size), ..*self })
+        } else {
+            None
+        }
+    }
+}
+
+impl From<(usize, usize)> for Span {
+    fn from((index, size): (usize, usize)) -> Self {
+        Self { index, size }
+    }
+}
+
+
+
+// =============
+// === IdMap ===
+// =============
+
+/// Associates stable IDs with nodes of a tree, identified by their [`Span`]s.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+pub struct IdMap {
+    entries: Vec<(Span, Uuid)>,
+}
+
+impl IdMap {
+    /// Return the ID associated with the given span, if any.
+    pub fn get(&self, span: Span) -> Option<Uuid> {
+        self.entries.iter().find(|(s, _)| *s == span).map(|(_, id)| *id)
+    }
+
+    /// Associate the ID with the given span, replacing any ID it was previously associated with.
+    pub fn insert(&mut self, span: Span, id: Uuid) {
+        match self.entries.iter_mut().find(|(s, _)| *s == span) {
+            Some((_, existing)) => *existing = id,
+            None => self.entries.push((span, id)),
+        }
+    }
+
+    /// Remove the entry for the given span, and return its ID.
+    pub fn remove(&mut self, span: Span) -> Option<Uuid> {
+        let index = self.entries.iter().position(|(s, _)| *s == span)?;
+        Some(self.entries.remove(index).1)
+    }
+
+    /// Iterate over the entries, in order.
+    pub fn iter(&self) -> impl Iterator<Item = (Span, Uuid)> + '_ {
+        self.entries.iter().copied()
+    }
+
+    /// Return the number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Return whether there are no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Update the spans to identify the same code after the given edit is applied to `code` (see
+    /// [`Span::remap`]). Entries whose nodes may have been changed by the edit are removed; their
+    /// IDs are returned.
+    pub fn remap(&mut self, edit: &Edit, code: &str) -> Vec<Uuid> {
+        let mut removed = vec![];
+        self.entries.retain_mut(|(span, id)| match span.remap(edit, code) {
+            Some(remapped) => {
+                *span = remapped;
+                true
+            }
+            None => {
+                removed.push(*id);
+                false
+            }
+        });
+        removed
+    }
+}
+
+impl FromIterator<(Span, Uuid)> for IdMap {
+    fn from_iter<T: IntoIterator<Item = (Span, Uuid)>>(iter: T) -> Self {
+        let mut id_map = Self::default();
+        id_map.extend(iter);
+        id_map
+    }
+}
+
+impl Extend<(Span, Uuid)> for IdMap {
+    fn extend<T: IntoIterator<Item = (Span, Uuid)>>(&mut self, iter: T) {
+        let mut index: HashMap<_, _> =
+            self.entries.iter().enumerate().map(|(i, (span, _))| (*span, i)).collect();
+        for (span, id) in iter {
+            match index.get(&span) {
+                Some(&i) => self.entries[i].1 = id,
+                None => {
+                    index.insert(span, self.entries.len());
+                    self.entries.push((span, id));
+                }
+            }
+        }
+    }
+}
+
+impl From<&Metadata> for IdMap {
+    fn from(metadata: &Metadata) -> Self {
+        let entries =
+            metadata.id_map.iter().map(|(location, id)| ((*location).into(), *id)).collect();
+        Self { entries }
+    }
+}
+
+
+
+// ===============
+// === Section ===
+// ===============
+
+/// The complete contents of a metadata section.
+///
+/// Data is written exactly as it was read, unless it has been modified: JSON values that have not
+/// changed are written with their original text, and any text following the metadata lines is
+/// preserved.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Section {
+    /// The first line of the section.
+    pub id_map:     IdMap,
+    /// The second line of the section, if present and valid.
+    pub properties: Option<ide::Properties>,
+    original:       OriginalText,
+}
+
+/// The text a [`Section`] was read from.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct OriginalText {
+    id_map:     Option<String>,
+    properties: Option<String>,
+    rest:       String,
+}
+
+impl FromStr for Section {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self> {
+        let (id_map_line, s) = s.split_once('\n').unwrap_or((s, ""));
+        let id_map = serde_json::from_str(id_map_line).map_err(|e| e.to_string())?;
+        let (properties_line, rest) = match s.find('\n') {
+            Some(end) => (&s[..end], &s[end..]),
+            None => (s, ""),
+        };
+        let has_properties = !properties_line.is_empty() || !rest.is_empty();
+        let properties = serde_json::from_str(properties_line).ok();
+        let original = OriginalText {
+            id_map:     Some(id_map_line.to_owned()),
+            properties: has_properties.then(|| properties_line.to_owned()),
+            rest:       rest.to_owned(),
+        };
+        Ok(Self { id_map, properties, original })
+    }
+}
+
+impl Display for Section {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let id_map = self.original.id_map.as_deref();
+        match id_map.filter(|line| serde_json::from_str(line).ok().as_ref() == Some(&self.id_map)) {
+            Some(line) => f.write_str(line)?,
+            None => f.write_str(&serde_json::to_string(&self.id_map).unwrap())?,
+        }
+        let properties = self.original.properties.as_deref();
+        let unchanged = |line: &&str| serde_json::from_str(line).ok() == self.properties;
+        match (properties.filter(unchanged), &self.properties) {
+            (Some(line), _) => write!(f, "\n{line}")?,
+            (None, Some(properties)) =>
+                write!(f, "\n{}", serde_json::to_string(properties).unwrap())?,
+            (None, None) => (),
+        }
+        f.write_str(&self.original.rest)
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -118,4 +423,113 @@ mod test {
     fn empty_metadata() {
         MetadataFormat::from_str("[]").expect("Empty sequence is valid.");
     }
+
+    const ID: &str = "5bad897e-099b-4b00-9348-64092636746d";
+
+    fn section_text() -> String {
+        let id_map = format!(r#"[[{{"index":{{"value":4}},"size":{{"value":3}}}},"{ID}"]]"#);
+        let node = r#"{"position":{"vector":[-73.0,49.5]},"selected":false,"visualization":null}"#;
+        let properties = format!(r#"{{"ide":{{"node":{{"{ID}":{node}}},"extra":[1, 2]}}}}"#);
+        format!("{id_map}\n{properties}\n")
+    }
+
+    #[test]
+    fn section_round_trip() {
+        let text = section_text();
+        let section: Section = text.parse().unwrap();
+        assert_eq!(section.id_map.get(Span::new(4, 3)), Some(ID.parse().unwrap()));
+        let ide = section.properties.as_ref().unwrap().ide.as_ref().unwrap();
+        let node = ide.node(ID.parse().unwrap()).unwrap();
+        assert_eq!(node.position, Some(ide::Position::new(-73.0, 49.5)));
+        assert_eq!(section.to_string(), text);
+        let code = "x = foo";
+        let file = attach(code, Some(&section));
+        let (parsed, parsed_code) = parse_section(&file).unwrap();
+        assert_eq!(parsed_code, code);
+        assert_eq!(parsed.unwrap(), section);
+    }
+
+    #[test]
+    fn section_modification() {
+        let mut section: Section = section_text().parse().unwrap();
+        let id = ID.parse().unwrap();
+        let node = section.properties.as_mut().unwrap().ide_mut().node_mut(id);
+        node.position = Some(ide::Position::new(1.0, 2.0));
+        node.color_override = Some("red".into());
+        let new_id = Uuid::from_u64_pair(1, 2);
+        section.id_map.insert(Span::new(0, 1), new_id);
+        let expected_node = r#"{"position":{"vector":[1.0,2.0]},"selected":false,"visualization":null,"colorOverride":"red"}"#;
+        let expected = format!(
+            r#"[[{{"index":{{"value":4}},"size":{{"value":3}}}},"{ID}"],[{{"index":{{"value":0}},"size":{{"value":1}}}},"{new_id}"]]
+{{"ide":{{"node":{{"{ID}":{expected_node}}},"extra":[1, 2]}}}}
+"#
+        );
+        assert_eq!(section.to_string(), expected);
+    }
+
+    #[test]
+    fn attach_without_metadata() {
+        assert_eq!(attach("x", None), "x");
+        let code = format!("x{MARKER}y");
+        let file = attach(&code, None);
+        assert_eq!(extract(&file), (code.as_str(), Some("")));
+    }
+
+    #[test]
+    fn remap_spans() {
+        let span = Span::new(4, 3);
+        let code = "012345678";
+        let remap = |range: Range<usize>, text: &str| span.remap(&Edit::new(range, text), code);
+        // Before the span.
+        assert_eq!(remap(0..1, "abc"), Some(Span::new(6, 3)));
+        assert_eq!(remap(4..4, "ab"), Some(Span::new(6, 3)));
+        // After the span.
+        assert_eq!(remap(7..7, "ab"), Some(span));
+        assert_eq!(remap(8..9, ""), Some(span));
+        // Within the span.
+        assert_eq!(remap(5..6, "abc"), Some(Span::new(4, 5)));
+        assert_eq!(remap(4..6, "a"), Some(Span::new(4, 2)));
+        // Replacing all of the span's code.
+        assert_eq!(remap(4..7, "a"), None);
+        assert_eq!(remap(4..7, ""), None);
+        // Overlapping the span's boundaries.
+        assert_eq!(remap(3..5, ""), None);
+        assert_eq!(remap(6..8, "x"), None);
+    }
+
+    #[test]
+    fn remap_spans_non_ascii() {
+        // `é` is 2 bytes and 1 UTF-16 code unit; `𝑥` is 4 bytes and 2 UTF-16 code units.
+        let code = "é 𝑥 bar";
+        let bar = Span::new(5, 3);
+        assert_eq!(Span::of_tree(&crate::Parser::new().run(code)), Span::new(0, 8));
+        let remap = |range: Range<usize>, text: &str| bar.remap(&Edit::new(range, text), code);
+        // `é 𝑥 bar` -> `é bar`
+        assert_eq!(remap(3..8, ""), Some(Span::new(2, 3)));
+        // `é 𝑥 bar` -> `é 𝑥𝑥 bar`
+        assert_eq!(remap(3..7, "𝑥𝑥"), Some(Span::new(7, 3)));
+        // `é 𝑥 bar` -> `é 𝑥 bér`
+        assert_eq!(remap(8..11, "bér"), None);
+        assert_eq!(remap(9..10, "é"), Some(bar));
+    }
+
+    #[test]
+    fn remap_id_map() {
+        let ids: Vec<_> = (0..3).map(|i| Uuid::from_u64_pair(0, i)).collect();
+        let mut id_map = IdMap::default();
+        // `foo bar`: The application, and its two identifiers.
+        id_map.insert(Span::new(0, 7), ids[0]);
+        id_map.insert(Span::new(0, 3), ids[1]);
+        id_map.insert(Span::new(4, 3), ids[2]);
+        // `foo bar` -> `x foo bar`
+        assert!(id_map.remap(&Edit::new(0..0, "x "), "foo bar").is_empty());
+        let spans: Vec<_> = id_map.iter().collect();
+        let expected =
+            [(Span::new(2, 7), ids[0]), (Span::new(2, 3), ids[1]), (Span::new(6, 3), ids[2])];
+        assert_eq!(spans, expected);
+        // `x foo bar` -> `x fbar`
+        assert_eq!(id_map.remap(&Edit::new(3..6, ""), "x foo bar"), vec![ids[1]]);
+        let spans: Vec<_> = id_map.iter().collect();
+        assert_eq!(spans, [(Span::new(2, 4), ids[0]), (Span::new(3, 3), ids[2])]);
+    }
 }
@@ -0,0 +1,193 @@
+//! Metadata stored by the IDE: the second line of the metadata section.
+//!
+//! Only the properties that are understood by tools other than the IDE are represented by typed
+//! fields; all other data is preserved as it was read (see [`json`]).
+
+use crate::prelude::*;
+
+use crate::metadata::json;
+use crate::metadata::json::Object;
+use crate::metadata::json::OrderedMap;
+
+use uuid::Uuid;
+
+
+
+// ==================
+// === Properties ===
+// ==================
+
+/// The contents of the second line of the metadata section.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Properties {
+    /// Metadata of the graph editor.
+    pub ide: Option<Ide>,
+    object:  Object,
+}
+
+impl Properties {
+    /// Return the metadata of the graph editor, creating it if it is not present.
+    pub fn ide_mut(&mut self) -> &mut Ide {
+        self.ide.get_or_insert_with(default)
+    }
+}
+
+impl Serialize for Properties {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        json::Writer::new(&self.object).field("ide", &self.ide).finish().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Properties {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let object = Object::deserialize(deserializer)?;
+        Ok(Self { ide: object.decode("ide"), object })
+    }
+}
+
+
+
+// ===========
+// === Ide ===
+// ===========
+
+/// Metadata of the graph editor.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Ide {
+    /// Properties of the nodes, identified by the IDs of their expressions in the ID map.
+    pub node:   Option<OrderedMap<Uuid, Node>>,
+    /// Properties of the imports, by an identifier assigned by the IDE.
+    pub import: Option<OrderedMap<String, Object>>,
+    object:     Object,
+}
+
+impl Ide {
+    /// Return the properties of the node with the given ID, if any.
+    pub fn node(&self, id: Uuid) -> Option<&Node> {
+        self.node.as_ref()?.get(&id)
+    }
+
+    /// Return the properties of the node with the given ID, creating them if not present.
+    pub fn node_mut(&mut self, id: Uuid) -> &mut Node {
+        let nodes = self.node.get_or_insert_with(default);
+        if nodes.get(&id).is_none() {
+            nodes.insert(id, default());
+        }
+        nodes.get_mut(&id).unwrap()
+    }
+}
+
+impl Serialize for Ide {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let writer = json::Writer::new(&self.object);
+        let writer = writer.field("node", &self.node).field("import", &self.import);
+        writer.finish().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Ide {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let object = Object::deserialize(deserializer)?;
+        Ok(Self { node: object.decode("node"), import: object.decode("import"), object })
+    }
+}
+
+
+
+// ============
+// === Node ===
+// ============
+
+/// Properties of a node in the graph editor.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Node {
+    /// The location of the node in the graph editor.
+    pub position:       Option<Position>,
+    /// The visualization attached to the node.
+    pub visualization:  Option<Visualization>,
+    /// A color that overrides the node's default color, in CSS syntax.
+    pub color_override: Option<String>,
+    object:             Object,
+}
+
+impl Serialize for Node {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let writer = json::Writer::new(&self.object);
+        let writer = writer.field("position", &self.position);
+        let writer = writer.field("visualization", &self.visualization);
+        let writer = writer.field("colorOverride", &self.color_override);
+        writer.finish().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Node {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let object = Object::deserialize(deserializer)?;
+        Ok(Self {
+            position: object.decode("position"),
+            visualization: object.decode("visualization"),
+            color_override: object.decode("colorOverride"),
+            object,
+        })
+    }
+}
+
+/// A location in the graph editor.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Position {
+    /// The coordinates of the location. The vertical axis points upwards.
+    pub vector: [f64; 2],
+}
+
+impl Position {
+    /// Constructor.
+    pub fn new(x: f64, y: f64) -> Self {
+        Self { vector: [x, y] }
+    }
+}
+
+
+
+// =====================
+// === Visualization ===
+// =====================
+
+/// A visualization attached to a node.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Visualization {
+    /// Whether the visualization is shown.
+    pub show:       Option<bool>,
+    /// The width of the visualization, if it has been resized.
+    pub width:      Option<f64>,
+    /// Whether the visualization is shown in fullscreen mode.
+    pub fullscreen: Option<bool>,
+    /// Identifies the project providing the visualization.
+    pub project:    Option<serde_json::Value>,
+    /// The name of the visualization within its project.
+    pub name:       Option<serde_json::Value>,
+    object:         Object,
+}
+
+impl Serialize for Visualization {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let writer = json::Writer::new(&self.object);
+        let writer = writer.field("show", &self.show).field("width", &self.width);
+        let writer = writer.field("fullscreen", &self.fullscreen);
+        let writer = writer.field("project", &self.project).field("name", &self.name);
+        writer.finish().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Visualization {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let object = Object::deserialize(deserializer)?;
+        Ok(Self {
+            show: object.decode("show"),
+            width: object.decode("width"),
+            fullscreen: object.decode("fullscreen"),
+            project: object.decode("project"),
+            name: object.decode("name"),
+            object,
+        })
+    }
+}
@@ -0,0 +1,228 @@
+//! Lossless representation of JSON data.
+//!
+//! Metadata is read and written by different tools, which may not understand all of its contents.
+//! To ensure that data is not lost or reformatted by a tool that only modifies part of it, typed
+//! metadata structures keep the [`Object`] they were read from; when written, fields whose typed
+//! values have not changed are copied from the original text, and unknown fields are preserved in
+//! their original order.
+
+use crate::prelude::*;
+
+use serde::de::DeserializeOwned;
+use serde::de::MapAccess;
+use serde::de::Visitor;
+use serde::ser::SerializeMap;
+use serde_json::value::RawValue;
+
+
+
+// ==============
+// === Object ===
+// ==============
+
+/// A JSON object, with its fields in their original order, and their values in their original
+/// text.
+#[derive(Clone, Debug, Default)]
+pub struct Object {
+    fields: Vec<(String, Box<RawValue>)>,
+}
+
+impl Object {
+    /// Return the text of the value of the given field, if present.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.get_raw(key).map(RawValue::get)
+    }
+
+    /// Iterate over the fields and the text of their values.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.fields.iter().map(|(key, value)| (key.as_str(), value.get()))
+    }
+
+    /// Return the value of the given field, if it is present and is a valid `T`.
+    pub fn decode<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        decode(self.get_raw(key)?)
+    }
+
+    fn get_raw(&self, key: &str) -> Option<&RawValue> {
+        self.fields.iter().find(|(k, _)| k == key).map(|(_, value)| &**value)
+    }
+}
+
+impl PartialEq for Object {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl Serialize for Object {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.fields.len()))?;
+        for (key, value) in &self.fields {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Object {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let fields = deserializer.deserialize_map(EntriesVisitor(PhantomData))?;
+        Ok(Self { fields })
+    }
+}
+
+fn decode<T: DeserializeOwned>(value: &RawValue) -> Option<T> {
+    serde_json::from_str::<Option<T>>(value.get()).ok().flatten()
+}
+
+
+// === Writer ===
+
+/// Produces an [`Object`] from typed fields and the [`Object`] they were originally read from.
+///
+/// A field is written with its original text if its value is unchanged, and omitted if it has been
+/// set to `None`. Fields that have no typed representation are copied from the original. Fields
+/// are written in their original order; new fields follow, in the order they are written.
+#[derive(Debug)]
+pub(crate) struct Writer<'a> {
+    original: &'a Object,
+    written:  Vec<(&'static str, Option<Box<RawValue>>)>,
+}
+
+impl<'a> Writer<'a> {
+    /// Constructor.
+    pub fn new(original: &'a Object) -> Self {
+        Self { original, written: default() }
+    }
+
+    /// Write a typed field.
+    pub fn field<T>(mut self, key: &'static str, value: &Option<T>) -> Self
+    where T: Serialize + DeserializeOwned + PartialEq {
+        let original = self.original.get_raw(key);
+        let value = match original {
+            Some(raw) if decode::<T>(raw).as_ref() == value.as_ref() => Some(raw.to_owned()),
+            _ => value.as_ref().map(|value| serde_json::value::to_raw_value(value).unwrap()),
+        };
+        self.written.push((key, value));
+        self
+    }
+
+    /// Return the written object.
+    pub fn finish(self) -> Object {
+        let mut written = self.written;
+        let mut fields = Vec::with_capacity(self.original.fields.len());
+        for (key, value) in &self.original.fields {
+            match written.iter_mut().find(|(k, _)| k == key) {
+                Some((_, value)) => fields.extend(value.take().map(|value| (key.clone(), value))),
+                None => fields.push((key.clone(), value.clone())),
+            }
+        }
+        let is_new = |key: &str| !self.original.fields.iter().any(|(k, _)| k == key);
+        for (key, value) in written {
+            if let Some(value) = value
+                && is_new(key)
+            {
+                fields.push((key.to_owned(), value));
+            }
+        }
+        Object { fields }
+    }
+}
+
+
+
+// ==================
+// === OrderedMap ===
+// ==================
+
+/// A JSON object with values of a single type, keeping its entries in their original order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OrderedMap<K, V> {
+    entries: Vec<(K, V)>,
+}
+
+impl<K, V> Default for OrderedMap<K, V> {
+    fn default() -> Self {
+        Self { entries: default() }
+    }
+}
+
+impl<K: PartialEq, V> OrderedMap<K, V> {
+    /// Return the value of the given key, if present.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.entries.iter().find(|(k, _)| k == key).map(|(_, value)| value)
+    }
+
+    /// Return a mutable reference to the value of the given key, if present.
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.entries.iter_mut().find(|(k, _)| k == key).map(|(_, value)| value)
+    }
+
+    /// Set the value of the given key. A new key is added after all the existing entries.
+    pub fn insert(&mut self, key: K, value: V) {
+        match self.get_mut(&key) {
+            Some(existing) => *existing = value,
+            None => self.entries.push((key, value)),
+        }
+    }
+
+    /// Remove the given key, and return its value.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let index = self.entries.iter().position(|(k, _)| k == key)?;
+        Some(self.entries.remove(index).1)
+    }
+
+    /// Iterate over the entries, in order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+
+    /// Return the number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Return whether there are no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl<K: Serialize, V: Serialize> Serialize for OrderedMap<K, V> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.entries.len()))?;
+        for (key, value) in &self.entries {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de, K: Deserialize<'de>, V: Deserialize<'de>> Deserialize<'de> for OrderedMap<K, V> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let entries = deserializer.deserialize_map(EntriesVisitor(PhantomData))?;
+        Ok(Self { entries })
+    }
+}
+
+
+// === EntriesVisitor ===
+
+/// Reads the entries of a map, in order.
+struct EntriesVisitor<K, V>(PhantomData<(K, V)>);
+
+impl<'de, K: Deserialize<'de>, V: Deserialize<'de>> Visitor<'de> for EntriesVisitor<K, V> {
+    type Value = Vec<(K, V)>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut entries = Vec::with_capacity(map.size_hint().unwrap_or_default());
+        while let Some(entry) = map.next_entry()? {
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+}
@@ -0,0 +1,624 @@
+//! The interface of a module: a summary of the definitions in its tree.
+//!
+//! [`extract`] produces the [`Outline`] of a module: its imports and exports, its type definitions
+//! with their constructors and methods, and its functions, each with the location of its code and
+//! the documentation and annotations applied to it. Tools that need the interface of a module, such
+//! as a documentation generator, can read it from the outline or its JSON serialization instead of
+//! interpreting the tree.
+//!
+//! A type signature (`name : Type`) followed by a definition of the same name is merged into the
+//! definition, so that the documentation and annotations written before the signature are
+//! attributed to the definition.
+
+use crate::prelude::*;
+
+use crate::source::code::Location;
+use crate::syntax::token;
+use crate::syntax::tree;
+use crate::syntax::Tree;
+
+
+
+// ===============
+// === Outline ===
+// ===============
+
+/// The definitions of a module.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Outline {
+    /// Whether the module is marked `private`.
+    pub private:   bool,
+    /// The import statements of the module.
+    pub imports:   Vec<Import>,
+    /// The export statements of the module.
+    pub exports:   Vec<Import>,
+    /// The types defined by the module.
+    pub types:     Vec<Type>,
+    /// The functions defined outside of any type, including extension methods.
+    pub functions: Vec<Function>,
+}
+
+/// An import or export statement.
+///
+/// The qualified names of modules are resolved: when the name of the library is known, a name
+/// starting with `project` is replaced by the corresponding name starting with the library's name.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Import {
+    /// The language of a polyglot import.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub polyglot: Option<String>,
+    /// The qualified name of the module imported, exported, or named by `from`. For a polyglot
+    /// import, the name of the foreign entity.
+    pub module:   String,
+    /// The qualified names of the entities of the module imported or exported by name.
+    pub names:    Vec<String>,
+    /// Whether all the entities of the module are imported or exported.
+    pub all:      bool,
+    /// The qualified names of the entities excluded with `hiding`.
+    pub hiding:   Vec<String>,
+    /// The name the module is bound to with `as`.
+    pub alias:    Option<String>,
+    /// The location of the statement.
+    pub range:    Range<Location>,
+}
+
+/// A type definition.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Type {
+    /// The name of the type.
+    pub name:         String,
+    /// The type parameters.
+    pub params:       Vec<Argument>,
+    /// The constructors of the type.
+    pub constructors: Vec<Constructor>,
+    /// The methods defined in the body of the type.
+    pub methods:      Vec<Function>,
+    /// The documentation, annotations and visibility of the type.
+    #[serde(flatten)]
+    pub attributes:   Attributes,
+    /// The location of the definition, including its documentation and annotations.
+    pub range:        Range<Location>,
+    /// The location of the name of the type.
+    pub name_range:   Range<Location>,
+}
+
+/// A constructor of a type.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Constructor {
+    /// The name of the constructor.
+    pub name:       String,
+    /// The arguments, whether specified inline or on their own lines.
+    pub arguments:  Vec<Argument>,
+    /// The documentation, annotations and visibility of the constructor.
+    #[serde(flatten)]
+    pub attributes: Attributes,
+    /// The location of the definition, including its documentation and annotations.
+    pub range:      Range<Location>,
+    /// The location of the name of the constructor.
+    pub name_range: Range<Location>,
+}
+
+/// A function or method definition.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Function {
+    /// The name of the function, as written: qualified by the name of a type for an extension
+    /// method (`Type.method`).
+    pub name:       String,
+    /// The arguments, including `self` for a method.
+    pub arguments:  Vec<Argument>,
+    /// The return type specified by the definition (`-> Type`).
+    pub returns:    Option<String>,
+    /// The type specified by a type signature preceding the definition.
+    pub signature:  Option<String>,
+    /// The language of a foreign function.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language:   Option<String>,
+    /// The documentation, annotations and visibility of the function.
+    #[serde(flatten)]
+    pub attributes: Attributes,
+    /// The location of the definition, including its signature, documentation and annotations.
+    pub range:      Range<Location>,
+    /// The location of the name of the function.
+    pub name_range: Range<Location>,
+}
+
+/// An argument of a function or constructor, or a parameter of a type.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Argument {
+    /// The pattern the argument is bound to; usually, its name.
+    pub name:      String,
+    /// The type of the argument.
+    #[serde(rename = "type")]
+    pub type_:     Option<String>,
+    /// The default value of the argument.
+    pub default:   Option<String>,
+    /// Whether the argument is suspended (`~`).
+    pub suspended: bool,
+    /// The location of the pattern.
+    pub range:     Range<Location>,
+}
+
+/// Properties of a definition, specified by the statement containing it.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Attributes {
+    /// The text of the documentation comment (see [`tree::DocComment::content`]).
+    pub documentation: Option<String>,
+    /// The annotations, in order.
+    pub annotations:   Vec<Annotation>,
+    /// Whether the definition is marked `private`.
+    pub private:       bool,
+}
+
+/// An annotation of a definition, like `@name argument`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Annotation {
+    /// The name of the annotation.
+    pub name:     String,
+    /// The argument of the annotation.
+    pub argument: Option<String>,
+    /// Whether the annotation is one interpreted by the compiler, like `@Builtin_Type`.
+    pub builtin:  bool,
+}
+
+
+
+// ==================
+// === Extraction ===
+// ==================
+
+/// Return the outline of the module. If the qualified name of the library containing the module is
+/// given, it is used to resolve the names in its imports and exports.
+pub fn extract(tree: &Tree, library: Option<&str>) -> Outline {
+    let mut outline = Outline::default();
+    let tree::Variant::BodyBlock(block) = &*tree.variant else { return outline };
+    for statement in statements(&block.statements) {
+        match &*statement.definition.variant {
+            tree::Variant::Private(tree::Private { body: None, .. }) => outline.private = true,
+            tree::Variant::Import(import) => {
+                let tree::Import { polyglot, from, import, all, as_, hiding } = import;
+                let polyglot = body(polyglot);
+                // The names of foreign entities are not relative to the library.
+                let library = if polyglot.is_some() { None } else { library };
+                let mut import = Import::new(library, from, import, all, hiding, statement.range);
+                import.polyglot = polyglot.map(|language| language.trimmed_code());
+                import.alias = body(as_).map(|alias| alias.trimmed_code());
+                outline.imports.push(import);
+            }
+            tree::Variant::Export(export) => {
+                let tree::Export { from, export, all, as_, hiding } = export;
+                let mut export = Import::new(library, from, export, all, hiding, statement.range);
+                export.alias = body(as_).map(|alias| alias.trimmed_code());
+                outline.exports.push(export);
+            }
+            tree::Variant::TypeDef(def) => outline.types.push(Type::new(def, statement)),
+            _ =>
+                if let Some(function) = Function::new(statement) {
+                    outline.functions.push(function);
+                },
+        }
+    }
+    outline
+}
+
+/// A statement of a block, without the documentation, annotations and visibility modifier applied
+/// to its definition.
+struct Statement<'s, 'a> {
+    definition: &'a Tree<'s>,
+    attributes: Attributes,
+    signature:  Option<String>,
+    range:      Range<Location>,
+}
+
+impl<'s, 'a> Statement<'s, 'a> {
+    fn new(statement: &'a Tree<'s>) -> Self {
+        let mut attributes = Attributes::default();
+        let mut definition = statement;
+        loop {
+            let inner = match &*definition.variant {
+                tree::Variant::Documented(documented) => {
+                    let content = documented.documentation.content();
+                    attributes.documentation.get_or_insert(content);
+                    &documented.expression
+                }
+                tree::Variant::Annotated(annotated) => {
+                    attributes.annotations.push(Annotation {
+                        name:     annotated.annotation.code.to_string(),
+                        argument: annotated.argument.as_ref().map(|arg| arg.trimmed_code()),
+                        builtin:  false,
+                    });
+                    &annotated.expression
+                }
+                tree::Variant::AnnotatedBuiltin(annotated) => {
+                    attributes.annotations.push(Annotation {
+                        name:     annotated.annotation.code.to_string(),
+                        argument: None,
+                        builtin:  true,
+                    });
+                    &annotated.expression
+                }
+                tree::Variant::Private(tree::Private { body: body @ Some(_), .. }) => {
+                    attributes.private = true;
+                    body
+                }
+                _ => &None,
+            };
+            match inner {
+                Some(inner) => definition = inner,
+                None => break,
+            }
+        }
+        let range = statement.span.range();
+        Self { definition, attributes, signature: None, range }
+    }
+
+    /// The name of the entity the statement defines, or declares the type of.
+    fn name(&self) -> Option<String> {
+        let name = match &*self.definition.variant {
+            tree::Variant::Function(function) => function.name.trimmed_code(),
+            tree::Variant::Assignment(assignment) => assignment.pattern.trimmed_code(),
+            tree::Variant::ForeignFunction(function) => function.name.code.to_string(),
+            tree::Variant::TypeSignature(signature) => signature.variable.trimmed_code(),
+            _ => return None,
+        };
+        Some(name)
+    }
+}
+
+/// The statements of the block, with each type signature merged into the following statement, if
+/// it defines the same name.
+fn statements<'s, 'a>(lines: &'a [tree::block::Line<'s>]) -> Vec<Statement<'s, 'a>> {
+    let mut statements = vec![];
+    let mut signature: Option<Statement> = None;
+    for expression in lines.iter().filter_map(|line| line.expression.as_ref()) {
+        let mut statement = Statement::new(expression);
+        if let tree::Variant::TypeSignature(_) = &*statement.definition.variant {
+            signature = Some(statement);
+            continue;
+        }
+        if let Some(signature) = signature.take()
+            && let tree::Variant::TypeSignature(declaration) = &*signature.definition.variant
+            && signature.name().is_some()
+            && signature.name() == statement.name()
+        {
+            let Attributes { documentation, annotations, private } = signature.attributes;
+            let attributes = &mut statement.attributes;
+            attributes.documentation = documentation.or(attributes.documentation.take());
+            attributes.annotations.splice(0..0, annotations);
+            attributes.private |= private;
+            statement.signature = Some(declaration.type_.trimmed_code());
+            statement.range.start = signature.range.start;
+        }
+        statements.push(statement);
+    }
+    statements
+}
+
+impl Import {
+    fn new(
+        library: Option<&str>,
+        from: &Option<tree::MultiSegmentAppSegment>,
+        segment: &tree::MultiSegmentAppSegment,
+        all: &Option<token::Ident>,
+        hiding: &Option<tree::MultiSegmentAppSegment>,
+        range: Range<Location>,
+    ) -> Self {
+        let (from, body, hiding) = (body(from), segment.body.as_ref(), body(hiding));
+        let (module, names) = match from {
+            Some(from) => {
+                let module = name(&from.trimmed_code(), library);
+                let names = body.map(list).unwrap_or_default();
+                let names = names.iter().map(|name| format!("{module}.{}", name.trimmed_code()));
+                let names = names.collect();
+                (module, names)
+            }
+            None => (body.map_or_else(default, |body| name(&body.trimmed_code(), library)), vec![]),
+        };
+        let hiding = hiding.map(list).unwrap_or_default();
+        let hiding =
+            hiding.iter().map(|name| format!("{module}.{}", name.trimmed_code())).collect();
+        let all = all.is_some();
+        Self { polyglot: None, module, names, all, hiding, alias: None, range }
+    }
+}
+
+impl Type {
+    fn new(def: &tree::TypeDef, statement: Statement) -> Self {
+        let mut constructors = vec![];
+        let mut methods = vec![];
+        for statement in statements(&def.body) {
+            match &*statement.definition.variant {
+                tree::Variant::ConstructorDefinition(constructor) =>
+                    constructors.push(Constructor::new(constructor, statement)),
+                _ =>
+                    if let Some(function) = Function::new(statement) {
+                        methods.push(function);
+                    },
+            }
+        }
+        Self {
+            name: def.name.code.to_string(),
+            params: def.params.iter().map(Argument::new).collect(),
+            constructors,
+            methods,
+            attributes: statement.attributes,
+            range: statement.range,
+            name_range: def.name.code.range(),
+        }
+    }
+}
+
+impl Constructor {
+    fn new(def: &tree::ConstructorDefinition, statement: Statement) -> Self {
+        let block = def.block.iter().filter_map(|line| line.argument.as_ref());
+        Self {
+            name:       def.constructor.code.to_string(),
+            arguments:  def.arguments.iter().chain(block).map(Argument::new).collect(),
+            attributes: statement.attributes,
+            range:      statement.range,
+            name_range: def.constructor.code.range(),
+        }
+    }
+}
+
+impl Function {
+    /// Describe the function the statement defines, if it defines one. An assignment to a name is
+    /// considered a definition of a function without arguments.
+    fn new(statement: Statement) -> Option<Self> {
+        let (name, arguments, returns, language, name_range) = match &*statement.definition.variant
+        {
+            tree::Variant::Function(function) => (
+                function.name.trimmed_code(),
+                function.args.iter().map(Argument::new).collect(),
+                function.returns.as_ref().map(|returns| returns.r#type.trimmed_code()),
+                None,
+                function.name.span.range(),
+            ),
+            tree::Variant::Assignment(assignment) if is_name(&assignment.pattern) => (
+                assignment.pattern.trimmed_code(),
+                vec![],
+                None,
+                None,
+                assignment.pattern.span.range(),
+            ),
+            tree::Variant::ForeignFunction(function) => (
+                function.name.code.to_string(),
+                function.args.iter().map(Argument::new).collect(),
+                None,
+                Some(function.language.code.to_string()),
+                function.name.code.range(),
+            ),
+            _ => return None,
+        };
+        let Statement { attributes, signature, range, .. } = statement;
+        Some(Self { name, arguments, returns, signature, language, attributes, range, name_range })
+    }
+}
+
+impl Argument {
+    fn new(arg: &tree::ArgumentDefinition) -> Self {
+        Self {
+            name:      arg.pattern.trimmed_code(),
+            type_:     arg.type_.as_ref().map(|type_| type_.type_.trimmed_code()),
+            default:   arg.default.as_ref().map(|default| default.expression.trimmed_code()),
+            suspended: arg.suspension.is_some(),
+            range:     arg.pattern.span.range(),
+        }
+    }
+}
+
+/// Resolve a qualified name relative to the library, if the library is known.
+fn name(name: &str, library: Option<&str>) -> String {
+    let name: String = name.split_whitespace().collect();
+    match (library, name.strip_prefix("project")) {
+        (Some(library), Some(rest)) if rest.is_empty() || rest.starts_with('.') =>
+            format!("{library}{rest}"),
+        _ => name,
+    }
+}
+
+/// The body of an optional segment of a statement, like the `from` segment of an import.
+fn body<'s, 'a>(segment: &'a Option<tree::MultiSegmentAppSegment<'s>>) -> Option<&'a Tree<'s>> {
+    segment.as_ref()?.body.as_ref()
+}
+
+/// The elements of a comma-separated list.
+fn list<'s, 'a>(tree: &'a Tree<'s>) -> Vec<&'a Tree<'s>> {
+    match &*tree.variant {
+        tree::Variant::OprApp(tree::OprApp { lhs: Some(lhs), opr: Ok(opr), rhs: Some(rhs) })
+            if opr.code.repr.0 == "," =>
+            list(lhs).into_iter().chain(list(rhs)).collect(),
+        _ => vec![tree],
+    }
+}
+
+/// Whether the tree is an identifier, or a qualified name such as `Type.method`.
+fn is_name(tree: &Tree) -> bool {
+    match &*tree.variant {
+        tree::Variant::Ident(_) => true,
+        tree::Variant::OprApp(tree::OprApp { lhs: Some(lhs), opr: Ok(opr), rhs: Some(rhs) }) =>
+            opr.code.repr.0 == "."
+                && is_name(lhs)
+                && matches!(&*rhs.variant, tree::Variant::Ident(_)),
+        _ => false,
+    }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outline(code: &str, library: Option<&str>) -> Outline {
+        extract(&crate::Parser::new().run(code), library)
+    }
+
+    fn names(functions: &[Function]) -> Vec<&str> {
+        functions.iter().map(|function| function.name.as_str()).collect()
+    }
+
+    #[test]
+    fn imports_and_exports() {
+        let code = [
+            "private",
+            "import Standard.Base.Data.Vector",
+            "import project.Data.Table as T",
+            "from Standard.Base import Vector, Text",
+            "from project.Internal import all hiding Helper, Other",
+            "polyglot java import org.example.Thing as Foreign_Thing",
+            "from project.Data.Table export Table",
+            "export project.Errors",
+        ]
+        .join("\n");
+        let outline = outline(&code, Some("Acme.Lib"));
+        assert!(outline.private);
+        let imports: Vec<_> = outline
+            .imports
+            .iter()
+            .map(|import| {
+                let Import { polyglot, module, names, all, hiding, alias, .. } = import;
+                (
+                    polyglot.as_deref(),
+                    module.as_str(),
+                    names.clone(),
+                    *all,
+                    hiding.clone(),
+                    alias.as_deref(),
+                )
+            })
+            .collect();
+        let strings =
+            |names: &[&str]| names.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+        assert_eq!(imports, [
+            (None, "Standard.Base.Data.Vector", vec![], false, vec![], None),
+            (None, "Acme.Lib.Data.Table", vec![], false, vec![], Some("T")),
+            (
+                None,
+                "Standard.Base",
+                strings(&["Standard.Base.Vector", "Standard.Base.Text"]),
+                false,
+                vec![],
+                None
+            ),
+            (
+                None,
+                "Acme.Lib.Internal",
+                vec![],
+                true,
+                strings(&["Acme.Lib.Internal.Helper", "Acme.Lib.Internal.Other"]),
+                None
+            ),
+            (Some("java"), "org.example.Thing", vec![], false, vec![], Some("Foreign_Thing")),
+        ]);
+        let exports: Vec<_> = outline
+            .exports
+            .iter()
+            .map(|export| (export.module.as_str(), export.names.clone()))
+            .collect();
+        assert_eq!(exports, [
+            ("Acme.Lib.Data.Table", strings(&["Acme.Lib.Data.Table.Table"])),
+            ("Acme.Lib.Errors", vec![]),
+        ]);
+        let unresolved = extract(&crate::Parser::new().run("import project.Data"), None);
+        assert_eq!(unresolved.imports[0].module, "project.Data");
+    }
+
+    #[test]
+    fn types() {
+        let code = [
+            "## A shape.",
+            "@Builtin_Type",
+            "type Shape a",
+            "    ## A circle.",
+            "    Circle (radius : Number = 1)",
+            "    Polygon",
+            "        points : Vector",
+            "        ~closed=True",
+            "",
+            "    ## The area of the shape.",
+            "    @precision (make_widget 2)",
+            "    area : Number",
+            "    area self (precision : Integer = 2) -> Number = 0",
+            "",
+            "    foreign js sides self = \"\"\"",
+            "        return 0",
+        ]
+        .join("\n");
+        let outline = outline(&code, None);
+        let [shape] = &outline.types[..] else { panic!("{outline:?}") };
+        assert_eq!(shape.name, "Shape");
+        assert_eq!(shape.attributes.documentation.as_deref(), Some(" A shape."));
+        assert_eq!(shape.attributes.annotations, [Annotation {
+            name:     "Builtin_Type".into(),
+            argument: None,
+            builtin:  true,
+        }]);
+        assert_eq!(shape.range.start.line, 0);
+        assert_eq!(shape.name_range.start.line, 2);
+        assert_eq!(shape.params[0].name, "a");
+        let [circle, polygon] = &shape.constructors[..] else { panic!("{shape:?}") };
+        assert_eq!(circle.name, "Circle");
+        assert_eq!(circle.attributes.documentation.as_deref(), Some(" A circle."));
+        assert_eq!(circle.arguments, [Argument {
+            name:      "radius".into(),
+            type_:     Some("Number".into()),
+            default:   Some("1".into()),
+            suspended: false,
+            range:     circle.arguments[0].range.clone(),
+        }]);
+        let arguments: Vec<_> = polygon
+            .arguments
+            .iter()
+            .map(|arg| {
+                (arg.name.as_str(), arg.type_.as_deref(), arg.default.as_deref(), arg.suspended)
+            })
+            .collect();
+        assert_eq!(arguments, [
+            ("points", Some("Vector"), None, false),
+            ("closed", None, Some("True"), true)
+        ]);
+        assert_eq!(names(&shape.methods), ["area", "sides"]);
+        let area = &shape.methods[0];
+        assert_eq!(area.signature.as_deref(), Some("Number"));
+        assert_eq!(area.returns.as_deref(), Some("Number"));
+        assert_eq!(area.attributes.documentation.as_deref(), Some(" The area of the shape."));
+        assert_eq!(area.attributes.annotations[0].name, "precision");
+        assert_eq!(area.attributes.annotations[0].argument.as_deref(), Some("(make_widget 2)"));
+        assert_eq!(area.range.start.line, 9);
+        assert_eq!(area.name_range.start.line, 12);
+        assert_eq!(area.arguments[1].default.as_deref(), Some("2"));
+        assert_eq!(shape.methods[1].language.as_deref(), Some("js"));
+    }
+
+    #[test]
+    fn functions() {
+        let code = [
+            "main = 42",
+            "Shape.perimeter self = 0",
+            "x : Integer",
+            "y = 1",
+            "add a b = a + b",
+            "(a, b) = pair",
+        ]
+        .join("\n");
+        let outline = outline(&code, None);
+        assert_eq!(names(&outline.functions), ["main", "Shape.perimeter", "y", "add"]);
+        assert_eq!(outline.functions[2].signature, None);
+        assert!(!outline.private);
+    }
+
+    #[test]
+    fn json() {
+        let outline = outline("## Doc\nfoo (x : Integer) = x", None);
+        let json = serde_json::to_value(outline).unwrap();
+        let function = &json["functions"][0];
+        assert_eq!(function["name"], "foo");
+        assert_eq!(function["documentation"], " Doc");
+        assert_eq!(function["arguments"][0]["type"], "Integer");
+        assert!(function.get("language").is_none());
+    }
+}
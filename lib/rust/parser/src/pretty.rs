@@ -0,0 +1,757 @@
+//! Canonical formatting of Enso source code.
+//!
+//! [`format`] re-emits a [`Tree`] in a canonical layout:
+//! - Blocks are indented by [`INDENT`] spaces per level. The block structure is determined by the
+//!   same rules as in the lexer, so a line that is indented inconsistently with the other lines of
+//!   its block is aligned with them.
+//! - Runs of spaces within a line are replaced by a single space; spaces after opening brackets,
+//!   and before closing brackets and commas, are removed. Whether there is space between two tokens
+//!   is never changed: in Enso, it affects the precedence of operators (see
+//!   [`crate::syntax::operator`]), so `a+b c` and `a + b c` are different expressions.
+//! - Trailing whitespace is removed. Consecutive blank lines are collapsed into one; blank lines at
+//!   the start of a block and between a documentation comment and the documented definition are
+//!   removed. The file ends with a single newline.
+//! - Consecutive imports at the top level of a module are sorted, as are consecutive exports.
+//! - The text of multiline text literals and documentation comments is preserved: their lines are
+//!   moved by the same number of columns as the line they start on.
+//!
+//! Formatting must not change the meaning of the code. [`verify`] checks that the result parses to
+//! a tree with the same structure as the input, and [`format_module`] formats only code that passes
+//! the check.
+
+use crate::prelude::*;
+
+use crate::metadata;
+use crate::metadata::IdMap;
+use crate::syntax::item;
+use crate::syntax::token;
+use crate::syntax::tree;
+use crate::syntax::tree::ItemVisitable;
+use crate::syntax::tree::ItemVisitor;
+use crate::syntax::Tree;
+use crate::Parser;
+
+use std::collections::HashMap;
+
+
+
+// =================
+// === Constants ===
+// =================
+
+/// The number of spaces each block is indented by, relative to its parent.
+pub const INDENT: usize = 4;
+
+
+
+// ==============
+// === Format ===
+// ==============
+
+/// Return the code of the tree, in the canonical layout. See the module documentation.
+pub fn format(tree: &Tree) -> String {
+    print(&sort_imports(tree))
+}
+
+/// Format the code of a module, which may end in a metadata section. The code is formatted only if
+/// the result is verified to have the same structure; the locations in the section's ID map are
+/// updated to refer to the same nodes in the formatted code.
+pub fn format_module(parser: &Parser, input: &str) -> Result<String, Mismatch> {
+    let (code, section) = match metadata::parse_section(input) {
+        Some((section, code)) => (code, Some(section)),
+        None => (input, None),
+    };
+    let original = parser.run(code);
+    let tree = sort_imports(&original);
+    let formatted = print(&tree);
+    let reparsed = parser.run(&formatted);
+    compare_formatted(&original, &reparsed)?;
+    // The IDs are remapped by the positions of the nodes, which requires the formatted code to have
+    // the structure of the sorted tree.
+    compare(&tree, &reparsed)?;
+    let without_newline = || formatted.strip_suffix('\n').unwrap_or(&formatted);
+    Ok(match section {
+        None => formatted.clone(),
+        Some(Ok(mut section)) => {
+            section.id_map = remap_ids(&tree, &reparsed, &section.id_map);
+            metadata::attach(without_newline(), Some(&section))
+        }
+        // The section can't be updated, but it must not be lost.
+        Some(Err(_)) => format!("{}{}", without_newline(), &input[code.len()..]),
+    })
+}
+
+/// Check that the given code, produced by formatting the tree, has the same structure: it must
+/// parse to the same nodes and tokens, differing only in whitespace, blank lines, and the order of
+/// imports that [`format`] sorts.
+pub fn verify(parser: &Parser, tree: &Tree, formatted: &str) -> Result<(), Mismatch> {
+    compare_formatted(tree, &parser.run(formatted))
+}
+
+
+// === Mismatch ===
+
+/// A difference between the structure of a tree and the structure of its formatted code.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mismatch {
+    /// Describes the first element of the original tree that was not reproduced.
+    pub expected: String,
+    /// Describes the element of the formatted code found in its place.
+    pub found:    String,
+}
+
+impl Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Formatting would change the code: expected {}, found {}.",
+            self.expected, self.found
+        )
+    }
+}
+
+impl std::error::Error for Mismatch {}
+
+
+
+// ================
+// === Elements ===
+// ================
+
+/// A node or token of a tree, in the order they occur in the source code.
+#[derive(Debug)]
+pub(crate) enum Element<'s, 'a> {
+    /// The start of a node; the elements of its children follow, up to the matching [`End`].
+    Tree(&'a Tree<'s>),
+    End,
+    Token(token::Ref<'s, 'a>),
+}
+
+pub(crate) fn elements<'s, 'a>(tree: &'a Tree<'s>) -> Vec<Element<'s, 'a>> {
+    let mut collector = Collector { elements: default() };
+    tree.visit_item(&mut collector);
+    collector.elements
+}
+
+struct Collector<'s, 'a> {
+    elements: Vec<Element<'s, 'a>>,
+}
+
+impl<'s, 'a> tree::Visitor for Collector<'s, 'a> {}
+impl<'s, 'a> ItemVisitor<'s, 'a> for Collector<'s, 'a> {
+    fn visit_item(&mut self, item: item::Ref<'s, 'a>) -> bool {
+        match item {
+            item::Ref::Tree(tree) => {
+                self.elements.push(Element::Tree(tree));
+                tree.variant.visit_item(self);
+                self.elements.push(Element::End);
+            }
+            item::Ref::Token(token) => self.elements.push(Element::Token(token)),
+        }
+        false
+    }
+}
+
+
+
+// ===============
+// === Printer ===
+// ===============
+
+fn print(tree: &Tree) -> String {
+    let mut printer = Printer::default();
+    let elements = elements(tree);
+    for (i, element) in elements.iter().enumerate() {
+        match element {
+            Element::Tree(tree) => printer.enter(tree),
+            Element::End => printer.exit(),
+            Element::Token(token) => {
+                // Within a text literal, a line break is followed by more text, unless it ends the
+                // literal.
+                let mut rest = elements[i + 1..].iter().filter(|e| !is_line_break(e));
+                let text_follows = matches!(rest.next(), Some(Element::Token(_)));
+                printer.token(*token, text_follows)
+            }
+        }
+    }
+    if !printer.out.is_empty() {
+        printer.out.push('\n');
+    }
+    printer.out
+}
+
+fn is_line_break(element: &Element) -> bool {
+    match element {
+        Element::Token(token) =>
+            matches!(token.data, token::Variant::Newline(_)) && !token.code.starts_with('#'),
+        _ => false,
+    }
+}
+
+/// The kind of a node the printer is inside.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Frame {
+    /// A text literal.
+    Text,
+    /// A documented expression, before the end of its documentation comment.
+    Doc,
+    Other,
+}
+
+/// Where the next token is, relative to the start of its line.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+enum Position {
+    /// The next token starts a line of code.
+    #[default]
+    Line,
+    /// The next token starts a line of a multiline text literal or documentation comment.
+    TextLine,
+    /// The next token follows another token on the same line.
+    Within,
+}
+
+#[derive(Debug)]
+struct Printer {
+    out:         String,
+    /// The whitespace before the next token.
+    space:       String,
+    /// The visible width of the whitespace before the next token.
+    space_width: usize,
+    position:    Position,
+    /// The number of line breaks before the next line of code.
+    newlines:    usize,
+    /// The original and canonical indentation of the enclosing blocks.
+    blocks:      Vec<(usize, usize)>,
+    /// The canonical indentation of the current line of code, minus its original indentation.
+    shift:       isize,
+    /// The nodes the next token is inside.
+    frames:      Vec<Frame>,
+    /// Whether the last line of code was the end of a documentation comment.
+    after_doc:   bool,
+    /// Whether the last token was an opening bracket.
+    after_open:  bool,
+}
+
+impl Default for Printer {
+    fn default() -> Self {
+        Self {
+            out:         default(),
+            space:       default(),
+            space_width: default(),
+            position:    default(),
+            newlines:    default(),
+            blocks:      vec![(0, 0)],
+            shift:       default(),
+            frames:      default(),
+            after_doc:   default(),
+            after_open:  default(),
+        }
+    }
+}
+
+impl Printer {
+    fn space(&mut self, offset: &crate::source::Offset) {
+        self.space.push_str(&offset.code);
+        self.space_width += offset.visible.width_in_spaces as usize;
+    }
+
+    fn clear_space(&mut self) {
+        self.space.clear();
+        self.space_width = 0;
+    }
+
+    fn enter(&mut self, tree: &Tree) {
+        if let Some(frame @ Frame::Doc) = self.frames.last_mut() {
+            // The documented expression follows the documentation.
+            *frame = Frame::Other;
+        }
+        self.frames.push(match &*tree.variant {
+            tree::Variant::TextLiteral(_) => Frame::Text,
+            tree::Variant::Documented(_) => Frame::Doc,
+            _ => Frame::Other,
+        });
+        self.space(&tree.span.left_offset);
+    }
+
+    fn exit(&mut self) {
+        self.frames.pop();
+    }
+
+    /// Whether the next token is part of a text literal or documentation comment.
+    fn in_text(&self) -> bool {
+        self.frames.iter().any(|frame| *frame != Frame::Other)
+    }
+
+    fn token(&mut self, token: token::Ref, text_follows: bool) {
+        use token::Variant;
+        self.space(token.left_offset);
+        let code: &str = token.code;
+        match token.data {
+            Variant::Newline(_) if !code.starts_with('#') => {
+                if self.in_text() && text_follows {
+                    self.out.push('\n');
+                    self.position = Position::TextLine;
+                } else if !code.is_empty() {
+                    if self.frames.last() == Some(&Frame::Doc) {
+                        self.after_doc = true;
+                    }
+                    self.newlines += 1;
+                    self.position = Position::Line;
+                }
+                self.clear_space();
+            }
+            // Empty tokens don't affect the layout; their whitespace belongs to the next token.
+            _ if code.is_empty() => (),
+            _ => {
+                self.separator(&token.data, code);
+                self.out.push_str(code);
+                self.clear_space();
+                self.position = Position::Within;
+                self.after_open = !self.in_text() && matches!(token.data, Variant::OpenSymbol(_));
+            }
+        }
+    }
+
+    /// Write the whitespace before a token.
+    fn separator(&mut self, variant: &token::Variant, code: &str) {
+        match self.position {
+            Position::Line => {
+                let (indent, opens_block) = self.indent(self.space_width);
+                self.shift = indent as isize - self.space_width as isize;
+                if !self.out.is_empty() {
+                    let blank_line = self.newlines > 1 && !opens_block && !self.after_doc;
+                    self.out.push_str(if blank_line { "\n\n" } else { "\n" });
+                }
+                self.push_spaces(indent);
+                self.newlines = 0;
+                self.after_doc = false;
+            }
+            Position::TextLine =>
+                if !self.space.is_empty() {
+                    let width = self.space_width as isize + self.shift;
+                    self.push_spaces(width.max(0) as usize);
+                },
+            Position::Within if self.in_text() => self.out.push_str(&self.space),
+            Position::Within => {
+                let is_close = matches!(variant, token::Variant::CloseSymbol(_));
+                let is_comma = matches!(variant, token::Variant::Operator(_)) && code == ",";
+                if !self.space.is_empty() && !self.after_open && !is_close && !is_comma {
+                    self.out.push(' ');
+                }
+            }
+        }
+    }
+
+    /// Return the canonical indentation of a line of code with the given original indentation, and
+    /// whether the line starts a new block. The lines of a block are the lines following the line
+    /// that opens it that are indented more than that line; see [`crate::lexer`].
+    fn indent(&mut self, width: usize) -> (usize, bool) {
+        let (current, canonical) = *self.blocks.last().unwrap();
+        if width > current {
+            let indent = canonical + INDENT;
+            self.blocks.push((width, indent));
+            return (indent, true);
+        }
+        while let [.., (parent, _), (current, _)] = self.blocks[..]
+            && width < current
+            && width <= parent
+        {
+            self.blocks.pop();
+        }
+        (self.blocks.last().unwrap().1, false)
+    }
+
+    fn push_spaces(&mut self, count: usize) {
+        self.out.extend(iter::repeat(' ').take(count));
+    }
+}
+
+
+
+// ===============
+// === Imports ===
+// ===============
+
+/// Sort each run of consecutive import statements at the top level of the module, and each run of
+/// consecutive export statements. Statements are ordered by the path of the module they refer to;
+/// polyglot imports follow the others. A comment at the end of a statement's line is moved with the
+/// statement.
+fn sort_imports<'s>(tree: &Tree<'s>) -> Tree<'s> {
+    let mut tree = tree.clone();
+    if let tree::Variant::BodyBlock(block) = &mut *tree.variant {
+        let lines = &mut block.statements;
+        for run in import_runs(lines) {
+            // The newline tokens that start the statements' lines are left in place; they don't
+            // contain comments.
+            let mut statements: Vec<_> = run
+                .iter()
+                .map(|statement| {
+                    let mut lines = lines[statement.clone()].iter_mut();
+                    let expression = lines.next().unwrap().expression.take().unwrap();
+                    let comment = lines.next().cloned();
+                    (expression, comment)
+                })
+                .collect();
+            statements.sort_by_cached_key(|(expression, _)| import_key(&print(expression)));
+            let start = run.first().unwrap().start;
+            let end = run.last().unwrap().end;
+            let mut newlines = run.iter().map(|statement| lines[statement.start].newline.clone());
+            let sorted: Vec<_> = statements
+                .into_iter()
+                .flat_map(|(expression, comment)| {
+                    let newline = newlines.next().unwrap();
+                    iter::once(tree::block::Line { newline, expression: Some(expression) })
+                        .chain(comment)
+                })
+                .collect();
+            lines.splice(start..end, sorted);
+        }
+    }
+    tree
+}
+
+/// Return the runs of statements that [`sort_imports`] sorts: runs of more than one consecutive
+/// import statement, or of more than one consecutive export statement. Each statement is given by
+/// the range of its lines: its own line, and the line of the comment at the end of its line, if
+/// any.
+fn import_runs(lines: &[tree::block::Line]) -> Vec<Vec<Range<usize>>> {
+    let kind = |line: &tree::block::Line| match line.expression.as_ref().map(|e| &*e.variant) {
+        Some(tree::Variant::Import(_)) => Some(true),
+        Some(tree::Variant::Export(_)) => Some(false),
+        _ => None,
+    };
+    // A comment is lexed as a newline token, which starts a line without an expression.
+    let is_comment =
+        |line: &tree::block::Line| line.expression.is_none() && line.newline.code.starts_with('#');
+    let mut runs = vec![];
+    let mut i = 0;
+    while i < lines.len() {
+        let Some(run_kind) = kind(&lines[i]) else {
+            i += 1;
+            continue;
+        };
+        let mut run = vec![];
+        while i < lines.len() && kind(&lines[i]) == Some(run_kind) {
+            let end = if lines.get(i + 1).is_some_and(is_comment) { i + 2 } else { i + 1 };
+            run.push(i..end);
+            i = end;
+        }
+        if run.len() > 1 {
+            runs.push(run);
+        }
+    }
+    runs
+}
+
+/// The sort key of an import or export statement, given its code.
+fn import_key(code: &str) -> (bool, String) {
+    let polyglot = code.strip_prefix("polyglot ");
+    let path = polyglot.unwrap_or(code).split_once(' ').map_or(code, |(_, path)| path);
+    (polyglot.is_some(), path.strip_prefix("import ").unwrap_or(path).to_owned())
+}
+
+
+
+// ====================
+// === Verification ===
+// ====================
+
+/// Check that the trees consist of the same nodes and tokens, disregarding line breaks.
+fn compare(expected: &Tree, found: &Tree) -> Result<(), Mismatch> {
+    compare_elements(&elements(expected), &elements(found))
+}
+
+fn compare_elements(expected: &[Element], found: &[Element]) -> Result<(), Mismatch> {
+    let mut expected = expected.iter().filter(|e| is_significant(e));
+    let mut found = found.iter().filter(|e| is_significant(e));
+    loop {
+        match (expected.next(), found.next()) {
+            (None, None) => return Ok(()),
+            (Some(a), Some(b)) if same_element(a, b) => (),
+            (a, b) => return Err(Mismatch { expected: describe(a), found: describe(b) }),
+        }
+    }
+}
+
+/// Check that the formatted tree consists of the same nodes and tokens as the original tree,
+/// disregarding line breaks; the statements of each run that [`sort_imports`] sorts may be
+/// reordered, but each must keep the comment at the end of its line.
+fn compare_formatted(original: &Tree, formatted: &Tree) -> Result<(), Mismatch> {
+    let original = parts(original);
+    let formatted = parts(formatted);
+    let mut formatted_parts = formatted.iter();
+    for expected in &original {
+        let found = formatted_parts.next();
+        match (expected, found) {
+            (Part::Elements(expected), Some(Part::Elements(found))) =>
+                compare_elements(expected, found)?,
+            (Part::Statements(expected), Some(Part::Statements(found)))
+                if expected.len() == found.len() =>
+            {
+                let mut unmatched: Vec<_> = found.iter().collect();
+                for statement in expected {
+                    let matching = unmatched
+                        .iter()
+                        .position(|candidate| compare_elements(statement, candidate).is_ok());
+                    match matching {
+                        Some(index) => {
+                            unmatched.swap_remove(index);
+                        }
+                        None =>
+                            return Err(Mismatch {
+                                expected: describe(statement.first()),
+                                found:    describe(unmatched.first().and_then(|s| s.first())),
+                            }),
+                    }
+                }
+            }
+            (expected, found) =>
+                return Err(Mismatch {
+                    expected: describe(expected.first()),
+                    found:    describe(found.and_then(Part::first)),
+                }),
+        }
+    }
+    match formatted_parts.next() {
+        None => Ok(()),
+        found => Err(Mismatch {
+            expected: describe(None),
+            found:    describe(found.and_then(Part::first)),
+        }),
+    }
+}
+
+/// A part of the elements of a tree, as compared by [`compare_formatted`].
+enum Part<'s, 'a> {
+    /// Elements that must occur in the same order.
+    Elements(Vec<Element<'s, 'a>>),
+    /// The statements of a run that [`sort_imports`] sorts, each followed by the comment at the
+    /// end of its line, if any.
+    Statements(Vec<Vec<Element<'s, 'a>>>),
+}
+
+impl<'s, 'a> Part<'s, 'a> {
+    fn first(&self) -> Option<&Element<'s, 'a>> {
+        match self {
+            Part::Elements(elements) => elements.first(),
+            Part::Statements(statements) => statements.first().and_then(|s| s.first()),
+        }
+    }
+}
+
+fn parts<'s, 'a>(tree: &'a Tree<'s>) -> Vec<Part<'s, 'a>> {
+    let tree::Variant::BodyBlock(block) = &*tree.variant else {
+        return vec![Part::Elements(elements(tree))];
+    };
+    let lines = &block.statements;
+    let line_elements = |line: &'a tree::block::Line<'s>| {
+        iter::once(Element::Token((&line.newline).into()))
+            .chain(line.expression.iter().flat_map(elements))
+    };
+    let mut parts = vec![];
+    let mut ordered = vec![Element::Tree(tree)];
+    let mut next = 0;
+    for run in import_runs(lines) {
+        let start = run.first().unwrap().start;
+        ordered.extend(lines[next..start].iter().flat_map(line_elements));
+        parts.push(Part::Elements(mem::take(&mut ordered)));
+        let statements = run.iter().map(|statement| {
+            let mut lines = lines[statement.clone()].iter();
+            let expression = lines.next().unwrap().expression.as_ref().unwrap();
+            let comment = lines.next().map(|line| Element::Token((&line.newline).into()));
+            elements(expression).into_iter().chain(comment).collect()
+        });
+        parts.push(Part::Statements(statements.collect()));
+        next = run.last().unwrap().end;
+    }
+    ordered.extend(lines[next..].iter().flat_map(line_elements));
+    ordered.push(Element::End);
+    parts.push(Part::Elements(ordered));
+    parts
+}
+
+/// Whether the element is compared by [`compare`]: line breaks are not, except for the comments
+/// that are represented by newline tokens.
+pub(crate) fn is_significant(element: &Element) -> bool {
+    match element {
+        Element::Token(token) => match token.data {
+            token::Variant::Newline(_) => token.code.starts_with('#'),
+            _ => true,
+        },
+        _ => true,
+    }
+}
+
+pub(crate) fn same_element(a: &Element, b: &Element) -> bool {
+    match (a, b) {
+        (Element::Tree(a), Element::Tree(b)) =>
+            mem::discriminant(&*a.variant) == mem::discriminant(&*b.variant),
+        (Element::End, Element::End) => true,
+        (Element::Token(a), Element::Token(b)) => {
+            let (a_code, b_code): (&str, &str) = (a.code, b.code);
+            mem::discriminant(&a.data) == mem::discriminant(&b.data) && a_code == b_code
+        }
+        _ => false,
+    }
+}
+
+pub(crate) fn describe(element: Option<&Element>) -> String {
+    match element {
+        Some(Element::Tree(tree)) => {
+            let variant = format!("{:?}", tree.variant);
+            let name = variant.split(|c: char| !c.is_alphanumeric()).next().unwrap_or_default();
+            let line = tree.span.range().start.line + 1;
+            format!("the start of a `{name}` node at line {line}")
+        }
+        Some(Element::End) => "the end of a node".into(),
+        Some(Element::Token(token)) => {
+            let code: &str = token.code;
+            let line = token.code.range().start.line + 1;
+            format!("`{code}` at line {line}")
+        }
+        None => "the end of the code".into(),
+    }
+}
+
+
+// === ID Map ===
+
+/// Given two trees with the same structure, return the ID map that identifies the nodes of `after`
+/// that correspond to the nodes identified in `before` by the given map.
+fn remap_ids(before: &Tree, after: &Tree, id_map: &IdMap) -> IdMap {
+    let spans = |tree| -> Vec<metadata::Span> {
+        let elements = elements(tree);
+        let trees = elements.into_iter().filter_map(|element| match element {
+            Element::Tree(tree) => Some(tree),
+            _ => None,
+        });
+        trees.map(metadata::Span::of_tree).collect()
+    };
+    let before = spans(before);
+    let after = spans(after);
+    let mut indices = HashMap::new();
+    for (index, span) in before.iter().enumerate() {
+        indices.entry(*span).or_insert(index);
+    }
+    let mut remapped = IdMap::default();
+    for (span, id) in id_map.iter() {
+        if let Some(&index) = indices.get(&span) {
+            remapped.insert(after[index], id);
+        }
+    }
+    remapped
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[track_caller]
+    fn test(input: &str, expected: &str) {
+        let parser = Parser::new();
+        let tree = parser.run(input);
+        let formatted = format(&tree);
+        assert_eq!(formatted, expected);
+        verify(&parser, &tree, &formatted).unwrap();
+        assert_eq!(format(&parser.run(&formatted)), formatted, "Formatting is not idempotent.");
+    }
+
+    #[test]
+    fn spacing() {
+        test("x  =   foo    bar", "x = foo bar\n");
+        test("x = a+b  *  c", "x = a+b * c\n");
+        test("f ( a  , b )  [1 ,2]", "f (a, b) [1,2]\n");
+        test("main =  # Comment.\n    x   # More.\n", "main = # Comment.\n    x # More.\n");
+        test("x = 'text  with   spaces'", "x = 'text  with   spaces'\n");
+    }
+
+    #[test]
+    fn indentation() {
+        test(
+            "main =\n  x = 1\n  y =\n        z\n  y",
+            "main =\n    x = 1\n    y =\n        z\n    y\n",
+        );
+        test("foo\n        a\n    b\n", "foo\n    a\n    b\n");
+        test("type T\n  A\n  B x\n", "type T\n    A\n    B x\n");
+    }
+
+    #[test]
+    fn blank_lines() {
+        test("\n\nfoo = 1\n\n\n\nbar = 2\n\n\n", "foo = 1\n\nbar = 2\n");
+        test("main =\n\n    x\n\n    y", "main =\n    x\n\n    y\n");
+        test("## Doc.\n\nfoo = 1", "## Doc.\nfoo = 1\n");
+    }
+
+    #[test]
+    fn multiline_text() {
+        let input = "main =\n  x = '''\n        abc\n          def\n  ## Doc\n       more\n  x";
+        let expected =
+            "main =\n    x = '''\n          abc\n            def\n    ## Doc\n         more\n    x\n";
+        test(input, expected);
+    }
+
+    #[test]
+    fn imports() {
+        let input =
+            "import Standard.Table\nfrom Standard.Base import all\nimport Standard.Base.Data\n\
+                     export project.B\nexport project.A\n\nimport Z";
+        let expected = "from Standard.Base import all\nimport Standard.Base.Data\n\
+                        import Standard.Table\nexport project.A\nexport project.B\n\nimport Z\n";
+        test(input, expected);
+        let input =
+            "polyglot java import java.util.List\nimport project.B\nfrom project.A import x";
+        let expected =
+            "from project.A import x\nimport project.B\npolyglot java import java.util.List\n";
+        test(input, expected);
+    }
+
+    #[test]
+    fn imports_with_comments() {
+        test("import B\nimport A # About A.\n", "import A # About A.\nimport B\n");
+        let input = "import C\nimport B # About B.\nimport A\n# Not about A.\nimport D";
+        let expected = "import A\nimport B # About B.\nimport C\n# Not about A.\nimport D\n";
+        test(input, expected);
+    }
+
+    #[test]
+    fn verify_detects_changes() {
+        let parser = Parser::new();
+        let tree = parser.run("x = f a+b");
+        assert!(verify(&parser, &tree, "x = f a+b").is_ok());
+        let error = verify(&parser, &tree, "x = f a + b").unwrap_err();
+        assert_eq!(error.expected, "the start of a `App` node at line 1");
+        assert_eq!(error.found, "the start of a `OprApp` node at line 1");
+        assert!(verify(&parser, &tree, "x = f a+c").is_err());
+    }
+
+    #[test]
+    fn verify_detects_moved_comments() {
+        let parser = Parser::new();
+        let tree = parser.run("import B\nimport A # About A.\n");
+        assert!(verify(&parser, &tree, "import A # About A.\nimport B\n").is_ok());
+        let error = verify(&parser, &tree, "import A\nimport B # About A.\n").unwrap_err();
+        assert_eq!(error.expected, "the start of a `Import` node at line 1");
+        assert!(verify(&parser, &tree, "import A # About A.\nimport C\n").is_err());
+    }
+
+    #[test]
+    fn module_with_metadata() {
+        use uuid::Uuid;
+        let id = Uuid::from_u64_pair(1, 2);
+        let mut section = metadata::Section::default();
+        section.id_map.insert(metadata::Span::new(14, 3), id);
+        let input = metadata::attach("main =\n  x =  foo", Some(&section));
+        let formatted = format_module(&Parser::new(), &input).unwrap();
+        let (section, code) = metadata::parse_section(&formatted).unwrap();
+        assert_eq!(code, "main =\n    x = foo");
+        let section = section.unwrap();
+        assert_eq!(section.id_map.get(metadata::Span::new(15, 3)), Some(id));
+        assert_eq!(section.id_map.len(), 1);
+    }
+}
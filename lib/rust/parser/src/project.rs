@@ -0,0 +1,399 @@
+//! Parsing all the source files of a project, in parallel.
+//!
+//! [`SourceFile::discover`] finds the Enso sources under a project root and loads them, and
+//! [`SourceFile::from_paths`] loads those named on a command line; [`parse`] then distributes them
+//! among worker threads, each using its own buffers with a shared [`Parser`]. For every file, the
+//! result includes its tree, the [`Diagnostic`]s found in it, and the time spent parsing it. A
+//! [`Library`] locates the sources of an Enso library and names its modules.
+
+use crate::prelude::*;
+
+use crate::diagnostics;
+use crate::diagnostics::Diagnostic;
+use crate::lexer;
+use crate::metadata;
+use crate::syntax::tree::error::Severity;
+use crate::syntax::Tree;
+use crate::Parser;
+
+use std::num::NonZeroUsize;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::Instant;
+
+
+
+// ==================
+// === SourceFile ===
+// ==================
+
+/// The extension of Enso source files.
+pub const EXTENSION: &str = "enso";
+
+/// The contents of a source file.
+#[derive(Clone, Debug)]
+pub struct SourceFile {
+    /// The path of the file.
+    pub path:     PathBuf,
+    /// The contents of the file, including its metadata section, if any.
+    pub contents: String,
+}
+
+impl SourceFile {
+    /// Find all the Enso source files under the given directory, and read them. Hidden directories,
+    /// such as `.enso`, are skipped. The files are ordered by path.
+    pub fn discover(root: impl AsRef<Path>) -> std::io::Result<Vec<Self>> {
+        let mut paths = vec![];
+        find_sources(root.as_ref(), &mut paths)?;
+        paths.sort();
+        paths.into_iter().map(Self::read).collect()
+    }
+
+    /// Read the sources named on a command line: each path is either a file, or a directory to
+    /// [`Self::discover`] sources in.
+    pub fn from_paths(
+        paths: impl IntoIterator<Item = impl Into<PathBuf>>,
+    ) -> std::io::Result<Vec<Self>> {
+        let mut sources = vec![];
+        for path in paths {
+            let path = path.into();
+            match path.is_dir() {
+                true => sources.extend(Self::discover(path)?),
+                false => sources.push(Self::read(path)?),
+            }
+        }
+        Ok(sources)
+    }
+
+    /// Read the file at the given path.
+    pub fn read(path: PathBuf) -> std::io::Result<Self> {
+        let contents = std::fs::read_to_string(&path)?;
+        Ok(Self { path, contents })
+    }
+
+    /// The code of the file, without its metadata section.
+    pub fn code(&self) -> &str {
+        metadata::extract(&self.contents).0
+    }
+}
+
+fn find_sources(dir: &Path, paths: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            if !entry.file_name().to_string_lossy().starts_with('.') {
+                find_sources(&path, paths)?;
+            }
+        } else if path.extension().is_some_and(|extension| extension == EXTENSION) {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+
+
+// ===============
+// === Library ===
+// ===============
+
+/// The name of the file describing a library, at its root.
+pub const PACKAGE_FILE: &str = "package.yaml";
+
+/// An Enso library: a directory containing a [`PACKAGE_FILE`] and the sources of its modules.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Library {
+    /// The root directory of the library.
+    pub root: PathBuf,
+    /// The qualified name of the library, `namespace.name`, if its package file specifies it.
+    pub name: Option<String>,
+}
+
+impl Library {
+    /// Open the library at the given root, reading its name from its package file, if present.
+    pub fn open(root: impl Into<PathBuf>) -> Self {
+        let root = root.into();
+        let name = std::fs::read_to_string(root.join(PACKAGE_FILE)).ok();
+        let name = name.as_deref().and_then(package_name);
+        Self { root, name }
+    }
+
+    /// Find the libraries in the given directory and its subdirectories, such as the versioned
+    /// libraries of a distribution (`lib/<namespace>/<name>/<version>`). The libraries are ordered
+    /// by path.
+    pub fn discover(dir: impl AsRef<Path>) -> std::io::Result<Vec<Self>> {
+        let mut roots = vec![];
+        find_libraries(dir.as_ref(), &mut roots)?;
+        roots.sort();
+        Ok(roots.into_iter().map(Self::open).collect())
+    }
+
+    /// The directory containing the sources of the library's modules: its `src` directory, or the
+    /// root if it has none.
+    pub fn sources_dir(&self) -> PathBuf {
+        let src = self.root.join("src");
+        if src.is_dir() {
+            src
+        } else {
+            self.root.clone()
+        }
+    }
+
+    /// The qualified name of the module at the given path, relative to the [`Self::sources_dir`].
+    /// The `Main` module at the root of the library is named by the library's name; if the name of
+    /// the library is unknown, module names are relative to the library.
+    pub fn module_name(&self, path: &Path) -> String {
+        let path = path.with_extension("");
+        let segments: Vec<_> = path.iter().map(|segment| segment.to_string_lossy()).collect();
+        match (&self.name, &segments[..]) {
+            (Some(library), [main]) if main == "Main" => library.clone(),
+            (Some(library), _) => format!("{library}.{}", segments.join(".")),
+            (None, _) => segments.join("."),
+        }
+    }
+}
+
+/// Read the qualified name of a library, `namespace.name`, from the contents of its package file.
+fn package_name(package: &str) -> Option<String> {
+    let field = |key: &str| {
+        let values = package.lines().filter_map(|line| line.strip_prefix(key)?.strip_prefix(':'));
+        values.map(|value| value.trim().trim_matches(['"', '\''])).next().map(str::to_owned)
+    };
+    Some(format!("{}.{}", field("namespace")?, field("name")?))
+}
+
+fn find_libraries(dir: &Path, roots: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    if dir.join(PACKAGE_FILE).is_file() {
+        roots.push(dir.to_owned());
+        return Ok(());
+    }
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() && !entry.file_name().to_string_lossy().starts_with('.') {
+            find_libraries(&entry.path(), roots)?;
+        }
+    }
+    Ok(())
+}
+
+
+
+// =============
+// === Parse ===
+// =============
+
+/// The result of parsing a [`SourceFile`].
+#[derive(Debug)]
+pub struct ParsedFile<'s> {
+    /// The path of the file.
+    pub path:        &'s Path,
+    /// The code that was parsed: the contents of the file, without its metadata section.
+    pub code:        &'s str,
+    /// The parsed code.
+    pub tree:        Tree<'s>,
+    /// The problems found in the code.
+    pub diagnostics: Vec<Diagnostic>,
+    /// The time spent parsing the code and collecting the diagnostics.
+    pub duration:    Duration,
+}
+
+impl<'s> ParsedFile<'s> {
+    /// Return whether any of the diagnostics is an error, rather than a warning.
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics.iter().any(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+}
+
+/// Timing information for a call to [`parse`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Statistics {
+    /// The number of files parsed.
+    pub files:        usize,
+    /// The total size of the parsed code, in bytes.
+    pub bytes:        usize,
+    /// The number of threads the files were distributed among.
+    pub threads:      usize,
+    /// The time from the start of parsing until all files were parsed.
+    pub elapsed:      Duration,
+    /// The sum of the times spent parsing each file.
+    pub parse_time:   Duration,
+    /// The file that took the most time to parse, and its duration.
+    pub slowest_file: Option<(PathBuf, Duration)>,
+}
+
+impl Display for Statistics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self { files, bytes, threads, elapsed, parse_time, .. } = self;
+        write!(f, "Parsed {files} files ({bytes} bytes) in {elapsed:?} using {threads} threads")?;
+        write!(f, "; {parse_time:?} spent parsing")?;
+        if let Some((path, duration)) = &self.slowest_file {
+            write!(f, "; slowest file: {} ({duration:?})", path.display())?;
+        }
+        Ok(())
+    }
+}
+
+/// The result of parsing a set of source files.
+#[derive(Debug)]
+pub struct Batch<'s> {
+    /// The parsed files, in the order of the inputs.
+    pub files:      Vec<ParsedFile<'s>>,
+    /// Timing information.
+    pub statistics: Statistics,
+}
+
+impl<'s> Batch<'s> {
+    /// Return whether any of the files has errors.
+    pub fn has_errors(&self) -> bool {
+        self.files.iter().any(ParsedFile::has_errors)
+    }
+}
+
+/// Return the number of threads [`parse`] should use by default: the available parallelism of the
+/// system.
+pub fn default_threads() -> NonZeroUsize {
+    std::thread::available_parallelism().unwrap_or(NonZeroUsize::MIN)
+}
+
+/// Parse the given files, distributing them among the given number of threads.
+pub fn parse<'s>(parser: &Parser, sources: &'s [SourceFile], threads: NonZeroUsize) -> Batch<'s> {
+    let threads = threads.get().min(sources.len()).max(1);
+    let start = Instant::now();
+    let next = AtomicUsize::new(0);
+    let worker = || {
+        let mut tokens = lexer::TokenBuffer::default();
+        let mut parsed = vec![];
+        loop {
+            let index = next.fetch_add(1, Ordering::Relaxed);
+            let Some(source) = sources.get(index) else { break };
+            parsed.push((index, parse_file(parser, source, &mut tokens)));
+        }
+        parsed
+    };
+    let mut files: Vec<_> = if threads == 1 {
+        worker()
+    } else {
+        std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads).map(|_| scope.spawn(worker)).collect();
+            workers.into_iter().flat_map(|worker| worker.join().unwrap()).collect()
+        })
+    };
+    let elapsed = start.elapsed();
+    files.sort_unstable_by_key(|(index, _)| *index);
+    let files: Vec<_> = files.into_iter().map(|(_, file)| file).collect();
+    let statistics = Statistics {
+        files: files.len(),
+        bytes: files.iter().map(|file| file.code.len()).sum(),
+        threads,
+        elapsed,
+        parse_time: files.iter().map(|file| file.duration).sum(),
+        slowest_file: files
+            .iter()
+            .max_by_key(|file| file.duration)
+            .map(|file| (file.path.to_owned(), file.duration)),
+    };
+    Batch { files, statistics }
+}
+
+fn parse_file<'s>(
+    parser: &Parser,
+    source: &'s SourceFile,
+    tokens: &mut lexer::TokenBuffer,
+) -> ParsedFile<'s> {
+    let start = Instant::now();
+    let code = source.code();
+    let tree = parser.run_with_buffer(code, tokens);
+    let diagnostics = diagnostics::collect(&tree);
+    let duration = start.elapsed();
+    ParsedFile { path: &source.path, code, tree, diagnostics, duration }
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(path: &str, contents: &str) -> SourceFile {
+        SourceFile { path: path.into(), contents: contents.into() }
+    }
+
+    #[test]
+    fn parser_is_shareable_between_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Parser>();
+    }
+
+    #[test]
+    fn parallel_results_match_sequential() {
+        let sources: Vec<_> = (0..16)
+            .map(|i| source(&format!("Main{i}.enso"), &format!("main =\n    x = {i}\n    foo x)")))
+            .chain([source("Meta.enso", "x = 1\n\n\n#### METADATA ####\n[[], {}]")])
+            .collect();
+        let parser = Parser::new();
+        let batch = parse(&parser, &sources, NonZeroUsize::new(4).unwrap());
+        assert_eq!(batch.statistics.files, sources.len());
+        assert_eq!(batch.statistics.threads, 4);
+        assert!(batch.has_errors());
+        for (file, source) in batch.files.iter().zip(&sources) {
+            assert_eq!(file.path, source.path);
+            assert_eq!(file.tree, parser.run(source.code()));
+            assert_eq!(file.diagnostics, diagnostics::collect(&file.tree));
+        }
+        let meta = batch.files.last().unwrap();
+        assert_eq!(meta.code, "x = 1");
+        assert!(!meta.has_errors());
+    }
+
+    #[test]
+    fn multithreaded_parse_matches_parser_run() {
+        let modules = [
+            "import Standard.Base.Data.Vector\n\ntype Maybe a\n    Some value\n    None\n",
+            "main =\n    xs = [1, 2, 3].map (+ 1)\n    IO.println 'Total: `xs.sum`'\n",
+            "f x y = case x of\n    0 -> y\n    _ -> f (x - 1) y\n",
+            "## Docs\nfoo : Integer -> Integer\nfoo n = bar (n +\n",
+            "",
+        ];
+        // Sources of different sizes, so that the token buffers of the workers are reused for
+        // inputs both longer and shorter than the ones they last held.
+        let sources: Vec<_> = (0..40)
+            .map(|i| {
+                let module = modules[i % modules.len()].repeat(1 + i % 7);
+                source(&format!("Module{i}.enso"), &module)
+            })
+            .collect();
+        let parser = Parser::new();
+        for threads in [2, 3, 8] {
+            let batch = parse(&parser, &sources, NonZeroUsize::new(threads).unwrap());
+            assert_eq!(batch.statistics.threads, threads);
+            assert_eq!(batch.files.len(), sources.len());
+            for (file, source) in batch.files.iter().zip(&sources) {
+                let expected = parser.run(source.code());
+                assert_eq!(file.path, source.path);
+                assert_eq!(file.tree, expected, "{}", source.path.display());
+                assert_eq!(file.diagnostics, diagnostics::collect(&expected));
+            }
+        }
+    }
+
+    #[test]
+    fn module_names() {
+        let package = "name: Table\nnamespace: 'Standard'\nversion: 0.0.0-dev\n";
+        let name = package_name(package);
+        assert_eq!(name.as_deref(), Some("Standard.Table"));
+        let library = Library { root: "Table".into(), name };
+        assert_eq!(library.module_name(Path::new("Main.enso")), "Standard.Table");
+        let module = library.module_name(Path::new("Data/Column.enso"));
+        assert_eq!(module, "Standard.Table.Data.Column");
+        let library = Library { root: "Table".into(), name: None };
+        assert_eq!(library.module_name(Path::new("Main.enso")), "Main");
+    }
+}
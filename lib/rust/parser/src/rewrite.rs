@@ -0,0 +1,636 @@
+//! Rewriting of source code that preserves its formatting.
+//!
+//! A [`Rewrite`] collects changes to a module that are expressed as operations on its [`Tree`]:
+//! replacing a node ([`Rewrite::replace`]), inserting a line into a block
+//! ([`Rewrite::insert_line`]), and removing an argument from a definition or an application
+//! ([`Rewrite::remove_argument`]). Each operation becomes a single [`Edit`] of the source code,
+//! which changes only the code the operation is concerned with; the comments and whitespace
+//! elsewhere are kept as they are.
+//!
+//! The whitespace before an element is stored as its left offset. When a node is built, its first
+//! child gives up its offset to the node (see [`Offset::take_as_prefix`]), so the whitespace
+//! before an element may be found in an ancestor; the operations account for this by computing
+//! the spans of the elements they remove with [`span::Builder`].
+//!
+//! [`Rewrite::verify`] checks that the rewritten code parses to the expected tree: the original
+//! tree, with the nodes and tokens of each replacement or inserted line, as parsed on its own, in
+//! place of the elements it replaced.
+
+use crate::prelude::*;
+
+use crate::incremental::Edit;
+use crate::pretty;
+use crate::pretty::Element;
+use crate::source::*;
+use crate::syntax::tree;
+use crate::syntax::Tree;
+use crate::Parser;
+
+
+
+// ===============
+// === Rewrite ===
+// ===============
+
+/// A set of changes to the code of a module. See the module documentation.
+#[derive(Debug)]
+pub struct Rewrite<'s, 't> {
+    code:     &'s str,
+    tree:     &'t Tree<'s>,
+    elements: Vec<Element<'s, 't>>,
+    /// For each element starting a node, the index of the element ending it.
+    ends:     Vec<usize>,
+    changes:  Vec<Change>,
+    snippets: Vec<Snippet>,
+}
+
+/// The effect of an operation, on the source code and on the elements of the tree.
+#[derive(Debug)]
+struct Change {
+    edit:    Edit,
+    splices: Vec<Splice>,
+}
+
+/// A range of the elements of the original tree, and the elements expected in its place.
+#[derive(Debug)]
+struct Splice {
+    removed:  Range<usize>,
+    inserted: Vec<Piece>,
+}
+
+#[derive(Debug)]
+enum Piece {
+    /// An element of the original tree, by index.
+    Element(usize),
+    /// The elements of a [`Snippet`], by index.
+    Snippet(usize),
+}
+
+/// Code introduced by an operation.
+#[derive(Debug)]
+enum Snippet {
+    /// Code that must parse to a single expression.
+    Expression(String),
+    /// Code consisting of any number of lines.
+    Lines(String),
+    /// Lines of the body of a type definition, which are parsed differently from other lines: a
+    /// constructor definition, for example, could be mistaken for an expression.
+    TypeBody(String),
+}
+
+impl<'s, 't> Rewrite<'s, 't> {
+    /// Start rewriting the tree, which must be the result of parsing the given code.
+    pub fn new(code: &'s str, tree: &'t Tree<'s>) -> Self {
+        let elements = pretty::elements(tree);
+        let mut ends: Vec<_> = (0..elements.len()).collect();
+        let mut open = vec![];
+        for (index, element) in elements.iter().enumerate() {
+            match element {
+                Element::Tree(_) => open.push(index),
+                Element::End => ends[open.pop().unwrap()] = index,
+                Element::Token(_) => {}
+            }
+        }
+        Self { code, tree, elements, ends, changes: default(), snippets: default() }
+    }
+
+    /// Replace the code of the node. The whitespace before the node is kept, and every line of the
+    /// code after the first is indented by the indentation of the line the node starts on.
+    pub fn replace(&mut self, node: &Tree<'s>, code: &str) -> Result<(), Error> {
+        let index = self.find(node)?;
+        let range = node.span.range();
+        let range = range.start.utf8 as usize..range.end.utf8 as usize;
+        let text = self.reindent(code, self.indentation(range.start));
+        let snippet = self.snippet(Snippet::Expression(code.to_owned()));
+        let removed = index..self.ends[index] + 1;
+        let splices = vec![Splice { removed, inserted: vec![snippet] }];
+        self.push(Change { edit: Edit::new(range, text), splices })
+    }
+
+    /// Insert a line into a block: before the line at the given index, or after the last line if
+    /// the index is the number of lines. A block is a [`tree::BodyBlock`], the argument block of a
+    /// [`tree::ArgumentBlockApplication`], or the body of a [`tree::TypeDef`]. The code may consist
+    /// of several lines, which are indented by the indentation of the block.
+    ///
+    /// Comments at the end of the line before the insertion point stay on that line. The lines of a
+    /// block that consist of such a comment are skipped when looking for the line to insert before.
+    pub fn insert_line(&mut self, block: &Tree<'s>, index: usize, code: &str) -> Result<(), Error> {
+        let block_index = self.find(block)?;
+        let lines = match &*block.variant {
+            tree::Variant::BodyBlock(block) => &block.statements,
+            tree::Variant::ArgumentBlockApplication(application) => &application.arguments,
+            tree::Variant::TypeDef(def) => &def.body,
+            _ => return Err(self.unsupported(block, "is not a block")),
+        };
+        if index > lines.len() {
+            let message = format!("Line {index} is out of bounds of a block of {}.", lines.len());
+            return Err(Error::Unsupported(message));
+        }
+        let first_expression = lines.iter().find_map(|line| line.expression.as_ref());
+        let indentation = match first_expression {
+            Some(expression) =>
+                self.indentation(expression.span.range().start.utf8 as usize).into(),
+            None if std::ptr::eq(block, self.tree) => String::new(),
+            None => {
+                let outer = self.indentation(block.span.range().start.utf8 as usize);
+                format!("{outer}{}", " ".repeat(pretty::INDENT))
+            }
+        };
+        let text = self.reindent(code, &indentation);
+        let line_ending = self.line_ending();
+        let snippet = match &*block.variant {
+            tree::Variant::TypeDef(_) => Snippet::TypeBody(code.to_owned()),
+            _ => Snippet::Lines(code.to_owned()),
+        };
+        let snippet = self.snippet(snippet);
+        // A newline token that does not end with a line break holds a comment, which belongs to
+        // the line before the next line break.
+        let starts_line = |code: &str| code.is_empty() || code.ends_with(['\n', '\r']);
+        let next_line = lines[index..].iter().find(|line| starts_line(&line.newline.code.repr));
+        let change = match next_line {
+            Some(line) => {
+                let position = line.newline.code.range().end.utf8 as usize;
+                let edit =
+                    Edit::new(position..position, format!("{indentation}{text}{line_ending}"));
+                let newline = (block_index..self.ends[block_index]).find(|index| {
+                    matches!(&self.elements[*index],
+                        Element::Token(token) if std::ptr::eq(token.code, &line.newline.code))
+                });
+                let newline = newline.unwrap();
+                let splices = vec![Splice { removed: newline..newline, inserted: vec![snippet] }];
+                Change { edit, splices }
+            }
+            None => {
+                let last_expression = lines.iter().rev().find_map(|line| line.expression.as_ref());
+                let end = last_expression.unwrap_or(block).span.range().end.utf8 as usize;
+                let rest = &self.code[end..];
+                let (rest_of_line, position, text) = match rest.find(['\n', '\r']) {
+                    Some(length) => {
+                        let line_break = if rest[length..].starts_with("\r\n") { 2 } else { 1 };
+                        let text = format!("{indentation}{text}{line_ending}");
+                        (&rest[..length], end + length + line_break, text)
+                    }
+                    None => (rest, self.code.len(), format!("{line_ending}{indentation}{text}")),
+                };
+                let rest_of_line = rest_of_line.trim_start();
+                if !rest_of_line.is_empty() && !rest_of_line.starts_with('#') {
+                    return Err(self.unsupported(block, "is followed by code on the same line"));
+                }
+                // The comment at the end of the last line is now followed by a line of the block,
+                // so it becomes a part of the block, as do the line breaks before the new line.
+                let block_end = self.ends[block_index];
+                let mut tokens = vec![];
+                let mut ends = vec![];
+                for (index, element) in self.elements.iter().enumerate().skip(block_end) {
+                    match element {
+                        Element::End => ends.push(Piece::Element(index)),
+                        Element::Token(token)
+                            if (token.code.range().start.utf8 as usize) < position =>
+                            tokens.push(Piece::Element(index)),
+                        _ => break,
+                    }
+                }
+                let removed = block_end..block_end + tokens.len() + ends.len();
+                let inserted = tokens.into_iter().chain([snippet]).chain(ends).collect();
+                let splices = vec![Splice { removed, inserted }];
+                Change { edit: Edit::new(position..position, text), splices }
+            }
+        };
+        self.push(change)
+    }
+
+    /// Remove an argument, with the whitespace before it. The node is either a
+    /// [`tree::Function`], whose argument definitions are counted, or an application: in the chain
+    /// of [`tree::App`] and [`tree::NamedApp`] nodes starting at the node, the arguments are
+    /// counted from the innermost application, so that in `f a b`, `a` is the argument at index 0.
+    ///
+    /// Removing the last argument of a function definition turns it into an assignment, which
+    /// [`Rewrite::verify`] reports as a mismatch.
+    pub fn remove_argument(&mut self, node: &Tree<'s>, index: usize) -> Result<(), Error> {
+        let node_index = self.find(node)?;
+        let out_of_bounds = |count: usize| {
+            let message = format!("Argument {index} is out of bounds of {count} arguments.");
+            Error::Unsupported(message)
+        };
+        if let tree::Variant::Function(function) = &*node.variant {
+            let argument =
+                function.args.get(index).ok_or_else(|| out_of_bounds(function.args.len()))?;
+            // The span of the argument includes the left offset of its first element, which may
+            // have been taken by a node the element starts.
+            let span = Span::empty_without_offset().add(&mut argument.clone());
+            let start = span.left_offset.code.range().start.utf8 as usize;
+            let code_range = span.range();
+            let (code_start, code_end) = (code_range.start.utf8, code_range.end.utf8);
+            let element_start = |element: &Element| match element {
+                Element::Tree(tree) => Some(tree.span.range().start.utf8),
+                Element::Token(token) => Some(token.code.range().start.utf8),
+                Element::End => None,
+            };
+            let function_end = self.ends[node_index];
+            let first = (node_index + 1..function_end)
+                .find(|index| element_start(&self.elements[*index]) >= Some(code_start))
+                .unwrap();
+            let after = (first + 1..function_end)
+                .find(|index| element_start(&self.elements[*index]) >= Some(code_end))
+                .unwrap_or(function_end);
+            let edit = Edit::new(start..code_end as usize, "");
+            let splices = vec![Splice { removed: first..after, inserted: vec![] }];
+            return self.push(Change { edit, splices });
+        }
+        let mut applications = vec![];
+        let mut current = node;
+        while let tree::Variant::App(tree::App { func, .. })
+        | tree::Variant::NamedApp(tree::NamedApp { func, .. }) = &*current.variant
+        {
+            applications.push(current);
+            current = func;
+        }
+        if applications.is_empty() {
+            return Err(
+                self.unsupported(node, "is neither a function definition nor an application")
+            );
+        }
+        let count = applications.len();
+        if index >= count {
+            return Err(out_of_bounds(count));
+        }
+        // Each application starts with the application of its function.
+        let application_index = node_index + count - 1 - index;
+        let application = applications[count - 1 - index];
+        let (tree::Variant::App(tree::App { func, .. })
+        | tree::Variant::NamedApp(tree::NamedApp { func, .. })) = &*application.variant
+        else {
+            unreachable!()
+        };
+        // Everything after the function of the application belongs to the argument.
+        let start = func.span.range().end.utf8 as usize;
+        let end = application.span.range().end.utf8 as usize;
+        let func_end = self.ends[application_index + 1];
+        let application_end = self.ends[application_index];
+        let splices = vec![
+            Splice { removed: application_index..application_index + 1, inserted: vec![] },
+            Splice { removed: func_end + 1..application_end + 1, inserted: vec![] },
+        ];
+        self.push(Change { edit: Edit::new(start..end, ""), splices })
+    }
+
+    /// Return the edits of the source code, ordered by their position. Their ranges refer to the
+    /// original code, and do not overlap.
+    pub fn edits(&self) -> Vec<Edit> {
+        let mut edits: Vec<_> = self.changes.iter().map(|change| change.edit.clone()).collect();
+        edits.sort_by_key(|edit| (edit.range.start, edit.range.end));
+        edits
+    }
+
+    /// Return the rewritten code.
+    pub fn apply(&self) -> String {
+        let mut code = String::with_capacity(self.code.len());
+        let mut position = 0;
+        for edit in self.edits() {
+            code.push_str(&self.code[position..edit.range.start]);
+            code.push_str(&edit.text);
+            position = edit.range.end;
+        }
+        code.push_str(&self.code[position..]);
+        code
+    }
+
+    /// Return the rewritten code, if it parses to the expected tree. See the module documentation.
+    pub fn verify(&self, parser: &Parser) -> Result<String, Error> {
+        let code = self.apply();
+        let snippet_sources: Vec<_> = self.snippets.iter().map(Snippet::source).collect();
+        let snippet_trees: Vec<_> = snippet_sources.iter().map(|code| parser.run(code)).collect();
+        let snippet_elements = self.snippets.iter().zip(&snippet_trees);
+        let snippet_elements: Vec<_> = snippet_elements
+            .map(|(snippet, tree)| snippet.elements(tree))
+            .collect::<Result<_, _>>()?;
+        let mut splices: Vec<_> = self.changes.iter().flat_map(|change| &change.splices).collect();
+        splices.sort_by_key(|splice| (splice.removed.start, splice.removed.end));
+        let mut expected: Vec<&Element> = vec![];
+        let mut position = 0;
+        for splice in splices {
+            expected.extend(&self.elements[position..splice.removed.start]);
+            for piece in &splice.inserted {
+                match piece {
+                    Piece::Element(index) => expected.push(&self.elements[*index]),
+                    Piece::Snippet(index) => expected.extend(&snippet_elements[*index]),
+                }
+            }
+            position = splice.removed.end;
+        }
+        expected.extend(&self.elements[position..]);
+        let result = {
+            let tree = parser.run(&code);
+            let found = pretty::elements(&tree);
+            let mut expected = expected.into_iter().filter(|e| pretty::is_significant(e));
+            let mut found = found.iter().filter(|e| pretty::is_significant(e));
+            loop {
+                match (expected.next(), found.next()) {
+                    (None, None) => break Ok(()),
+                    (Some(a), Some(b)) if pretty::same_element(a, b) => (),
+                    (a, b) => {
+                        let (expected, found) = (pretty::describe(a), pretty::describe(b));
+                        break Err(Error::Mismatch { expected, found });
+                    }
+                }
+            }
+        };
+        result.map(|()| code)
+    }
+
+    fn push(&mut self, change: Change) -> Result<(), Error> {
+        let range = &change.edit.range;
+        let overlaps = |other: &Change| {
+            let other = &other.edit.range;
+            // Insertions at the same position could be applied in either order.
+            let same_insertion = range.is_empty() && *other == *range;
+            (range.start < other.end && other.start < range.end) || same_insertion
+        };
+        if self.changes.iter().any(overlaps) {
+            return Err(Error::Overlap);
+        }
+        self.changes.push(change);
+        Ok(())
+    }
+
+    fn snippet(&mut self, snippet: Snippet) -> Piece {
+        self.snippets.push(snippet);
+        Piece::Snippet(self.snippets.len() - 1)
+    }
+
+    /// Return the index of the element starting the node.
+    fn find(&self, node: &Tree<'s>) -> Result<usize, Error> {
+        let is_node =
+            |element: &Element| matches!(element, Element::Tree(tree) if std::ptr::eq(*tree, node));
+        self.elements.iter().position(is_node).ok_or(Error::NotFound)
+    }
+
+    /// The whitespace at the start of the line containing the position.
+    fn indentation(&self, position: usize) -> &'s str {
+        let line_start = self.code[..position].rfind(['\n', '\r']).map_or(0, |index| index + 1);
+        let line = &self.code[line_start..];
+        &line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+    }
+
+    /// Indent the lines of the code after the first.
+    fn reindent(&self, code: &str, indentation: &str) -> String {
+        let mut lines = code.lines();
+        let first = lines.next().unwrap_or_default().to_owned();
+        let rest = lines.map(|line| match line.is_empty() {
+            true => String::new(),
+            false => format!("{indentation}{line}"),
+        });
+        iter::once(first).chain(rest).collect::<Vec<_>>().join(self.line_ending())
+    }
+
+    fn line_ending(&self) -> &'static str {
+        if self.code.contains("\r\n") {
+            "\r\n"
+        } else {
+            "\n"
+        }
+    }
+
+    fn unsupported(&self, node: &Tree, reason: &str) -> Error {
+        let line = node.span.range().start.line + 1;
+        Error::Unsupported(format!("The node at line {line} {reason}."))
+    }
+}
+
+impl Snippet {
+    /// The code to parse the snippet from.
+    fn source(&self) -> Cow<str> {
+        match self {
+            Snippet::Expression(code) | Snippet::Lines(code) => code.into(),
+            Snippet::TypeBody(code) => {
+                let indent = " ".repeat(pretty::INDENT);
+                let lines = code.lines().map(|line| format!("\n{indent}{line}"));
+                iter::once("type T".to_owned()).chain(lines).collect::<String>().into()
+            }
+        }
+    }
+
+    /// Return the elements the snippet is expected to be represented by, given its tree.
+    fn elements<'s, 'a>(&self, tree: &'a Tree<'s>) -> Result<Vec<Element<'s, 'a>>, Error> {
+        let statements = match &*tree.variant {
+            tree::Variant::BodyBlock(block) => &block.statements[..],
+            _ => &[],
+        };
+        let mut expressions = statements.iter().filter_map(|line| line.expression.as_ref());
+        let expression = match (expressions.next(), expressions.next()) {
+            (Some(expression), None) => Some(expression),
+            _ => None,
+        };
+        match self {
+            Snippet::Expression(code) => match expression {
+                Some(expression) => Ok(pretty::elements(expression)),
+                None => Err(Error::InvalidCode(format!("`{code}` is not a single expression."))),
+            },
+            Snippet::Lines(_) => {
+                let mut elements = pretty::elements(tree);
+                elements.pop();
+                elements.remove(0);
+                Ok(elements)
+            }
+            Snippet::TypeBody(code) => match expression {
+                Some(definition) if matches!(&*definition.variant, tree::Variant::TypeDef(_)) => {
+                    let mut elements = pretty::elements(definition);
+                    // The body follows the start of the definition, and its `type` and name
+                    // tokens.
+                    elements.pop();
+                    Ok(elements.split_off(3))
+                }
+                _ => {
+                    let message = format!("`{code}` is not the body of a type definition.");
+                    Err(Error::InvalidCode(message))
+                }
+            },
+        }
+    }
+}
+
+
+
+// === Error ===
+
+/// An operation of a [`Rewrite`] that could not be performed, or a rewritten module that does not
+/// have the expected structure.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The node is not a part of the tree being rewritten.
+    NotFound,
+    /// The operation does not apply to the node.
+    Unsupported(String),
+    /// The change affects code already affected by another change.
+    Overlap,
+    /// Code given to an operation does not parse as the operation requires.
+    InvalidCode(String),
+    /// The rewritten code does not parse to the expected tree.
+    Mismatch {
+        /// Describes the first element of the expected tree that was not found.
+        expected: String,
+        /// Describes the element found in its place.
+        found:    String,
+    },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NotFound => write!(f, "The node is not a part of the tree being rewritten."),
+            Error::Unsupported(message) | Error::InvalidCode(message) => write!(f, "{message}"),
+            Error::Overlap => write!(f, "The change overlaps another change."),
+            Error::Mismatch { expected, found } =>
+                write!(f, "The rewritten code has an unexpected structure: expected {expected}, found {found}."),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Return the nodes whose code, excluding their left offsets, is the given text.
+    fn nodes<'s, 't>(tree: &'t Tree<'s>, code: &str, text: &str) -> Vec<&'t Tree<'s>> {
+        let elements = pretty::elements(tree).into_iter();
+        let trees = elements.filter_map(|element| match element {
+            Element::Tree(tree) => Some(tree),
+            _ => None,
+        });
+        let range = |tree: &Tree| {
+            let range = tree.span.range();
+            range.start.utf8 as usize..range.end.utf8 as usize
+        };
+        trees.filter(|tree| &code[range(tree)] == text).collect()
+    }
+
+    #[test]
+    fn rename() {
+        let code = "type T\n    ## Doc.\n    foo self x = x  # Identity.\n\nmain =\n    t = T\n    t.foo 1\n";
+        let parser = Parser::new();
+        let tree = parser.run(code);
+        let mut rewrite = Rewrite::new(code, &tree);
+        let names = nodes(&tree, code, "foo");
+        assert_eq!(names.len(), 2);
+        for name in names {
+            rewrite.replace(name, "identity").unwrap();
+        }
+        assert_eq!(rewrite.edits(), [Edit::new(23..26, "identity"), Edit::new(75..78, "identity")]);
+        let expected =
+            "type T\n    ## Doc.\n    identity self x = x  # Identity.\n\nmain =\n    t = T\n    t.identity 1\n";
+        assert_eq!(rewrite.verify(&parser).unwrap(), expected);
+    }
+
+    #[test]
+    fn replace() {
+        #[track_caller]
+        fn test(code: &str, node: &str, replacement: &str, expected: &str) {
+            let parser = Parser::new();
+            let tree = parser.run(code);
+            let mut rewrite = Rewrite::new(code, &tree);
+            rewrite.replace(nodes(&tree, code, node)[0], replacement).unwrap();
+            assert_eq!(rewrite.verify(&parser).unwrap(), expected);
+        }
+        test("f (x = 1) y = x", "1", "2+3", "f (x = 2+3) y = x");
+        // The left offset of `a` was taken by the application, and is kept.
+        test("main =\n    a b\n", "a", "c.d", "main =\n    c.d b\n");
+        test(
+            "main =\n    x = 1\n",
+            "1",
+            "case y of\n    _ -> 2",
+            "main =\n    x = case y of\n        _ -> 2\n",
+        );
+    }
+
+    #[test]
+    fn insert_line() {
+        #[track_caller]
+        fn test(code: &str, block: Option<&str>, index: usize, line: &str, expected: &str) {
+            let parser = Parser::new();
+            let tree = parser.run(code);
+            let mut rewrite = Rewrite::new(code, &tree);
+            let block = block.map_or(&tree, |block| nodes(&tree, code, block)[0]);
+            rewrite.insert_line(block, index, line).unwrap();
+            assert_eq!(rewrite.verify(&parser).unwrap(), expected);
+        }
+        test("main = 1\n", None, 0, "import Standard.Base", "import Standard.Base\nmain = 1\n");
+        test("import A\n\nmain = 1", None, 1, "import B", "import A\nimport B\n\nmain = 1");
+        test("main = 1", None, 1, "foo = 2", "main = 1\nfoo = 2");
+        let code = "type T\n    A\n    f self = 1 # One.\n\nmain = 0";
+        let expected = "type T\n    A\n    f self = 1 # One.\n    g self =\n        2\n\nmain = 0";
+        test(code, Some("type T\n    A\n    f self = 1"), 2, "g self =\n    2", expected);
+        let code = "main =  # Start.\n    x = 1\n    x";
+        let expected = "main =  # Start.\n    y = 2\n    x = 1\n    x";
+        test(code, Some("# Start.\n    x = 1\n    x"), 0, "y = 2", expected);
+        test("main =\n    x", Some("\n    x"), 1, "y", "main =\n    x\n    y");
+        test("type T\n\nmain = 0", Some("type T"), 0, "A", "type T\n    A\n\nmain = 0");
+    }
+
+    #[test]
+    fn remove_argument() {
+        #[track_caller]
+        fn test(code: &str, node: &str, index: usize, expected: &str) {
+            let parser = Parser::new();
+            let tree = parser.run(code);
+            let mut rewrite = Rewrite::new(code, &tree);
+            // The innermost node, as a function definition may span the whole module.
+            rewrite.remove_argument(nodes(&tree, code, node).last().unwrap(), index).unwrap();
+            assert_eq!(rewrite.verify(&parser).unwrap(), expected);
+        }
+        test("foo a b c = a + c", "foo a b c = a + c", 1, "foo a c = a + c");
+        test("foo a (b = 1) = a", "foo a (b = 1) = a", 1, "foo a = a");
+        test("foo (a : Integer) b = b", "foo (a : Integer) b = b", 0, "foo b = b");
+        test("x = foo 1 2 3", "foo 1 2 3", 0, "x = foo 2 3");
+        test("x = foo 1 (b = 2)", "foo 1 (b = 2)", 1, "x = foo 1");
+        // The left offset of the innermost application was taken by the outermost.
+        test("main =\n    foo 1  2\n", "foo 1  2", 0, "main =\n    foo  2\n");
+    }
+
+    #[test]
+    fn errors() {
+        let code = "x = a * b\ny = c";
+        let parser = Parser::new();
+        let tree = parser.run(code);
+        let other_tree = parser.run(code);
+        let node = |text| nodes(&tree, code, text)[0];
+
+        let mut rewrite = Rewrite::new(code, &tree);
+        rewrite.replace(node("a"), "a + 1").unwrap();
+        assert!(matches!(rewrite.verify(&parser), Err(Error::Mismatch { .. })));
+        assert_eq!(rewrite.replace(node("a * b"), "d"), Err(Error::Overlap));
+
+        let mut rewrite = Rewrite::new(code, &tree);
+        rewrite.replace(node("c"), "c\nd").unwrap();
+        assert!(matches!(rewrite.verify(&parser), Err(Error::InvalidCode(_))));
+
+        let mut rewrite = Rewrite::new(code, &tree);
+        assert_eq!(rewrite.replace(nodes(&other_tree, code, "a")[0], "d"), Err(Error::NotFound));
+        assert!(matches!(rewrite.insert_line(node("a"), 0, "d"), Err(Error::Unsupported(_))));
+        assert!(matches!(rewrite.remove_argument(node("a * b"), 0), Err(Error::Unsupported(_))));
+        assert!(matches!(rewrite.insert_line(&tree, 3, "d"), Err(Error::Unsupported(_))));
+        assert_eq!(rewrite.edits(), []);
+
+        let mut rewrite = Rewrite::new(code, &tree);
+        rewrite.insert_line(&tree, 1, "z = 0").unwrap();
+        assert_eq!(rewrite.insert_line(&tree, 1, "w = 0"), Err(Error::Overlap));
+        rewrite.insert_line(&tree, 2, "w = 0").unwrap();
+        assert_eq!(rewrite.edits().len(), 2);
+
+        // A line break that is not a line feed ends the body of the type.
+        let code = "type T\n    A";
+        let tree = parser.run(code);
+        let mut rewrite = Rewrite::new(code, &tree);
+        let is_type = |node: &&Tree| matches!(&*node.variant, tree::Variant::TypeDef(_));
+        let def = nodes(&tree, code, code).into_iter().find(is_type).unwrap();
+        rewrite.insert_line(def, 1, "x\r= 5").unwrap();
+        assert!(matches!(rewrite.verify(&parser), Err(Error::InvalidCode(_))));
+    }
+}
@@ -0,0 +1,665 @@
+//! Classification of source code for syntax highlighting.
+//!
+//! [`collect`] returns the tokens of a [`Tree`] that should be highlighted, each with the
+//! [`Kind`] of entity it refers to and [`Modifiers`] describing its role, in the terms of the
+//! semantic tokens of the Language Server Protocol. Unlike a classification of the raw
+//! [`token::Variant`]s, the result takes into account the syntactic context of each token: it
+//! distinguishes e.g. the name of a method in its definition from a reference to a variable, and
+//! identifies the keywords of built-in macros, which the lexer reports as ordinary identifiers.
+//!
+//! [`encode`] converts the result to the relative encoding used by the protocol; the indices of
+//! token types and modifiers refer to [`Kind::ALL`] and [`Modifier::ALL`].
+
+use crate::prelude::*;
+
+use crate::source::code::Length;
+use crate::source::code::Location;
+use crate::syntax::item;
+use crate::syntax::token;
+use crate::syntax::tree;
+use crate::syntax::tree::ItemVisitable;
+use crate::syntax::tree::ItemVisitor;
+use crate::syntax::Tree;
+
+use std::collections::HashMap;
+
+
+
+// ============
+// === Kind ===
+// ============
+
+/// The kind of entity a token refers to, corresponding to a standard LSP semantic token type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum Kind {
+    Namespace,
+    Type,
+    TypeParameter,
+    Parameter,
+    Variable,
+    Property,
+    EnumMember,
+    Function,
+    Method,
+    Keyword,
+    Comment,
+    String,
+    Number,
+    Operator,
+    Decorator,
+}
+
+impl Kind {
+    /// All token types, in the order of their indices in the LSP encoding.
+    pub const ALL: [Kind; 15] = [
+        Kind::Namespace,
+        Kind::Type,
+        Kind::TypeParameter,
+        Kind::Parameter,
+        Kind::Variable,
+        Kind::Property,
+        Kind::EnumMember,
+        Kind::Function,
+        Kind::Method,
+        Kind::Keyword,
+        Kind::Comment,
+        Kind::String,
+        Kind::Number,
+        Kind::Operator,
+        Kind::Decorator,
+    ];
+
+    /// The name of the token type in the LSP.
+    pub fn name(self) -> &'static str {
+        match self {
+            Kind::Namespace => "namespace",
+            Kind::Type => "type",
+            Kind::TypeParameter => "typeParameter",
+            Kind::Parameter => "parameter",
+            Kind::Variable => "variable",
+            Kind::Property => "property",
+            Kind::EnumMember => "enumMember",
+            Kind::Function => "function",
+            Kind::Method => "method",
+            Kind::Keyword => "keyword",
+            Kind::Comment => "comment",
+            Kind::String => "string",
+            Kind::Number => "number",
+            Kind::Operator => "operator",
+            Kind::Decorator => "decorator",
+        }
+    }
+}
+
+
+// === Modifiers ===
+
+/// A property of a token, corresponding to a standard LSP semantic token modifier.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Modifier {
+    /// The token introduces the name of an entity.
+    Declaration,
+    /// The token introduces the name of an entity that is defined at the same place, e.g. a
+    /// function with its body.
+    Definition,
+    /// The token is part of a documentation comment.
+    Documentation,
+}
+
+impl Modifier {
+    /// All modifiers, in the order of their bits in the LSP encoding.
+    pub const ALL: [Modifier; 3] =
+        [Modifier::Declaration, Modifier::Definition, Modifier::Documentation];
+
+    /// The name of the modifier in the LSP.
+    pub fn name(self) -> &'static str {
+        match self {
+            Modifier::Declaration => "declaration",
+            Modifier::Definition => "definition",
+            Modifier::Documentation => "documentation",
+        }
+    }
+}
+
+/// A set of [`Modifier`]s.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Modifiers(u32);
+
+impl Modifiers {
+    /// Return the set with the given modifier added.
+    pub fn with(self, modifier: Modifier) -> Self {
+        Self(self.0 | 1 << modifier as u32)
+    }
+
+    /// Return whether the set contains the given modifier.
+    pub fn contains(self, modifier: Modifier) -> bool {
+        self.0 & 1 << modifier as u32 != 0
+    }
+
+    /// The set in the LSP encoding: a bit for each modifier, by its index in [`Modifier::ALL`].
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+}
+
+impl From<Modifier> for Modifiers {
+    fn from(modifier: Modifier) -> Self {
+        Self::default().with(modifier)
+    }
+}
+
+
+
+// =====================
+// === SemanticToken ===
+// =====================
+
+/// A classified token.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SemanticToken {
+    /// The code of the token. It never spans multiple lines.
+    pub range:     Range<Location>,
+    /// The kind of entity the token refers to.
+    pub kind:      Kind,
+    /// The role of the token.
+    pub modifiers: Modifiers,
+}
+
+/// Return the classified tokens of the tree, ordered by location. Tokens that are not
+/// highlighted, such as brackets and line breaks, are omitted.
+pub fn collect(tree: &Tree) -> Vec<SemanticToken> {
+    let mut classifier = Classifier::default();
+    tree.visit_item(&mut classifier);
+    classifier.tokens
+}
+
+/// Encode the tokens as the data of an LSP `SemanticTokens` response. For each token, five numbers
+/// are produced: the difference between its line and the line of the previous token; its start
+/// column, relative to the start of the previous token if it is on the same line; its length; the
+/// index of its kind; and its modifier bits. Columns and lengths are in UTF-16 code units.
+pub fn encode(tokens: &[SemanticToken]) -> Vec<u32> {
+    let mut data = Vec::with_capacity(tokens.len() * 5);
+    let mut line = 0;
+    let mut column = 0;
+    for token in tokens {
+        let start = token.range.start;
+        if start.line != line {
+            column = 0;
+        }
+        data.extend([
+            start.line - line,
+            start.col16 - column,
+            token.range.end.utf16 - start.utf16,
+            token.kind as u32,
+            token.modifiers.bits(),
+        ]);
+        line = start.line;
+        column = start.col16;
+    }
+    data
+}
+
+
+
+// ==================
+// === Classifier ===
+// ==================
+
+#[derive(Default)]
+struct Classifier {
+    tokens:     Vec<SemanticToken>,
+    /// Classifications of tokens, by their start offsets in UTF-8 code units, that have been
+    /// determined from the nodes containing them and override the default classification.
+    contextual: HashMap<u32, (Kind, Modifiers)>,
+}
+
+impl tree::Visitor for Classifier {}
+impl<'s, 'a> ItemVisitor<'s, 'a> for Classifier {
+    fn visit_item(&mut self, item: item::Ref<'s, 'a>) -> bool {
+        match item {
+            item::Ref::Tree(tree) => self.tree(tree),
+            item::Ref::Token(token) => self.token(token),
+        }
+        true
+    }
+}
+
+impl Classifier {
+    fn token(&mut self, token: token::Ref) {
+        use token::Variant;
+        let code: &str = token.code;
+        if code.is_empty() {
+            return;
+        }
+        let start = token.code.range().start;
+        let contextual = self.contextual.remove(&start.utf8);
+        let kind = contextual.map(|(kind, _)| kind).or(match token.data {
+            Variant::Ident(ident) if ident.is_type => Some(Kind::Type),
+            Variant::Ident(_) | Variant::Wildcard(_) => Some(Kind::Variable),
+            Variant::Operator(_) | Variant::SuspendedDefaultArguments(_) => Some(Kind::Operator),
+            Variant::Digits(_) | Variant::NumberBase(_) => Some(Kind::Number),
+            Variant::Private(_) => Some(Kind::Keyword),
+            Variant::TextStart(_)
+            | Variant::TextEnd(_)
+            | Variant::TextSection(_)
+            | Variant::TextEscape(_) => Some(Kind::String),
+            Variant::Newline(_) if code.starts_with('#') => Some(Kind::Comment),
+            _ => None,
+        });
+        if let Some(kind) = kind {
+            let modifiers = contextual.map(|(_, modifiers)| modifiers).unwrap_or_default();
+            let range = start..start + Length::of(code);
+            self.tokens.push(SemanticToken { range, kind, modifiers });
+        }
+    }
+
+    fn classify<T>(&mut self, token: &token::Token<T>, kind: Kind, modifiers: Modifiers) {
+        self.set(&token.code, kind, modifiers);
+    }
+
+    /// Set the classification of the token with the given code, unless it has already been
+    /// classified in the context of an enclosing node.
+    fn set(&mut self, code: &crate::source::Code, kind: Kind, modifiers: Modifiers) {
+        self.contextual.entry(code.range().start.utf8).or_insert((kind, modifiers));
+    }
+
+    /// Classify the identifiers in a pattern, such as the left-hand side of an assignment, as the
+    /// names of the entities it declares. Constructors matched by the pattern are not affected.
+    fn pattern(&mut self, pattern: &Tree, kind: Kind) {
+        let declaration = Modifiers::from(Modifier::Declaration);
+        for_each_token(pattern, &mut |token| {
+            if let token::Variant::Ident(ident) = token.data
+                && !ident.is_type
+            {
+                self.set(token.code, kind, declaration);
+            }
+        });
+    }
+
+    /// Classify all the identifiers of the tree.
+    fn idents(&mut self, tree: &Tree, kind: Kind) {
+        for_each_token(tree, &mut |token| {
+            if let token::Variant::Ident(_) = token.data {
+                self.set(token.code, kind, default());
+            }
+        });
+    }
+
+    fn arguments(&mut self, arguments: &[tree::ArgumentDefinition], kind: Kind) {
+        for argument in arguments {
+            self.pattern(&argument.pattern, kind);
+        }
+    }
+
+    fn segment(&mut self, segment: &tree::MultiSegmentAppSegment) {
+        self.classify(&segment.header, Kind::Keyword, default());
+    }
+
+    fn tree(&mut self, tree: &Tree) {
+        use tree::Variant;
+        let definition = Modifiers::from(Modifier::Declaration).with(Modifier::Definition);
+        match &*tree.variant {
+            Variant::MultiSegmentApp(app) =>
+                for segment in &app.segments {
+                    if let token::Variant::Ident(_) = segment.header.variant {
+                        self.segment(segment);
+                    }
+                },
+            Variant::TypeDef(def) => {
+                self.classify(&def.keyword, Kind::Keyword, default());
+                self.classify(&def.name, Kind::Type, definition);
+                self.arguments(&def.params, Kind::TypeParameter);
+                for line in &def.body {
+                    if let Some(expression) = &line.expression {
+                        self.method(expression, definition);
+                    }
+                }
+            }
+            Variant::ConstructorDefinition(def) => {
+                self.classify(&def.constructor, Kind::EnumMember, definition);
+                self.arguments(&def.arguments, Kind::Property);
+                for line in &def.block {
+                    self.arguments(line.argument.as_slice(), Kind::Property);
+                }
+            }
+            Variant::Function(function) => {
+                self.function_name(&function.name, definition);
+                self.arguments(&function.args, Kind::Parameter);
+            }
+            Variant::ForeignFunction(function) => {
+                self.classify(&function.foreign, Kind::Keyword, default());
+                self.classify(&function.language, Kind::Namespace, default());
+                self.classify(&function.name, Kind::Function, definition);
+                self.arguments(&function.args, Kind::Parameter);
+            }
+            Variant::TypeSignature(signature) =>
+                self.function_name(&signature.variable, Modifier::Declaration.into()),
+            Variant::Assignment(assignment) => self.pattern(&assignment.pattern, Kind::Variable),
+            Variant::CaseOf(case) => {
+                self.classify(&case.case, Kind::Keyword, default());
+                self.classify(&case.of, Kind::Keyword, default());
+                for case in case.cases.iter().filter_map(|line| line.case.as_ref()) {
+                    if let Some(pattern) = &case.pattern {
+                        self.pattern(pattern, Kind::Variable);
+                    }
+                }
+            }
+            Variant::OprApp(tree::OprApp { lhs, opr: Ok(opr), rhs }) => {
+                let opr: &str = &opr.code;
+                match (lhs, opr, rhs) {
+                    (_, ".", Some(rhs)) => self.accessor(rhs, Kind::Method),
+                    (Some(lhs), "->", _) => self.pattern(lhs, Kind::Parameter),
+                    _ => (),
+                }
+            }
+            Variant::Number(number) =>
+                if let Some(fraction) = &number.fractional_digits {
+                    self.classify(&fraction.dot, Kind::Number, default());
+                },
+            Variant::NamedApp(app) => self.classify(&app.name, Kind::Parameter, default()),
+            Variant::Annotated(annotated) => {
+                self.classify(&annotated.token, Kind::Decorator, default());
+                self.classify(&annotated.annotation, Kind::Decorator, default());
+            }
+            Variant::AnnotatedBuiltin(annotated) => {
+                self.classify(&annotated.token, Kind::Decorator, default());
+                self.classify(&annotated.annotation, Kind::Decorator, default());
+            }
+            Variant::Documented(documented) => {
+                let documentation = Modifiers::from(Modifier::Documentation);
+                let doc = &documented.documentation;
+                self.classify(&doc.open, Kind::Comment, documentation);
+                for element in &doc.elements {
+                    match element {
+                        tree::TextElement::Section { text } =>
+                            self.classify(text, Kind::Comment, documentation),
+                        tree::TextElement::Escape { token } =>
+                            self.classify(token, Kind::Comment, documentation),
+                        _ => (),
+                    }
+                }
+            }
+            Variant::Import(import) => {
+                for segment in [&import.polyglot, &import.from, &import.as_, &import.hiding] {
+                    segment.iter().for_each(|segment| self.segment(segment));
+                }
+                self.segment(&import.import);
+                if let Some(all) = &import.all {
+                    self.classify(all, Kind::Keyword, default());
+                }
+                // The module or language the names are imported from.
+                let source = import.from.as_ref().or(import.polyglot.as_ref());
+                if let Some(body) = source.and_then(|segment| segment.body.as_ref()) {
+                    self.idents(body, Kind::Namespace);
+                }
+                if import.from.is_none()
+                    && let Some(body) = &import.import.body
+                {
+                    self.module_path(body);
+                }
+            }
+            Variant::Export(export) => {
+                for segment in [&export.from, &export.as_, &export.hiding] {
+                    segment.iter().for_each(|segment| self.segment(segment));
+                }
+                self.segment(&export.export);
+                if let Some(all) = &export.all {
+                    self.classify(all, Kind::Keyword, default());
+                }
+                if let Some(body) = export.from.as_ref().and_then(|segment| segment.body.as_ref()) {
+                    self.idents(body, Kind::Namespace);
+                }
+                if export.from.is_none()
+                    && let Some(body) = &export.export.body
+                {
+                    self.module_path(body);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Classify the name of a function or method, which may be qualified by the type it is defined
+    /// for, e.g. `Integer.double`.
+    fn function_name(&mut self, name: &Tree, modifiers: Modifiers) {
+        match &*name.variant {
+            tree::Variant::Ident(ident) => self.classify(&ident.token, Kind::Function, modifiers),
+            tree::Variant::OprApp(tree::OprApp { rhs: Some(rhs), opr: Ok(opr), .. })
+                if opr.code.repr.0 == "." =>
+                if let tree::Variant::Ident(ident) = &*rhs.variant {
+                    self.classify(&ident.token, Kind::Method, modifiers);
+                },
+            _ => (),
+        }
+    }
+
+    /// Classify the name of the method defined by a statement in the body of a type.
+    fn method(&mut self, statement: &Tree, modifiers: Modifiers) {
+        match &*statement.variant {
+            tree::Variant::Documented(tree::Documented {
+                expression: Some(expression), ..
+            })
+            | tree::Variant::Annotated(tree::Annotated { expression: Some(expression), .. })
+            | tree::Variant::AnnotatedBuiltin(tree::AnnotatedBuiltin {
+                expression: Some(expression),
+                ..
+            })
+            | tree::Variant::Private(tree::Private { body: Some(expression), .. }) =>
+                self.method(expression, modifiers),
+            tree::Variant::Function(tree::Function { name, .. })
+            | tree::Variant::Assignment(tree::Assignment { pattern: name, .. }) =>
+                if let tree::Variant::Ident(ident) = &*name.variant {
+                    self.classify(&ident.token, Kind::Method, modifiers);
+                },
+            tree::Variant::TypeSignature(tree::TypeSignature { variable: name, .. }) =>
+                if let tree::Variant::Ident(ident) = &*name.variant {
+                    self.classify(&ident.token, Kind::Method, Modifier::Declaration.into());
+                },
+            _ => (),
+        }
+    }
+
+    /// Classify the right-hand side of an accessor application, unless it names a type or
+    /// constructor.
+    fn accessor(&mut self, rhs: &Tree, kind: Kind) {
+        if let tree::Variant::Ident(ident) = &*rhs.variant
+            && !ident.token.variant.is_type
+        {
+            self.classify(&ident.token, kind, default());
+        }
+    }
+
+    /// Classify a qualified name of a module: every segment but the last is a namespace; the last
+    /// may refer to a module or a type, and keeps its default classification.
+    fn module_path(&mut self, path: &Tree) {
+        if let tree::Variant::OprApp(tree::OprApp { lhs: Some(lhs), .. }) = &*path.variant {
+            self.idents(lhs, Kind::Namespace);
+        }
+    }
+}
+
+fn for_each_token<'s, 'a>(tree: &'a Tree<'s>, f: &mut dyn FnMut(token::Ref<'s, 'a>)) {
+    struct Tokens<'f, 's, 'a> {
+        f: &'f mut dyn FnMut(token::Ref<'s, 'a>),
+    }
+    impl<'f, 's, 'a> tree::Visitor for Tokens<'f, 's, 'a> {}
+    impl<'f, 's, 'a> ItemVisitor<'s, 'a> for Tokens<'f, 's, 'a> {
+        fn visit_item(&mut self, item: item::Ref<'s, 'a>) -> bool {
+            if let item::Ref::Token(token) = item {
+                (self.f)(token);
+            }
+            true
+        }
+    }
+    tree.visit_item(&mut Tokens { f });
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The code and classification of each token.
+    fn classify(code: &str) -> Vec<(&str, Kind, Modifiers)> {
+        let tree = crate::Parser::new().run(code);
+        let tokens = collect(&tree);
+        let code_of = |token: &SemanticToken| {
+            &code[token.range.start.utf8 as usize..token.range.end.utf8 as usize]
+        };
+        tokens.iter().map(|token| (code_of(token), token.kind, token.modifiers)).collect()
+    }
+
+    #[track_caller]
+    fn test(code: &str, expected: &[(&str, Kind, Modifiers)]) {
+        assert_eq!(classify(code), expected);
+    }
+
+    fn plain() -> Modifiers {
+        default()
+    }
+
+    fn declaration() -> Modifiers {
+        Modifier::Declaration.into()
+    }
+
+    fn definition() -> Modifiers {
+        declaration().with(Modifier::Definition)
+    }
+
+    #[test]
+    fn functions() {
+        use Kind::*;
+        test("foo a (b : Integer = 2) = a.bar b # Comment.", &[
+            ("foo", Function, definition()),
+            ("a", Parameter, declaration()),
+            ("b", Parameter, declaration()),
+            (":", Operator, plain()),
+            ("Integer", Type, plain()),
+            ("=", Operator, plain()),
+            ("2", Number, plain()),
+            ("=", Operator, plain()),
+            ("a", Variable, plain()),
+            (".", Operator, plain()),
+            ("bar", Method, plain()),
+            ("b", Variable, plain()),
+            ("# Comment.", Comment, plain()),
+        ]);
+        test("Integer.double : Integer\nInteger.double self = x -> self", &[
+            ("Integer", Type, plain()),
+            (".", Operator, plain()),
+            ("double", Method, declaration()),
+            (":", Operator, plain()),
+            ("Integer", Type, plain()),
+            ("Integer", Type, plain()),
+            (".", Operator, plain()),
+            ("double", Method, definition()),
+            ("self", Parameter, declaration()),
+            ("=", Operator, plain()),
+            ("x", Parameter, declaration()),
+            ("->", Operator, plain()),
+            ("self", Variable, plain()),
+        ]);
+        test("f x=1", &[
+            ("f", Variable, plain()),
+            ("x", Parameter, plain()),
+            ("=", Operator, plain()),
+            ("1", Number, plain()),
+        ]);
+    }
+
+    #[test]
+    fn types() {
+        use Kind::*;
+        let doc = Modifiers::from(Modifier::Documentation);
+        test("## Doc.\ntype T a\n    C x\n    @x Widget\n    m = 'text'", &[
+            ("##", Comment, doc),
+            (" Doc.", Comment, doc),
+            ("type", Keyword, plain()),
+            ("T", Type, definition()),
+            ("a", TypeParameter, declaration()),
+            ("C", EnumMember, definition()),
+            ("x", Property, declaration()),
+            ("@", Decorator, plain()),
+            ("x", Decorator, plain()),
+            ("Widget", Type, plain()),
+            ("m", Method, definition()),
+            ("=", Operator, plain()),
+            ("'", String, plain()),
+            ("text", String, plain()),
+            ("'", String, plain()),
+        ]);
+    }
+
+    #[test]
+    fn keywords() {
+        use Kind::*;
+        test("x = if a then b else case c of\n    Foo y -> y", &[
+            ("x", Variable, declaration()),
+            ("=", Operator, plain()),
+            ("if", Keyword, plain()),
+            ("a", Variable, plain()),
+            ("then", Keyword, plain()),
+            ("b", Variable, plain()),
+            ("else", Keyword, plain()),
+            ("case", Keyword, plain()),
+            ("c", Variable, plain()),
+            ("of", Keyword, plain()),
+            ("Foo", Type, plain()),
+            ("y", Variable, declaration()),
+            ("->", Operator, plain()),
+            ("y", Variable, plain()),
+        ]);
+        test("private\nforeign js f = 'x'", &[
+            ("private", Keyword, plain()),
+            ("foreign", Keyword, plain()),
+            ("js", Namespace, plain()),
+            ("f", Function, definition()),
+            ("=", Operator, plain()),
+            ("'", String, plain()),
+            ("x", String, plain()),
+            ("'", String, plain()),
+        ]);
+    }
+
+    #[test]
+    fn imports() {
+        use Kind::*;
+        test("from Standard.Base import all\nimport Standard.Table.Table as T", &[
+            ("from", Keyword, plain()),
+            ("Standard", Namespace, plain()),
+            (".", Operator, plain()),
+            ("Base", Namespace, plain()),
+            ("import", Keyword, plain()),
+            ("all", Keyword, plain()),
+            ("import", Keyword, plain()),
+            ("Standard", Namespace, plain()),
+            (".", Operator, plain()),
+            ("Table", Namespace, plain()),
+            (".", Operator, plain()),
+            ("Table", Type, plain()),
+            ("as", Keyword, plain()),
+            ("T", Type, plain()),
+        ]);
+    }
+
+    #[test]
+    fn encoding() {
+        let tokens = collect(&crate::Parser::new().run("x = 1.5 # ä\nfoo"));
+        #[rustfmt::skip]
+        assert_eq!(encode(&tokens), [
+            0, 0, 1, Kind::Variable as u32, declaration().bits(),
+            0, 2, 1, Kind::Operator as u32, 0,
+            0, 2, 1, Kind::Number as u32, 0,
+            0, 1, 1, Kind::Number as u32, 0,
+            0, 1, 1, Kind::Number as u32, 0,
+            0, 2, 3, Kind::Comment as u32, 0,
+            1, 0, 3, Kind::Variable as u32, 0,
+        ]);
+    }
+}
@@ -7,28 +7,314 @@ use crate::prelude::*;
 
 use crate::source::code::StrRef;
 
+use std::collections::HashMap;
+
 
 
 // ============
 // === Tree ===
 // ============
 
-/// Serialize a `Tree` to its binary representation.
+/// The version of the binary representation produced by [`serialize_tree`]. This must be changed
+/// when the representation changes in a way not reflected in the types being serialized. Versions
+/// are allocated from the same sequence as
+/// [`format::FORMAT_VERSION`](crate::format::FORMAT_VERSION), so that the header also distinguishes
+/// the two formats; a new version takes the next unused number.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Serialize a `Tree` to its binary representation, preceded by a [`Header`].
 pub fn serialize_tree(data: &crate::syntax::tree::Tree) -> Result<Vec<u8>, bincode::Error> {
+    let mut output = vec![];
+    serialize_tree_into(data, &mut output)?;
+    Ok(output)
+}
+
+/// Serialize a `Tree` to its binary representation, preceded by a [`Header`], appending it to the
+/// given buffer.
+pub fn serialize_tree_into(
+    data: &crate::syntax::tree::Tree,
+    output: &mut Vec<u8>,
+) -> Result<(), bincode::Error> {
     use bincode::Options;
     let options = bincode::DefaultOptions::new().with_fixint_encoding();
-    options.serialize(data)
+    output.extend_from_slice(&Header::new(FORMAT_VERSION).to_bytes());
+    options.serialize_into(output, data)
 }
 
-/// Deserialize a `Tree` from its binary representation.
+/// Deserialize a `Tree` from its binary representation. Fails if the data does not begin with the
+/// [`Header`] of the current format version and types.
 pub fn deserialize_tree(data: &[u8]) -> Result<crate::syntax::tree::Tree, bincode::Error> {
     use bincode::Options;
     let options = bincode::DefaultOptions::new().with_fixint_encoding();
+    let data = Header::new(FORMAT_VERSION)
+        .check(data)
+        .map_err(|error| bincode::ErrorKind::Custom(error.to_string()))?;
     options.deserialize(data)
 }
 
 
 
+// ============
+// === JSON ===
+// ============
+
+/// Convert a `Tree` to JSON, in the representation described by [`tree_json_schema`]. This is the
+/// representation `serde_json` produces, except that the `repr` of each `code` has no `begin`: in
+/// the binary formats, it is the address of the code in the process that produced the tree, which
+/// would make the JSON differ between runs. Code is located by the `start` and `len` of each
+/// `code`.
+pub fn tree_to_json(tree: &crate::syntax::tree::Tree) -> serde_json::Result<serde_json::Value> {
+    let mut json = serde_json::to_value(tree)?;
+    remove_code_addresses(&mut json);
+    Ok(json)
+}
+
+/// Return a JSON Schema describing the JSON representation of a `Tree`.
+pub fn tree_json_schema() -> serde_json::Value {
+    with_json_format(|format| {
+        let mut schema = format.schema();
+        describe_code_repr(&mut schema["$defs"], format);
+        schema
+    })
+}
+
+/// Return an OpenAPI document defining the JSON representations of `Tree` and the types it
+/// contains as component schemas.
+pub fn tree_openapi() -> serde_json::Value {
+    let version = FORMAT_VERSION.to_string();
+    with_json_format(|format| {
+        let mut openapi = format.openapi("Enso Parser AST", &version);
+        describe_code_repr(&mut openapi["components"]["schemas"], format);
+        openapi
+    })
+}
+
+/// Apply a function to the [`Format`](reflect::metamodel::json::Format) describing the JSON
+/// representation of `Tree`s.
+fn with_json_format<T>(f: impl FnOnce(&reflect::metamodel::json::Format) -> T) -> T {
+    thread_local! {
+        static FORMAT: reflect::metamodel::json::Format =
+            reflect::metamodel::json::Format::new(crate::syntax::Tree::reflect());
+    }
+    FORMAT.with(f)
+}
+
+fn remove_code_addresses(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(object) => {
+            if let Some(serde_json::Value::Object(repr)) = object.get_mut("repr") {
+                repr.remove("begin");
+            }
+            object.values_mut().for_each(remove_code_addresses);
+        }
+        serde_json::Value::Array(array) => array.iter_mut().for_each(remove_code_addresses),
+        _ => {}
+    }
+}
+
+/// Remove the `begin` field from the definition of the `repr` of code in the given definitions, and
+/// explain the remaining field; see [`tree_to_json`].
+fn describe_code_repr(
+    definitions: &mut serde_json::Value,
+    format: &reflect::metamodel::json::Format,
+) {
+    let name = format.definition_name(Code::reflect().id).expect("`Code` is defined.");
+    let definition = &mut definitions[name];
+    definition["properties"].as_object_mut().unwrap().remove("begin");
+    definition["required"] = serde_json::json!(["len"]);
+    definition["description"] = "The length in bytes of a piece of code. The code is located by \
+        the `start` of the code object containing this representation."
+        .into();
+}
+
+
+
+// ==============
+// === Header ===
+// ==============
+
+/// Identifies the format of serialized data, and the definitions of the types it was serialized
+/// from. Readers generated from the type definitions check the header, so that a reader that is out
+/// of date, or that expects a different format, fails instead of misinterpreting the data.
+///
+/// The header is encoded as the version (a little-endian `u32`) followed by the type graph hash (a
+/// little-endian `u64`).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Header {
+    /// The version of the format. Each format has its own versions, so this also identifies the
+    /// format.
+    pub version:         u32,
+    /// A hash of the graph of types rooted at [`Tree`](crate::syntax::Tree); see
+    /// [`type_graph_hash`].
+    pub type_graph_hash: u64,
+}
+
+impl Header {
+    /// The size of the encoded header, in bytes.
+    pub const SIZE: usize = 12;
+
+    /// Return the header of data serialized in the given format version from the current types.
+    pub fn new(version: u32) -> Self {
+        Self { version, type_graph_hash: type_graph_hash() }
+    }
+
+    /// Return the encoded header.
+    pub fn to_bytes(self) -> [u8; Self::SIZE] {
+        let mut bytes = [0; Self::SIZE];
+        bytes[..4].copy_from_slice(&self.version.to_le_bytes());
+        bytes[4..].copy_from_slice(&self.type_graph_hash.to_le_bytes());
+        bytes
+    }
+
+    /// Decode the header at the start of the data, if the data is long enough to contain one.
+    pub fn read(data: &[u8]) -> Option<Self> {
+        let version = u32::from_le_bytes(data.get(..4)?.try_into().unwrap());
+        let type_graph_hash = u64::from_le_bytes(data.get(4..Self::SIZE)?.try_into().unwrap());
+        Some(Self { version, type_graph_hash })
+    }
+
+    /// Check that the data begins with this header, and return the data following it.
+    pub fn check(self, data: &[u8]) -> Result<&[u8], HeaderMismatch> {
+        match Self::read(data) {
+            Some(found) if found == self => Ok(&data[Self::SIZE..]),
+            found => Err(HeaderMismatch { expected: self, found }),
+        }
+    }
+}
+
+/// Indicates that serialized data is not in the format a reader expects.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct HeaderMismatch {
+    /// The header the reader expected.
+    pub expected: Header,
+    /// The header found in the data, if it was long enough to contain one.
+    pub found:    Option<Header>,
+}
+
+impl Display for HeaderMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Header { version, type_graph_hash } = self.expected;
+        write!(f, "Expected format version {version} with type graph hash {type_graph_hash:016x}")?;
+        match self.found {
+            Some(Header { version, type_graph_hash }) =>
+                write!(f, ", found version {version} with hash {type_graph_hash:016x}."),
+            None => write!(f, ", but the data is too short to contain a header."),
+        }
+    }
+}
+
+impl std::error::Error for HeaderMismatch {}
+
+/// Return a hash identifying the definitions of [`Tree`](crate::syntax::Tree) and all the types it
+/// contains. The hash is computed from the `Reflect` type graph, so that it changes whenever the
+/// serialized representation of any of the types does.
+pub fn type_graph_hash() -> u64 {
+    static HASH: std::sync::OnceLock<u64> = std::sync::OnceLock::new();
+    *HASH.get_or_init(|| hash_type_graph(crate::syntax::Tree::reflect()))
+}
+
+/// Hash the definitions of the types reachable from the given type. The types are visited in
+/// breadth-first order, and references to types are hashed as the order in which the type was
+/// first encountered, so that the result depends only on the structure of the definitions.
+///
+/// The hash function is 64-bit FNV-1a, which (unlike the standard library's `Hasher`s) is stable
+/// across compiler versions and platforms.
+fn hash_type_graph(root: reflect::metamodel::rust::TypeData) -> u64 {
+    use reflect::metamodel::rust::*;
+    #[derive(Default)]
+    struct Hasher {
+        hash:  u64,
+        ids:   HashMap<TypeId, usize>,
+        queue: std::collections::VecDeque<TypeData>,
+    }
+    impl Hasher {
+        fn bytes(&mut self, bytes: &[u8]) {
+            for byte in bytes {
+                self.hash ^= u64::from(*byte);
+                self.hash = self.hash.wrapping_mul(0x100000001b3);
+            }
+        }
+        fn number(&mut self, value: usize) {
+            self.bytes(&u32::try_from(value).unwrap().to_le_bytes());
+        }
+        fn str(&mut self, value: &str) {
+            self.number(value.len());
+            self.bytes(value.as_bytes());
+        }
+        fn reference(&mut self, ty: LazyType) {
+            let next = self.ids.len();
+            let index = *self.ids.entry(ty.id).or_insert(next);
+            if index == next {
+                self.queue.push_back(ty.evaluate());
+            }
+            self.number(index);
+        }
+        fn fields(&mut self, fields: &Fields) {
+            match fields {
+                Fields::Named(fields) => {
+                    self.bytes(b"n");
+                    self.number(fields.len());
+                    for field in fields {
+                        self.str(&field.name);
+                        self.str(field.rename.as_deref().unwrap_or_default());
+                        self.bytes(&[field.subtype, field.flatten, field.hide].map(u8::from));
+                        self.reference(field.type_);
+                    }
+                }
+                Fields::Unnamed(fields) => {
+                    self.bytes(b"u");
+                    self.number(fields.len());
+                    fields.iter().for_each(|field| self.reference(field.type_));
+                }
+                Fields::Unit => self.bytes(b"0"),
+            }
+        }
+        fn type_data(&mut self, ty: TypeData) {
+            self.str(&ty.name);
+            match ty.data {
+                Data::Struct(Struct { fields, transparent }) => {
+                    self.bytes(&[b's', transparent.into()]);
+                    self.fields(&fields);
+                }
+                Data::Enum(Enum { variants }) => {
+                    self.bytes(b"e");
+                    self.number(variants.len());
+                    for Variant { ident, fields, inline } in &variants {
+                        self.str(ident);
+                        self.bytes(&[u8::from(*inline)]);
+                        self.fields(fields);
+                    }
+                }
+                Data::Primitive(primitive) => {
+                    let name = match primitive {
+                        Primitive::Bool => "bool",
+                        Primitive::Usize => "usize",
+                        Primitive::U32 => "u32",
+                        Primitive::U64 => "u64",
+                        Primitive::I32 => "i32",
+                        Primitive::Char => "char",
+                        Primitive::String => "String",
+                        Primitive::Vec(_) => "Vec",
+                        Primitive::Option(_) => "Option",
+                        Primitive::Result(_, _) => "Result",
+                    };
+                    self.str(name);
+                    primitive.referenced_types().into_iter().for_each(|ty| self.reference(ty));
+                }
+            }
+        }
+    }
+    let mut hasher = Hasher { hash: 0xcbf29ce484222325, ..default() };
+    hasher.ids.insert(root.id, 0);
+    hasher.queue.push_back(root);
+    while let Some(ty) = hasher.queue.pop_front() {
+        hasher.type_data(ty);
+    }
+    hasher.hash
+}
+
+
+
 // ============
 // === Code ===
 // ============
@@ -58,23 +344,6 @@ where D: serde::Deserializer<'de> {
 
 
 
-// =============
-// === Error ===
-// =============
-
-/// Deserialization type for `crate::syntax::tree::Error`.
-#[derive(Deserialize, Debug, Clone)]
-pub(crate) struct Error(String);
-
-impl From<Error> for crate::syntax::tree::Error {
-    fn from(error: Error) -> Self {
-        let message = error.0.into();
-        crate::syntax::tree::Error { message }
-    }
-}
-
-
-
 // ================
 // === Visitors ===
 // ================
@@ -94,16 +363,16 @@ impl<'de> serde::de::Visitor<'de> for DeserializeU64 {
     }
 }
 
-struct DeserializeU32;
+struct DeserializeI32;
 
-impl<'de> serde::de::Visitor<'de> for DeserializeU32 {
-    type Value = u32;
+impl<'de> serde::de::Visitor<'de> for DeserializeI32 {
+    type Value = i32;
 
     fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        write!(formatter, "An unsigned 32-bit integer.")
+        write!(formatter, "A signed 32-bit integer.")
     }
 
-    fn visit_u32<E>(self, i: u32) -> Result<Self::Value, E>
+    fn visit_i32<E>(self, i: i32) -> Result<Self::Value, E>
     where E: serde::de::Error {
         Ok(i)
     }
@@ -115,18 +384,349 @@ impl<'de> serde::de::Visitor<'de> for DeserializeU32 {
 // === General purpose value transforms ===
 // ========================================
 
+/// Serialize an optional index as an `i32`, with `-1` representing `None`, as described by its
+/// `#[reflect(as = "i32")]` attribute.
 pub(crate) fn serialize_optional_int<S>(x: &Option<u32>, s: S) -> Result<S::Ok, S::Error>
 where S: serde::Serializer {
-    s.serialize_u32(x.unwrap_or(0xFFFF_FFFF))
+    s.serialize_i32(x.map_or(-1, |x| x as i32))
 }
 
 pub(crate) fn deserialize_optional_int<'c, 'de, D>(
     deserializer: D,
 ) -> Result<Option<u32>, D::Error>
 where D: serde::Deserializer<'de> {
-    let value = deserializer.deserialize_u32(DeserializeU32)?;
+    let value = deserializer.deserialize_i32(DeserializeI32)?;
     Ok(match value {
-        0xFFFF_FFFF => None,
-        x => Some(x),
+        -1 => None,
+        x => Some(x as u32),
     })
 }
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_round_trip() {
+        let tree = crate::Parser::new().run("main = 1 + 2");
+        let data = serialize_tree(&tree).unwrap();
+        assert_eq!(Header::read(&data), Some(Header::new(FORMAT_VERSION)));
+        deserialize_tree(&data).unwrap();
+    }
+
+    #[test]
+    fn mismatched_header_rejected() {
+        let tree = crate::Parser::new().run("main = 1 + 2");
+        let data = serialize_tree(&tree).unwrap();
+        let with_header = |header: Header| [&header.to_bytes()[..], &data[Header::SIZE..]].concat();
+        let current = Header::new(FORMAT_VERSION);
+        let old_version = Header { version: FORMAT_VERSION - 1, ..current };
+        let old_types = Header { type_graph_hash: current.type_graph_hash ^ 1, ..current };
+        for header in [old_version, old_types] {
+            let error = current.check(&with_header(header)).unwrap_err();
+            assert_eq!(error, HeaderMismatch { expected: current, found: Some(header) });
+            assert!(deserialize_tree(&with_header(header)).is_err());
+        }
+        assert_eq!(current.check(&data[..4]).unwrap_err().found, None);
+    }
+
+    #[test]
+    fn other_format_rejected() {
+        assert_ne!(FORMAT_VERSION, crate::format::FORMAT_VERSION);
+        let tree = crate::Parser::new().run("main = 1 + 2");
+        let data = crate::format::serialize(&tree).unwrap();
+        let error = Header::new(FORMAT_VERSION).check(&data).unwrap_err();
+        assert_eq!(error.found, Some(Header::new(crate::format::FORMAT_VERSION)));
+        assert!(deserialize_tree(&data).is_err());
+    }
+
+    /// Check the JSON Schema of a set of types covering the `serde` representations against the
+    /// JSON `serde` produces for a value of the types.
+    #[test]
+    fn json_schema_conventions() {
+        #[derive(Serialize, Reflect)]
+        struct Call {
+            name:   String,
+            #[reflect(rename = "arguments")]
+            args:   Vec<Status>,
+            r#type: Option<char>,
+            id:     Id,
+            result: Result<u32, bool>,
+        }
+        #[derive(Serialize, Reflect)]
+        struct Id(u32);
+        #[derive(Serialize, Reflect)]
+        enum Status {
+            Unknown,
+            Line(u32),
+            Range { start: u32, end: u32 },
+        }
+        let value = Call {
+            name:   "f".into(),
+            args:   vec![Status::Unknown, Status::Line(1), Status::Range { start: 1, end: 2 }],
+            r#type: Some('x'),
+            id:     Id(7),
+            result: Ok(3),
+        };
+        assert_eq!(
+            serde_json::to_value(value).unwrap(),
+            serde_json::json!({
+                "name": "f",
+                "args": ["Unknown", { "Line": 1 }, { "Range": { "start": 1, "end": 2 } }],
+                "type": "x",
+                "id": 7,
+                "result": { "Ok": 3 },
+            })
+        );
+        let u32_ = serde_json::json!({ "type": "integer", "minimum": 0, "maximum": u32::MAX });
+        let object = |properties: serde_json::Value| {
+            let required: Vec<_> = properties.as_object().unwrap().keys().cloned().collect();
+            serde_json::json!({
+                "type": "object",
+                "properties": properties,
+                "required": required,
+                "additionalProperties": false,
+            })
+        };
+        let format = reflect::metamodel::json::Format::new(Call::reflect());
+        assert_eq!(
+            format.schema(),
+            serde_json::json!({
+                "$schema": reflect::metamodel::json::JSON_SCHEMA_DIALECT,
+                "$ref": "#/$defs/Call",
+                "$defs": {
+                    "Call": object(serde_json::json!({
+                        "name": { "type": "string" },
+                        "args": { "type": "array", "items": { "$ref": "#/$defs/Status" } },
+                        "type": {
+                            "anyOf": [
+                                { "type": "string", "minLength": 1, "maxLength": 1 },
+                                { "type": "null" },
+                            ],
+                        },
+                        "id": { "$ref": "#/$defs/Id" },
+                        "result": {
+                            "oneOf": [
+                                object(serde_json::json!({ "Ok": u32_ })),
+                                object(serde_json::json!({ "Err": { "type": "boolean" } })),
+                            ],
+                        },
+                    })),
+                    "Id": u32_,
+                    "Status": {
+                        "oneOf": [
+                            { "const": "Unknown" },
+                            object(serde_json::json!({ "Line": u32_ })),
+                            object(serde_json::json!({
+                                "Range": object(serde_json::json!({ "start": u32_, "end": u32_ })),
+                            })),
+                        ],
+                    },
+                },
+            })
+        );
+    }
+
+    /// Check the JSON representation of a tree, and the schema definitions of the types it
+    /// contains.
+    #[test]
+    fn tree_json() {
+        fn code(start: u32, len: u32) -> serde_json::Value {
+            serde_json::json!({
+                "repr": { "len": len },
+                "start": { "utf8": start, "utf16": start, "line": 0, "col16": start },
+                "len": { "utf16": len, "newlines": 0, "line_chars16": len },
+            })
+        }
+        let offset = |start: u32, len: u32| {
+            serde_json::json!({
+                "visible": { "width_in_spaces": len },
+                "code": code(start, len),
+            })
+        };
+        let length =
+            |len: u32| serde_json::json!({ "utf16": len, "newlines": 0, "line_chars16": len });
+        let tree = crate::Parser::new().run("f x");
+        let json = tree_to_json(&tree).unwrap();
+        // The output doesn't depend on where the code is in memory.
+        let code_copy = String::from("f x");
+        assert_eq!(tree_to_json(&crate::Parser::new().run(&code_copy)).unwrap(), json);
+        let expression = &json["variant"]["BodyBlock"]["statements"][0]["expression"];
+        assert_eq!(
+            expression,
+            &serde_json::json!({
+                "span": { "left_offset": offset(0, 0), "code_length": length(3) },
+                "variant": { "App": {
+                    "func": {
+                        "span": { "left_offset": offset(0, 0), "code_length": length(1) },
+                        "variant": { "Ident": { "token": {
+                            "left_offset": offset(0, 0),
+                            "code": code(0, 1),
+                            "variant": {
+                                "is_free": false,
+                                "lift_level": 0,
+                                "is_type": false,
+                                "is_operator_lexically": false,
+                            },
+                        } } },
+                    },
+                    "arg": {
+                        "span": { "left_offset": offset(1, 1), "code_length": length(1) },
+                        "variant": { "Ident": { "token": {
+                            "left_offset": offset(2, 0),
+                            "code": code(2, 1),
+                            "variant": {
+                                "is_free": false,
+                                "lift_level": 0,
+                                "is_type": false,
+                                "is_operator_lexically": false,
+                            },
+                        } } },
+                    },
+                } },
+            })
+        );
+        // An absent index is serialized as `-1`, a value of the `i32` type it is reflected as.
+        let function = tree_to_json(&crate::Parser::new().run("f _ = x")).unwrap();
+        let function = &function["variant"]["BodyBlock"]["statements"][0]["expression"];
+        let pattern = &function["variant"]["Function"]["args"][0]["pattern"];
+        assert_eq!(pattern["variant"]["Wildcard"]["de_bruijn_index"], -1);
+        let schema = tree_json_schema();
+        let definitions = &schema["$defs"];
+        let reference = |name: &str| serde_json::json!({ "$ref": format!("#/$defs/{name}") });
+        assert_eq!(schema["$ref"], "#/$defs/Tree");
+        assert_eq!(
+            definitions["Tree"]["properties"],
+            serde_json::json!({
+                "span": reference("Span"),
+                "variant": reference("Variant"),
+            })
+        );
+        assert_eq!(
+            definitions["Wildcard"]["properties"],
+            serde_json::json!({
+                "token": reference("Token.Wildcard"),
+                "de_bruijn_index": { "type": "integer", "minimum": i32::MIN, "maximum": i32::MAX },
+            })
+        );
+        assert_eq!(
+            definitions["Token.Wildcard"]["properties"],
+            serde_json::json!({
+                "left_offset": reference("Offset"),
+                "code": reference("Code"),
+                "variant": reference("Wildcard_1"),
+            })
+        );
+        let repr = with_json_format(|format| {
+            format.definition_name(Code::reflect().id).unwrap().to_owned()
+        });
+        assert_eq!(definitions["Code"]["properties"]["repr"], reference(&repr));
+        assert_eq!(
+            definitions[&repr]["properties"],
+            serde_json::json!({ "len": { "type": "integer", "minimum": 0, "maximum": u32::MAX } })
+        );
+        let openapi = tree_openapi();
+        assert_eq!(openapi["components"]["schemas"][&repr]["required"], serde_json::json!(["len"]));
+        assert_eq!(
+            &openapi["components"]["schemas"]["Tree"]["properties"]["span"],
+            &serde_json::json!({
+                "$ref": "#/components/schemas/Span",
+            })
+        );
+    }
+
+    /// Validate the JSON of the trees of a set of modules covering the syntax against the schema.
+    #[test]
+    fn tree_json_matches_schema() {
+        use reflect::metamodel::json::validate;
+        const MODULES: &[&str] = &[
+            "",
+            "main = 1 + 2",
+            r#"from Standard.Base import all
+import Standard.Base.Data.Vector.Vector
+polyglot java import java.lang.Long
+export project.Data.Table
+
+## The entry point.
+main =
+    x = [1, 2.5, 0x1F, -3]
+    y = x.map (n -> n * 2) . filter (> 1)
+    IO.println "Result: `y.length` of `x`"
+    y.at 0 . catch Any _-> Nothing
+"#,
+            r#"type Maybe a
+    ## A present value.
+    Some (value : a = 0)
+    None
+
+    is_some : Boolean
+    is_some self = case self of
+        Maybe.Some _ -> True
+        None -> False
+
+    map self f = if self.is_some then Maybe.Some (f self.value) else self
+
+Maybe.from (that : Vector) = Maybe.Some that
+"#,
+            r#"@Builtin_Method "Foo.bar"
+@x (Widget.Text_Input)
+foo ~x (y = ..Default) -> Integer ! Illegal_Argument =
+    z = x.to_text + '''
+        multiline
+        text
+    xs = [] . fold 0 (+)
+    (a, b) = (1, 2)
+    z.length + a
+private
+foreign js f x = """
+    return x + 1
+"#,
+            "f x = (\n    @ (\n\ntype\nx = - ) ]\n\"unclosed\n'\\q'",
+        ];
+        let schema = tree_json_schema();
+        let openapi = tree_openapi();
+        for module in MODULES {
+            let json = tree_to_json(&crate::Parser::new().run(module)).unwrap();
+            validate(&schema, "#", &json).unwrap_or_else(|e| panic!("{e}\nModule: {module:?}"));
+            validate(&openapi, "#/components/schemas/Tree", &json)
+                .unwrap_or_else(|e| panic!("{e}\nModule: {module:?}"));
+        }
+        // Values that don't match the representation are rejected.
+        let json = tree_to_json(&crate::Parser::new().run("f x")).unwrap();
+        let invalid = |edit: &dyn Fn(&mut serde_json::Value)| {
+            let mut json = json.clone();
+            edit(&mut json);
+            validate(&schema, "#", &json).unwrap_err()
+        };
+        let error = invalid(&|json| _ = json.as_object_mut().unwrap().remove("span"));
+        assert_eq!(error.message, "missing property `span`");
+        let error = invalid(&|json| json["span"]["code_length"]["utf16"] = (-1).into());
+        assert_eq!(error.path, "/span/code_length/utf16");
+        let error = invalid(&|json| json["span"]["extra"] = 0.into());
+        assert_eq!(error.path, "/span/extra");
+        let error = invalid(&|json| json["variant"] = serde_json::json!({ "NotAVariant": {} }));
+        assert_eq!(error.path, "/variant");
+    }
+
+    #[test]
+    fn type_graph_hash_identifies_types() {
+        #[derive(Reflect)]
+        struct A {
+            value: u32,
+        }
+        #[derive(Reflect)]
+        struct B {
+            value: u64,
+        }
+        let tree_hash = type_graph_hash();
+        assert_eq!(hash_type_graph(crate::syntax::Tree::reflect()), tree_hash);
+        assert_ne!(hash_type_graph(A::reflect()), tree_hash);
+        assert_ne!(hash_type_graph(A::reflect()), hash_type_graph(B::reflect()));
+    }
+}
@@ -5,6 +5,9 @@ use crate::prelude::*;
 use crate::syntax;
 use crate::syntax::token;
 use crate::syntax::token::Token;
+use crate::syntax::tree::Error;
+use crate::syntax::tree::ErrorCode;
+use crate::syntax::tree::ErrorSpan;
 
 
 
@@ -226,8 +229,7 @@ impl<'s> ExpressionBuilder<'s> {
             self.application();
             if self.nospace {
                 if let Unary::Simple(token) = arity {
-                    let error = "Space required between term and unary-operator expression.".into();
-                    arity = Unary::Invalid { token, error };
+                    arity = Unary::Invalid { token, error: ErrorCode::SpaceRequired };
                 }
             }
         }
@@ -280,6 +282,21 @@ impl<'s> ExpressionBuilder<'s> {
             tokens.push(opr);
             return;
         }
+        if self.prev_type == Some(ItemType::Opr) {
+            // Unary operators followed by a binary operator are missing their operand.
+            let mut operand = None;
+            while let Some(Operator { opr: Arity::Unary(unary), .. }) =
+                self.operator_stack.pop_if(|opr| {
+                    matches!(opr.opr, Arity::Unary(Unary::Simple(_) | Unary::Invalid { .. }))
+                })
+            {
+                operand = Some(unary.apply(operand));
+            }
+            if let Some(operand) = operand {
+                self.output.push(operand);
+                self.prev_type = Some(ItemType::Ast);
+            }
+        }
         self.push_operator(prec, assoc, Arity::binary(opr));
     }
 
@@ -296,11 +313,13 @@ impl<'s> ExpressionBuilder<'s> {
         // unary operator's affinity for its operand is stronger than any operator precedence.
         let defer_reducing_stack = match (&self.prev_type, &opr.opr) {
             (Some(ItemType::Opr), Arity::Unary(Unary::Simple(_))) if self.nospace => true,
+            (Some(ItemType::Opr), Arity::Unary(Unary::Invalid { .. })) => true,
             (Some(ItemType::Opr), Arity::Unary(Unary::Fragment { .. })) => true,
             _ => false,
         };
         if !defer_reducing_stack {
-            let mut rhs = self.output.pop();
+            let mut rhs =
+                (self.prev_type == Some(ItemType::Ast)).and_option_from(|| self.output.pop());
             self.reduce(precedence, &mut rhs);
             if let Some(rhs) = rhs {
                 self.output.push(rhs);
@@ -320,16 +339,7 @@ impl<'s> ExpressionBuilder<'s> {
         }) {
             let rhs_ = rhs.take();
             let ast = match opr.opr {
-                Arity::Unary(Unary::Simple(opr)) =>
-                    Operand::new(rhs_).map(|item| syntax::tree::apply_unary_operator(opr, item)),
-                Arity::Unary(Unary::Invalid { token, error }) => Operand::from(rhs_)
-                    .map(|item| syntax::tree::apply_unary_operator(token, item).with_error(error)),
-                Arity::Unary(Unary::Fragment { mut fragment }) => {
-                    if let Some(rhs_) = rhs_ {
-                        fragment.operand(rhs_);
-                    }
-                    fragment.finish().unwrap()
-                }
+                Arity::Unary(unary) => unary.apply(rhs_),
                 Arity::Binary { tokens, lhs_section_termination } => {
                     let lhs = self.output.pop();
                     if let Some(lhs_termination) = lhs_section_termination {
@@ -494,10 +504,45 @@ impl<'s> Arity<'s> {
 #[derive(Debug, PartialEq, Eq)]
 enum Unary<'s> {
     Simple(token::Operator<'s>),
-    Invalid { token: token::Operator<'s>, error: Cow<'static, str> },
+    Invalid { token: token::Operator<'s>, error: ErrorCode },
     Fragment { fragment: ExpressionBuilder<'s> },
 }
 
+impl<'s> Unary<'s> {
+    /// Apply the operator to its operand, if any.
+    fn apply(self, rhs: Option<Operand<syntax::Tree<'s>>>) -> Operand<syntax::Tree<'s>> {
+        match self {
+            Unary::Simple(opr) =>
+                Operand::new(rhs).map(|item| syntax::tree::apply_unary_operator(opr, item)),
+            Unary::Invalid { token, error } => Operand::from(rhs).map(|item| {
+                let operator = token.code.range();
+                let tree = syntax::tree::apply_unary_operator(token, item);
+                tree.with_error_from(|tree| invalid_unary_operator(tree, operator, error))
+            }),
+            Unary::Fragment { mut fragment } => {
+                if let Some(rhs) = rhs {
+                    fragment.operand(rhs);
+                }
+                fragment.finish().unwrap()
+            }
+        }
+    }
+}
+
+/// Report an error for an invalid application of the unary operator at the given range.
+fn invalid_unary_operator(
+    tree: &syntax::Tree,
+    operator: Range<crate::source::code::Location>,
+    code: ErrorCode,
+) -> Error {
+    let error = Error::new(code, ErrorSpan::within(tree, operator.clone()));
+    match code {
+        ErrorCode::SpaceRequired =>
+            error.with_fix(ErrorSpan::at(tree, operator.start), " ", "Insert a space"),
+        _ => error,
+    }
+}
+
 
 // === Operand ===
 
@@ -6,7 +6,6 @@ use crate::syntax::*;
 
 use crate::span_builder;
 
-#[cfg(feature = "debug")]
 use enso_parser_syntax_tree_visitor::Visitor;
 
 
@@ -15,6 +14,13 @@ use enso_parser_syntax_tree_visitor::Visitor;
 // ==============
 
 pub mod block;
+pub mod error;
+pub mod id;
+pub mod literal;
+
+pub use error::Error;
+pub use error::ErrorCode;
+pub use error::ErrorSpan;
 
 
 
@@ -62,7 +68,7 @@ impl<'s> Default for Tree<'s> {
 macro_rules! with_ast_definition { ($f:ident ($($args:tt)*)) => { $f! { $($args)*
     /// [`Tree`] variants definition. See its docs to learn more.
     #[tagged_enum]
-    #[cfg_attr(feature = "debug", derive(Visitor))]
+    #[derive(Visitor)]
     #[derive(Clone, Eq, PartialEq, Serialize, Reflect, Deserialize)]
     #[allow(clippy::large_enum_variant)] // Inefficient. Will be fixed in #182878443.
     #[tagged_enum(apply_attributes_to = "variants")]
@@ -382,45 +388,10 @@ macro_rules! generate_ast_definition {
 with_ast_definition!(generate_ast_definition());
 
 
-// === Invalid ===
-
-/// Error of parsing attached to an [`Tree`] node.
-#[cfg_attr(feature = "debug", derive(Visitor))]
-#[derive(Clone, Debug, Eq, PartialEq, Serialize, Reflect, Deserialize)]
-#[allow(missing_docs)]
-#[reflect(transparent)]
-#[serde(from = "crate::serialization::Error")]
-pub struct Error {
-    #[serde(skip_deserializing)]
-    pub message: Cow<'static, str>,
-}
-
-impl Error {
-    /// Constructor.
-    pub fn new(message: impl Into<Cow<'static, str>>) -> Self {
-        let message = message.into();
-        Self { message }
-    }
-}
-
-impl<'s> Tree<'s> {
-    /// Constructor.
-    pub fn with_error(self, message: impl Into<Cow<'static, str>>) -> Self {
-        Tree::invalid(Error::new(message), self)
-    }
-}
-
-impl<'s> span::Builder<'s> for Error {
-    fn add_to_span(&mut self, span: Span<'s>) -> Span<'s> {
-        span
-    }
-}
-
-
 // === Argument blocks ===
 
 /// An argument specification on its own line.
-#[cfg_attr(feature = "debug", derive(Visitor))]
+#[derive(Visitor)]
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Reflect, Deserialize)]
 pub struct ArgumentDefinitionLine<'s> {
     /// The token beginning the line.
@@ -439,7 +410,7 @@ impl<'s> span::Builder<'s> for ArgumentDefinitionLine<'s> {
 // === Text literals ===
 
 /// A component of a text literal, within the quotation marks.
-#[cfg_attr(feature = "debug", derive(Visitor))]
+#[derive(Visitor)]
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Reflect, Deserialize)]
 pub enum TextElement<'s> {
     /// The text content of the literal. If it is multiline, the offset information may contain
@@ -486,7 +457,7 @@ impl<'s> span::Builder<'s> for TextElement<'s> {
 // === Documentation ===
 
 /// A documentation comment.
-#[cfg_attr(feature = "debug", derive(Visitor))]
+#[derive(Visitor)]
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Reflect, Deserialize)]
 pub struct DocComment<'s> {
     /// The comment-initiating token.
@@ -497,6 +468,28 @@ pub struct DocComment<'s> {
     pub newlines: Vec<token::Newline<'s>>,
 }
 
+impl<'s> DocComment<'s> {
+    /// Return the text of the comment, without the `##` token and the following empty lines. Its
+    /// lines are separated by `\n`, and escape sequences are replaced by the characters they
+    /// represent.
+    pub fn content(&self) -> String {
+        let mut buf = String::new();
+        for element in &self.elements {
+            match element {
+                TextElement::Section { text } => buf.push_str(&text.code.repr),
+                TextElement::Newline { .. } => buf.push('\n'),
+                TextElement::Escape { token } => match token.variant.value.to_char() {
+                    Some(c) => buf.push(c),
+                    // An invalid escape, or an unpaired surrogate that a `String` can't represent.
+                    None => buf.push_str(&token.code.repr),
+                },
+                TextElement::Splice { .. } => {}
+            }
+        }
+        buf
+    }
+}
+
 impl<'s> span::Builder<'s> for DocComment<'s> {
     fn add_to_span(&mut self, span: Span<'s>) -> Span<'s> {
         span.add(&mut self.open).add(&mut self.elements).add(&mut self.newlines)
@@ -506,7 +499,7 @@ impl<'s> span::Builder<'s> for DocComment<'s> {
 
 // === Number literals ===
 
-#[cfg_attr(feature = "debug", derive(Visitor))]
+#[derive(Visitor)]
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Reflect, Deserialize)]
 #[allow(missing_docs)]
 pub struct FractionalDigits<'s> {
@@ -526,7 +519,7 @@ impl<'s> span::Builder<'s> for FractionalDigits<'s> {
 // === Functions ===
 
 /// A function argument definition.
-#[cfg_attr(feature = "debug", derive(Visitor))]
+#[derive(Visitor)]
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Reflect, Deserialize)]
 pub struct ArgumentDefinition<'s> {
     /// Opening parenthesis (outer).
@@ -562,7 +555,7 @@ impl<'s> span::Builder<'s> for ArgumentDefinition<'s> {
 }
 
 /// A default value specification in a function argument definition.
-#[cfg_attr(feature = "debug", derive(Visitor))]
+#[derive(Visitor)]
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Reflect, Deserialize)]
 pub struct ArgumentDefault<'s> {
     /// The `=` token.
@@ -578,7 +571,7 @@ impl<'s> span::Builder<'s> for ArgumentDefault<'s> {
 }
 
 /// A type ascribed to an argument definition.
-#[cfg_attr(feature = "debug", derive(Visitor))]
+#[derive(Visitor)]
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Reflect, Deserialize)]
 pub struct ArgumentType<'s> {
     /// The `:` token.
@@ -595,7 +588,7 @@ impl<'s> span::Builder<'s> for ArgumentType<'s> {
 }
 
 /// A function return type specification.
-#[cfg_attr(feature = "debug", derive(Visitor))]
+#[derive(Visitor)]
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Reflect, Deserialize)]
 pub struct ReturnSpecification<'s> {
     /// The `->` operator.
@@ -615,7 +608,7 @@ impl<'s> span::Builder<'s> for ReturnSpecification<'s> {
 // === CaseOf ===
 
 /// A line that may contain a case-expression in a case-of expression.
-#[cfg_attr(feature = "debug", derive(Visitor))]
+#[derive(Visitor)]
 #[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Reflect, Deserialize)]
 pub struct CaseLine<'s> {
     /// The token beginning the line. This will always be present, unless the first case-expression
@@ -643,7 +636,7 @@ impl<'s> span::Builder<'s> for CaseLine<'s> {
 }
 
 /// A case-expression in a case-of expression.
-#[cfg_attr(feature = "debug", derive(Visitor))]
+#[derive(Visitor)]
 #[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Reflect, Deserialize)]
 pub struct Case<'s> {
     /// Documentation, if present.
@@ -683,7 +676,7 @@ impl<'s> span::Builder<'s> for Case<'s> {
 pub type OperatorOrError<'s> = Result<token::Operator<'s>, MultipleOperatorError<'s>>;
 
 /// Error indicating multiple operators found next to each other, like `a + * b`.
-#[cfg_attr(feature = "debug", derive(Visitor))]
+#[derive(Visitor)]
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Reflect, Deserialize)]
 #[allow(missing_docs)]
 pub struct MultipleOperatorError<'s> {
@@ -723,7 +716,7 @@ impl<'s> NonEmptyOperatorSequence<'s> for OperatorOrError<'s> {
 // === MultiSegmentApp ===
 
 /// A segment of [`MultiSegmentApp`], like `if cond` in the `if cond then ok else fail` expression.
-#[cfg_attr(feature = "debug", derive(Visitor))]
+#[derive(Visitor)]
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Reflect, Deserialize)]
 #[allow(missing_docs)]
 pub struct MultiSegmentAppSegment<'s> {
@@ -741,7 +734,7 @@ impl<'s> span::Builder<'s> for MultiSegmentAppSegment<'s> {
 // === Array and Tuple ===
 
 /// A node following an operator.
-#[cfg_attr(feature = "debug", derive(Visitor))]
+#[derive(Visitor)]
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Reflect, Deserialize)]
 pub struct OperatorDelimitedTree<'s> {
     /// The delimiting operator.
@@ -783,6 +776,9 @@ pub fn apply<'s>(mut func: Tree<'s>, mut arg: Tree<'s>) -> Tree<'s> {
             Variant::ArgumentBlockApplication(ArgumentBlockApplication { lhs: None, arguments }),
         ) => {
             func.span.code_length += arg.span.length_including_whitespace();
+            if let Some(first) = arguments.first_mut() {
+                first.newline.left_offset += arg.span.left_offset.take_as_prefix();
+            }
             *rhs = block::body_from_lines(mem::take(arguments)).into();
             func
         }
@@ -904,7 +900,7 @@ pub fn apply_operator<'s>(
         && opr_.properties.is_special()
     {
         let tree = Tree::opr_app(lhs, opr, rhs);
-        return tree.with_error("Invalid use of special operator.");
+        return tree.with_error(ErrorCode::InvalidSpecialOperator);
     }
     if let Ok(opr_) = &opr
         && opr_.properties.is_type_annotation()
@@ -912,8 +908,12 @@ pub fn apply_operator<'s>(
         return match (lhs, rhs) {
             (Some(lhs), Some(rhs)) => Tree::type_annotated(lhs, opr.unwrap(), rhs),
             (lhs, rhs) => {
+                let operator = opr_.code.range();
                 let invalid = Tree::opr_app(lhs, opr, rhs);
-                invalid.with_error("`:` operator must be applied to two operands.")
+                invalid.with_error_from(|tree| {
+                    let primary = ErrorSpan::within(tree, operator);
+                    Error::new(ErrorCode::IncompleteTypeAnnotation, primary)
+                })
             }
         };
     }
@@ -922,9 +922,11 @@ pub fn apply_operator<'s>(
         && lhs.is_none()
         && rhs.is_none()
     {
-        let error = format!("Operator `{opr:?}` must be applied to two operands.");
+        let message = format!("Operator `{}` must be applied to two operands.", opr_.code);
         let invalid = Tree::opr_app(lhs, opr, rhs);
-        return invalid.with_error(error);
+        return invalid.with_error_from(|tree| {
+            Error::new(ErrorCode::MissingOperands, ErrorSpan::of_tree(tree)).with_message(message)
+        });
     }
     if let Ok(opr) = &opr
         && opr.properties.is_decimal()
@@ -981,19 +983,23 @@ pub fn apply_unary_operator<'s>(opr: token::Operator<'s>, rhs: Option<Tree<'s>>)
             return if applied_to_type {
                 autoscope_application
             } else {
-                autoscope_application.with_error(
-                    "The auto-scope operator may only be applied to a capitalized identifier.",
-                )
+                autoscope_application.with_error_from(|tree| {
+                    Error::new(ErrorCode::AutoscopeNonConstructor, ErrorSpan::of_tree(tree))
+                        .with_note(
+                            "The auto-scope operator refers to a constructor of the expected type.",
+                        )
+                })
             };
         } else {
-            Tree::unary_opr_app(opr, Some(rhs))
-                .with_error("The auto-scope operator (..) may only be applied to an identifier.")
+            Tree::unary_opr_app(opr, Some(rhs)).with_error(ErrorCode::AutoscopeNonIdentifier)
         };
     }
     if !opr.properties.can_form_section() && rhs.is_none() {
-        let error = format!("Operator `{opr:?}` must be applied to an operand.");
+        let message = format!("Operator `{}` must be applied to an operand.", opr.code);
         let invalid = Tree::unary_opr_app(opr, rhs);
-        return invalid.with_error(error);
+        return invalid.with_error_from(|tree| {
+            Error::new(ErrorCode::MissingOperand, ErrorSpan::of_tree(tree)).with_message(message)
+        });
     }
     Tree::unary_opr_app(opr, rhs)
 }
@@ -1031,9 +1037,12 @@ pub fn to_ast(token: Token) -> Tree {
         token::Variant::Wildcard(wildcard) => Tree::wildcard(token.with_variant(wildcard), default()),
         token::Variant::SuspendedDefaultArguments(t) => Tree::suspended_default_arguments(token.with_variant(t)),
         token::Variant::OpenSymbol(s) =>
-            Tree::group(Some(token.with_variant(s)), default(), default()).with_error("Unmatched delimiter"),
+            Tree::group(Some(token.with_variant(s)), default(), default()).with_error(ErrorCode::UnmatchedDelimiter),
         token::Variant::CloseSymbol(s) =>
-            Tree::group(default(), default(), Some(token.with_variant(s))).with_error("Unmatched delimiter"),
+            Tree::group(default(), default(), Some(token.with_variant(s))).with_error_from(|tree| {
+                Error::new(ErrorCode::UnmatchedDelimiter, ErrorSpan::of_tree(tree))
+                    .with_fix(ErrorSpan::of_tree(tree), "", "Remove the unmatched delimiter")
+            }),
         // These should be unreachable: They are handled when assembling items into blocks,
         // before parsing proper.
         token::Variant::Newline(_)
@@ -1044,10 +1053,12 @@ pub fn to_ast(token: Token) -> Tree {
         | token::Variant::Private(_)
         // Map an error case in the lexer to an error in the AST.
         | token::Variant::Invalid(_) => {
-            let message = format!("Unexpected token: {token:?}");
+            let message = format!("Unexpected token: `{}` ({:?})", token.code, token.variant);
             let ident = token::variant::Ident(false, 0, false, false, false);
             let value = Tree::ident(token.with_variant(ident));
-            Tree::with_error(value, message)
+            value.with_error_from(|tree| {
+                Error::new(ErrorCode::UnexpectedToken, ErrorSpan::of_tree(tree)).with_message(message)
+            })
         }
     }
 }
@@ -1092,12 +1103,10 @@ impl<'s> From<token::Ident<'s>> for Tree<'s> {
 /// could move to it as soon as this error gets resolved:
 /// https://github.com/rust-lang/rust/issues/96634.
 #[allow(missing_docs)]
-#[cfg(feature = "debug")]
 pub trait Visitor {}
 
 /// The visitor trait allowing for [`Item`] traversal.
 #[allow(missing_docs)]
-#[cfg(feature = "debug")]
 pub trait ItemVisitor<'s, 'a>: Visitor {
     fn visit_item(&mut self, ast: item::Ref<'s, 'a>) -> bool;
 }
@@ -1110,13 +1119,11 @@ macro_rules! define_visitor {
         $visitable:ident
     ) => {
         /// The visitable trait. See documentation of [`define_visitor`] to learn more.
-        #[cfg(feature = "debug")]
         #[allow(missing_docs)]
         pub trait $visitable<'s, 'a> {
             fn $visit<V: $visitor<'s, 'a>>(&'a self, _visitor: &mut V) {}
         }
 
-        #[cfg(feature = "debug")]
         impl<'s, 'a, T: $visitable<'s, 'a>> $visitable<'s, 'a> for Option<T> {
             fn $visit<V: $visitor<'s, 'a>>(&'a self, visitor: &mut V) {
                 if let Some(elem) = self {
@@ -1125,7 +1132,6 @@ macro_rules! define_visitor {
             }
         }
 
-        #[cfg(feature = "debug")]
         impl<'s, 'a, T: $visitable<'s, 'a>, E: $visitable<'s, 'a>> $visitable<'s, 'a>
             for Result<T, E>
         {
@@ -1137,14 +1143,12 @@ macro_rules! define_visitor {
             }
         }
 
-        #[cfg(feature = "debug")]
         impl<'s, 'a, T: $visitable<'s, 'a>> $visitable<'s, 'a> for Vec<T> {
             fn $visit<V: $visitor<'s, 'a>>(&'a self, visitor: &mut V) {
                 self.iter().map(|t| $visitable::$visit(t, visitor)).for_each(drop);
             }
         }
 
-        #[cfg(feature = "debug")]
         impl<'s, 'a, T: $visitable<'s, 'a>> $visitable<'s, 'a> for NonEmptyVec<T> {
             fn $visit<V: $visitor<'s, 'a>>(&'a self, visitor: &mut V) {
                 self.iter().map(|t| $visitable::$visit(t, visitor)).for_each(drop);
@@ -1160,7 +1164,6 @@ define_visitor!(Item, visit_item, ItemVisitor, ItemVisitable);
 
 macro_rules! spanless_leaf_impls {
     ($ty:ty) => {
-        #[cfg(feature = "debug")]
         impl<'a, 's> ItemVisitable<'s, 'a> for $ty {}
         impl<'s> span::Builder<'s> for $ty {
             fn add_to_span(&mut self, span: Span<'s>) -> Span<'s> {
@@ -1174,12 +1177,12 @@ spanless_leaf_impls!(u32);
 spanless_leaf_impls!(bool);
 spanless_leaf_impls!(VisibleOffset);
 spanless_leaf_impls!(Cow<'static, str>);
+spanless_leaf_impls!(Error);
 
 
 
 // === ItemVisitable special cases ===
 
-#[cfg(feature = "debug")]
 impl<'s, 'a> ItemVisitable<'s, 'a> for Tree<'s> {
     fn visit_item<V: ItemVisitor<'s, 'a>>(&'a self, visitor: &mut V) {
         if visitor.visit_item(item::Ref::Tree(self)) {
@@ -1188,7 +1191,6 @@ impl<'s, 'a> ItemVisitable<'s, 'a> for Tree<'s> {
     }
 }
 
-#[cfg(feature = "debug")]
 impl<'s: 'a, 'a, T: 'a> ItemVisitable<'s, 'a> for Token<'s, T>
 where &'a Token<'s, T>: Into<token::Ref<'s, 'a>>
 {
@@ -1204,16 +1206,13 @@ where &'a Token<'s, T>: Into<token::Ref<'s, 'a>>
 // ==========================
 
 /// A visitor collecting code representation of AST nodes.
-#[cfg(feature = "debug")]
 #[derive(Debug, Default)]
 #[allow(missing_docs)]
 struct CodePrinterVisitor {
     pub code: String,
 }
 
-#[cfg(feature = "debug")]
 impl Visitor for CodePrinterVisitor {}
-#[cfg(feature = "debug")]
 impl<'s, 'a> ItemVisitor<'s, 'a> for CodePrinterVisitor {
     fn visit_item(&mut self, item: item::Ref<'s, 'a>) -> bool {
         match item {
@@ -1227,7 +1226,6 @@ impl<'s, 'a> ItemVisitor<'s, 'a> for CodePrinterVisitor {
     }
 }
 
-#[cfg(feature = "debug")]
 impl<'s> Tree<'s> {
     /// Code generator of this AST.
     pub fn code(&self) -> String {
@@ -1250,7 +1248,6 @@ impl<'s> Tree<'s> {
 // === ItemFnVisitor ===
 // =====================
 
-#[cfg(feature = "debug")]
 impl<'s> Tree<'s> {
     /// Apply the provided function to each [`Token`] or [`Tree`] that is a child of the node.
     pub fn visit_items<F>(&self, f: F)
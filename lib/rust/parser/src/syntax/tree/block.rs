@@ -9,7 +9,7 @@ use crate::syntax::tree::*;
 // =============
 
 /// A line of code.
-#[cfg_attr(feature = "debug", derive(Visitor))]
+#[derive(Visitor)]
 #[derive(Debug, Clone, PartialEq, Eq, Reflect, Serialize, Deserialize)]
 pub struct Line<'s> {
     /// Token ending the previous line, if any.
@@ -197,7 +197,7 @@ impl<'s> From<Prefix<'s>> for Tree<'s> {
 // ======================
 
 /// The content of a line in an operator block.
-#[cfg_attr(feature = "debug", derive(Visitor))]
+#[derive(Visitor)]
 #[derive(Debug, Clone, PartialEq, Eq, Reflect, Serialize, Deserialize)]
 pub struct OperatorBlockExpression<'s> {
     /// The operator at the beginning of the line.
@@ -236,7 +236,7 @@ impl<'s> span::Builder<'s> for OperatorBlockExpression<'s> {
 // === Operator block lines ====
 
 /// A line in an operator block.
-#[cfg_attr(feature = "debug", derive(Visitor))]
+#[derive(Visitor)]
 #[derive(Debug, Clone, PartialEq, Eq, Reflect, Serialize, Deserialize)]
 pub struct OperatorLine<'s> {
     /// Token ending the previous line, if any.
@@ -0,0 +1,360 @@
+//! Structured syntax errors attached to [`Invalid`] nodes.
+//!
+//! Every error carries a stable [`ErrorCode`], so that consumers (the IDE, the compiler) can
+//! identify and filter errors without inspecting the human-readable message. Spans within an error
+//! are stored relative to the start of the code of the `Invalid` node carrying it; this keeps the
+//! error valid when the tree is moved (e.g. by incremental reparsing).
+
+use crate::syntax::tree::*;
+
+use crate::source::code::Length;
+use crate::source::code::Location;
+
+
+
+// =================
+// === ErrorCode ===
+// =================
+
+/// Identifies a kind of syntax error.
+///
+/// Variants must only be appended to this enum: the variant index is part of the serialized format,
+/// and the [`ErrorCode::code`] strings are meant to be stable identifiers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Reflect, Deserialize)]
+pub enum ErrorCode {
+    /// The parser encountered an internal inconsistency.
+    Internal,
+    /// A token that cannot appear at this position.
+    UnexpectedToken,
+    /// An opening or closing delimiter without a counterpart.
+    UnmatchedDelimiter,
+    /// A macro invocation that doesn't match any of the macro's patterns.
+    InvalidMacroInvocation,
+    /// Tokens following a complete macro invocation.
+    UnexpectedMacroTokens,
+    /// A macro segment that requires a body has none.
+    ExpectedTokens,
+    /// An identifier was expected.
+    ExpectedIdentifier,
+    /// A (possibly qualified) name was expected.
+    ExpectedQualifiedName,
+    /// A `type` keyword isn't followed by the name of the type.
+    ExpectedTypeName,
+    /// An `import` statement without anything to import.
+    IncompleteImport,
+    /// An `export` statement without anything to export.
+    IncompleteExport,
+    /// A `case` expression containing malformed cases.
+    InvalidCase,
+    /// A comma-delimited sequence with a missing element.
+    MalformedSequence,
+    /// A `foreign` declaration without a language identifier.
+    ForeignLanguageExpected,
+    /// A `foreign` declaration without a function name.
+    ForeignNameExpected,
+    /// A `foreign` declaration not followed by a function definition.
+    ForeignFunctionExpected,
+    /// A `foreign` function whose body isn't a text literal.
+    ForeignBodyNotText,
+    /// An operator that is only valid in special contexts, used elsewhere.
+    InvalidSpecialOperator,
+    /// A type annotation operator (`:`) missing an operand.
+    IncompleteTypeAnnotation,
+    /// A binary operator applied to no operands.
+    MissingOperands,
+    /// A unary operator applied to no operand.
+    MissingOperand,
+    /// A unary operator directly following a term.
+    SpaceRequired,
+    /// The autoscope operator applied to an identifier that doesn't refer to a constructor.
+    AutoscopeNonConstructor,
+    /// The autoscope operator applied to something other than an identifier.
+    AutoscopeNonIdentifier,
+    /// The autoscope operator used in a pattern.
+    AutoscopeInPattern,
+    /// An argument block not preceded by an expression.
+    ExpectedExpressionBeforeBlock,
+    /// An assignment whose left-hand side is neither a variable nor a function definition.
+    InvalidAssignment,
+    /// An escape sequence in a text literal that doesn't denote a valid character.
+    InvalidEscapeSequence,
+}
+
+impl ErrorCode {
+    /// The stable identifier of the error, e.g. `E0001`.
+    pub fn code(self) -> &'static str {
+        use ErrorCode::*;
+        match self {
+            Internal => "E0000",
+            UnexpectedToken => "E0001",
+            UnmatchedDelimiter => "E0002",
+            InvalidMacroInvocation => "E0003",
+            UnexpectedMacroTokens => "E0004",
+            ExpectedTokens => "E0005",
+            ExpectedIdentifier => "E0006",
+            ExpectedQualifiedName => "E0007",
+            ExpectedTypeName => "E0008",
+            IncompleteImport => "E0009",
+            IncompleteExport => "E0010",
+            InvalidCase => "E0011",
+            MalformedSequence => "E0012",
+            ForeignLanguageExpected => "E0013",
+            ForeignNameExpected => "E0014",
+            ForeignFunctionExpected => "E0015",
+            ForeignBodyNotText => "E0016",
+            InvalidSpecialOperator => "E0017",
+            IncompleteTypeAnnotation => "E0018",
+            MissingOperands => "E0019",
+            MissingOperand => "E0020",
+            SpaceRequired => "E0021",
+            AutoscopeNonConstructor => "E0022",
+            AutoscopeNonIdentifier => "E0023",
+            AutoscopeInPattern => "E0024",
+            ExpectedExpressionBeforeBlock => "E0025",
+            InvalidAssignment => "E0026",
+            InvalidEscapeSequence => "E0027",
+        }
+    }
+
+    /// The default message describing the error.
+    pub fn message(self) -> &'static str {
+        use ErrorCode::*;
+        match self {
+            Internal => "Internal error.",
+            UnexpectedToken => "Unexpected token.",
+            UnmatchedDelimiter => "Unmatched delimiter.",
+            InvalidMacroInvocation => "Invalid macro invocation.",
+            UnexpectedMacroTokens => "Unexpected tokens in macro invocation.",
+            ExpectedTokens => "Expected tokens.",
+            ExpectedIdentifier => "Expected identifier.",
+            ExpectedQualifiedName => "Expected qualified name.",
+            ExpectedTypeName => "Expected identifier after `type` keyword.",
+            IncompleteImport => "Expected name or `all` keyword following `import` keyword.",
+            IncompleteExport => "Expected name or `all` keyword following `export` keyword.",
+            InvalidCase => "Invalid case expression.",
+            MalformedSequence => "Malformed comma-delimited sequence.",
+            ForeignLanguageExpected =>
+                "Expected an identifier specifying foreign method's language.",
+            ForeignNameExpected => "Expected an identifier specifying foreign function's name.",
+            ForeignFunctionExpected => "Expected a function definition after foreign declaration.",
+            ForeignBodyNotText => "Expected a text literal as body of `foreign` declaration.",
+            InvalidSpecialOperator => "Invalid use of special operator.",
+            IncompleteTypeAnnotation => "`:` operator must be applied to two operands.",
+            MissingOperands => "Operator must be applied to two operands.",
+            MissingOperand => "Operator must be applied to an operand.",
+            SpaceRequired => "Space required between term and unary-operator expression.",
+            AutoscopeNonConstructor =>
+                "The auto-scope operator may only be applied to a capitalized identifier.",
+            AutoscopeNonIdentifier =>
+                "The auto-scope operator (..) may only be applied to an identifier.",
+            AutoscopeInPattern => "The autoscope operator (..) cannot be used in a pattern.",
+            ExpectedExpressionBeforeBlock => "Expected expression before indented block.",
+            InvalidAssignment => "Invalid use of assignment operator `=`.",
+            InvalidEscapeSequence => "Invalid escape sequence.",
+        }
+    }
+
+    /// The severity errors of this kind are reported with. Invalid escape sequences are only
+    /// warnings: the parser doesn't reject the literal containing them, it just omits the escape
+    /// from the literal's value.
+    pub fn severity(self) -> Severity {
+        use ErrorCode::*;
+        match self {
+            InvalidEscapeSequence => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+impl Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+
+// === Severity ===
+
+/// How serious a reported problem is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Reflect, Deserialize)]
+pub enum Severity {
+    /// The code is not valid Enso.
+    Error,
+    /// The code is valid, but likely not what was intended.
+    Warning,
+}
+
+
+
+// =================
+// === ErrorSpan ===
+// =================
+
+/// A range of code, relative to the start of the code of the [`Invalid`] node an error is
+/// attached to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Reflect, Deserialize)]
+pub struct ErrorSpan {
+    /// Distance from the start of the node's code to the start of the range.
+    pub offset: Length,
+    /// Length of the range.
+    pub length: Length,
+}
+
+impl ErrorSpan {
+    /// A span covering all the code of the given node.
+    pub fn of_tree(tree: &Tree) -> Self {
+        Self { offset: default(), length: tree.span.code_length }
+    }
+
+    /// A span covering the given range of the source, which must be within the given node.
+    pub fn within(tree: &Tree, range: Range<Location>) -> Self {
+        let start = tree.span.range().start;
+        Self {
+            offset: length_between(start, range.start),
+            length: length_between(range.start, range.end),
+        }
+    }
+
+    /// An empty span at the given location, which must be within the given node.
+    pub fn at(tree: &Tree, location: Location) -> Self {
+        Self::within(tree, location..location)
+    }
+
+    /// Return the range of the source covered by this span, given the start of the code of the node
+    /// it belongs to.
+    pub fn range(&self, node_start: Location) -> Range<Location> {
+        let start = node_start + self.offset;
+        start..(start + self.length)
+    }
+}
+
+/// The [`Length`] of the code between two locations.
+fn length_between(start: Location, end: Location) -> Length {
+    let newlines = end.line.saturating_sub(start.line);
+    Length {
+        utf8: end.utf8.saturating_sub(start.utf8),
+        utf16: end.utf16.saturating_sub(start.utf16),
+        newlines,
+        line_chars16: match newlines {
+            0 => end.col16.saturating_sub(start.col16),
+            _ => end.col16,
+        },
+    }
+}
+
+
+// === Label ===
+
+/// A secondary span related to an error, with an explanation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Reflect, Deserialize)]
+pub struct Label {
+    /// The code the label refers to.
+    pub span:    ErrorSpan,
+    /// Explanation of how the code is related to the error.
+    pub message: Cow<'static, str>,
+}
+
+
+// === Fix ===
+
+/// A suggested edit that would resolve an error.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Reflect, Deserialize)]
+pub struct Fix {
+    /// The code to be replaced.
+    pub span:        ErrorSpan,
+    /// The code to replace it with.
+    pub replacement: Cow<'static, str>,
+    /// Short description of the edit, suitable for presenting as a quick-fix action.
+    pub description: Cow<'static, str>,
+}
+
+
+
+// =============
+// === Error ===
+// =============
+
+/// Error of parsing attached to an [`Tree`] node.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Reflect, Deserialize)]
+pub struct Error {
+    /// Identifies the kind of error.
+    pub code:     ErrorCode,
+    /// How serious the error is.
+    pub severity: Severity,
+    /// Human-readable description of the error.
+    pub message:  Cow<'static, str>,
+    /// The code the error is reported at.
+    pub primary:  ErrorSpan,
+    /// Other code related to the error.
+    pub labels:   Vec<Label>,
+    /// Additional explanations.
+    pub notes:    Vec<Cow<'static, str>>,
+    /// An edit that would resolve the error, if one is known.
+    pub fix:      Option<Fix>,
+}
+
+impl Error {
+    /// Constructor. The message and severity are the defaults for the error code.
+    pub fn new(code: ErrorCode, primary: ErrorSpan) -> Self {
+        Self {
+            code,
+            severity: code.severity(),
+            message: code.message().into(),
+            primary,
+            labels: default(),
+            notes: default(),
+            fix: default(),
+        }
+    }
+
+    /// Replace the default message.
+    pub fn with_message(mut self, message: impl Into<Cow<'static, str>>) -> Self {
+        self.message = message.into();
+        self
+    }
+
+    /// Add a secondary span.
+    pub fn with_label(mut self, span: ErrorSpan, message: impl Into<Cow<'static, str>>) -> Self {
+        self.labels.push(Label { span, message: message.into() });
+        self
+    }
+
+    /// Add a note.
+    pub fn with_note(mut self, note: impl Into<Cow<'static, str>>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    /// Attach a suggested fix.
+    pub fn with_fix(
+        mut self,
+        span: ErrorSpan,
+        replacement: impl Into<Cow<'static, str>>,
+        description: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        let replacement = replacement.into();
+        let description = description.into();
+        self.fix = Some(Fix { span, replacement, description });
+        self
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.code, self.message)
+    }
+}
+
+impl<'s> Tree<'s> {
+    /// Wrap this tree in an [`Invalid`] node, reporting an error covering the whole tree.
+    pub fn with_error(self, code: ErrorCode) -> Self {
+        let error = Error::new(code, ErrorSpan::of_tree(&self));
+        Tree::invalid(error, self)
+    }
+
+    /// Wrap this tree in an [`Invalid`] node, reporting the error produced by the given function.
+    pub fn with_error_from(self, f: impl FnOnce(&Self) -> Error) -> Self {
+        let error = f(&self);
+        Tree::invalid(error, self)
+    }
+}
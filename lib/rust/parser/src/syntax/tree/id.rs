@@ -0,0 +1,191 @@
+//! Stable identifiers of [`Tree`] nodes.
+//!
+//! Node IDs allow tools to refer to parts of a program across edits. The IDs of a module's nodes
+//! are persisted in the [`IdMap`] of its metadata section, where each ID is associated with the
+//! source code location of its node. [`assign`] completes such a map, so that it has an entry for
+//! the location of every node of a parsed tree:
+//! - A node whose code matches the location of an entry in the map receives the ID of the entry.
+//!   This is the same ID that [`crate::metadata::Metadata::get_uuid`] returns for the node.
+//! - Any other node receives a fresh ID, derived from the ID of its parent and its position among
+//!   its parent's children. Fresh IDs are deterministic: the same input always produces the same
+//!   IDs.
+//!
+//! As a parent's own ID may be fresh, a fresh ID is determined by the nearest ancestor whose ID is
+//! found in the map (or the root, if there is none), and the positions of the nodes on the path
+//! from that ancestor. Edits outside that ancestor don't change the fresh IDs of its descendants,
+//! provided that the map is updated to follow the edits (see [`IdMap::remap`]); but edits that
+//! insert or remove a node before one on the path do.
+//!
+//! Because nodes are identified by their locations, nested nodes with the same code share an ID:
+//! the ID of the outermost such node.
+//!
+//! The IDs are kept in a side table rather than in the [`Tree`], so that trees and their serialized
+//! forms are not enlarged by them.
+
+use crate::syntax::tree::*;
+
+use crate::metadata::IdMap;
+use crate::metadata::Span as IdSpan;
+
+use std::collections::HashMap;
+
+use uuid::Uuid;
+
+
+
+// ==============
+// === Assign ===
+// ==============
+
+/// Return an [`IdMap`] that contains the entries of the given map that match nodes of the tree,
+/// and an entry for every other node of the tree. See the module documentation for details.
+///
+/// Locations in the map are compared to the code of the nodes in UTF-16 code units, consistent with
+/// [`crate::metadata::Metadata::get_uuid`].
+pub fn assign(tree: &Tree, id_map: &IdMap) -> IdMap {
+    let known = id_map.iter().collect();
+    let mut assigner = Assigner { known, assigned: default(), parent: Uuid::nil(), index: 0 };
+    tree.visit_item(&mut assigner);
+    let Assigner { assigned, .. } = assigner;
+    let mut entries: Vec<_> = assigned.into_iter().collect();
+    entries.sort_unstable_by_key(|(_, (order, _))| *order);
+    entries.into_iter().map(|(span, (_, id))| (span, id)).collect()
+}
+
+struct Assigner {
+    known:    HashMap<IdSpan, Uuid>,
+    /// The ID assigned to each location, and the order in which it was assigned.
+    assigned: HashMap<IdSpan, (usize, Uuid)>,
+    /// The ID of the parent of the next node visited.
+    parent:   Uuid,
+    /// The number of children of the parent visited so far.
+    index:    usize,
+}
+
+impl Visitor for Assigner {}
+impl<'s, 'a> ItemVisitor<'s, 'a> for Assigner {
+    fn visit_item(&mut self, item: item::Ref<'s, 'a>) -> bool {
+        let item::Ref::Tree(tree) = item else { return false };
+        let span = IdSpan::of_tree(tree);
+        let fresh = child_id(self.parent, self.index);
+        let order = self.assigned.len();
+        let id = self.known.get(&span).copied().unwrap_or(fresh);
+        let id = self.assigned.entry(span).or_insert((order, id)).1;
+        let parent = mem::replace(&mut self.parent, id);
+        let index = mem::replace(&mut self.index, 0);
+        tree.variant.visit_item(self);
+        self.parent = parent;
+        self.index = index + 1;
+        false
+    }
+}
+
+/// Derive the ID of a child of the node with the given ID, from the child's index.
+///
+/// The result is a version 8 (custom) UUID.
+fn child_id(parent: Uuid, index: usize) -> Uuid {
+    let (parent_high, parent_low) = parent.as_u64_pair();
+    let seed = mix(parent_high ^ mix(parent_low ^ mix(index as u64)));
+    let high = mix(seed);
+    let low = mix(high ^ seed);
+    let high = (high & !0xF000) | 0x8000;
+    let low = (low & !(0b11 << 62)) | (0b10 << 62);
+    Uuid::from_u64_pair(high, low)
+}
+
+/// The finalizer of the SplitMix64 generator: a bijective function on 64-bit values, that changes
+/// about half of the bits of its output when any bit of its input is changed.
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The locations of the nodes of the tree, in pre-order.
+    fn spans(tree: &Tree) -> Vec<IdSpan> {
+        let mut spans = vec![];
+        struct Spans<'v>(&'v mut Vec<IdSpan>);
+        impl<'v> Visitor for Spans<'v> {}
+        impl<'v, 's, 'a> ItemVisitor<'s, 'a> for Spans<'v> {
+            fn visit_item(&mut self, item: item::Ref<'s, 'a>) -> bool {
+                if let item::Ref::Tree(tree) = item {
+                    self.0.push(IdSpan::of_tree(tree));
+                }
+                true
+            }
+        }
+        tree.visit_item(&mut Spans(&mut spans));
+        spans
+    }
+
+    #[test]
+    fn fresh_ids_are_deterministic_and_unique() {
+        let code = "main =\n    x = foo bar\n    x + 1\n";
+        let tree = crate::Parser::new().run(code);
+        let ids = assign(&tree, &default());
+        assert_eq!(ids, assign(&tree, &default()));
+        let mut spans = spans(&tree);
+        spans.dedup();
+        assert_eq!(ids.iter().map(|(span, _)| span).collect::<Vec<_>>(), spans);
+        assert!(ids.iter().all(|(_, id)| id.get_version_num() == 8));
+        let unique: std::collections::HashSet<_> = ids.iter().map(|(_, id)| id).collect();
+        assert_eq!(unique.len(), ids.len());
+    }
+
+    #[test]
+    fn ids_from_map() {
+        let app = Uuid::from_u64_pair(1, 1);
+        let foo = Uuid::from_u64_pair(1, 2);
+        let unmatched = Uuid::from_u64_pair(1, 3);
+        let mut id_map = IdMap::default();
+        id_map.insert(IdSpan::new(4, 7), app);
+        id_map.insert(IdSpan::new(4, 3), foo);
+        id_map.insert(IdSpan::new(5, 3), unmatched);
+        let ids = assign(&crate::Parser::new().run("x = foo bar"), &id_map);
+        assert_eq!(ids.get(IdSpan::new(4, 7)), Some(app));
+        assert_eq!(ids.get(IdSpan::new(4, 3)), Some(foo));
+        assert_eq!(ids.get(IdSpan::new(5, 3)), None);
+        for (span, id) in ids.iter() {
+            if span != IdSpan::new(4, 7) && span != IdSpan::new(4, 3) {
+                assert_eq!(id.get_version_num(), 8);
+            }
+        }
+    }
+
+    #[test]
+    fn fresh_ids_follow_the_nearest_ancestor_from_the_map() {
+        let parser = crate::Parser::new();
+        let app = Uuid::from_u64_pair(1, 1);
+        let foo = |code: &str, app_span: Option<IdSpan>| {
+            let mut id_map = IdMap::default();
+            if let Some(span) = app_span {
+                id_map.insert(span, app);
+            }
+            let ids = assign(&parser.run(code), &id_map);
+            let foo = code.find("foo").unwrap();
+            ids.get(IdSpan::new(foo, 3)).unwrap()
+        };
+        let app_id = Some(IdSpan::new(4, 7));
+        let moved_app_id = Some(IdSpan::new(10, 7));
+        assert_eq!(foo("x = foo bar", app_id), foo("z = 1\nx = foo bar", moved_app_id));
+        assert_ne!(foo("x = foo bar", None), foo("z = 1\nx = foo bar", None));
+    }
+
+    #[test]
+    fn nested_nodes_with_the_same_code_share_an_id() {
+        let tree = crate::Parser::new().run("x");
+        assert_eq!(spans(&tree), [IdSpan::new(0, 1), IdSpan::new(0, 1)]);
+        assert_eq!(assign(&tree, &default()).len(), 1);
+    }
+}
@@ -0,0 +1,496 @@
+//! The values of literals.
+//!
+//! A [`Number`] stores the tokens it was written with, and a [`TextLiteral`] stores its content as
+//! sections of source code interleaved with escape sequences. [`Number::value`] decodes a numeric
+//! literal to its exact value: an arbitrary-precision [`Integer`], or a [`Decimal`] consisting of
+//! an integer mantissa and a decimal exponent. [`TextLiteral::value`] decodes a text literal to the
+//! string it denotes, with escape sequences replaced by the characters they represent, and the
+//! indentation of a multiline literal trimmed (the lexer excludes the trimmed indentation from the
+//! text sections, so that it is not part of their code).
+//!
+//! Literals that don't denote a value, such as `0x` or `'\u{110000}'`, produce a [`LiteralError`]
+//! identifying the problem and its location.
+
+use crate::syntax::tree::*;
+
+use crate::source::code::Location;
+
+
+
+// ===============
+// === Integer ===
+// ===============
+
+/// A non-negative integer of arbitrary size.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Integer {
+    /// The base-2^32 digits of the number, least significant first, without trailing zeros.
+    limbs: Vec<u32>,
+}
+
+impl Integer {
+    /// Parse the digits of a number in the given radix, ignoring any underscores separating them.
+    /// Returns `None` if any other character is not a digit in the radix.
+    pub fn from_digits(digits: &str, radix: u32) -> Option<Self> {
+        let mut value = Self::default();
+        for c in digits.chars().filter(|&c| c != '_') {
+            value.mul_add(radix, c.to_digit(radix)?);
+        }
+        Some(value)
+    }
+
+    /// Return whether the value is zero.
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    /// The value, if it fits in a `u64`.
+    pub fn to_u64(&self) -> Option<u64> {
+        match self.limbs[..] {
+            [] => Some(0),
+            [low] => Some(low as u64),
+            [low, high] => Some((high as u64) << 32 | low as u64),
+            _ => None,
+        }
+    }
+
+    /// The value, if it fits in an `i64`.
+    pub fn to_i64(&self) -> Option<i64> {
+        self.to_u64()?.try_into().ok()
+    }
+
+    /// The nearest `f64` to the value; infinity if it exceeds the range of `f64`.
+    pub fn to_f64(&self) -> f64 {
+        self.to_string().parse().unwrap()
+    }
+
+    fn mul_add(&mut self, mul: u32, add: u32) {
+        let mut carry = add as u64;
+        for limb in &mut self.limbs {
+            let value = *limb as u64 * mul as u64 + carry;
+            *limb = value as u32;
+            carry = value >> 32;
+        }
+        if carry != 0 {
+            self.limbs.push(carry as u32);
+        }
+    }
+
+    /// Divide the value in place, returning the remainder.
+    fn div_rem(&mut self, divisor: u32) -> u32 {
+        let mut remainder = 0u64;
+        for limb in self.limbs.iter_mut().rev() {
+            let value = remainder << 32 | *limb as u64;
+            *limb = (value / divisor as u64) as u32;
+            remainder = value % divisor as u64;
+        }
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+        remainder as u32
+    }
+}
+
+impl From<u64> for Integer {
+    fn from(value: u64) -> Self {
+        let limbs = [value as u32, (value >> 32) as u32];
+        let len = limbs.iter().rposition(|&limb| limb != 0).map_or(0, |i| i + 1);
+        Self { limbs: limbs[..len].to_vec() }
+    }
+}
+
+impl Display for Integer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const CHUNK: u32 = 1_000_000_000;
+        let mut value = self.clone();
+        let mut chunks = vec![];
+        loop {
+            chunks.push(value.div_rem(CHUNK));
+            if value.is_zero() {
+                break;
+            }
+        }
+        let mut chunks = chunks.iter().rev();
+        write!(f, "{}", chunks.next().unwrap())?;
+        chunks.try_for_each(|chunk| write!(f, "{chunk:09}"))
+    }
+}
+
+
+
+// ===================
+// === NumberValue ===
+// ===================
+
+/// A number with a fractional part, represented exactly: `mantissa * 10^exponent`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Decimal {
+    /// The digits of the number, as an integer.
+    pub mantissa: Integer,
+    /// The power of ten the mantissa is multiplied by; the negation of the number of fractional
+    /// digits.
+    pub exponent: i64,
+}
+
+impl Decimal {
+    /// The nearest `f64` to the value.
+    pub fn to_f64(&self) -> f64 {
+        format!("{}e{}", self.mantissa, self.exponent).parse().unwrap()
+    }
+}
+
+impl Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.mantissa.to_string();
+        let fractional = usize::try_from(-self.exponent.min(0)).unwrap_or(usize::MAX);
+        if fractional == 0 {
+            let zeros = "0".repeat(self.exponent as usize);
+            return write!(f, "{digits}{zeros}");
+        }
+        let digits = format!("{digits:0>width$}", width = fractional + 1);
+        let (integer, fraction) = digits.split_at(digits.len() - fractional);
+        write!(f, "{integer}.{fraction}")
+    }
+}
+
+/// The value of a numeric literal.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum NumberValue {
+    /// A literal without a fractional part.
+    Integer(Integer),
+    /// A literal with a fractional part, like `3.14`.
+    Decimal(Decimal),
+}
+
+impl NumberValue {
+    /// The nearest `f64` to the value.
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            NumberValue::Integer(integer) => integer.to_f64(),
+            NumberValue::Decimal(decimal) => decimal.to_f64(),
+        }
+    }
+}
+
+impl Display for NumberValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NumberValue::Integer(integer) => Display::fmt(integer, f),
+            NumberValue::Decimal(decimal) => Display::fmt(decimal, f),
+        }
+    }
+}
+
+impl<'s> Number<'s> {
+    /// The value of the literal. Digits of any base are supported; a fractional part is only
+    /// allowed in a decimal number.
+    pub fn value(&self) -> Result<NumberValue, LiteralError> {
+        let Some(integer) = &self.integer else {
+            let range = match &self.base {
+                Some(base) => base.code.range(),
+                None => default(),
+            };
+            return Err(LiteralError { kind: LiteralErrorKind::MissingDigits, range });
+        };
+        let radix = match integer.variant.base {
+            None => 10,
+            Some(token::Base::Binary) => 2,
+            Some(token::Base::Octal) => 8,
+            Some(token::Base::Hexadecimal) => 16,
+        };
+        let invalid = || LiteralError {
+            kind:  LiteralErrorKind::InvalidDigits { radix },
+            range: integer.code.range(),
+        };
+        let mantissa = Integer::from_digits(integer.code.repr.0, radix).ok_or_else(invalid)?;
+        let Some(FractionalDigits { dot, digits }) = &self.fractional_digits else {
+            return Ok(NumberValue::Integer(mantissa));
+        };
+        let range = dot.code.range().start..digits.code.range().end;
+        if radix != 10 {
+            return Err(LiteralError { kind: LiteralErrorKind::NonDecimalFraction, range });
+        }
+        let mut mantissa = mantissa;
+        let mut exponent = 0;
+        for c in digits.code.repr.0.chars().filter(|&c| c != '_') {
+            let digit = c.to_digit(10).ok_or(LiteralError {
+                kind:  LiteralErrorKind::InvalidDigits { radix },
+                range: digits.code.range(),
+            })?;
+            mantissa.mul_add(10, digit);
+            exponent -= 1;
+        }
+        Ok(NumberValue::Decimal(Decimal { mantissa, exponent }))
+    }
+}
+
+
+
+// ============
+// === Text ===
+// ============
+
+/// A part of the content of a text literal.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TextPiece<'a, 's> {
+    /// Text, with escape sequences decoded.
+    Text(String),
+    /// An interpolated expression; `None` if the splice is empty.
+    Splice(Option<&'a Tree<'s>>),
+}
+
+impl<'s> TextLiteral<'s> {
+    /// The string the literal denotes. Fails if an escape sequence is invalid, or if the literal
+    /// contains an interpolated expression; [`Self::pieces`] supports interpolation.
+    pub fn value(&self) -> Result<String, LiteralError> {
+        let mut value = String::new();
+        for piece in self.pieces()? {
+            match piece {
+                TextPiece::Text(text) => value.push_str(&text),
+                TextPiece::Splice(_) => {
+                    let range = self.elements.iter().find_map(|element| match element {
+                        TextElement::Splice { open, close, .. } =>
+                            Some(open.code.range().start..close.code.range().end),
+                        _ => None,
+                    });
+                    let kind = LiteralErrorKind::Interpolation;
+                    return Err(LiteralError { kind, range: range.unwrap_or_default() });
+                }
+            }
+        }
+        Ok(value)
+    }
+
+    /// The content of the literal, as a sequence of text and interpolated expressions. Consecutive
+    /// sections, escape sequences and line breaks are decoded into a single piece of text. Fails
+    /// if an escape sequence is invalid.
+    pub fn pieces(&self) -> Result<Vec<TextPiece<'_, 's>>, LiteralError> {
+        let mut pieces = vec![];
+        let mut text = String::new();
+        for element in &self.elements {
+            match element {
+                TextElement::Section { text: section } => text.push_str(section.code.repr.0),
+                TextElement::Escape { token } => text.push(escape_value(token)?),
+                TextElement::Newline { .. } => text.push('\n'),
+                TextElement::Splice { expression, .. } => {
+                    if !text.is_empty() {
+                        pieces.push(TextPiece::Text(mem::take(&mut text)));
+                    }
+                    pieces.push(TextPiece::Splice(expression.as_ref()));
+                }
+            }
+        }
+        if !text.is_empty() || pieces.is_empty() {
+            pieces.push(TextPiece::Text(text));
+        }
+        Ok(pieces)
+    }
+}
+
+/// The character an escape sequence denotes. If it is invalid, its code is examined to determine
+/// why.
+fn escape_value(token: &token::TextEscape) -> Result<char, LiteralError> {
+    let error = |kind| LiteralError { kind, range: token.code.range() };
+    let codepoint = token.variant.value;
+    if let Some(char) = codepoint.to_char() {
+        return Ok(char);
+    }
+    let sequence = token.code.repr.0.strip_prefix('\\').unwrap_or_default();
+    let mut chars = sequence.chars();
+    if !matches!(chars.next(), Some('x' | 'u' | 'U')) {
+        return Err(error(LiteralErrorKind::UnknownEscape));
+    }
+    let digits = chars.as_str().trim_start_matches('{').trim_end_matches('}');
+    match Integer::from_digits(digits, 16).and_then(|value| value.to_u64()) {
+        _ if digits.is_empty() => Err(error(LiteralErrorKind::MissingDigits)),
+        Some(value) if !codepoint.is_none() =>
+            Err(error(LiteralErrorKind::UnpairedSurrogate(value as u32))),
+        Some(value) => Err(error(LiteralErrorKind::CodepointOutOfRange(value))),
+        None => Err(error(LiteralErrorKind::UnknownEscape)),
+    }
+}
+
+
+
+// ====================
+// === LiteralError ===
+// ====================
+
+/// A literal that doesn't denote a value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LiteralError {
+    /// The kind of problem.
+    pub kind:  LiteralErrorKind,
+    /// The code of the part of the literal that is invalid.
+    pub range: Range<Location>,
+}
+
+/// Identifies a problem with a literal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LiteralErrorKind {
+    /// A number with a base but no digits, like `0x`; or an escape sequence without the digits of
+    /// the codepoint, like `\u`.
+    MissingDigits,
+    /// Digits that are not valid in the base of the number.
+    InvalidDigits {
+        /// The base of the number.
+        radix: u32,
+    },
+    /// A fractional part in a number that is not decimal, like `0x1.5`.
+    NonDecimalFraction,
+    /// An escape sequence that doesn't denote a character, like `\q`.
+    UnknownEscape,
+    /// An escape sequence denoting a value beyond the greatest Unicode codepoint, like
+    /// `\u{110000}`.
+    CodepointOutOfRange(u64),
+    /// An escape sequence denoting half of a UTF-16 surrogate pair, like `\uD800`. Such values can
+    /// be included in Enso text, but cannot be represented in a Rust string.
+    UnpairedSurrogate(u32),
+    /// An interpolated expression, which has no value until it is evaluated.
+    Interpolation,
+}
+
+impl Display for LiteralErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LiteralErrorKind::MissingDigits => write!(f, "Expected digits"),
+            LiteralErrorKind::InvalidDigits { radix } =>
+                write!(f, "Invalid digits for a number in base {radix}"),
+            LiteralErrorKind::NonDecimalFraction =>
+                write!(f, "Only decimal numbers can have a fractional part"),
+            LiteralErrorKind::UnknownEscape => write!(f, "Unknown escape sequence"),
+            LiteralErrorKind::CodepointOutOfRange(value) => write!(
+                f,
+                "Codepoint U+{value:X} is out of range; the greatest codepoint is U+{:X}",
+                char::MAX as u32
+            ),
+            LiteralErrorKind::UnpairedSurrogate(value) =>
+                write!(f, "Codepoint U+{value:X} is an unpaired surrogate"),
+            LiteralErrorKind::Interpolation =>
+                write!(f, "Interpolated text does not have a constant value"),
+        }
+    }
+}
+
+impl Display for LiteralError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let start = self.range.start;
+        write!(f, "{} (at {}:{})", self.kind, start.line + 1, start.col16 + 1)
+    }
+}
+
+impl std::error::Error for LiteralError {}
+
+
+
+// =============
+// === Tests ===
+// =============
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parse the code, and return the literal it consists of.
+    fn literal(code: &str) -> Tree {
+        let tree = crate::Parser::new().run(code);
+        let Variant::BodyBlock(block) = *tree.variant else { unreachable!() };
+        block.statements.into_iter().find_map(|line| line.expression).unwrap()
+    }
+
+    fn number(code: &str) -> Result<String, LiteralErrorKind> {
+        let Variant::Number(number) = &*literal(code).variant else { panic!("{code}") };
+        number.value().map(|value| value.to_string()).map_err(|error| error.kind)
+    }
+
+    fn text(code: &str) -> Result<String, LiteralError> {
+        let Variant::TextLiteral(text) = &*literal(code).variant else { panic!("{code}") };
+        text.value()
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(number("42").unwrap(), "42");
+        assert_eq!(number("0b1010").unwrap(), "10");
+        assert_eq!(number("0o777").unwrap(), "511");
+        assert_eq!(number("0xFF").unwrap(), "255");
+        assert_eq!(number("16_ff").unwrap(), "255");
+        assert_eq!(number("2_0110").unwrap(), "6");
+        assert_eq!(number("1_000").unwrap(), "1000");
+        let big = "0x100000000000000000000000000000000";
+        assert_eq!(number(big).unwrap(), "340282366920938463463374607431768211456");
+        assert_eq!(number("3.14").unwrap(), "3.14");
+        assert_eq!(number("0.001").unwrap(), "0.001");
+        assert_eq!(number("1_000.5_0").unwrap(), "1000.50");
+        assert_eq!(number("0x"), Err(LiteralErrorKind::MissingDigits));
+        assert_eq!(number("0x1.5"), Err(LiteralErrorKind::NonDecimalFraction));
+    }
+
+    #[test]
+    fn number_conversions() {
+        let Variant::Number(number) = &*literal("18446744073709551615").variant else { panic!() };
+        let NumberValue::Integer(integer) = number.value().unwrap() else { panic!() };
+        assert_eq!(integer.to_u64(), Some(u64::MAX));
+        assert_eq!(integer.to_i64(), None);
+        assert_eq!(Integer::from(u64::MAX), integer);
+        assert_eq!(Integer::from(0).to_string(), "0");
+        let Variant::Number(number) = &*literal("2.5").variant else { panic!() };
+        let value = number.value().unwrap();
+        assert_eq!(value, NumberValue::Decimal(Decimal { mantissa: 25.into(), exponent: -1 }));
+        assert_eq!(value.to_f64(), 2.5);
+        assert_eq!(Decimal { mantissa: 25.into(), exponent: 2 }.to_string(), "2500");
+    }
+
+    #[test]
+    fn text_values() {
+        assert_eq!(text("'abc'").unwrap(), "abc");
+        assert_eq!(text(r#""raw \n""#).unwrap(), "raw \\n");
+        assert_eq!(text(r"'a\nb\t\'\\'").unwrap(), "a\nb\t'\\");
+        assert_eq!(text(r"'\x41B\u{43}\U00000044'").unwrap(), "ABCD");
+        assert_eq!(text("''").unwrap(), "");
+        let code = "'''\n    first\n      indented\n\n    last";
+        assert_eq!(text(code).unwrap(), "first\n  indented\n\nlast");
+        let code = "x =\n    y = '''\n        a\n         b\n    z";
+        let Variant::BodyBlock(block) = *crate::Parser::new().run(code).variant else { panic!() };
+        let function = block.statements.into_iter().find_map(|line| line.expression).unwrap();
+        let Variant::Function(function) = *function.variant else { panic!() };
+        let Variant::BodyBlock(body) = *function.body.unwrap().variant else { panic!() };
+        let assignment = body.statements.into_iter().find_map(|line| line.expression).unwrap();
+        let Variant::Assignment(assignment) = *assignment.variant else { panic!() };
+        let Variant::TextLiteral(literal) = &*assignment.expr.variant else { panic!() };
+        assert_eq!(literal.value().unwrap(), "a\n b");
+    }
+
+    #[test]
+    fn text_errors() {
+        let error = text(r"'ab\u{110000}'").unwrap_err();
+        assert_eq!(error.kind, LiteralErrorKind::CodepointOutOfRange(0x110000));
+        assert_eq!((error.range.start.col16, error.range.end.col16), (3, 13));
+        let message =
+            "Codepoint U+110000 is out of range; the greatest codepoint is U+10FFFF (at 1:4)";
+        assert_eq!(error.to_string(), message);
+        let error = text(r"'\UFFFFFFFF'").unwrap_err();
+        assert_eq!(error.kind, LiteralErrorKind::CodepointOutOfRange(0xFFFF_FFFF));
+        assert_eq!(text(r"'\q'").unwrap_err().kind, LiteralErrorKind::UnknownEscape);
+        assert_eq!(text(r"'\x'").unwrap_err().kind, LiteralErrorKind::MissingDigits);
+        assert_eq!(
+            text(r"'\uD800'").unwrap_err().kind,
+            LiteralErrorKind::UnpairedSurrogate(0xD800)
+        );
+        assert_eq!(text("'a `b` c'").unwrap_err().kind, LiteralErrorKind::Interpolation);
+    }
+
+    #[test]
+    fn interpolation() {
+        let tree = literal("'a `b` \\n`c`'");
+        let Variant::TextLiteral(text) = &*tree.variant else { panic!() };
+        let pieces = text.pieces().unwrap();
+        let [TextPiece::Text(a), TextPiece::Splice(Some(b)), TextPiece::Text(n), TextPiece::Splice(Some(_))] =
+            &pieces[..]
+        else {
+            panic!("{pieces:?}")
+        };
+        assert_eq!((a.as_str(), n.as_str()), ("a ", " \n"));
+        assert!(matches!(&*b.variant, Variant::Ident(_)));
+    }
+}